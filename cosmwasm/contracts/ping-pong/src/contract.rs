@@ -1,20 +1,29 @@
-use crate::execution::i_ping;
+use crate::error::ContractError;
+use crate::execution::{i_ping, i_ping_many, record_fanout_ack, record_ping_ack, PingRequest};
+use crate::migrations::MIGRATIONS;
 use crate::query::handle_query;
-use crate::state::{CREATE_I_SEND_REQUEST, PING_FROM_SOURCE, PONG_FROM_DESTINATION, REQUEST_ID};
+use crate::state::{PING_FROM_SOURCE, PONG_FROM_DESTINATION, REQUEST_ID, REQUEST_TRACKER};
 #[cfg(not(feature = "library"))]
-use cosmwasm_std::{entry_point, Binary, Deps, DepsMut, Env, MessageInfo, Response, StdResult};
-use cosmwasm_std::{from_binary, Coin, Event, Reply, StdError, SubMsgResult};
-use new_crosstalk_sample::ping_pong::{ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg};
+use cosmwasm_std::entry_point;
+use cosmwasm_std::{Binary, Deps, DepsMut, Env, MessageInfo, Response};
+use cosmwasm_std::{Event, Reply};
+use new_crosstalk_sample::crosstalk::{
+    handle_sudo, CrosstalkDapp, InboundRequest, RequestAck, RequestTracker, TrackedRequest,
+};
+use new_crosstalk_sample::migration::migrate_contract;
+use new_crosstalk_sample::ping_pong::{
+    ExecuteMsg, InstantiateMsg, MigrateMsg, PingPayload, PongReceipt, PongRecord, QueryMsg,
+    PONG_RECEIPT_VERSION,
+};
 
 use cw2::set_contract_version;
 
-use router_wasm_bindings::ethabi::{decode, ParamType};
-use router_wasm_bindings::types::CrosschainRequestResponse;
+use router_wasm_bindings::ethabi;
 use router_wasm_bindings::{RouterMsg, RouterQuery, SudoMsg};
 
 // version info for migration info
 const CONTRACT_NAME: &str = "PingPong";
-const CONTRACT_VERSION: &str = "0.1.0";
+const CONTRACT_VERSION: &str = "0.2.0";
 
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn instantiate(
@@ -22,141 +31,149 @@ pub fn instantiate(
     _env: Env,
     _info: MessageInfo,
     _msg: InstantiateMsg,
-) -> StdResult<Response> {
+) -> Result<Response, ContractError> {
     set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
     REQUEST_ID.save(deps.storage, &0)?;
     Ok(Response::new().add_attribute("action", "ping_pong_init"))
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn reply(deps: DepsMut<RouterQuery>, _env: Env, msg: Reply) -> StdResult<Response<RouterMsg>> {
-    match msg.id {
-        CREATE_I_SEND_REQUEST => {
-            deps.api.debug(&msg.id.to_string());
-            // TODO: need to handle nonce data here, Nonce handling logic depends on the use-case.
-            let response: Response<RouterMsg> = Response::new();
-            match msg.result {
-                SubMsgResult::Ok(msg_result) => match msg_result.data {
-                    Some(binary_data) => {
-                        deps.api.debug("Binary Data Found");
-                        let cross_chain_req_res: CrosschainRequestResponse =
-                            from_binary(&binary_data).unwrap();
-
-                        let info_str: String = format!(
-                            "Binary data {:?}, response {:?}",
-                            &binary_data.to_string(),
-                            cross_chain_req_res
-                        );
-                        deps.api.debug(&info_str);
-                        return Ok(response);
-                    }
-                    None => deps.api.debug("No Binary Data Found"),
-                },
-                SubMsgResult::Err(err) => deps.api.debug(&err.to_string()),
-            }
-        }
-        id => return Err(StdError::generic_err(format!("Unknown reply id: {}", id))),
+pub fn reply(
+    deps: DepsMut<RouterQuery>,
+    _env: Env,
+    msg: Reply,
+) -> Result<Response<RouterMsg>, ContractError> {
+    deps.api.debug(&msg.id.to_string());
+    let tracked: TrackedRequest = REQUEST_TRACKER
+        .handle_reply(deps.storage, &msg)?
+        .ok_or(ContractError::UnknownReplyId { id: msg.id })?;
+    let mut response: Response<RouterMsg> =
+        Response::new().add_attribute("request_id", tracked.request_id.to_string());
+    if let Some(request_identifier) = tracked.request_identifier {
+        response = response.add_attribute("request_identifier", request_identifier.to_string());
     }
-    Ok(Response::new())
+    Ok(response)
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn execute(
     deps: DepsMut<RouterQuery>,
-    _env: Env,
-    _info: MessageInfo,
+    env: Env,
+    info: MessageInfo,
     msg: ExecuteMsg,
-) -> StdResult<Response<RouterMsg>> {
+) -> Result<Response<RouterMsg>, ContractError> {
     match msg {
         ExecuteMsg::IPing {
             ping,
             dest_contract_address,
             dest_chain_id,
             request_metadata,
-        } => i_ping(
-            deps,
-            ping,
-            dest_contract_address,
-            dest_chain_id,
-            request_metadata,
-        ),
+            body,
+            recipient,
+        } => {
+            let request: PingRequest = PingRequest {
+                ping,
+                dest_contract_address,
+                dest_chain_id,
+                request_metadata,
+                body: body.unwrap_or_default(),
+                recipient: recipient.unwrap_or_default(),
+            };
+            i_ping(deps, env, info, request)
+        }
+        ExecuteMsg::IPingMany { ping, destinations } => {
+            i_ping_many(deps, env, info, ping, destinations)
+        }
     }
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn sudo(deps: DepsMut<RouterQuery>, env: Env, msg: SudoMsg) -> StdResult<Response<RouterMsg>> {
-    match msg {
-        SudoMsg::HandleIReceive {
-            request_sender,
-            src_chain_id,
-            request_identifier,
-            payload,
-        } => handle_sudo_request(
-            deps,
-            env,
-            request_sender,
-            src_chain_id,
-            request_identifier,
-            payload,
-        ),
-        SudoMsg::HandleIAck {
-            request_identifier,
-            exec_flag,
-            exec_data,
-            refund_amount,
-        } => handle_sudo_ack(
-            deps,
-            env,
-            request_identifier,
-            exec_flag,
-            exec_data,
-            refund_amount,
-        ),
+pub fn sudo(
+    deps: DepsMut<RouterQuery>,
+    env: Env,
+    msg: SudoMsg,
+) -> Result<Response<RouterMsg>, ContractError> {
+    handle_sudo::<PingPong>(deps, env, msg)
+}
+
+pub struct PingPong;
+
+impl CrosstalkDapp for PingPong {
+    type Error = ContractError;
+
+    const TRACKER: Option<RequestTracker<'static>> = Some(REQUEST_TRACKER);
+
+    fn on_receive(
+        deps: DepsMut<RouterQuery>,
+        env: Env,
+        request: InboundRequest,
+    ) -> Result<Response<RouterMsg>, ContractError> {
+        handle_sudo_request(deps, env, request)
+    }
+
+    fn on_ack(
+        deps: DepsMut<RouterQuery>,
+        env: Env,
+        ack: RequestAck,
+    ) -> Result<Response<RouterMsg>, ContractError> {
+        handle_sudo_ack(deps, env, ack)
     }
 }
 
 pub fn handle_sudo_request(
     deps: DepsMut<RouterQuery>,
-    _env: Env,
-    request_sender: String,
-    src_chain_id: String,
-    request_identifier: u64,
-    payload: Binary,
-) -> StdResult<Response<RouterMsg>> {
-    let token_vec = match decode(&[ParamType::Uint(64), ParamType::String], &payload.0) {
-        Ok(data) => data,
-        Err(_) => {
-            return Err(StdError::GenericErr {
-                msg: String::from("err.into()"),
-            })
-        }
-    };
+    env: Env,
+    request: InboundRequest,
+) -> Result<Response<RouterMsg>, ContractError> {
+    let InboundRequest {
+        request_sender,
+        src_chain_id,
+        request_identifier,
+        payload,
+    } = request;
+    let ping_payload: PingPayload = decode_ping_payload(&payload)?;
 
     deps.api.debug("Inside the Inbound handler");
-    let request_id: u64 = token_vec[0].clone().into_uint().unwrap().as_u64();
-    let data_string: String = token_vec[1].clone().into_string().unwrap();
+    PING_FROM_SOURCE.save(
+        deps.storage,
+        (&src_chain_id, ping_payload.request_id),
+        &ping_payload.message,
+    )?;
 
-    PING_FROM_SOURCE.save(deps.storage, (&src_chain_id, request_id), &data_string)?;
+    let receipt: PongReceipt = PongReceipt {
+        version: PONG_RECEIPT_VERSION,
+        request_id: ping_payload.request_id,
+        src_timestamp: ping_payload.timestamp,
+        dest_block_height: env.block.height,
+        dest_block_time: env.block.time.seconds(),
+        message: ping_payload.message,
+    };
 
     let mut res = Response::new()
         .add_attribute("sender", request_sender)
         .add_attribute("request_identifier", request_identifier.to_string())
-        .add_attribute("src_chain_id", src_chain_id);
-    res.data = Some(payload);
+        .add_attribute("src_chain_id", src_chain_id)
+        .add_attribute("payload_version", ping_payload.version.to_string());
+    if !ping_payload.recipient.is_empty() {
+        res = res.add_attribute("recipient", ping_payload.recipient);
+    }
+    res.data = Some(Binary(receipt.encode()?));
     Ok(res)
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn migrate(deps: DepsMut<RouterQuery>, env: Env, _msg: MigrateMsg) -> StdResult<Response> {
-    let ver = cw2::get_contract_version(deps.storage)?;
-    // ensure we are migrating from an allowed contract
-    if ver.contract != CONTRACT_NAME.to_string() {
-        return Err(StdError::generic_err("Can only upgrade from same type").into());
-    }
-    // note: better to do proper semver compare, but string compare *usually* works
-    // if ver.version >= CONTRACT_VERSION.to_string() {
-    //     return Err(StdError::generic_err("Cannot upgrade from a newer version").into());
-    // }
+pub fn migrate(
+    deps: DepsMut<RouterQuery>,
+    env: Env,
+    msg: MigrateMsg,
+) -> Result<Response, ContractError> {
+    let applied: Vec<&str> = migrate_contract(
+        deps.storage,
+        CONTRACT_NAME,
+        CONTRACT_VERSION,
+        MIGRATIONS,
+        &msg,
+    )?;
 
     let info_str: String = format!(
         "migrating contract: {}, new_contract_version: {}, contract_name: {}",
@@ -165,41 +182,96 @@ pub fn migrate(deps: DepsMut<RouterQuery>, env: Env, _msg: MigrateMsg) -> StdRes
         CONTRACT_NAME.to_string()
     );
     deps.api.debug(&info_str);
-    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
-    Ok(Response::default())
+    Ok(Response::new()
+        .add_attribute("action", "migrate")
+        .add_attribute("version", CONTRACT_VERSION)
+        .add_attribute("migrations", applied.join(",")))
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn query(deps: Deps<RouterQuery>, env: Env, msg: QueryMsg) -> StdResult<Binary> {
-    handle_query(deps, env, msg)
+pub fn query(deps: Deps<RouterQuery>, env: Env, msg: QueryMsg) -> Result<Binary, ContractError> {
+    Ok(handle_query(deps, env, msg)?)
 }
 
 fn handle_sudo_ack(
     deps: DepsMut<RouterQuery>,
-    _env: Env,
-    _request_identifier: u64,
-    exec_flag: bool,
-    exec_data: Binary,
-    _refund_amount: Coin,
-) -> StdResult<Response<RouterMsg>> {
-    assert_eq!(exec_flag, true);
-    let token_vec = match decode(&[ParamType::Uint(64), ParamType::String], &exec_data.0) {
-        Ok(data) => data,
-        Err(_) => {
-            return Err(StdError::GenericErr {
-                msg: String::from("err.into()"),
-            })
-        }
-    };
-
-    let request_id: u64 = token_vec[0].clone().into_uint().unwrap().as_u64();
-    let data_string: String = token_vec[1].clone().into_string().unwrap();
+    env: Env,
+    ack: RequestAck,
+) -> Result<Response<RouterMsg>, ContractError> {
+    let exec_flag: bool = ack.exec_flag;
+    // the tracker resolves the request id from the request identifier, the id
+    // echoed by the destination is only used for acks of untracked requests
+    let mut request_id: Option<u64> = ack.request_id;
+    if exec_flag {
+        let pong: PongRecord = match PongReceipt::decode(&ack.exec_data.0) {
+            Ok(receipt) => PongRecord {
+                request_id: receipt.request_id,
+                message: receipt.message,
+                src_timestamp: receipt.src_timestamp,
+                dest_block_height: receipt.dest_block_height,
+                dest_block_time: receipt.dest_block_time,
+                ack_block_height: env.block.height,
+                ack_block_time: env.block.time.seconds(),
+            },
+            // the ping-pong dapps of the other chains ack with the ping payload
+            // they received
+            Err(_) => {
+                let ping_payload: PingPayload = decode_ping_payload(&ack.exec_data)?;
+                PongRecord {
+                    request_id: ping_payload.request_id,
+                    message: ping_payload.message,
+                    src_timestamp: ping_payload.timestamp,
+                    dest_block_height: 0,
+                    dest_block_time: 0,
+                    ack_block_height: env.block.height,
+                    ack_block_time: env.block.time.seconds(),
+                }
+            }
+        };
+        let tracked_request_id: u64 = match request_id {
+            Some(tracked_request_id) if tracked_request_id != pong.request_id => {
+                return Err(ContractError::RequestIdMismatch {
+                    expected: tracked_request_id,
+                    received: pong.request_id,
+                })
+            }
+            Some(tracked_request_id) => tracked_request_id,
+            None => pong.request_id,
+        };
+        PONG_FROM_DESTINATION.save(deps.storage, tracked_request_id, &pong)?;
+        request_id = Some(tracked_request_id);
+    }
 
-    PONG_FROM_DESTINATION.save(deps.storage, &request_id.to_string(), &data_string)?;
+    let mut fanout_event: Option<Event> = None;
+    let mut event = Event::new("ExecutionStatus").add_attribute("execFlag", exec_flag.to_string());
+    if let Some(request_id) = request_id {
+        event = event.add_attribute("requestIdentifier", request_id.to_string());
+        let rtt: Option<u64> = record_ping_ack(
+            deps.storage,
+            request_id,
+            exec_flag,
+            env.block.time.seconds(),
+        )?;
+        if let Some(rtt) = rtt {
+            event = event.add_attribute("roundTripTime", rtt.to_string());
+        }
+        if let Some(fanout) = record_fanout_ack(deps.storage, request_id, exec_flag)? {
+            let completed_event = Event::new("FanoutCompleted")
+                .add_attribute("fanoutId", fanout.fanout_id.to_string())
+                .add_attribute("acked", fanout.acked.to_string())
+                .add_attribute("succeeded", fanout.succeeded.to_string())
+                .add_attribute("failed", (fanout.acked - fanout.succeeded).to_string());
+            fanout_event = Some(completed_event);
+        }
+    }
 
-    let event = Event::new("ExecutionStatus")
-        .add_attribute("requestIdentifier", request_id.to_string())
-        .add_attribute("execFlag", exec_flag.to_string());
+    let mut res: Response<RouterMsg> = Response::new().add_event(event);
+    if let Some(fanout_event) = fanout_event {
+        res = res.add_event(fanout_event);
+    }
+    Ok(res)
+}
 
-    Ok(Response::new().add_event(event))
+fn decode_ping_payload(payload: &Binary) -> Result<PingPayload, ContractError> {
+    PingPayload::decode(&payload.0).map_err(|_| ContractError::from(ethabi::Error::InvalidData))
 }
@@ -0,0 +1,49 @@
+use cosmwasm_std::StdError;
+use new_crosstalk_sample::migration::MigrationError;
+use router_wasm_bindings::ethabi;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum ContractError {
+    #[error("{0}")]
+    Std(#[from] StdError),
+
+    #[error("Decode error: {reason}")]
+    Decode { reason: String },
+
+    #[error("Unauthorized")]
+    Unauthorized {},
+
+    #[error("Unknown route for chain {chain_id}")]
+    UnknownRoute { chain_id: String },
+
+    #[error("Contract is paused")]
+    Paused {},
+
+    #[error("Invalid address: {address}")]
+    InvalidAddress { address: String },
+
+    #[error("destinations cannot be empty")]
+    NoDestinations {},
+
+    #[error("destinations cannot be more than {max}")]
+    TooManyDestinations { max: usize },
+
+    #[error("Request id mismatch: expected {expected}, received {received}")]
+    RequestIdMismatch { expected: u64, received: u64 },
+
+    #[error("Unknown reply id: {id}")]
+    UnknownReplyId { id: u64 },
+
+    #[error("{0}")]
+    Migration(#[from] MigrationError),
+}
+
+// ethabi::Error has no Display in the no-std build of ethabi
+impl From<ethabi::Error> for ContractError {
+    fn from(err: ethabi::Error) -> Self {
+        ContractError::Decode {
+            reason: format!("{:?}", err),
+        }
+    }
+}
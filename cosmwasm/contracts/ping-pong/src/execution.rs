@@ -1,59 +1,272 @@
-use cosmwasm_std::{Binary, DepsMut, Event, ReplyOn, Response, StdResult, SubMsg, Uint128};
-use router_wasm_bindings::{
-    ethabi::{encode, ethereum_types::U256, Token},
-    Bytes, RouterMsg, RouterQuery,
+use cosmwasm_std::{
+    Binary, DepsMut, Env, Event, MessageInfo, Order, Response, StdResult, Storage, SubMsg,
 };
+use new_crosstalk_sample::crosstalk::{ISendRequest, RequestPacket};
+use new_crosstalk_sample::ping_pong::{FanoutInfo, PingPayload, RouteInfo, PING_PAYLOAD_VERSION};
+use router_wasm_bindings::{RouterMsg, RouterQuery};
 
 use crate::{
+    error::ContractError,
     query::fetch_request_id,
-    state::{CREATE_I_SEND_REQUEST, REQUEST_ID},
+    state::{
+        RouteSample, CREATE_I_SEND_REQUEST, FANOUTS, FANOUT_ID, I_PING_MANY_REPLY_ID_OFFSET,
+        MAX_FANOUT_DESTINATIONS, PENDING_PINGS, REQUEST_FANOUT, REQUEST_ID, REQUEST_TRACKER,
+        ROUTES, ROUTE_SAMPLES, ROUTE_STATS_WINDOW,
+    },
 };
 
+/// A ping to a single destination, sent by `i_ping` and by every destination
+/// of `i_ping_many`.
+pub struct PingRequest {
+    pub ping: String,
+    pub dest_contract_address: String,
+    pub dest_chain_id: String,
+    pub request_metadata: Binary,
+    pub body: Binary,
+    pub recipient: String,
+}
+
 pub fn i_ping(
     deps: DepsMut<RouterQuery>,
-    ping: String,
-    dest_contract_address: String,
-    dest_chain_id: String,
-    request_metadata: Binary,
-) -> StdResult<Response<RouterMsg>> {
+    env: Env,
+    info: MessageInfo,
+    request: PingRequest,
+) -> Result<Response<RouterMsg>, ContractError> {
     let request_id: u64 = fetch_request_id(deps.as_ref())? + 1;
-    let u256: U256 = U256::from(request_id);
     REQUEST_ID.save(deps.storage, &(request_id))?;
-    let payload: Vec<u8> = encode(&[Token::Uint(u256), Token::String(ping.clone())]);
+    let cross_chain_sub_msg: SubMsg<RouterMsg> = create_ping_sub_msg(
+        deps,
+        &env,
+        &info,
+        request_id,
+        CREATE_I_SEND_REQUEST,
+        &request,
+    )?;
+    let event: Event = Event::new("new_ping")
+        .add_attribute("ping", request.ping)
+        .add_attribute("request_id", request_id.to_string())
+        .add_attribute("timestamp", env.block.time.seconds().to_string());
+    let res = Response::new()
+        .add_event(event)
+        .add_submessage(cross_chain_sub_msg)
+        .add_attribute("dest_contract_address", request.dest_contract_address);
+    Ok(res)
+}
+
+/// Sends the same ping to every destination in a single transaction.
+/// Every destination gets its own request id and its own reply id
+/// (`I_PING_MANY_REPLY_ID_OFFSET + request_id`) so the replies can be
+/// correlated back to the fan-out.
+pub fn i_ping_many(
+    mut deps: DepsMut<RouterQuery>,
+    env: Env,
+    info: MessageInfo,
+    ping: String,
+    destinations: Vec<(String, String, Binary)>,
+) -> Result<Response<RouterMsg>, ContractError> {
+    if destinations.is_empty() {
+        return Err(ContractError::NoDestinations {});
+    }
+    if destinations.len() > MAX_FANOUT_DESTINATIONS {
+        return Err(ContractError::TooManyDestinations {
+            max: MAX_FANOUT_DESTINATIONS,
+        });
+    }
+
+    let fanout_id: u64 = FANOUT_ID.may_load(deps.storage)?.unwrap_or_default() + 1;
+    FANOUT_ID.save(deps.storage, &fanout_id)?;
+
+    let mut request_id: u64 = fetch_request_id(deps.as_ref())?;
+    let mut request_ids: Vec<u64> = vec![];
+    let mut sub_msgs: Vec<SubMsg<RouterMsg>> = vec![];
+    for (dest_chain_id, dest_contract_address, request_metadata) in destinations {
+        request_id += 1;
+        let request: PingRequest = PingRequest {
+            ping: ping.clone(),
+            dest_contract_address,
+            dest_chain_id,
+            request_metadata,
+            body: Binary::default(),
+            recipient: String::new(),
+        };
+        let sub_msg: SubMsg<RouterMsg> = create_ping_sub_msg(
+            deps.branch(),
+            &env,
+            &info,
+            request_id,
+            I_PING_MANY_REPLY_ID_OFFSET + request_id,
+            &request,
+        )?;
+        REQUEST_FANOUT.save(deps.storage, request_id, &fanout_id)?;
+        request_ids.push(request_id);
+        sub_msgs.push(sub_msg);
+    }
+    REQUEST_ID.save(deps.storage, &request_id)?;
+
+    let fanout: FanoutInfo = FanoutInfo {
+        fanout_id,
+        request_ids: request_ids.clone(),
+        acked: 0,
+        succeeded: 0,
+        completed: false,
+    };
+    FANOUTS.save(deps.storage, fanout_id, &fanout)?;
+
+    let request_ids: Vec<String> = request_ids.iter().map(|id| id.to_string()).collect();
+    let event: Event = Event::new("new_ping_many")
+        .add_attribute("ping", ping)
+        .add_attribute("fanout_id", fanout_id.to_string())
+        .add_attribute("request_ids", request_ids.join(","))
+        .add_attribute("timestamp", env.block.time.seconds().to_string());
+    let res = Response::new().add_event(event).add_submessages(sub_msgs);
+    Ok(res)
+}
+
+fn create_ping_sub_msg(
+    deps: DepsMut<RouterQuery>,
+    env: &Env,
+    info: &MessageInfo,
+    request_id: u64,
+    reply_id: u64,
+    request: &PingRequest,
+) -> StdResult<SubMsg<RouterMsg>> {
+    let dest_chain_id: &str = &request.dest_chain_id;
+    let dest_contract_address: &str = &request.dest_contract_address;
+    record_ping_sent(
+        deps.storage,
+        dest_chain_id,
+        request_id,
+        env.block.time.seconds(),
+    )?;
+    let ping_payload: PingPayload = PingPayload {
+        version: PING_PAYLOAD_VERSION,
+        request_id,
+        timestamp: env.block.time.seconds(),
+        sender: Binary(info.sender.as_bytes().to_vec()),
+        message: request.ping.clone(),
+        body: request.body.clone(),
+        recipient: request.recipient.clone(),
+    };
+    let payload: Vec<u8> = ping_payload.encode()?;
     let info_str: String = format!("create_outbound_request-- payload: {:?}", payload.clone(),);
     deps.api.debug(&info_str);
-    let route_amount: Uint128 = Uint128::zero();
 
     let info_str: String = format!(
         "create_outbound_request-- dest_chain_id: {}, dest_contract_address: {}, request_metadata: {}",
-        dest_chain_id, dest_contract_address.clone(), request_metadata
+        dest_chain_id, dest_contract_address, request.request_metadata
     );
     deps.api.debug(&info_str);
-    let request_packet: Bytes = encode(&[
-        Token::String(dest_contract_address.clone()),
-        Token::Bytes(payload),
-    ]);
-
-    let i_send_request: RouterMsg = RouterMsg::CrosschainCall {
-        version: 1,
-        route_amount,
-        route_recipient: String::default(),
+    let request_packet: RequestPacket = RequestPacket::new(dest_contract_address, payload);
+
+    REQUEST_TRACKER.track(deps.storage, reply_id, request_id)?;
+    Ok(ISendRequest::new(
         dest_chain_id,
-        request_metadata: request_metadata.0,
         request_packet,
+        request.request_metadata.0.clone(),
+    )
+    .into_sub_msg(reply_id))
+}
+
+pub fn record_ping_sent(
+    storage: &mut dyn Storage,
+    dest_chain_id: &str,
+    request_id: u64,
+    sent_at: u64,
+) -> StdResult<()> {
+    let mut route: RouteInfo =
+        ROUTES
+            .may_load(storage, dest_chain_id)?
+            .unwrap_or_else(|| RouteInfo {
+                dest_chain_id: dest_chain_id.to_string(),
+                ..RouteInfo::default()
+            });
+    route.sent += 1;
+    ROUTES.save(storage, dest_chain_id, &route)?;
+
+    let sample: RouteSample = RouteSample {
+        sent_at,
+        acked_at: None,
+        exec_flag: None,
     };
-    let cross_chain_sub_msg: SubMsg<RouterMsg> = SubMsg {
-        id: CREATE_I_SEND_REQUEST,
-        msg: i_send_request.into(),
-        gas_limit: None,
-        reply_on: ReplyOn::Success,
+    ROUTE_SAMPLES.save(storage, (dest_chain_id, request_id), &sample)?;
+    PENDING_PINGS.save(storage, request_id, &dest_chain_id.to_string())?;
+
+    // slide the window, only the last ROUTE_STATS_WINDOW samples of a route are kept
+    if route.sent > ROUTE_STATS_WINDOW {
+        let oldest: Option<u64> = ROUTE_SAMPLES
+            .prefix(dest_chain_id)
+            .keys(storage, None, None, Order::Ascending)
+            .next()
+            .transpose()?;
+        if let Some(oldest) = oldest {
+            ROUTE_SAMPLES.remove(storage, (dest_chain_id, oldest));
+        }
+    }
+    Ok(())
+}
+
+/// Marks the ping as acked and returns its round trip time in seconds,
+/// `None` if the ack failed or the ping is unknown or already slid out of the
+/// stats window.
+pub fn record_ping_ack(
+    storage: &mut dyn Storage,
+    request_id: u64,
+    exec_flag: bool,
+    acked_at: u64,
+) -> StdResult<Option<u64>> {
+    let dest_chain_id: String = match PENDING_PINGS.may_load(storage, request_id)? {
+        Some(dest_chain_id) => dest_chain_id,
+        None => return Ok(None),
     };
-    let event: Event = Event::new("new_ping")
-        .add_attribute("ping", ping)
-        .add_attribute("request_id", request_id.to_string());
-    let res = Response::new()
-        .add_event(event)
-        .add_submessage(cross_chain_sub_msg.into())
-        .add_attribute("dest_contract_address", dest_contract_address);
-    Ok(res)
+    PENDING_PINGS.remove(storage, request_id);
+
+    ROUTES.update(storage, &dest_chain_id, |route| -> StdResult<RouteInfo> {
+        let mut route: RouteInfo = route.unwrap_or_default();
+        route.acked += 1;
+        if exec_flag {
+            route.succeeded += 1;
+        }
+        Ok(route)
+    })?;
+
+    let key = (dest_chain_id.as_str(), request_id);
+    match ROUTE_SAMPLES.may_load(storage, key)? {
+        Some(mut sample) => {
+            sample.acked_at = Some(acked_at);
+            sample.exec_flag = Some(exec_flag);
+            ROUTE_SAMPLES.save(storage, key, &sample)?;
+            if !exec_flag {
+                return Ok(None);
+            }
+            Ok(Some(acked_at.saturating_sub(sample.sent_at)))
+        }
+        None => Ok(None),
+    }
+}
+
+/// Counts the ack towards the fan-out of the request, returns the fan-out
+/// once every destination of it has acked.
+pub fn record_fanout_ack(
+    storage: &mut dyn Storage,
+    request_id: u64,
+    exec_flag: bool,
+) -> StdResult<Option<FanoutInfo>> {
+    let fanout_id: u64 = match REQUEST_FANOUT.may_load(storage, request_id)? {
+        Some(fanout_id) => fanout_id,
+        None => return Ok(None),
+    };
+    REQUEST_FANOUT.remove(storage, request_id);
+
+    let mut fanout: FanoutInfo = FANOUTS.load(storage, fanout_id)?;
+    fanout.acked += 1;
+    if exec_flag {
+        fanout.succeeded += 1;
+    }
+    fanout.completed = fanout.acked == fanout.request_ids.len() as u64;
+    FANOUTS.save(storage, fanout_id, &fanout)?;
+
+    if fanout.completed {
+        return Ok(Some(fanout));
+    }
+    Ok(None)
 }
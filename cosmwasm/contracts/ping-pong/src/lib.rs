@@ -1,8 +1,12 @@
 pub mod contract;
+pub mod error;
 pub mod execution;
+pub mod migrations;
 pub mod query;
 pub mod state;
 
 pub use serde::{Deserialize, Serialize};
 #[cfg(test)]
+mod multitest;
+#[cfg(test)]
 mod tests;
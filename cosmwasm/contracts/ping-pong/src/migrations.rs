@@ -0,0 +1,42 @@
+use cosmwasm_std::{Order, StdResult, Storage};
+use new_crosstalk_sample::migration::MigrationStep;
+use new_crosstalk_sample::ping_pong::{MigrateMsg, PongRecord};
+
+use crate::{
+    error::ContractError,
+    state::{LEGACY_PONG_FROM_DESTINATION, PONG_FROM_DESTINATION},
+};
+
+// state migrations, ordered by ascending version
+pub const MIGRATIONS: &[MigrationStep<MigrateMsg, ContractError>] = &[MigrationStep {
+    version: "0.2.0",
+    migrate: migrate_pong_records,
+}];
+
+/// Re-keys the pongs stored by string request id into `PongRecord`s keyed by
+/// `u64`. The legacy pongs only carried the message, the receipt and ack
+/// fields of the migrated records are left to zero.
+fn migrate_pong_records(storage: &mut dyn Storage, _msg: &MigrateMsg) -> Result<(), ContractError> {
+    let legacy_pongs: Vec<(String, String)> = LEGACY_PONG_FROM_DESTINATION
+        .range(storage, None, None, Order::Ascending)
+        .collect::<StdResult<_>>()?;
+    for (request_id, message) in legacy_pongs {
+        LEGACY_PONG_FROM_DESTINATION.remove(storage, &request_id);
+        // keys which are not a request id were never written by the contract
+        let request_id: u64 = match request_id.parse() {
+            Ok(request_id) => request_id,
+            Err(_) => continue,
+        };
+        let pong: PongRecord = PongRecord {
+            request_id,
+            message,
+            src_timestamp: 0,
+            dest_block_height: 0,
+            dest_block_time: 0,
+            ack_block_height: 0,
+            ack_block_time: 0,
+        };
+        PONG_FROM_DESTINATION.save(storage, request_id, &pong)?;
+    }
+    Ok(())
+}
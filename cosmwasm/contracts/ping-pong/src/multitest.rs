@@ -0,0 +1,169 @@
+use cosmwasm_std::{Addr, Binary, DepsMut, Env, MessageInfo, Response};
+use cw_multi_test::{Contract, ContractWrapper, Executor};
+use new_crosstalk_sample::ping_pong::{
+    ExecuteMsg, InstantiateMsg, PongRecord, QueryMsg, RouteInfo,
+};
+use router_multi_test::{
+    customize_response, outbound_requests, relay, router_app, RouterApp, RouterModule,
+};
+use router_wasm_bindings::{RouterMsg, RouterQuery};
+
+use crate::contract::{execute, instantiate, query, reply, sudo};
+use crate::error::ContractError;
+
+const OWNER: &str = "owner";
+const SRC_CHAIN_ID: &str = "router_9000-1";
+const DEST_CHAIN_ID: &str = "80001";
+
+fn instantiate_contract(
+    deps: DepsMut<RouterQuery>,
+    env: Env,
+    info: MessageInfo,
+    msg: InstantiateMsg,
+) -> Result<Response<RouterMsg>, ContractError> {
+    instantiate(deps, env, info, msg).map(customize_response)
+}
+
+fn ping_pong_contract() -> Box<dyn Contract<RouterMsg, RouterQuery>> {
+    let contract = ContractWrapper::new(execute, instantiate_contract, query)
+        .with_reply(reply)
+        .with_sudo(sudo);
+    Box::new(contract)
+}
+
+// deploys the ping-pong of the source chain and the one of the destination chain
+fn deploy(app: &mut RouterApp) -> (Addr, Addr) {
+    let code_id: u64 = app.store_code(ping_pong_contract());
+    let src = app
+        .instantiate_contract(
+            code_id,
+            Addr::unchecked(OWNER),
+            &InstantiateMsg {},
+            &[],
+            "ping-pong",
+            None,
+        )
+        .unwrap();
+    let dest = app
+        .instantiate_contract(
+            code_id,
+            Addr::unchecked(OWNER),
+            &InstantiateMsg {},
+            &[],
+            "ping-pong",
+            None,
+        )
+        .unwrap();
+    (src, dest)
+}
+
+fn ping_msg(ping: &str, dest_contract_address: &str) -> ExecuteMsg {
+    ExecuteMsg::IPing {
+        ping: ping.to_string(),
+        dest_contract_address: dest_contract_address.to_string(),
+        dest_chain_id: DEST_CHAIN_ID.to_string(),
+        request_metadata: Binary(vec![]),
+        body: None,
+        recipient: None,
+    }
+}
+
+fn fetch_routes(app: &RouterApp, contract: &Addr) -> Vec<RouteInfo> {
+    let msg: QueryMsg = QueryMsg::ListRoutes {
+        start_after: None,
+        limit: None,
+    };
+    app.wrap().query_wasm_smart(contract, &msg).unwrap()
+}
+
+#[test]
+fn test_ping_pong_round_trip() {
+    let mut app: RouterApp = router_app(RouterModule::new());
+    let (src, dest) = deploy(&mut app);
+
+    let res = app
+        .execute_contract(
+            Addr::unchecked(OWNER),
+            src.clone(),
+            &ping_msg("hello", dest.as_str()),
+            &[],
+        )
+        .unwrap();
+    let requests = outbound_requests(&res).unwrap();
+    assert_eq!(requests.len(), 1);
+    assert_eq!(requests[0].sender, src);
+    assert_eq!(requests[0].request_identifier, 1);
+    assert_eq!(requests[0].dest_chain_id, DEST_CHAIN_ID);
+    assert_eq!(
+        requests[0].request_packet.dest_contract_address,
+        dest.to_string()
+    );
+
+    let delivery = relay(&mut app, &requests[0], SRC_CHAIN_ID).unwrap();
+    assert!(delivery.receive.is_ok());
+
+    let ping: String = app
+        .wrap()
+        .query_wasm_smart(
+            &dest,
+            &QueryMsg::FetchPing {
+                chain_id: SRC_CHAIN_ID.to_string(),
+                request_id: 1,
+            },
+        )
+        .unwrap();
+    assert_eq!(ping, "hello");
+
+    let pong: PongRecord = app
+        .wrap()
+        .query_wasm_smart(&src, &QueryMsg::FetchPong { request_id: 1 })
+        .unwrap();
+    assert_eq!(pong.request_id, 1);
+    assert_eq!(pong.message, "hello");
+    assert_eq!(pong.dest_block_height, app.block_info().height);
+
+    let routes = fetch_routes(&app, &src);
+    assert_eq!(routes.len(), 1);
+    assert_eq!(routes[0].sent, 1);
+    assert_eq!(routes[0].acked, 1);
+    assert_eq!(routes[0].succeeded, 1);
+}
+
+#[test]
+fn test_ping_pong_failed_delivery() {
+    let mut app: RouterApp = router_app(RouterModule::new());
+    let (src, _dest) = deploy(&mut app);
+
+    // nothing is deployed at the destination address, the request fails
+    // and the ack is correlated through the request identifier only
+    let res = app
+        .execute_contract(
+            Addr::unchecked(OWNER),
+            src.clone(),
+            &ping_msg("hello", "contract404"),
+            &[],
+        )
+        .unwrap();
+    let requests = outbound_requests(&res).unwrap();
+    let delivery = relay(&mut app, &requests[0], SRC_CHAIN_ID).unwrap();
+    assert!(delivery.receive.is_err());
+
+    let status = delivery
+        .ack
+        .events
+        .iter()
+        .find(|event| event.ty == "wasm-ExecutionStatus")
+        .unwrap();
+    assert!(status
+        .attributes
+        .iter()
+        .any(|attribute| attribute.key == "requestIdentifier" && attribute.value == "1"));
+
+    let routes = fetch_routes(&app, &src);
+    assert_eq!(routes[0].acked, 1);
+    assert_eq!(routes[0].succeeded, 0);
+    let pong: Result<PongRecord, _> = app
+        .wrap()
+        .query_wasm_smart(&src, &QueryMsg::FetchPong { request_id: 1 });
+    assert!(pong.is_err());
+}
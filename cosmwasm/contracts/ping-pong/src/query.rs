@@ -1,9 +1,18 @@
-use cosmwasm_std::{to_binary, Binary, Deps, Env, StdResult};
+use cosmwasm_std::{to_binary, Binary, Decimal, Deps, Env, Order, StdResult};
 use cw2::get_contract_version;
-use new_crosstalk_sample::ping_pong::QueryMsg;
+use cw_storage_plus::Bound;
+use new_crosstalk_sample::ping_pong::{
+    FanoutInfo, PongRecord, QueryMsg, RouteInfo, RouteStatsResponse,
+};
 use router_wasm_bindings::RouterQuery;
 
-use crate::state::{PING_FROM_SOURCE, PONG_FROM_DESTINATION, REQUEST_ID};
+use crate::state::{
+    RouteSample, FANOUTS, PING_FROM_SOURCE, PONG_FROM_DESTINATION, REQUEST_ID, ROUTES,
+    ROUTE_SAMPLES, ROUTE_STATS_WINDOW,
+};
+
+const DEFAULT_LIMIT: u32 = 10;
+const MAX_LIMIT: u32 = 30;
 
 pub fn handle_query(deps: Deps<RouterQuery>, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
     match msg {
@@ -14,6 +23,13 @@ pub fn handle_query(deps: Deps<RouterQuery>, _env: Env, msg: QueryMsg) -> StdRes
         } => to_binary(&fetch_ping(deps, &chain_id, request_id)?),
         QueryMsg::FetchPong { request_id } => to_binary(&fetch_pong(deps, request_id)?),
         QueryMsg::FetchRequestId {} => to_binary(&fetch_request_id(deps)?),
+        QueryMsg::RouteStats { dest_chain_id } => {
+            to_binary(&fetch_route_stats(deps, &dest_chain_id)?)
+        }
+        QueryMsg::ListRoutes { start_after, limit } => {
+            to_binary(&fetch_routes(deps, start_after, limit)?)
+        }
+        QueryMsg::FetchFanout { fanout_id } => to_binary(&fetch_fanout(deps, fanout_id)?),
     }
 }
 
@@ -21,10 +37,85 @@ pub fn fetch_ping(deps: Deps<RouterQuery>, chain_id: &str, request_id: u64) -> S
     PING_FROM_SOURCE.load(deps.storage, (chain_id, request_id))
 }
 
-pub fn fetch_pong(deps: Deps<RouterQuery>, request_id: u64) -> StdResult<String> {
-    PONG_FROM_DESTINATION.load(deps.storage, &request_id.to_string())
+pub fn fetch_pong(deps: Deps<RouterQuery>, request_id: u64) -> StdResult<PongRecord> {
+    PONG_FROM_DESTINATION.load(deps.storage, request_id)
 }
 
 pub fn fetch_request_id(deps: Deps<RouterQuery>) -> StdResult<u64> {
     REQUEST_ID.load(deps.storage)
 }
+
+pub fn fetch_route_stats(
+    deps: Deps<RouterQuery>,
+    dest_chain_id: &str,
+) -> StdResult<RouteStatsResponse> {
+    let samples: Vec<RouteSample> = ROUTE_SAMPLES
+        .prefix(dest_chain_id)
+        .range(deps.storage, None, None, Order::Descending)
+        .take(ROUTE_STATS_WINDOW as usize)
+        .map(|item| item.map(|(_, sample)| sample))
+        .collect::<StdResult<Vec<RouteSample>>>()?;
+
+    // failed acks are counted apart, their round trip time includes the
+    // failed execution on the destination chain
+    let mut acked: u64 = 0;
+    let mut rtts: Vec<u64> = vec![];
+    for sample in samples.iter() {
+        if let Some(acked_at) = sample.acked_at {
+            acked += 1;
+            if sample.exec_flag == Some(true) {
+                rtts.push(acked_at.saturating_sub(sample.sent_at));
+            }
+        }
+    }
+    let succeeded: u64 = rtts.len() as u64;
+    let success_rate: Decimal = if acked == 0 {
+        Decimal::zero()
+    } else {
+        Decimal::from_ratio(succeeded, acked)
+    };
+
+    rtts.sort_unstable();
+    let (min_rtt, avg_rtt, p95_rtt) = if rtts.is_empty() {
+        (None, None, None)
+    } else {
+        // nearest-rank percentile
+        let p95_index: usize = (rtts.len() * 95).div_ceil(100) - 1;
+        (
+            Some(rtts[0]),
+            Some(rtts.iter().sum::<u64>() / succeeded),
+            Some(rtts[p95_index]),
+        )
+    };
+
+    Ok(RouteStatsResponse {
+        dest_chain_id: dest_chain_id.to_string(),
+        window: ROUTE_STATS_WINDOW,
+        count: samples.len() as u64,
+        acked,
+        succeeded,
+        failed: acked - succeeded,
+        success_rate,
+        min_rtt,
+        avg_rtt,
+        p95_rtt,
+    })
+}
+
+pub fn fetch_routes(
+    deps: Deps<RouterQuery>,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<Vec<RouteInfo>> {
+    let limit: usize = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.as_deref().map(Bound::exclusive);
+    ROUTES
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| item.map(|(_, route)| route))
+        .collect()
+}
+
+pub fn fetch_fanout(deps: Deps<RouterQuery>, fanout_id: u64) -> StdResult<FanoutInfo> {
+    FANOUTS.load(deps.storage, fanout_id)
+}
@@ -1,10 +1,52 @@
+use crate::{Deserialize, Serialize};
 use cw_storage_plus::{Item, Map};
+use new_crosstalk_sample::crosstalk::RequestTracker;
+use new_crosstalk_sample::ping_pong::{FanoutInfo, PongRecord, RouteInfo};
+use schemars::JsonSchema;
 
 pub const CREATE_I_SEND_REQUEST: u64 = 1;
+// reply ids of the i_ping_many submessages are I_PING_MANY_REPLY_ID_OFFSET + requestId
+pub const I_PING_MANY_REPLY_ID_OFFSET: u64 = 1 << 32;
+
+pub const MAX_FANOUT_DESTINATIONS: usize = 20;
 // PingMapping (src_chain_id, requestId) => pingFromSource
 pub const PING_FROM_SOURCE: Map<(&str, u64), String> = Map::new("ping_from_source");
 
-// PongMapping requestId => pongFromSource
-pub const PONG_FROM_DESTINATION: Map<&str, String> = Map::new("pong_from_destination");
+// PongMapping requestId => pongFromDestination
+pub const PONG_FROM_DESTINATION: Map<u64, PongRecord> = Map::new("pong_records");
+
+// PongMapping requestId.to_string() => pong message, replaced by PONG_FROM_DESTINATION in 0.2.0
+pub const LEGACY_PONG_FROM_DESTINATION: Map<&str, String> = Map::new("pong_from_destination");
 
 pub const REQUEST_ID: Item<u64> = Item::new("request_id");
+
+// number of most recent pings per route used to compute the route statistics
+pub const ROUTE_STATS_WINDOW: u64 = 100;
+
+// dest_chain_id => lifetime counters of the route
+pub const ROUTES: Map<&str, RouteInfo> = Map::new("routes");
+
+// (dest_chain_id, requestId) => round trip sample, only the last ROUTE_STATS_WINDOW are kept
+pub const ROUTE_SAMPLES: Map<(&str, u64), RouteSample> = Map::new("route_samples");
+
+// requestId => dest_chain_id of the pings which are not acked yet
+pub const PENDING_PINGS: Map<u64, String> = Map::new("pending_pings");
+
+// gateway request_identifier => requestId, filled in the reply of the i_send submessage
+pub const REQUEST_TRACKER: RequestTracker =
+    RequestTracker::new("pending_replies", "request_identifier_mapping");
+
+pub const FANOUT_ID: Item<u64> = Item::new("fanout_id");
+
+// fanoutId => fan-out ping info
+pub const FANOUTS: Map<u64, FanoutInfo> = Map::new("fanouts");
+
+// requestId => fanoutId of the fan-out pings which are not acked yet
+pub const REQUEST_FANOUT: Map<u64, u64> = Map::new("request_fanout");
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct RouteSample {
+    pub sent_at: u64,
+    pub acked_at: Option<u64>,
+    pub exec_flag: Option<bool>,
+}
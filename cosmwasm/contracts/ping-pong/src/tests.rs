@@ -1,15 +1,29 @@
 use std::marker::PhantomData;
 
 use crate::contract::instantiate;
-use crate::contract::{execute, sudo};
+use crate::contract::{execute, migrate, reply, sudo};
+use crate::error::ContractError;
+use crate::query::{
+    fetch_fanout, fetch_ping, fetch_pong, fetch_request_id, fetch_route_stats, fetch_routes,
+};
+use crate::state::{
+    CREATE_I_SEND_REQUEST, I_PING_MANY_REPLY_ID_OFFSET, LEGACY_PONG_FROM_DESTINATION, PENDING_PINGS,
+};
 use cosmwasm_std::testing::{MockApi, MockQuerier, MockStorage};
 use cosmwasm_std::{
     testing::{mock_env, mock_info},
     DepsMut,
 };
-use cosmwasm_std::{Binary, Coin, CosmosMsg, OwnedDeps, Uint128};
-use new_crosstalk_sample::ping_pong::{ExecuteMsg, InstantiateMsg};
-use router_wasm_bindings::ethabi::{decode, ParamType};
+use cosmwasm_std::{
+    Binary, Coin, CosmosMsg, Decimal, Env, Order, OwnedDeps, Reply, StdError, SubMsgResponse,
+    SubMsgResult, Uint128,
+};
+use new_crosstalk_sample::migration::MigrationError;
+use new_crosstalk_sample::ping_pong::{
+    ExecuteMsg, InstantiateMsg, MigrateMsg, PingPayload, PongReceipt, PongRecord,
+    LEGACY_PING_PAYLOAD_VERSION, PING_PAYLOAD_VERSION, PONG_RECEIPT_VERSION,
+};
+use router_wasm_bindings::ethabi::{decode, encode, ethereum_types::U256, ParamType, Token};
 use router_wasm_bindings::types::{AckType, ChainType, RequestMetaData};
 use router_wasm_bindings::utils::convert_address_from_string_to_bytes;
 use router_wasm_bindings::{Bytes, RouterMsg, RouterQuery, SudoMsg};
@@ -46,8 +60,16 @@ fn test_sudo_inbound_function() {
     };
     do_instantiate(deps.as_mut());
     let env = mock_env();
-    let payload: Bytes = hex::decode("0000000000000000000000000000000000000000000000000000000000000020000000000000000000000000000000000000000000000000000000000000008000000000000000000000000000000000000000000000000000000000000000010000000000000000000000000000000000000000000000000000000000000040000000000000000000000000000000000000000000000000000000000000000a68656c6c6f2070696e6700000000000000000000000000000000000000000000").unwrap();
-    let binary: Binary = Binary(payload);
+    let ping_payload: PingPayload = PingPayload {
+        version: PING_PAYLOAD_VERSION,
+        request_id: 1,
+        timestamp: 1_700_000_000,
+        sender: Binary(BRIDGE_ADDRESS.as_bytes().to_vec()),
+        message: String::from("hello ping"),
+        body: Binary(vec![1, 2, 3]),
+        recipient: String::from("alice"),
+    };
+    let binary: Binary = Binary(ping_payload.encode().unwrap());
     let msg: SudoMsg = SudoMsg::HandleIReceive {
         request_sender: BRIDGE_ADDRESS.into(),
         src_chain_id: String::from("80001"),
@@ -63,6 +85,100 @@ fn test_sudo_inbound_function() {
     }
     let response = result.unwrap();
     assert_eq!(response.messages.len(), 0);
+    assert!(response
+        .attributes
+        .iter()
+        .any(|attr| attr.key == "recipient" && attr.value == "alice"));
+
+    let receipt: PongReceipt = PongReceipt::decode(&response.data.unwrap().0).unwrap();
+    assert_eq!(receipt.version, PONG_RECEIPT_VERSION);
+    assert_eq!(receipt.request_id, 1);
+    assert_eq!(receipt.src_timestamp, 1_700_000_000);
+    assert_eq!(receipt.dest_block_height, mock_env().block.height);
+    assert_eq!(receipt.dest_block_time, mock_env().block.time.seconds());
+    assert_eq!(receipt.message, "hello ping");
+    assert_eq!(
+        fetch_ping(deps.as_ref(), "80001", 1).unwrap(),
+        String::from("hello ping")
+    );
+}
+
+#[test]
+fn test_sudo_inbound_accepts_every_payload_version() {
+    let mut deps = OwnedDeps {
+        storage: MockStorage::default(),
+        api: MockApi::default(),
+        querier: MockQuerier::default(),
+        custom_query_type: PhantomData,
+    };
+    do_instantiate(deps.as_mut());
+    let env = mock_env();
+    let v1_payload: PingPayload = PingPayload {
+        version: 1,
+        request_id: 1,
+        timestamp: 1_700_000_000,
+        sender: Binary(BRIDGE_ADDRESS.as_bytes().to_vec()),
+        message: String::from("v1 ping"),
+        body: Binary(vec![]),
+        recipient: String::new(),
+    };
+    let payloads: Vec<(u64, Bytes)> = vec![
+        (1, v1_payload.encode().unwrap()),
+        (
+            2,
+            encode(&[
+                Token::Uint(U256::from(2u64)),
+                Token::String(String::from("legacy ping")),
+            ]),
+        ),
+        (
+            3,
+            encode(&[
+                Token::Uint(U256::from(3u64)),
+                Token::String(String::from("near ping")),
+                Token::String(String::from("alice.near")),
+            ]),
+        ),
+    ];
+    for (request_id, payload) in payloads {
+        let msg: SudoMsg = SudoMsg::HandleIReceive {
+            request_sender: BRIDGE_ADDRESS.into(),
+            src_chain_id: String::from("80001"),
+            request_identifier: request_id,
+            payload: Binary(payload),
+        };
+        let response = sudo(deps.as_mut(), env.clone(), msg).unwrap();
+        let receipt: PongReceipt = PongReceipt::decode(&response.data.unwrap().0).unwrap();
+        assert_eq!(receipt.request_id, request_id);
+    }
+    assert_eq!(fetch_ping(deps.as_ref(), "80001", 1).unwrap(), "v1 ping");
+    assert_eq!(
+        fetch_ping(deps.as_ref(), "80001", 2).unwrap(),
+        "legacy ping"
+    );
+    assert_eq!(fetch_ping(deps.as_ref(), "80001", 3).unwrap(), "near ping");
+
+    let legacy: PingPayload = PingPayload::decode(&encode(&[
+        Token::Uint(U256::from(3u64)),
+        Token::String(String::from("near ping")),
+        Token::String(String::from("alice.near")),
+    ]))
+    .unwrap();
+    assert_eq!(legacy.version, LEGACY_PING_PAYLOAD_VERSION);
+    assert_eq!(legacy.recipient, "alice.near");
+    assert_eq!(
+        PingPayload::decode(&[1, 2, 3]).unwrap_err(),
+        StdError::generic_err("PingPayload: invalid data")
+    );
+
+    let msg: SudoMsg = SudoMsg::HandleIReceive {
+        request_sender: BRIDGE_ADDRESS.into(),
+        src_chain_id: String::from("80001"),
+        request_identifier: 4,
+        payload: Binary(vec![1, 2, 3]),
+    };
+    let err = sudo(deps.as_mut(), env, msg).unwrap_err();
+    assert!(matches!(err, ContractError::Decode { .. }));
 }
 
 #[test]
@@ -76,8 +192,15 @@ fn test_sudo_outbound_ack_function() {
     do_instantiate(deps.as_mut());
     let env = mock_env();
 
-    let payload: Bytes = hex::decode("0000000000000000000000000000000000000000000000000000000000000020000000000000000000000000000000000000000000000000000000000000008000000000000000000000000000000000000000000000000000000000000000010000000000000000000000000000000000000000000000000000000000000040000000000000000000000000000000000000000000000000000000000000000a68656c6c6f2070696e6700000000000000000000000000000000000000000000").unwrap();
-    let binary_data: Binary = Binary(payload);
+    let receipt: PongReceipt = PongReceipt {
+        version: PONG_RECEIPT_VERSION,
+        request_id: 1,
+        src_timestamp: env.block.time.seconds() - 30,
+        dest_block_height: 100,
+        dest_block_time: env.block.time.seconds() - 10,
+        message: String::from("hello ping"),
+    };
+    let binary_data: Binary = Binary(receipt.encode().unwrap());
     let msg: SudoMsg = SudoMsg::HandleIAck {
         request_identifier: 1,
         exec_flag: true,
@@ -85,7 +208,7 @@ fn test_sudo_outbound_ack_function() {
         refund_amount: Coin::new(123u128, String::from("route")),
     };
 
-    let result = sudo(deps.as_mut(), env, msg);
+    let result = sudo(deps.as_mut(), env.clone(), msg);
     if result.is_err() {
         println!("{:?}", result.as_ref().err());
         assert!(false);
@@ -93,6 +216,64 @@ fn test_sudo_outbound_ack_function() {
     }
     let response = result.unwrap();
     assert_eq!(response.messages.len(), 0);
+
+    let pong: PongRecord = fetch_pong(deps.as_ref(), 1).unwrap();
+    assert_eq!(
+        pong,
+        PongRecord {
+            request_id: 1,
+            message: String::from("hello ping"),
+            src_timestamp: env.block.time.seconds() - 30,
+            dest_block_height: 100,
+            dest_block_time: env.block.time.seconds() - 10,
+            ack_block_height: env.block.height,
+            ack_block_time: env.block.time.seconds(),
+        }
+    );
+}
+
+#[test]
+fn test_sudo_outbound_ack_with_echoed_ping() {
+    let mut deps = OwnedDeps {
+        storage: MockStorage::default(),
+        api: MockApi::default(),
+        querier: MockQuerier::default(),
+        custom_query_type: PhantomData,
+    };
+    do_instantiate(deps.as_mut());
+    let env = mock_env();
+
+    // the NEAR and ink! dapps ack with the ping payload they received
+    let ping_payload: PingPayload = PingPayload {
+        version: PING_PAYLOAD_VERSION,
+        request_id: 1,
+        timestamp: env.block.time.seconds() - 30,
+        sender: Binary(INIT_ADDRESS.as_bytes().to_vec()),
+        message: String::from("hello ping"),
+        body: Binary(vec![]),
+        recipient: String::new(),
+    };
+    let msg: SudoMsg = SudoMsg::HandleIAck {
+        request_identifier: 1,
+        exec_flag: true,
+        exec_data: Binary(ping_payload.encode().unwrap()),
+        refund_amount: Coin::new(0u128, String::from("route")),
+    };
+    sudo(deps.as_mut(), env.clone(), msg).unwrap();
+
+    let pong: PongRecord = fetch_pong(deps.as_ref(), 1).unwrap();
+    assert_eq!(pong.message, "hello ping");
+    assert_eq!(pong.src_timestamp, env.block.time.seconds() - 30);
+    assert_eq!(pong.dest_block_height, 0);
+
+    let msg: SudoMsg = SudoMsg::HandleIAck {
+        request_identifier: 2,
+        exec_flag: true,
+        exec_data: Binary(vec![1, 2, 3]),
+        refund_amount: Coin::new(0u128, String::from("route")),
+    };
+    let err = sudo(deps.as_mut(), env, msg).unwrap_err();
+    assert!(matches!(err, ContractError::Decode { .. }));
 }
 
 #[test]
@@ -112,9 +293,11 @@ fn test_execute_create_outbound_request() {
         dest_chain_id: String::from("80001"),
         ping: greeting,
         request_metadata: Binary(vec![]),
+        body: Some(Binary(vec![0xde, 0xad])),
+        recipient: Some(String::from("alice")),
     };
     let info = mock_info(INIT_ADDRESS, &[]);
-    let response = execute(deps.as_mut(), env, info, msg).unwrap();
+    let response = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
     assert_eq!(response.messages.len(), 1);
 
     let message = response.messages.get(0).unwrap();
@@ -135,13 +318,256 @@ fn test_execute_create_outbound_request() {
                 assert_eq!(dest_chain_id, "80001");
                 assert_eq!(version, 1);
                 assert_eq!(hex::encode(request_metadata), "");
-                assert_eq!(hex::encode(request_packet), "000000000000000000000000000000000000000000000000000000000000004000000000000000000000000000000000000000000000000000000000000000a0000000000000000000000000000000000000000000000000000000000000002a30786565646233616236386435363761366364366431396661383139666537376239663865643135333800000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000008000000000000000000000000000000000000000000000000000000000000000010000000000000000000000000000000000000000000000000000000000000040000000000000000000000000000000000000000000000000000000000000001048656c6c6f204c6f72642056656e6b7900000000000000000000000000000000");
+                let tokens =
+                    decode(&[ParamType::String, ParamType::Bytes], &request_packet).unwrap();
+                assert_eq!(tokens[0].clone().into_string().unwrap(), BRIDGE_ADDRESS);
+                let ping_payload: PingPayload =
+                    PingPayload::decode(&tokens[1].clone().into_bytes().unwrap()).unwrap();
+                assert_eq!(
+                    ping_payload,
+                    PingPayload {
+                        version: PING_PAYLOAD_VERSION,
+                        request_id: 1,
+                        timestamp: env.block.time.seconds(),
+                        sender: Binary(INIT_ADDRESS.as_bytes().to_vec()),
+                        message: String::from("Hello Lord Venky"),
+                        body: Binary(vec![0xde, 0xad]),
+                        recipient: String::from("alice"),
+                    }
+                );
             }
         },
         _ => {}
     }
 }
 
+fn do_ping(deps: DepsMut<RouterQuery>, env: Env, dest_chain_id: &str) {
+    let msg: ExecuteMsg = ExecuteMsg::IPing {
+        dest_contract_address: String::from(BRIDGE_ADDRESS),
+        dest_chain_id: String::from(dest_chain_id),
+        ping: String::from("ping"),
+        request_metadata: Binary(vec![]),
+        body: None,
+        recipient: None,
+    };
+    let info = mock_info(INIT_ADDRESS, &[]);
+    execute(deps, env, info, msg).unwrap();
+}
+
+#[test]
+fn test_route_stats() {
+    let mut deps = OwnedDeps {
+        storage: MockStorage::default(),
+        api: MockApi::default(),
+        querier: MockQuerier::default(),
+        custom_query_type: PhantomData,
+    };
+    do_instantiate(deps.as_mut());
+    let env = mock_env();
+
+    do_ping(deps.as_mut(), env.clone(), "80001");
+    do_ping(deps.as_mut(), env.clone(), "80001");
+    // gateway assigns request_identifier 7 to the second ping
+    let reply_msg: Reply = Reply {
+        id: CREATE_I_SEND_REQUEST,
+        result: SubMsgResult::Ok(SubMsgResponse {
+            events: vec![],
+            data: Some(Binary::from(
+                br#"{"request_identifier":7,"fee_deducted":"0"}"#.to_vec(),
+            )),
+        }),
+    };
+    reply(deps.as_mut(), env.clone(), reply_msg).unwrap();
+    do_ping(deps.as_mut(), env.clone(), "43113");
+
+    // first ping is acked successfully after 30 seconds
+    let mut ack_env = env.clone();
+    ack_env.block.time = env.block.time.plus_seconds(30);
+    let receipt: PongReceipt = PongReceipt {
+        version: PONG_RECEIPT_VERSION,
+        request_id: 1,
+        src_timestamp: env.block.time.seconds(),
+        dest_block_height: 100,
+        dest_block_time: env.block.time.seconds() + 10,
+        message: String::from("ping"),
+    };
+    let msg: SudoMsg = SudoMsg::HandleIAck {
+        request_identifier: 6,
+        exec_flag: true,
+        exec_data: Binary(receipt.encode().unwrap()),
+        refund_amount: Coin::new(0u128, String::from("route")),
+    };
+    sudo(deps.as_mut(), ack_env.clone(), msg).unwrap();
+
+    // second ping fails on the destination and is acked after 50 seconds
+    ack_env.block.time = env.block.time.plus_seconds(50);
+    let msg: SudoMsg = SudoMsg::HandleIAck {
+        request_identifier: 7,
+        exec_flag: false,
+        exec_data: Binary(b"execution reverted".to_vec()),
+        refund_amount: Coin::new(0u128, String::from("route")),
+    };
+    let response = sudo(deps.as_mut(), ack_env, msg).unwrap();
+    assert!(!response.events[0]
+        .attributes
+        .iter()
+        .any(|attr| attr.key == "roundTripTime"));
+    assert!(PENDING_PINGS.may_load(&deps.storage, 1).unwrap().is_none());
+    assert!(PENDING_PINGS.may_load(&deps.storage, 2).unwrap().is_none());
+
+    // the round trip time of the failed ack is left out of the stats
+    let stats = fetch_route_stats(deps.as_ref(), "80001").unwrap();
+    assert_eq!(stats.count, 2);
+    assert_eq!(stats.acked, 2);
+    assert_eq!(stats.succeeded, 1);
+    assert_eq!(stats.failed, 1);
+    assert_eq!(stats.success_rate, Decimal::percent(50));
+    assert_eq!(stats.min_rtt, Some(30));
+    assert_eq!(stats.avg_rtt, Some(30));
+    assert_eq!(stats.p95_rtt, Some(30));
+
+    let stats = fetch_route_stats(deps.as_ref(), "43113").unwrap();
+    assert_eq!(stats.count, 1);
+    assert_eq!(stats.acked, 0);
+    assert_eq!(stats.success_rate, Decimal::zero());
+    assert_eq!(stats.min_rtt, None);
+
+    let routes = fetch_routes(deps.as_ref(), None, None).unwrap();
+    assert_eq!(routes.len(), 2);
+    assert_eq!(routes[0].dest_chain_id, "43113");
+    assert_eq!(routes[1].dest_chain_id, "80001");
+    assert_eq!(routes[1].sent, 2);
+    assert_eq!(routes[1].acked, 2);
+    assert_eq!(routes[1].succeeded, 1);
+
+    let routes = fetch_routes(deps.as_ref(), Some(String::from("43113")), None).unwrap();
+    assert_eq!(routes.len(), 1);
+    assert_eq!(routes[0].dest_chain_id, "80001");
+}
+
+fn ack_receipt(request_id: u64, env: &Env) -> Binary {
+    let receipt: PongReceipt = PongReceipt {
+        version: PONG_RECEIPT_VERSION,
+        request_id,
+        src_timestamp: env.block.time.seconds(),
+        dest_block_height: 100,
+        dest_block_time: env.block.time.seconds(),
+        message: String::from("ping"),
+    };
+    Binary(receipt.encode().unwrap())
+}
+
+#[test]
+fn test_i_ping_many() {
+    let mut deps = OwnedDeps {
+        storage: MockStorage::default(),
+        api: MockApi::default(),
+        querier: MockQuerier::default(),
+        custom_query_type: PhantomData,
+    };
+    do_instantiate(deps.as_mut());
+    let env = mock_env();
+    let info = mock_info(INIT_ADDRESS, &[]);
+
+    let msg: ExecuteMsg = ExecuteMsg::IPingMany {
+        ping: String::from("ping"),
+        destinations: vec![],
+    };
+    let err = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap_err();
+    assert!(matches!(err, ContractError::NoDestinations {}));
+
+    let msg: ExecuteMsg = ExecuteMsg::IPingMany {
+        ping: String::from("ping"),
+        destinations: vec![
+            (
+                String::from("80001"),
+                String::from(BRIDGE_ADDRESS),
+                Binary(vec![]),
+            ),
+            (
+                String::from("43113"),
+                String::from(BRIDGE_ADDRESS),
+                Binary(vec![]),
+            ),
+            (
+                String::from("5"),
+                String::from(BRIDGE_ADDRESS),
+                Binary(vec![]),
+            ),
+        ],
+    };
+    let response = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+    assert_eq!(response.messages.len(), 3);
+    for (i, message) in response.messages.iter().enumerate() {
+        assert_eq!(message.id, I_PING_MANY_REPLY_ID_OFFSET + i as u64 + 1);
+    }
+    assert_eq!(fetch_request_id(deps.as_ref()).unwrap(), 3);
+
+    // gateway assigns request_identifier 11 to the ping sent to 43113
+    for (reply_id, request_identifier) in [(1u64, 21u64), (2u64, 11u64), (3u64, 23u64)] {
+        let reply_msg: Reply = Reply {
+            id: I_PING_MANY_REPLY_ID_OFFSET + reply_id,
+            result: SubMsgResult::Ok(SubMsgResponse {
+                events: vec![],
+                data: Some(Binary::from(
+                    format!(
+                        r#"{{"request_identifier":{},"fee_deducted":"0"}}"#,
+                        request_identifier
+                    )
+                    .into_bytes(),
+                )),
+            }),
+        };
+        reply(deps.as_mut(), env.clone(), reply_msg).unwrap();
+    }
+
+    // the request id echoed by the destination has to match the tracked one
+    let msg: SudoMsg = SudoMsg::HandleIAck {
+        request_identifier: 21,
+        exec_flag: true,
+        exec_data: ack_receipt(3, &env),
+        refund_amount: Coin::new(0u128, String::from("route")),
+    };
+    let err = sudo(deps.as_mut(), env.clone(), msg).unwrap_err();
+    assert!(matches!(
+        err,
+        ContractError::RequestIdMismatch {
+            expected: 1,
+            received: 3
+        }
+    ));
+
+    for request_id in [1u64, 3u64] {
+        let msg: SudoMsg = SudoMsg::HandleIAck {
+            request_identifier: 20 + request_id,
+            exec_flag: true,
+            exec_data: ack_receipt(request_id, &env),
+            refund_amount: Coin::new(0u128, String::from("route")),
+        };
+        let response = sudo(deps.as_mut(), env.clone(), msg).unwrap();
+        assert_eq!(response.events.len(), 1);
+    }
+    let fanout = fetch_fanout(deps.as_ref(), 1).unwrap();
+    assert_eq!(fanout.request_ids, vec![1, 2, 3]);
+    assert_eq!(fanout.acked, 2);
+    assert!(!fanout.completed);
+
+    let msg: SudoMsg = SudoMsg::HandleIAck {
+        request_identifier: 11,
+        exec_flag: false,
+        exec_data: Binary(vec![]),
+        refund_amount: Coin::new(0u128, String::from("route")),
+    };
+    let response = sudo(deps.as_mut(), env, msg).unwrap();
+    assert_eq!(response.events.len(), 2);
+    assert_eq!(response.events[1].ty, "FanoutCompleted");
+
+    let fanout = fetch_fanout(deps.as_ref(), 1).unwrap();
+    assert_eq!(fanout.acked, 3);
+    assert_eq!(fanout.succeeded, 2);
+    assert!(fanout.completed);
+}
+
 #[test]
 fn test_encode() {
     let addr: String =
@@ -172,3 +598,61 @@ fn test_encode() {
     };
     println!("{:?}", Binary(rm.get_abi_encoded_bytes()).to_base64());
 }
+
+#[test]
+fn test_migrate() {
+    let mut deps = OwnedDeps {
+        storage: MockStorage::default(),
+        api: MockApi::default(),
+        querier: MockQuerier::default(),
+        custom_query_type: PhantomData,
+    };
+    do_instantiate(deps.as_mut());
+    let env = mock_env();
+
+    // fabricate a 0.1.0 deployment with string keyed pongs
+    cw2::set_contract_version(deps.as_mut().storage, "PingPong", "0.1.0").unwrap();
+    LEGACY_PONG_FROM_DESTINATION
+        .save(deps.as_mut().storage, "1", &String::from("pong 1"))
+        .unwrap();
+    LEGACY_PONG_FROM_DESTINATION
+        .save(deps.as_mut().storage, "12", &String::from("pong 12"))
+        .unwrap();
+
+    let res = migrate(deps.as_mut(), env.clone(), MigrateMsg::default()).unwrap();
+    assert_eq!(res.attributes[2].value, "0.2.0");
+    assert_eq!(fetch_pong(deps.as_ref(), 1).unwrap().message, "pong 1");
+    assert_eq!(fetch_pong(deps.as_ref(), 12).unwrap().message, "pong 12");
+    assert!(LEGACY_PONG_FROM_DESTINATION
+        .keys(deps.as_ref().storage, None, None, Order::Ascending)
+        .next()
+        .is_none());
+    let version = cw2::get_contract_version(deps.as_ref().storage).unwrap();
+    assert_eq!(version.version, "0.2.0");
+
+    // migrating again to the same version applies no step
+    let res = migrate(deps.as_mut(), env.clone(), MigrateMsg::default()).unwrap();
+    assert_eq!(res.attributes[2].value, "");
+
+    // "0.10.0" > "0.2.0" in semver, not in string order
+    cw2::set_contract_version(deps.as_mut().storage, "PingPong", "0.10.0").unwrap();
+    let err = migrate(deps.as_mut(), env.clone(), MigrateMsg::default()).unwrap_err();
+    assert!(matches!(
+        err,
+        ContractError::Migration(MigrationError::Downgrade { .. })
+    ));
+
+    cw2::set_contract_version(deps.as_mut().storage, "PingPong", "latest").unwrap();
+    let err = migrate(deps.as_mut(), env.clone(), MigrateMsg::default()).unwrap_err();
+    assert!(matches!(
+        err,
+        ContractError::Migration(MigrationError::InvalidVersion { .. })
+    ));
+
+    cw2::set_contract_version(deps.as_mut().storage, "XERC20", "0.1.0").unwrap();
+    let err = migrate(deps.as_mut(), env, MigrateMsg::default()).unwrap_err();
+    assert!(matches!(
+        err,
+        ContractError::Migration(MigrationError::InvalidContract { .. })
+    ));
+}
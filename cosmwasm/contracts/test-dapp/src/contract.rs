@@ -1,63 +1,89 @@
-use crate::execution::send_i_request;
+use crate::error::ContractError;
+use crate::execution::{query_remote, send_i_request, set_route_denom};
+use crate::migrations::MIGRATIONS;
 use crate::query::handle_query;
-use crate::state::{CREATE_I_SEND_REQUEST, PING_FROM_SOURCE, PONG_FROM_DESTINATION, REQUEST_ID};
+use crate::state::{
+    CREATE_READ_CALL_REQUEST, DEFAULT_ROUTE_DENOM, OWNER, PENDING_ROUTE_REFUND, PING_FROM_SOURCE,
+    PONG_FROM_DESTINATION, READ_CALLS, REQUEST_ID, REQUEST_TRACKER, ROUTE_DENOM, ROUTE_REFUND,
+    SEND_REQUESTS,
+};
 #[cfg(not(feature = "library"))]
-use cosmwasm_std::{entry_point, Binary, Deps, DepsMut, Env, MessageInfo, Response, StdResult};
-use cosmwasm_std::{from_binary, Coin, Event, Reply, StdError, SubMsgResult};
-use new_crosstalk_sample::test_dapp::{ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg};
+use cosmwasm_std::entry_point;
+use cosmwasm_std::{BankMsg, Event, Reply, SubMsg, SubMsgResult};
+use cosmwasm_std::{Binary, Deps, DepsMut, Env, MessageInfo, Response};
+use new_crosstalk_sample::crosstalk::{
+    decode_request_message, handle_sudo, CrosstalkDapp, InboundRequest, RequestAck, RequestTracker,
+    TrackedRequest,
+};
+use new_crosstalk_sample::migration::migrate_contract;
+use new_crosstalk_sample::test_dapp::{
+    ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg, ReadCallRecord, ReadCallStatus,
+    SendRequestRecord,
+};
 
 use cw2::set_contract_version;
 
-use router_wasm_bindings::ethabi::{decode, ParamType};
-use router_wasm_bindings::types::CrosschainRequestResponse;
 use router_wasm_bindings::{RouterMsg, RouterQuery, SudoMsg};
 
 // version info for migration info
 const CONTRACT_NAME: &str = "PingPong";
-const CONTRACT_VERSION: &str = "0.1.0";
+const CONTRACT_VERSION: &str = "0.2.0";
 
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn instantiate(
     deps: DepsMut<RouterQuery>,
     _env: Env,
-    _info: MessageInfo,
-    _msg: InstantiateMsg,
-) -> StdResult<Response> {
+    info: MessageInfo,
+    msg: InstantiateMsg,
+) -> Result<Response, ContractError> {
     set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
     REQUEST_ID.save(deps.storage, &0)?;
+    OWNER.save(deps.storage, &info.sender.to_string())?;
+    let route_denom: String = msg
+        .route_denom
+        .unwrap_or_else(|| DEFAULT_ROUTE_DENOM.to_string());
+    ROUTE_DENOM.save(deps.storage, &route_denom)?;
     Ok(Response::new().add_attribute("action", "ping_pong_init"))
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn reply(deps: DepsMut<RouterQuery>, _env: Env, msg: Reply) -> StdResult<Response<RouterMsg>> {
+pub fn reply(
+    deps: DepsMut<RouterQuery>,
+    _env: Env,
+    msg: Reply,
+) -> Result<Response<RouterMsg>, ContractError> {
+    deps.api.debug(&msg.id.to_string());
+    if msg.id == ROUTE_REFUND {
+        return handle_route_refund_reply(deps, msg);
+    }
+    let tracked: TrackedRequest = REQUEST_TRACKER
+        .handle_reply(deps.storage, &msg)?
+        .ok_or(ContractError::UnknownReplyId { id: msg.id })?;
+    let request_id: u64 = tracked.request_id;
+    let request_identifier: u64 = match tracked.request_identifier {
+        Some(request_identifier) => request_identifier,
+        None => {
+            deps.api.debug("No Binary Data Found");
+            return Ok(Response::new());
+        }
+    };
+
     match msg.id {
-        CREATE_I_SEND_REQUEST => {
-            deps.api.debug(&msg.id.to_string());
-            // TODO: need to handle nonce data here, Nonce handling logic depends on the use-case.
-            let response: Response<RouterMsg> = Response::new();
-            match msg.result {
-                SubMsgResult::Ok(msg_result) => match msg_result.data {
-                    Some(binary_data) => {
-                        deps.api.debug("Binary Data Found");
-                        let cross_chain_req_res: CrosschainRequestResponse =
-                            from_binary(&binary_data).unwrap();
-
-                        let info_str: String = format!(
-                            "Binary data {:?}, response {:?}",
-                            &binary_data.to_string(),
-                            cross_chain_req_res
-                        );
-                        deps.api.debug(&info_str);
-                        return Ok(response);
-                    }
-                    None => deps.api.debug("No Binary Data Found"),
-                },
-                SubMsgResult::Err(err) => deps.api.debug(&err.to_string()),
-            }
+        CREATE_READ_CALL_REQUEST => {
+            let mut read_call: ReadCallRecord = READ_CALLS.load(deps.storage, request_id)?;
+            read_call.request_identifier = Some(request_identifier);
+            READ_CALLS.save(deps.storage, request_id, &read_call)?;
+        }
+        _ => {
+            let mut send_request: SendRequestRecord =
+                SEND_REQUESTS.load(deps.storage, request_id)?;
+            send_request.request_identifier = Some(request_identifier);
+            SEND_REQUESTS.save(deps.storage, request_id, &send_request)?;
         }
-        id => return Err(StdError::generic_err(format!("Unknown reply id: {}", id))),
     }
-    Ok(Response::new())
+    Ok(Response::new()
+        .add_attribute("request_id", request_id.to_string())
+        .add_attribute("request_identifier", request_identifier.to_string()))
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
@@ -66,7 +92,7 @@ pub fn execute(
     _env: Env,
     info: MessageInfo,
     msg: ExecuteMsg,
-) -> StdResult<Response<RouterMsg>> {
+) -> Result<Response<RouterMsg>, ContractError> {
     match msg {
         ExecuteMsg::SendIRequest {
             payload,
@@ -85,88 +111,112 @@ pub fn execute(
             amount,
             route_recipient,
         ),
+        ExecuteMsg::SetRouteDenom { denom } => set_route_denom(deps, info, denom),
+        ExecuteMsg::QueryRemote {
+            dest_chain_id,
+            dest_contract,
+            calldata,
+            request_metadata,
+        } => query_remote(
+            deps,
+            dest_chain_id,
+            dest_contract,
+            calldata,
+            request_metadata,
+        ),
     }
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn sudo(deps: DepsMut<RouterQuery>, env: Env, msg: SudoMsg) -> StdResult<Response<RouterMsg>> {
-    match msg {
-        SudoMsg::HandleIReceive {
-            request_sender,
-            src_chain_id,
-            request_identifier,
-            payload,
-        } => handle_sudo_request(
-            deps,
-            env,
-            request_sender,
-            src_chain_id,
-            request_identifier,
-            payload,
-        ),
-        SudoMsg::HandleIAck {
-            request_identifier,
-            exec_flag,
-            exec_data,
-            refund_amount,
-        } => handle_sudo_ack(
-            deps,
-            env,
-            request_identifier,
-            exec_flag,
-            exec_data,
-            refund_amount,
-        ),
+pub fn sudo(
+    deps: DepsMut<RouterQuery>,
+    env: Env,
+    msg: SudoMsg,
+) -> Result<Response<RouterMsg>, ContractError> {
+    handle_sudo::<TestDapp>(deps, env, msg)
+}
+
+pub struct TestDapp;
+
+impl CrosstalkDapp for TestDapp {
+    type Error = ContractError;
+
+    const TRACKER: Option<RequestTracker<'static>> = Some(REQUEST_TRACKER);
+
+    fn on_receive(
+        deps: DepsMut<RouterQuery>,
+        env: Env,
+        request: InboundRequest,
+    ) -> Result<Response<RouterMsg>, ContractError> {
+        handle_sudo_request(deps, env, request)
+    }
+
+    fn on_ack(
+        deps: DepsMut<RouterQuery>,
+        env: Env,
+        ack: RequestAck,
+    ) -> Result<Response<RouterMsg>, ContractError> {
+        handle_sudo_ack(deps, env, ack)
     }
 }
 
 pub fn handle_sudo_request(
     deps: DepsMut<RouterQuery>,
     _env: Env,
-    request_sender: String,
-    src_chain_id: String,
-    request_identifier: u64,
-    payload: Binary,
-) -> StdResult<Response<RouterMsg>> {
-    let token_vec = match decode(&[ParamType::Uint(64), ParamType::String], &payload.0) {
-        Ok(data) => data,
-        Err(_) => {
-            return Err(StdError::GenericErr {
-                msg: String::from("err.into()"),
-            })
-        }
-    };
-
+    request: InboundRequest,
+) -> Result<Response<RouterMsg>, ContractError> {
     deps.api.debug("Inside the Inbound handler");
-    let request_id: u64 = token_vec[0].clone().into_uint().unwrap().as_u64();
-    let data_string: String = token_vec[1].clone().into_string().unwrap();
+    let (request_id, data_string): (u64, String) = decode_request_message(&request.payload.0)?;
 
     if data_string.clone() == "Fail Dest Req".to_string() {
-        return Err(StdError::GenericErr {
-            msg: String::from("String != Fail Dest Req"),
+        return Err(ContractError::ForcedFailure {
+            reason: data_string,
         });
     }
 
-    PING_FROM_SOURCE.save(deps.storage, (&src_chain_id, request_id), &data_string)?;
+    PING_FROM_SOURCE.save(
+        deps.storage,
+        (&request.src_chain_id, request_id),
+        &data_string,
+    )?;
 
     let mut res = Response::new()
-        .add_attribute("sender", request_sender)
-        .add_attribute("request_identifier", request_identifier.to_string())
-        .add_attribute("src_chain_id", src_chain_id);
-    res.data = Some(payload);
+        .add_attribute("sender", request.request_sender)
+        .add_attribute("request_identifier", request.request_identifier.to_string())
+        .add_attribute("src_chain_id", request.src_chain_id);
+    res.data = Some(request.payload);
     Ok(res)
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn migrate(deps: DepsMut<RouterQuery>, env: Env, _msg: MigrateMsg) -> StdResult<Response> {
-    let ver = cw2::get_contract_version(deps.storage)?;
-    // ensure we are migrating from an allowed contract
-    if ver.contract != CONTRACT_NAME.to_string() {
-        return Err(StdError::generic_err("Can only upgrade from same type").into());
+pub fn migrate(
+    deps: DepsMut<RouterQuery>,
+    env: Env,
+    msg: MigrateMsg,
+) -> Result<Response, ContractError> {
+    let applied: Vec<&str> = migrate_contract(
+        deps.storage,
+        CONTRACT_NAME,
+        CONTRACT_VERSION,
+        MIGRATIONS,
+        &msg,
+    )?;
+
+    if let Some(owner) = msg.owner {
+        deps.api
+            .addr_validate(&owner)
+            .map_err(|_| ContractError::InvalidAddress {
+                address: owner.clone(),
+            })?;
+        OWNER.save(deps.storage, &owner)?;
     }
-    // note: better to do proper semver compare, but string compare *usually* works
-    if ver.version >= CONTRACT_VERSION.to_string() {
-        return Err(StdError::generic_err("Cannot upgrade from a newer version").into());
+    if let Some(route_denom) = msg.route_denom {
+        if route_denom.is_empty() {
+            return Err(ContractError::EmptyField {
+                field: String::from("route_denom"),
+            });
+        }
+        ROUTE_DENOM.save(deps.storage, &route_denom)?;
     }
 
     let info_str: String = format!(
@@ -176,40 +226,66 @@ pub fn migrate(deps: DepsMut<RouterQuery>, env: Env, _msg: MigrateMsg) -> StdRes
         CONTRACT_NAME.to_string()
     );
     deps.api.debug(&info_str);
-    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
-    Ok(Response::default())
+    Ok(Response::new()
+        .add_attribute("action", "migrate")
+        .add_attribute("version", CONTRACT_VERSION)
+        .add_attribute("migrations", applied.join(",")))
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn query(deps: Deps<RouterQuery>, env: Env, msg: QueryMsg) -> StdResult<Binary> {
-    handle_query(deps, env, msg)
+pub fn query(deps: Deps<RouterQuery>, env: Env, msg: QueryMsg) -> Result<Binary, ContractError> {
+    Ok(handle_query(deps, env, msg)?)
 }
 
 fn handle_sudo_ack(
     deps: DepsMut<RouterQuery>,
     _env: Env,
-    _request_identifier: u64,
-    exec_flag: bool,
-    exec_data: Binary,
-    _refund_amount: Coin,
-) -> StdResult<Response<RouterMsg>> {
-    let mut request_id: u64 = 0;
-    if exec_flag {
-        let token_vec = match decode(&[ParamType::Uint(64), ParamType::String], &exec_data.0) {
-            Ok(data) => data,
-            Err(_) => {
-                return Err(StdError::GenericErr {
-                    msg: String::from("err.into()"),
-                })
+    ack: RequestAck,
+) -> Result<Response<RouterMsg>, ContractError> {
+    let RequestAck {
+        request_id: tracked_request_id,
+        exec_flag,
+        exec_data,
+        refund_amount,
+        ..
+    } = ack;
+
+    let mut res: Response<RouterMsg> = Response::new();
+    if let Some(tracked_request_id) = tracked_request_id {
+        if READ_CALLS.has(deps.storage, tracked_request_id) {
+            return handle_read_call_ack(deps, tracked_request_id, exec_flag, exec_data);
+        }
+        if let Some(mut send_request) = SEND_REQUESTS.may_load(deps.storage, tracked_request_id)? {
+            send_request.ack_refund = Some(refund_amount.amount);
+            SEND_REQUESTS.save(deps.storage, tracked_request_id, &send_request)?;
+            // the route amount refunded by the gateway goes back to the sender of the
+            // request, a failed transfer is reported by the reply without failing the ack
+            if !refund_amount.amount.is_zero() {
+                PENDING_ROUTE_REFUND.save(deps.storage, &tracked_request_id)?;
+                res = res.add_submessage(SubMsg::reply_on_error(
+                    BankMsg::Send {
+                        to_address: send_request.sender.clone(),
+                        amount: vec![refund_amount.clone()],
+                    },
+                    ROUTE_REFUND,
+                ));
             }
-        };
+            let refund_event = Event::new("RouteRefund")
+                .add_attribute("requestId", tracked_request_id.to_string())
+                .add_attribute("sender", send_request.sender)
+                .add_attribute("refundAmount", refund_amount.to_string());
+            res = res.add_event(refund_event);
+        }
+    }
 
-        request_id = token_vec[0].clone().into_uint().unwrap().as_u64();
-        let data_string: String = token_vec[1].clone().into_string().unwrap();
+    let mut request_id: u64 = 0;
+    if exec_flag {
+        let (ack_request_id, data_string): (u64, String) = decode_request_message(&exec_data.0)?;
+        request_id = ack_request_id;
 
         if data_string.clone() == "Fail Ack Req".to_string() {
-            return Err(StdError::GenericErr {
-                msg: String::from("String != Fail Ack Req"),
+            return Err(ContractError::ForcedFailure {
+                reason: data_string,
             });
         }
 
@@ -220,5 +296,52 @@ fn handle_sudo_ack(
         .add_attribute("requestIdentifier", request_id.to_string())
         .add_attribute("execFlag", exec_flag.to_string());
 
+    Ok(res.add_event(event))
+}
+
+// the refund of a failed transfer stays in the contract, the ack is kept
+fn handle_route_refund_reply(
+    deps: DepsMut<RouterQuery>,
+    msg: Reply,
+) -> Result<Response<RouterMsg>, ContractError> {
+    let request_id: u64 = PENDING_ROUTE_REFUND.load(deps.storage)?;
+    PENDING_ROUTE_REFUND.remove(deps.storage);
+    let error: String = match msg.result {
+        SubMsgResult::Err(error) => error,
+        SubMsgResult::Ok(_) => return Ok(Response::new()),
+    };
+    let send_request: SendRequestRecord = SEND_REQUESTS.load(deps.storage, request_id)?;
+
+    let event = Event::new("RouteRefundFailed")
+        .add_attribute("requestId", request_id.to_string())
+        .add_attribute("sender", send_request.sender)
+        .add_attribute("error", error);
+    Ok(Response::new().add_event(event))
+}
+
+fn handle_read_call_ack(
+    deps: DepsMut<RouterQuery>,
+    request_id: u64,
+    exec_flag: bool,
+    exec_data: Binary,
+) -> Result<Response<RouterMsg>, ContractError> {
+    let mut read_call: ReadCallRecord = READ_CALLS.load(deps.storage, request_id)?;
+    if exec_flag {
+        // the return data of the destination is stored as is, it is only
+        // decoded when it is a request message
+        read_call.status = ReadCallStatus::Success;
+        read_call.decoded_result = decode_request_message(&exec_data.0)
+            .ok()
+            .map(|(_, data_string)| data_string);
+    } else {
+        read_call.status = ReadCallStatus::Failed;
+    }
+    read_call.result = Some(exec_data);
+    READ_CALLS.save(deps.storage, request_id, &read_call)?;
+
+    let event = Event::new("ReadCallResult")
+        .add_attribute("requestId", request_id.to_string())
+        .add_attribute("execFlag", exec_flag.to_string());
+
     Ok(Response::new().add_event(event))
 }
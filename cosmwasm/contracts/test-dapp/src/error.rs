@@ -0,0 +1,58 @@
+use cosmwasm_std::{StdError, Uint128};
+use new_crosstalk_sample::migration::MigrationError;
+use router_wasm_bindings::ethabi;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum ContractError {
+    #[error("{0}")]
+    Std(#[from] StdError),
+
+    #[error("Decode error: {reason}")]
+    Decode { reason: String },
+
+    #[error("Unauthorized")]
+    Unauthorized {},
+
+    #[error("Unknown route for chain {chain_id}")]
+    UnknownRoute { chain_id: String },
+
+    #[error("Contract is paused")]
+    Paused {},
+
+    #[error("Invalid address: {address}")]
+    InvalidAddress { address: String },
+
+    #[error("{field} cannot be empty")]
+    EmptyField { field: String },
+
+    #[error("Request failed on purpose: {reason}")]
+    ForcedFailure { reason: String },
+
+    #[error("Invalid denom {denom}, only {route_denom} is accepted")]
+    InvalidDenom { denom: String, route_denom: String },
+
+    #[error("Insufficient route funds, expected {expected} received {received}")]
+    InsufficientRouteFunds {
+        expected: Uint128,
+        received: Uint128,
+    },
+
+    #[error("Invalid route recipient: {recipient}")]
+    InvalidRouteRecipient { recipient: String },
+
+    #[error("Unknown reply id: {id}")]
+    UnknownReplyId { id: u64 },
+
+    #[error("{0}")]
+    Migration(#[from] MigrationError),
+}
+
+// ethabi::Error has no Display in the no-std build of ethabi
+impl From<ethabi::Error> for ContractError {
+    fn from(err: ethabi::Error) -> Self {
+        ContractError::Decode {
+            reason: format!("{:?}", err),
+        }
+    }
+}
@@ -1,12 +1,16 @@
-use cosmwasm_std::{
-    Binary, DepsMut, MessageInfo, ReplyOn, Response, StdError, StdResult, SubMsg, Uint128,
-};
-use router_wasm_bindings::{
-    ethabi::{decode, encode, ParamType, Token},
-    Bytes, RouterMsg, RouterQuery,
-};
+use cosmwasm_std::{coins, BankMsg, Binary, DepsMut, MessageInfo, Response, SubMsg, Uint128};
+use new_crosstalk_sample::crosstalk::{decode_request_message, ISendRequest, RequestPacket};
+use new_crosstalk_sample::test_dapp::{ReadCallRecord, ReadCallStatus, SendRequestRecord};
+use router_wasm_bindings::{types::RequestMetaData, RouterMsg, RouterQuery};
 
-use crate::state::CREATE_I_SEND_REQUEST;
+use crate::{
+    error::ContractError,
+    query::fetch_route_denom,
+    state::{
+        CREATE_I_SEND_REQUEST, CREATE_READ_CALL_REQUEST, OWNER, READ_CALLS, REQUEST_ID,
+        REQUEST_TRACKER, ROUTE_DENOM, SEND_REQUESTS,
+    },
+};
 
 pub fn send_i_request(
     deps: DepsMut<RouterQuery>,
@@ -17,60 +21,178 @@ pub fn send_i_request(
     request_metadata: Binary,
     amount: Uint128,
     route_recipient: String,
-) -> StdResult<Response<RouterMsg>> {
+) -> Result<Response<RouterMsg>, ContractError> {
     let info_str: String = format!("create_outbound_request-- payload: {:?}", payload.clone(),);
     deps.api.debug(&info_str);
 
-    if amount != Uint128::zero() {
-        assert_eq!(info.funds.len(), 1);
-        assert_eq!(info.funds[0].amount, amount);
+    let route_denom: String = fetch_route_denom(deps.as_ref())?;
+    let received: Uint128 = validate_route_funds(&info, &route_denom, amount)?;
+    if !amount.is_zero() {
+        validate_route_recipient(&route_recipient)?;
     }
+    let overpayment_refund: Uint128 = received - amount;
+
     let info_str: String = format!(
         "create_outbound_request-- dest_chain_id: {}, dest_contract_address: {}, request_metadata: {}",
         dest_chain_id, dest_contract_address.clone(), request_metadata
     );
     deps.api.debug(&info_str);
-    let request_packet: Bytes = encode(&[
-        Token::String(dest_contract_address.clone()),
-        Token::Bytes(payload.clone().0),
-    ]);
+    let (_, greeting): (u64, String) = decode_request_message(&payload.0)?;
 
-    let i_send_request: RouterMsg = RouterMsg::CrosschainCall {
-        version: 1,
+    if greeting == "".to_string() {
+        return Err(ContractError::EmptyField {
+            field: String::from("greeting"),
+        });
+    }
+
+    let request_id: u64 = REQUEST_ID.load(deps.storage)? + 1;
+    REQUEST_ID.save(deps.storage, &request_id)?;
+    let send_request: SendRequestRecord = SendRequestRecord {
+        request_id,
+        request_identifier: None,
+        sender: info.sender.to_string(),
+        dest_chain_id: dest_chain_id.clone(),
         route_amount: amount,
-        route_recipient,
-        dest_chain_id,
-        request_metadata: request_metadata.0,
-        request_packet,
+        route_recipient: route_recipient.clone(),
+        route_denom: route_denom.clone(),
+        overpayment_refund,
+        ack_refund: None,
     };
+    SEND_REQUESTS.save(deps.storage, request_id, &send_request)?;
+    REQUEST_TRACKER.track(deps.storage, CREATE_I_SEND_REQUEST, request_id)?;
 
-    let cross_chain_sub_msg: SubMsg<RouterMsg> = SubMsg {
-        id: CREATE_I_SEND_REQUEST,
-        msg: i_send_request.into(),
-        gas_limit: None,
-        reply_on: ReplyOn::Success,
-    };
+    let request_packet: RequestPacket =
+        RequestPacket::new(dest_contract_address.clone(), payload.0);
+    let cross_chain_sub_msg: SubMsg<RouterMsg> =
+        ISendRequest::new(dest_chain_id, request_packet, request_metadata.0)
+            .with_route(amount, route_recipient)
+            .into_sub_msg(CREATE_I_SEND_REQUEST);
 
-    let token_vec: Vec<Token> = match decode(&[ParamType::Uint(64), ParamType::String], &payload.0)
-    {
-        Ok(data) => data,
-        Err(_) => {
-            return Err(StdError::GenericErr {
-                msg: String::from("err.into()"),
+    let mut res: Response<RouterMsg> = Response::new()
+        .add_submessage(cross_chain_sub_msg)
+        .add_attribute("dest_contract_address", dest_contract_address)
+        .add_attribute("request_id", request_id.to_string())
+        .add_attribute("route_amount", amount.to_string());
+    if !overpayment_refund.is_zero() {
+        res = res
+            .add_message(BankMsg::Send {
+                to_address: info.sender.to_string(),
+                amount: coins(overpayment_refund.u128(), route_denom),
             })
+            .add_attribute("overpayment_refund", overpayment_refund.to_string());
+    }
+    Ok(res)
+}
+
+/// Returns the route amount sent with the message, which must be of the
+/// route denom and cover `amount`.
+fn validate_route_funds(
+    info: &MessageInfo,
+    route_denom: &str,
+    amount: Uint128,
+) -> Result<Uint128, ContractError> {
+    let mut received: Uint128 = Uint128::zero();
+    for coin in info.funds.iter() {
+        if coin.denom != route_denom {
+            return Err(ContractError::InvalidDenom {
+                denom: coin.denom.clone(),
+                route_denom: route_denom.to_string(),
+            });
+        }
+        received += coin.amount;
+    }
+    if received < amount {
+        return Err(ContractError::InsufficientRouteFunds {
+            expected: amount,
+            received,
+        });
+    }
+    Ok(received)
+}
+
+fn validate_route_recipient(route_recipient: &str) -> Result<(), ContractError> {
+    let is_valid: bool = match route_recipient.strip_prefix("0x") {
+        Some(hex_address) => hex_address.len() == 40 && hex::decode(hex_address).is_ok(),
+        None => {
+            !route_recipient.is_empty()
+                && route_recipient
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_'))
         }
     };
+    if !is_valid {
+        return Err(ContractError::InvalidRouteRecipient {
+            recipient: route_recipient.to_string(),
+        });
+    }
+    Ok(())
+}
 
-    let greeting: String = token_vec[1].clone().into_string().unwrap();
+pub fn set_route_denom(
+    deps: DepsMut<RouterQuery>,
+    info: MessageInfo,
+    denom: String,
+) -> Result<Response<RouterMsg>, ContractError> {
+    if OWNER.may_load(deps.storage)? != Some(info.sender.to_string()) {
+        return Err(ContractError::Unauthorized {});
+    }
+    if denom.is_empty() {
+        return Err(ContractError::EmptyField {
+            field: String::from("denom"),
+        });
+    }
+    ROUTE_DENOM.save(deps.storage, &denom)?;
+    Ok(Response::new()
+        .add_attribute("action", "set_route_denom")
+        .add_attribute("denom", denom))
+}
 
-    if greeting == "".to_string() {
-        return Err(StdError::GenericErr {
-            msg: String::from("greeting cannot be empty"),
+pub fn query_remote(
+    deps: DepsMut<RouterQuery>,
+    dest_chain_id: String,
+    dest_contract: String,
+    calldata: Binary,
+    mut request_metadata: RequestMetaData,
+) -> Result<Response<RouterMsg>, ContractError> {
+    if calldata.is_empty() {
+        return Err(ContractError::EmptyField {
+            field: String::from("calldata"),
         });
     }
+    request_metadata.is_read_call = true;
+
+    let request_id: u64 = REQUEST_ID.load(deps.storage)? + 1;
+    REQUEST_ID.save(deps.storage, &request_id)?;
+    let read_call: ReadCallRecord = ReadCallRecord {
+        request_id,
+        request_identifier: None,
+        dest_chain_id: dest_chain_id.clone(),
+        dest_contract: dest_contract.clone(),
+        calldata: calldata.clone(),
+        status: ReadCallStatus::Pending,
+        result: None,
+        decoded_result: None,
+    };
+    READ_CALLS.save(deps.storage, request_id, &read_call)?;
+    REQUEST_TRACKER.track(deps.storage, CREATE_READ_CALL_REQUEST, request_id)?;
+
+    let info_str: String = format!(
+        "create_read_request-- dest_chain_id: {}, dest_contract: {}, request_metadata: {:?}",
+        dest_chain_id, dest_contract, request_metadata
+    );
+    deps.api.debug(&info_str);
+    let request_packet: RequestPacket = RequestPacket::new(dest_contract.clone(), calldata.0);
+    let cross_chain_sub_msg: SubMsg<RouterMsg> = ISendRequest::new(
+        dest_chain_id,
+        request_packet,
+        request_metadata.get_abi_encoded_bytes(),
+    )
+    .into_sub_msg(CREATE_READ_CALL_REQUEST);
 
     let res: Response<RouterMsg> = Response::new()
-        .add_submessage(cross_chain_sub_msg.into())
-        .add_attribute("dest_contract_address", dest_contract_address);
+        .add_submessage(cross_chain_sub_msg)
+        .add_attribute("action", "query_remote")
+        .add_attribute("request_id", request_id.to_string())
+        .add_attribute("dest_contract", dest_contract);
     Ok(res)
 }
@@ -1,5 +1,7 @@
 pub mod contract;
+pub mod error;
 pub mod execution;
+pub mod migrations;
 pub mod query;
 pub mod state;
 
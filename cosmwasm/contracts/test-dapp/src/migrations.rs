@@ -0,0 +1,23 @@
+use cosmwasm_std::Storage;
+use new_crosstalk_sample::migration::MigrationStep;
+use new_crosstalk_sample::test_dapp::MigrateMsg;
+
+use crate::{
+    error::ContractError,
+    state::{DEFAULT_ROUTE_DENOM, ROUTE_DENOM},
+};
+
+// state migrations, ordered by ascending version
+pub const MIGRATIONS: &[MigrationStep<MigrateMsg, ContractError>] = &[MigrationStep {
+    version: "0.2.0",
+    migrate: migrate_route_denom,
+}];
+
+/// Stores the default route denom for the contracts instantiated before it
+/// was configurable.
+fn migrate_route_denom(storage: &mut dyn Storage, _msg: &MigrateMsg) -> Result<(), ContractError> {
+    if ROUTE_DENOM.may_load(storage)?.is_none() {
+        ROUTE_DENOM.save(storage, &DEFAULT_ROUTE_DENOM.to_string())?;
+    }
+    Ok(())
+}
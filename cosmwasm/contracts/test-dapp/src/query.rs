@@ -1,9 +1,12 @@
 use cosmwasm_std::{to_binary, Binary, Deps, Env, StdResult};
 use cw2::get_contract_version;
-use new_crosstalk_sample::test_dapp::QueryMsg;
+use new_crosstalk_sample::test_dapp::{QueryMsg, ReadCallRecord, SendRequestRecord};
 use router_wasm_bindings::RouterQuery;
 
-use crate::state::{PING_FROM_SOURCE, PONG_FROM_DESTINATION};
+use crate::state::{
+    DEFAULT_ROUTE_DENOM, PING_FROM_SOURCE, PONG_FROM_DESTINATION, READ_CALLS, ROUTE_DENOM,
+    SEND_REQUESTS,
+};
 
 pub fn handle_query(deps: Deps<RouterQuery>, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
     match msg {
@@ -13,6 +16,11 @@ pub fn handle_query(deps: Deps<RouterQuery>, _env: Env, msg: QueryMsg) -> StdRes
             request_id,
         } => to_binary(&fetch_ping(deps, &chain_id, request_id)?),
         QueryMsg::FetchAckRecord { request_id } => to_binary(&fetch_pong(deps, request_id)?),
+        QueryMsg::FetchReadCall { request_id } => to_binary(&fetch_read_call(deps, request_id)?),
+        QueryMsg::FetchSendRequest { request_id } => {
+            to_binary(&fetch_send_request(deps, request_id)?)
+        }
+        QueryMsg::FetchRouteDenom {} => to_binary(&fetch_route_denom(deps)?),
     }
 }
 
@@ -23,3 +31,20 @@ pub fn fetch_ping(deps: Deps<RouterQuery>, chain_id: &str, request_id: u64) -> S
 pub fn fetch_pong(deps: Deps<RouterQuery>, request_id: u64) -> StdResult<String> {
     PONG_FROM_DESTINATION.load(deps.storage, &request_id.to_string())
 }
+
+pub fn fetch_read_call(deps: Deps<RouterQuery>, request_id: u64) -> StdResult<ReadCallRecord> {
+    READ_CALLS.load(deps.storage, request_id)
+}
+
+pub fn fetch_send_request(
+    deps: Deps<RouterQuery>,
+    request_id: u64,
+) -> StdResult<SendRequestRecord> {
+    SEND_REQUESTS.load(deps.storage, request_id)
+}
+
+pub fn fetch_route_denom(deps: Deps<RouterQuery>) -> StdResult<String> {
+    Ok(ROUTE_DENOM
+        .may_load(deps.storage)?
+        .unwrap_or_else(|| DEFAULT_ROUTE_DENOM.to_string()))
+}
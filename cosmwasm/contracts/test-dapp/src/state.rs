@@ -1,6 +1,10 @@
 use cw_storage_plus::{Item, Map};
+use new_crosstalk_sample::crosstalk::RequestTracker;
+use new_crosstalk_sample::test_dapp::{ReadCallRecord, SendRequestRecord};
 
 pub const CREATE_I_SEND_REQUEST: u64 = 1;
+pub const CREATE_READ_CALL_REQUEST: u64 = 2;
+pub const ROUTE_REFUND: u64 = 3;
 // PingMapping (src_chain_id, requestId) => pingFromSource
 pub const PING_FROM_SOURCE: Map<(&str, u64), String> = Map::new("ping_from_source");
 
@@ -8,3 +12,22 @@ pub const PING_FROM_SOURCE: Map<(&str, u64), String> = Map::new("ping_from_sourc
 pub const PONG_FROM_DESTINATION: Map<&str, String> = Map::new("pong_from_destination");
 
 pub const REQUEST_ID: Item<u64> = Item::new("request_id");
+
+pub const OWNER: Item<String> = Item::new("owner");
+
+pub const DEFAULT_ROUTE_DENOM: &str = "route";
+pub const ROUTE_DENOM: Item<String> = Item::new("route_denom");
+
+// requestId => route transfer details of the request sent with SendIRequest
+pub const SEND_REQUESTS: Map<u64, SendRequestRecord> = Map::new("send_requests");
+
+// requestId of the last route refund sent with an ack, read by the reply of a
+// failed refund
+pub const PENDING_ROUTE_REFUND: Item<u64> = Item::new("pending_route_refund");
+
+// requestId => read call sent with QueryRemote
+pub const READ_CALLS: Map<u64, ReadCallRecord> = Map::new("read_calls");
+
+// gateway request_identifier => requestId of the requests sent with SendIRequest and QueryRemote
+pub const REQUEST_TRACKER: RequestTracker =
+    RequestTracker::new("pending_replies", "request_identifiers");
@@ -1,16 +1,27 @@
 use std::marker::PhantomData;
 
 use crate::contract::instantiate;
-use crate::contract::{execute, sudo};
+use crate::contract::{execute, migrate, query, reply, sudo};
+use crate::error::ContractError;
+use crate::query::{fetch_read_call, fetch_route_denom, fetch_send_request};
+use crate::state::{
+    CREATE_I_SEND_REQUEST, CREATE_READ_CALL_REQUEST, OWNER, ROUTE_DENOM, ROUTE_REFUND,
+};
 use cosmwasm_std::testing::{MockApi, MockQuerier, MockStorage};
+use cosmwasm_std::{
+    coins, from_binary, BankMsg, Binary, Coin, CosmosMsg, Event, OwnedDeps, Reply, SubMsg,
+    SubMsgResponse, SubMsgResult, Uint128,
+};
 use cosmwasm_std::{
     testing::{mock_env, mock_info},
     DepsMut,
 };
-use cosmwasm_std::{Binary, Coin, CosmosMsg, OwnedDeps, Uint128};
-use new_crosstalk_sample::test_dapp::{ExecuteMsg, InstantiateMsg};
-use router_wasm_bindings::ethabi::{decode, ParamType};
-use router_wasm_bindings::types::ChainType;
+use new_crosstalk_sample::migration::MigrationError;
+use new_crosstalk_sample::test_dapp::{
+    ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg, ReadCallStatus, SendRequestRecord,
+};
+use router_wasm_bindings::ethabi::{decode, encode, ethereum_types::U256, ParamType, Token};
+use router_wasm_bindings::types::{AckType, ChainType, RequestMetaData};
 use router_wasm_bindings::utils::convert_address_from_string_to_bytes;
 use router_wasm_bindings::{Bytes, RouterMsg, RouterQuery, SudoMsg};
 
@@ -18,13 +29,31 @@ const INIT_ADDRESS: &str = "router1apapk9zfz3rp4x87fsm6h0s3zd0wlmkz0fx8tx";
 const BRIDGE_ADDRESS: &str = "0xeedb3ab68d567a6cd6d19fa819fe77b9f8ed1538";
 
 fn do_instantiate(mut deps: DepsMut<RouterQuery>) {
-    let instantiate_msg = InstantiateMsg {};
+    let instantiate_msg = InstantiateMsg { route_denom: None };
     let info = mock_info(INIT_ADDRESS, &[]);
     let env = mock_env();
     let res = instantiate(deps.branch(), env.clone(), info, instantiate_msg).unwrap();
     assert_eq!(0, res.messages.len());
 }
 
+fn greeting_payload(greeting: &str) -> Binary {
+    Binary(encode(&[
+        Token::Uint(U256::from(1u64)),
+        Token::String(greeting.to_string()),
+    ]))
+}
+
+fn send_request_msg(amount: u128, route_recipient: &str) -> ExecuteMsg {
+    ExecuteMsg::SendIRequest {
+        payload: greeting_payload("hello"),
+        dest_contract_address: String::from(BRIDGE_ADDRESS),
+        dest_chain_id: String::from("80001"),
+        request_metadata: Binary(vec![]),
+        amount: Uint128::new(amount),
+        route_recipient: String::from(route_recipient),
+    }
+}
+
 #[test]
 fn test_basic() {
     let mut deps = OwnedDeps {
@@ -95,6 +124,46 @@ fn test_sudo_outbound_ack_function() {
     assert_eq!(response.messages.len(), 0);
 }
 
+#[test]
+fn test_sudo_errors() {
+    let mut deps = OwnedDeps {
+        storage: MockStorage::default(),
+        api: MockApi::default(),
+        querier: MockQuerier::default(),
+        custom_query_type: PhantomData,
+    };
+    do_instantiate(deps.as_mut());
+    let env = mock_env();
+
+    let msg: SudoMsg = SudoMsg::HandleIReceive {
+        request_sender: BRIDGE_ADDRESS.into(),
+        src_chain_id: String::from("80001"),
+        request_identifier: 2,
+        payload: Binary(vec![1, 2, 3]),
+    };
+    let err = sudo(deps.as_mut(), env.clone(), msg).unwrap_err();
+    // the reason is the decode failure of ethabi
+    assert!(matches!(err, ContractError::Decode { reason } if reason == "InvalidData"));
+
+    let msg: SudoMsg = SudoMsg::HandleIReceive {
+        request_sender: BRIDGE_ADDRESS.into(),
+        src_chain_id: String::from("80001"),
+        request_identifier: 3,
+        payload: greeting_payload("Fail Dest Req"),
+    };
+    let err = sudo(deps.as_mut(), env.clone(), msg).unwrap_err();
+    assert!(matches!(err, ContractError::ForcedFailure { .. }));
+
+    let msg: SudoMsg = SudoMsg::HandleIAck {
+        request_identifier: 4,
+        exec_flag: true,
+        exec_data: greeting_payload("Fail Ack Req"),
+        refund_amount: Coin::new(0u128, String::from("route")),
+    };
+    let err = sudo(deps.as_mut(), env, msg).unwrap_err();
+    assert!(matches!(err, ContractError::ForcedFailure { .. }));
+}
+
 #[test]
 fn test_execute_create_outbound_request() {
     let mut deps = OwnedDeps {
@@ -107,7 +176,7 @@ fn test_execute_create_outbound_request() {
     let env = mock_env();
 
     let msg: ExecuteMsg = ExecuteMsg::SendIRequest {
-        payload: Binary(vec![]),
+        payload: greeting_payload("hello"),
         dest_contract_address: String::from(BRIDGE_ADDRESS),
         dest_chain_id: String::from("80001"),
         request_metadata: Binary(vec![]),
@@ -136,13 +205,384 @@ fn test_execute_create_outbound_request() {
                 assert_eq!(dest_chain_id, "80001");
                 assert_eq!(version, 1);
                 assert_eq!(hex::encode(request_metadata), "");
-                assert_eq!(hex::encode(request_packet), "000000000000000000000000000000000000000000000000000000000000004000000000000000000000000000000000000000000000000000000000000000a0000000000000000000000000000000000000000000000000000000000000002a307865656462336162363864353637613663643664313966613831396665373762396638656431353338000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000");
+                let tokens =
+                    decode(&[ParamType::String, ParamType::Bytes], &request_packet).unwrap();
+                assert_eq!(tokens[0].clone().into_string().unwrap(), BRIDGE_ADDRESS);
+                assert_eq!(
+                    tokens[1].clone().into_bytes().unwrap(),
+                    greeting_payload("hello").0
+                );
             }
         },
         _ => {}
     }
 }
 
+#[test]
+fn test_query_remote() {
+    let mut deps = OwnedDeps {
+        storage: MockStorage::default(),
+        api: MockApi::default(),
+        querier: MockQuerier::default(),
+        custom_query_type: PhantomData,
+    };
+    do_instantiate(deps.as_mut());
+    let env = mock_env();
+    let request_metadata: RequestMetaData = RequestMetaData {
+        dest_gas_limit: 200_000,
+        dest_gas_price: 50_000_000_000,
+        ack_gas_limit: 200_000,
+        ack_gas_price: 50_000_000_000,
+        relayer_fee: Uint128::zero(),
+        ack_type: AckType::AckOnBoth,
+        is_read_call: false,
+        asm_address: String::default(),
+    };
+    // balanceOf(0xeedb3ab68d567a6cd6d19fa819fe77b9f8ed1538)
+    let calldata: Binary = Binary(
+        hex::decode("70a08231000000000000000000000000eedb3ab68d567a6cd6d19fa819fe77b9f8ed1538")
+            .unwrap(),
+    );
+
+    let msg: ExecuteMsg = ExecuteMsg::QueryRemote {
+        dest_chain_id: String::from("80001"),
+        dest_contract: String::from(BRIDGE_ADDRESS),
+        calldata: calldata.clone(),
+        request_metadata: request_metadata.clone(),
+    };
+    let info = mock_info(INIT_ADDRESS, &[]);
+    let response = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+    assert_eq!(response.messages.len(), 1);
+    assert_eq!(response.messages[0].id, CREATE_READ_CALL_REQUEST);
+    match response.messages[0].msg.clone() {
+        CosmosMsg::Custom(RouterMsg::CrosschainCall {
+            dest_chain_id,
+            request_metadata: encoded_metadata,
+            request_packet,
+            ..
+        }) => {
+            assert_eq!(dest_chain_id, "80001");
+            let mut read_metadata: RequestMetaData = request_metadata;
+            read_metadata.is_read_call = true;
+            assert_eq!(encoded_metadata, read_metadata.get_abi_encoded_bytes());
+            let tokens = decode(&[ParamType::String, ParamType::Bytes], &request_packet).unwrap();
+            assert_eq!(tokens[1].clone().into_bytes().unwrap(), calldata.0);
+        }
+        _ => panic!("expected a CrosschainCall"),
+    }
+
+    let reply_msg: Reply = Reply {
+        id: CREATE_READ_CALL_REQUEST,
+        result: SubMsgResult::Ok(SubMsgResponse {
+            events: vec![],
+            data: Some(Binary::from(
+                br#"{"request_identifier":5,"fee_deducted":"0"}"#.to_vec(),
+            )),
+        }),
+    };
+    reply(deps.as_mut(), env.clone(), reply_msg).unwrap();
+    let read_call = fetch_read_call(deps.as_ref(), 1).unwrap();
+    assert_eq!(read_call.request_identifier, Some(5));
+    assert_eq!(read_call.status, ReadCallStatus::Pending);
+
+    let balance: Binary = Binary(
+        hex::decode("00000000000000000000000000000000000000000000000000000000000003e8").unwrap(),
+    );
+    let msg: SudoMsg = SudoMsg::HandleIAck {
+        request_identifier: 5,
+        exec_flag: true,
+        exec_data: balance.clone(),
+        refund_amount: Coin::new(0u128, String::from("route")),
+    };
+    sudo(deps.as_mut(), env, msg).unwrap();
+    let read_call = fetch_read_call(deps.as_ref(), 1).unwrap();
+    assert_eq!(read_call.status, ReadCallStatus::Success);
+    assert_eq!(read_call.result, Some(balance));
+    assert_eq!(read_call.decoded_result, None);
+}
+
+#[test]
+fn test_read_call_ack_decoding() {
+    let mut deps = OwnedDeps {
+        storage: MockStorage::default(),
+        api: MockApi::default(),
+        querier: MockQuerier::default(),
+        custom_query_type: PhantomData,
+    };
+    do_instantiate(deps.as_mut());
+    let env = mock_env();
+    let request_metadata: RequestMetaData = RequestMetaData {
+        dest_gas_limit: 200_000,
+        dest_gas_price: 50_000_000_000,
+        ack_gas_limit: 200_000,
+        ack_gas_price: 50_000_000_000,
+        relayer_fee: Uint128::zero(),
+        ack_type: AckType::AckOnBoth,
+        is_read_call: true,
+        asm_address: String::default(),
+    };
+    for request_identifier in [5u64, 6u64] {
+        let msg: ExecuteMsg = ExecuteMsg::QueryRemote {
+            dest_chain_id: String::from("80001"),
+            dest_contract: String::from(BRIDGE_ADDRESS),
+            calldata: Binary(vec![0x70, 0xa0, 0x82, 0x31]),
+            request_metadata: request_metadata.clone(),
+        };
+        let info = mock_info(INIT_ADDRESS, &[]);
+        execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+        let reply_msg: Reply = Reply {
+            id: CREATE_READ_CALL_REQUEST,
+            result: SubMsgResult::Ok(SubMsgResponse {
+                events: vec![],
+                data: Some(Binary::from(
+                    format!(
+                        r#"{{"request_identifier":{},"fee_deducted":"0"}}"#,
+                        request_identifier
+                    )
+                    .into_bytes(),
+                )),
+            }),
+        };
+        reply(deps.as_mut(), env.clone(), reply_msg).unwrap();
+    }
+
+    // successful read call returning a request message
+    let msg: SudoMsg = SudoMsg::HandleIAck {
+        request_identifier: 5,
+        exec_flag: true,
+        exec_data: greeting_payload("1000"),
+        refund_amount: Coin::new(0u128, String::from("route")),
+    };
+    sudo(deps.as_mut(), env.clone(), msg).unwrap();
+    let read_call = fetch_read_call(deps.as_ref(), 1).unwrap();
+    assert_eq!(read_call.status, ReadCallStatus::Success);
+    assert_eq!(read_call.result, Some(greeting_payload("1000")));
+    assert_eq!(read_call.decoded_result, Some(String::from("1000")));
+
+    // the error data of a failed read call is kept as is
+    let msg: SudoMsg = SudoMsg::HandleIAck {
+        request_identifier: 6,
+        exec_flag: false,
+        exec_data: Binary(b"execution reverted".to_vec()),
+        refund_amount: Coin::new(0u128, String::from("route")),
+    };
+    sudo(deps.as_mut(), env, msg).unwrap();
+    let read_call = fetch_read_call(deps.as_ref(), 2).unwrap();
+    assert_eq!(read_call.status, ReadCallStatus::Failed);
+    assert_eq!(
+        read_call.result,
+        Some(Binary(b"execution reverted".to_vec()))
+    );
+    assert_eq!(read_call.decoded_result, None);
+}
+
+#[test]
+fn test_send_i_request_route_funds() {
+    let mut deps = OwnedDeps {
+        storage: MockStorage::default(),
+        api: MockApi::default(),
+        querier: MockQuerier::default(),
+        custom_query_type: PhantomData,
+    };
+    do_instantiate(deps.as_mut());
+    let env = mock_env();
+
+    let info = mock_info(INIT_ADDRESS, &coins(100, "uatom"));
+    let err = execute(
+        deps.as_mut(),
+        env.clone(),
+        info,
+        send_request_msg(100, BRIDGE_ADDRESS),
+    );
+    assert!(matches!(
+        err.unwrap_err(),
+        ContractError::InvalidDenom { denom, route_denom } if denom == "uatom" && route_denom == "route"
+    ));
+
+    let info = mock_info(INIT_ADDRESS, &coins(50, "route"));
+    let err = execute(
+        deps.as_mut(),
+        env.clone(),
+        info,
+        send_request_msg(100, BRIDGE_ADDRESS),
+    );
+    assert!(matches!(
+        err.unwrap_err(),
+        ContractError::InsufficientRouteFunds { expected, received }
+            if expected == Uint128::new(100) && received == Uint128::new(50)
+    ));
+
+    let info = mock_info(INIT_ADDRESS, &coins(100, "route"));
+    let err = execute(
+        deps.as_mut(),
+        env.clone(),
+        info,
+        send_request_msg(100, "0x1234"),
+    );
+    assert!(matches!(
+        err.unwrap_err(),
+        ContractError::InvalidRouteRecipient { recipient } if recipient == "0x1234"
+    ));
+
+    // overpayment is sent back to the sender
+    let info = mock_info(INIT_ADDRESS, &coins(150, "route"));
+    let response = execute(
+        deps.as_mut(),
+        env.clone(),
+        info,
+        send_request_msg(100, BRIDGE_ADDRESS),
+    )
+    .unwrap();
+    assert_eq!(response.messages.len(), 2);
+    assert_eq!(
+        response.messages[1].msg,
+        CosmosMsg::Bank(BankMsg::Send {
+            to_address: String::from(INIT_ADDRESS),
+            amount: coins(50, "route"),
+        })
+    );
+    let send_request = fetch_send_request(deps.as_ref(), 1).unwrap();
+    assert_eq!(send_request.route_amount, Uint128::new(100));
+    assert_eq!(send_request.route_recipient, BRIDGE_ADDRESS);
+    assert_eq!(send_request.route_denom, "route");
+    assert_eq!(send_request.overpayment_refund, Uint128::new(50));
+
+    // route amount refunded with the ack goes back to the sender
+    let reply_msg: Reply = Reply {
+        id: CREATE_I_SEND_REQUEST,
+        result: SubMsgResult::Ok(SubMsgResponse {
+            events: vec![],
+            data: Some(Binary::from(
+                br#"{"request_identifier":9,"fee_deducted":"0"}"#.to_vec(),
+            )),
+        }),
+    };
+    reply(deps.as_mut(), env.clone(), reply_msg).unwrap();
+    let msg: SudoMsg = SudoMsg::HandleIAck {
+        request_identifier: 9,
+        exec_flag: false,
+        exec_data: Binary(vec![]),
+        refund_amount: Coin::new(100u128, String::from("route")),
+    };
+    let response = sudo(deps.as_mut(), env.clone(), msg).unwrap();
+    assert_eq!(
+        response.messages[0],
+        SubMsg::reply_on_error(
+            BankMsg::Send {
+                to_address: String::from(INIT_ADDRESS),
+                amount: coins(100, "route"),
+            },
+            ROUTE_REFUND,
+        )
+    );
+    let send_request = fetch_send_request(deps.as_ref(), 1).unwrap();
+    assert_eq!(send_request.request_identifier, Some(9));
+    assert_eq!(send_request.ack_refund, Some(Uint128::new(100)));
+
+    // a failed refund is reported without reverting the ack
+    let reply_msg: Reply = Reply {
+        id: ROUTE_REFUND,
+        result: SubMsgResult::Err(String::from("insufficient funds")),
+    };
+    let response = reply(deps.as_mut(), env, reply_msg).unwrap();
+    assert_eq!(
+        response.events,
+        vec![Event::new("RouteRefundFailed")
+            .add_attribute("requestId", "1")
+            .add_attribute("sender", INIT_ADDRESS)
+            .add_attribute("error", "insufficient funds")]
+    );
+    let send_request = fetch_send_request(deps.as_ref(), 1).unwrap();
+    assert_eq!(send_request.ack_refund, Some(Uint128::new(100)));
+}
+
+#[test]
+fn test_set_route_denom() {
+    let mut deps = OwnedDeps {
+        storage: MockStorage::default(),
+        api: MockApi::default(),
+        querier: MockQuerier::default(),
+        custom_query_type: PhantomData,
+    };
+    do_instantiate(deps.as_mut());
+    let env = mock_env();
+    let msg: ExecuteMsg = ExecuteMsg::SetRouteDenom {
+        denom: String::from("uroute"),
+    };
+
+    let info = mock_info(BRIDGE_ADDRESS, &[]);
+    let err = execute(deps.as_mut(), env.clone(), info, msg.clone());
+    assert!(matches!(err.unwrap_err(), ContractError::Unauthorized {}));
+
+    let info = mock_info(INIT_ADDRESS, &[]);
+    execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+    assert_eq!(fetch_route_denom(deps.as_ref()).unwrap(), "uroute");
+
+    let info = mock_info(INIT_ADDRESS, &coins(10, "uroute"));
+    let response = execute(
+        deps.as_mut(),
+        env,
+        info,
+        send_request_msg(10, BRIDGE_ADDRESS),
+    );
+    assert!(response.is_ok());
+}
+
+#[test]
+fn test_query_send_request() {
+    let mut deps = OwnedDeps {
+        storage: MockStorage::default(),
+        api: MockApi::default(),
+        querier: MockQuerier::default(),
+        custom_query_type: PhantomData,
+    };
+    do_instantiate(deps.as_mut());
+    let env = mock_env();
+
+    let msg = QueryMsg::FetchSendRequest { request_id: 1 };
+    assert!(query(deps.as_ref(), env.clone(), msg.clone()).is_err());
+
+    let info = mock_info(INIT_ADDRESS, &coins(100, "route"));
+    execute(
+        deps.as_mut(),
+        env.clone(),
+        info,
+        send_request_msg(100, BRIDGE_ADDRESS),
+    )
+    .unwrap();
+    let send_request: SendRequestRecord =
+        from_binary(&query(deps.as_ref(), env, msg).unwrap()).unwrap();
+    assert_eq!(send_request, fetch_send_request(deps.as_ref(), 1).unwrap());
+    assert_eq!(send_request.route_amount, Uint128::new(100));
+    assert_eq!(send_request.route_recipient, BRIDGE_ADDRESS);
+}
+
+#[test]
+fn test_query_route_denom() {
+    let mut deps = OwnedDeps {
+        storage: MockStorage::default(),
+        api: MockApi::default(),
+        querier: MockQuerier::default(),
+        custom_query_type: PhantomData,
+    };
+    do_instantiate(deps.as_mut());
+    let env = mock_env();
+
+    let route_denom: String =
+        from_binary(&query(deps.as_ref(), env.clone(), QueryMsg::FetchRouteDenom {}).unwrap())
+            .unwrap();
+    assert_eq!(route_denom, "route");
+
+    let info = mock_info(INIT_ADDRESS, &[]);
+    let msg = ExecuteMsg::SetRouteDenom {
+        denom: String::from("uroute"),
+    };
+    execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+    let route_denom: String =
+        from_binary(&query(deps.as_ref(), env, QueryMsg::FetchRouteDenom {}).unwrap()).unwrap();
+    assert_eq!(route_denom, "uroute");
+}
+
 #[test]
 fn test_encode() {
     let addr: String =
@@ -161,3 +601,57 @@ fn test_encode() {
         decode(&[ParamType::Uint(64), ParamType::String], &binary.0)
     );
 }
+
+#[test]
+fn test_migrate() {
+    let mut deps = OwnedDeps {
+        storage: MockStorage::default(),
+        api: MockApi::default(),
+        querier: MockQuerier::default(),
+        custom_query_type: PhantomData,
+    };
+    do_instantiate(deps.as_mut());
+    let env = mock_env();
+
+    // fabricate a 0.1.0 deployment, instantiated before the route denom was stored
+    cw2::set_contract_version(deps.as_mut().storage, "PingPong", "0.1.0").unwrap();
+    ROUTE_DENOM.remove(deps.as_mut().storage);
+
+    let res = migrate(deps.as_mut(), env.clone(), MigrateMsg::default()).unwrap();
+    assert_eq!(res.attributes[2].value, "0.2.0");
+    assert_eq!(
+        ROUTE_DENOM.load(deps.as_ref().storage).unwrap(),
+        String::from("route")
+    );
+    assert_eq!(
+        OWNER.load(deps.as_ref().storage).unwrap(),
+        String::from(INIT_ADDRESS)
+    );
+
+    // the overrides are applied on top of the state migrations
+    let msg = MigrateMsg {
+        owner: Some(String::from("router1newowner")),
+        route_denom: Some(String::from("uroute")),
+    };
+    let res = migrate(deps.as_mut(), env.clone(), msg).unwrap();
+    assert_eq!(res.attributes[2].value, "");
+    assert_eq!(fetch_route_denom(deps.as_ref()).unwrap(), "uroute");
+    assert_eq!(
+        OWNER.load(deps.as_ref().storage).unwrap(),
+        String::from("router1newowner")
+    );
+
+    let msg = MigrateMsg {
+        owner: None,
+        route_denom: Some(String::new()),
+    };
+    let err = migrate(deps.as_mut(), env.clone(), msg).unwrap_err();
+    assert!(matches!(err, ContractError::EmptyField { field } if field == "route_denom"));
+
+    cw2::set_contract_version(deps.as_mut().storage, "PingPong", "1.0.0").unwrap();
+    let err = migrate(deps.as_mut(), env, MigrateMsg::default()).unwrap_err();
+    assert!(matches!(
+        err,
+        ContractError::Migration(MigrationError::Downgrade { .. })
+    ));
+}
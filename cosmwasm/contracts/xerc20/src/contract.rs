@@ -1,20 +1,24 @@
+use crate::error::ContractError;
 use crate::execution::handle_execute;
 use crate::handle_sudo_execution::{handle_sudo_ack, handle_sudo_request};
 use crate::query::handle_query;
-use crate::state::{CREATE_I_SEND_REQUEST, CROSS_CHAIN_TOKEN, INSTANTIATE_REPLY_ID, OWNER};
-#[cfg(not(feature = "library"))]
-use cosmwasm_std::{entry_point, Binary, Deps, DepsMut, Env, MessageInfo, Response, StdResult};
-use cosmwasm_std::{
-    from_binary, to_binary, Reply, ReplyOn, StdError, SubMsg, SubMsgResult, WasmMsg,
+use crate::state::{
+    CHAIN_ID, CREATE_I_SEND_REQUEST, CROSS_CHAIN_TOKEN, INSTANTIATE_REPLY_ID, OWNER,
 };
-use cw0::parse_reply_instantiate_data;
+#[cfg(not(feature = "library"))]
+use cosmwasm_std::{entry_point, Binary, Deps, DepsMut, Env, MessageInfo, Response};
+use cosmwasm_std::{to_binary, Reply, ReplyOn, SubMsg, WasmMsg};
+use cw_utils::parse_reply_instantiate_data;
 use cw20::MinterResponse;
+use new_crosstalk_sample::crosstalk::{
+    handle_sudo, parse_i_send_reply, CrosstalkDapp, InboundRequest, RequestAck,
+};
+use new_crosstalk_sample::migration::migrate_contract;
 use new_crosstalk_sample::xerc20::{ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg};
 
 use cw2::set_contract_version;
 
 use cw20_base::msg::InstantiateMsg as TokenInstantiateMsg;
-use router_wasm_bindings::types::CrosschainRequestResponse;
 use router_wasm_bindings::{RouterMsg, RouterQuery, SudoMsg};
 
 // version info for migration info
@@ -27,7 +31,7 @@ pub fn instantiate(
     env: Env,
     info: MessageInfo,
     msg: InstantiateMsg,
-) -> StdResult<Response> {
+) -> Result<Response, ContractError> {
     OWNER.save(deps.storage, &info.sender.to_string())?;
     set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
     Ok(Response::new().add_submessage(SubMsg {
@@ -57,11 +61,15 @@ pub fn instantiate(
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn reply(deps: DepsMut<RouterQuery>, _env: Env, msg: Reply) -> StdResult<Response<RouterMsg>> {
+pub fn reply(
+    deps: DepsMut<RouterQuery>,
+    _env: Env,
+    msg: Reply,
+) -> Result<Response<RouterMsg>, ContractError> {
     match msg.id {
         INSTANTIATE_REPLY_ID => {
             // let data = msg.result.unwrap().data.unwrap();
-            let response = parse_reply_instantiate_data(msg).unwrap();
+            let response = parse_reply_instantiate_data(msg)?;
             CROSS_CHAIN_TOKEN.save(deps.storage, &response.contract_address)?;
             return Ok(
                 Response::new().add_attribute("cw20token", response.contract_address.clone())
@@ -70,28 +78,15 @@ pub fn reply(deps: DepsMut<RouterQuery>, _env: Env, msg: Reply) -> StdResult<Res
         CREATE_I_SEND_REQUEST => {
             deps.api.debug(&msg.id.to_string());
             // TODO: need to handle nonce data here, Nonce handling logic depends on the use-case.
-            let response: Response<RouterMsg> = Response::new();
-            match msg.result {
-                SubMsgResult::Ok(msg_result) => match msg_result.data {
-                    Some(binary_data) => {
-                        deps.api.debug("Binary Data Found");
-                        let cross_chain_req_res: CrosschainRequestResponse =
-                            from_binary(&binary_data).unwrap();
-
-                        let info_str: String = format!(
-                            "Binary data {:?}, response {:?}",
-                            &binary_data.to_string(),
-                            cross_chain_req_res
-                        );
-                        deps.api.debug(&info_str);
-                        return Ok(response);
-                    }
-                    None => deps.api.debug("No Binary Data Found"),
-                },
-                SubMsgResult::Err(err) => deps.api.debug(&err.to_string()),
+            match parse_i_send_reply(&msg)? {
+                Some(cross_chain_req_res) => {
+                    let info_str: String = format!("response {:?}", cross_chain_req_res);
+                    deps.api.debug(&info_str);
+                }
+                None => deps.api.debug("No Binary Data Found"),
             }
         }
-        id => return Err(StdError::generic_err(format!("Unknown reply id: {}", id))),
+        id => return Err(ContractError::UnknownReplyId { id }),
     }
     Ok(Response::new())
 }
@@ -102,53 +97,72 @@ pub fn execute(
     env: Env,
     info: MessageInfo,
     msg: ExecuteMsg,
-) -> StdResult<Response<RouterMsg>> {
+) -> Result<Response<RouterMsg>, ContractError> {
     handle_execute(deps, env, info, msg)
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn sudo(deps: DepsMut<RouterQuery>, env: Env, msg: SudoMsg) -> StdResult<Response<RouterMsg>> {
-    match msg {
-        SudoMsg::HandleIReceive {
-            request_sender,
-            src_chain_id,
-            request_identifier,
-            payload,
-        } => handle_sudo_request(
+pub fn sudo(
+    deps: DepsMut<RouterQuery>,
+    env: Env,
+    msg: SudoMsg,
+) -> Result<Response<RouterMsg>, ContractError> {
+    handle_sudo::<XErc20>(deps, env, msg)
+}
+
+pub struct XErc20;
+
+impl CrosstalkDapp for XErc20 {
+    type Error = ContractError;
+
+    fn on_receive(
+        deps: DepsMut<RouterQuery>,
+        env: Env,
+        request: InboundRequest,
+    ) -> Result<Response<RouterMsg>, ContractError> {
+        handle_sudo_request(
             deps,
             env,
-            request_sender,
-            src_chain_id,
-            request_identifier,
-            payload,
-        ),
-        SudoMsg::HandleIAck {
-            request_identifier,
-            exec_flag,
-            exec_data,
-            refund_amount,
-        } => handle_sudo_ack(
+            request.request_sender,
+            request.src_chain_id,
+            request.request_identifier,
+            request.payload,
+        )
+    }
+
+    fn on_ack(
+        deps: DepsMut<RouterQuery>,
+        env: Env,
+        ack: RequestAck,
+    ) -> Result<Response<RouterMsg>, ContractError> {
+        handle_sudo_ack(
             deps,
             env,
-            request_identifier,
-            exec_flag,
-            exec_data,
-            refund_amount,
-        ),
+            ack.request_identifier,
+            ack.exec_flag,
+            ack.exec_data,
+            ack.refund_amount,
+        )
     }
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn migrate(deps: DepsMut<RouterQuery>, env: Env, _msg: MigrateMsg) -> StdResult<Response> {
-    let ver = cw2::get_contract_version(deps.storage)?;
-    // ensure we are migrating from an allowed contract
-    if ver.contract != CONTRACT_NAME.to_string() {
-        return Err(StdError::generic_err("Can only upgrade from same type").into());
+pub fn migrate(
+    deps: DepsMut<RouterQuery>,
+    env: Env,
+    msg: MigrateMsg,
+) -> Result<Response, ContractError> {
+    let applied: Vec<&str> = migrate_contract::<MigrateMsg, ContractError>(
+        deps.storage,
+        CONTRACT_NAME,
+        CONTRACT_VERSION,
+        &[],
+        &msg,
+    )?;
+
+    if let Some(chain_id) = msg.chain_id {
+        CHAIN_ID.save(deps.storage, &chain_id)?;
     }
-    // note: better to do proper semver compare, but string compare *usually* works
-    // if ver.version >= CONTRACT_VERSION.to_string() {
-    //     return Err(StdError::generic_err("Cannot upgrade from a newer version").into());
-    // }
 
     let info_str: String = format!(
         "migrating contract: {}, new_contract_version: {}, contract_name: {}",
@@ -157,11 +171,13 @@ pub fn migrate(deps: DepsMut<RouterQuery>, env: Env, _msg: MigrateMsg) -> StdRes
         CONTRACT_NAME.to_string()
     );
     deps.api.debug(&info_str);
-    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
-    Ok(Response::default())
+    Ok(Response::new()
+        .add_attribute("action", "migrate")
+        .add_attribute("version", CONTRACT_VERSION)
+        .add_attribute("migrations", applied.join(",")))
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn query(deps: Deps<RouterQuery>, env: Env, msg: QueryMsg) -> StdResult<Binary> {
-    handle_query(deps, env, msg)
+pub fn query(deps: Deps<RouterQuery>, env: Env, msg: QueryMsg) -> Result<Binary, ContractError> {
+    Ok(handle_query(deps, env, msg)?)
 }
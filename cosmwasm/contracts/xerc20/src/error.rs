@@ -0,0 +1,50 @@
+use cosmwasm_std::StdError;
+use cw_utils::ParseReplyError;
+use new_crosstalk_sample::migration::MigrationError;
+use router_wasm_bindings::ethabi;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum ContractError {
+    #[error("{0}")]
+    Std(#[from] StdError),
+
+    #[error("{0}")]
+    ParseReply(#[from] ParseReplyError),
+
+    #[error("Decode error: {reason}")]
+    Decode { reason: String },
+
+    #[error("Unauthorized")]
+    Unauthorized {},
+
+    #[error("Unknown route, no contract whitelisted for chain {chain_id}")]
+    UnknownRoute { chain_id: String },
+
+    #[error("The sender contract {request_sender} of chain {src_chain_id} is not whitelisted")]
+    InvalidRequestSender {
+        src_chain_id: String,
+        request_sender: String,
+    },
+
+    #[error("Contract is paused")]
+    Paused {},
+
+    #[error("Invalid address: {address}")]
+    InvalidAddress { address: String },
+
+    #[error("Unknown reply id: {id}")]
+    UnknownReplyId { id: u64 },
+
+    #[error("{0}")]
+    Migration(#[from] MigrationError),
+}
+
+// ethabi::Error has no Display in the no-std build of ethabi
+impl From<ethabi::Error> for ContractError {
+    fn from(err: ethabi::Error) -> Self {
+        ContractError::Decode {
+            reason: format!("{:?}", err),
+        }
+    }
+}
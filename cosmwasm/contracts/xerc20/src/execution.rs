@@ -1,17 +1,21 @@
 use cosmwasm_std::{
-    to_binary, Binary, CosmosMsg, DepsMut, Env, Event, MessageInfo, ReplyOn, Response, StdResult,
-    SubMsg, Uint128, WasmMsg,
+    to_binary, Binary, CosmosMsg, DepsMut, Env, Event, MessageInfo, Response, SubMsg, Uint128,
+    WasmMsg,
+};
+use new_crosstalk_sample::crosstalk::{
+    ISendRequest, RequestMetadataBuilder, RequestPacket, DEFAULT_GAS_LIMIT,
 };
 use new_crosstalk_sample::xerc20::{ChainTypeInfo, ContractInfo, ExecuteMsg};
 use router_wasm_bindings::{
     ethabi::{encode, ethereum_types::U256, Token},
-    types::{AckType, RequestMetaData},
-    Bytes, RouterMsg, RouterQuery,
+    types::RequestMetaData,
+    RouterMsg, RouterQuery,
 };
 
 use crate::{
+    error::ContractError,
     modifiers::is_owner_modifier,
-    query::{fetch_oracle_gas_price, fetch_white_listed_contract},
+    query::fetch_oracle_gas_price,
     state::{
         CHAIN_ID, CHAIN_TYPE_MAPPING, CREATE_I_SEND_REQUEST, CROSS_CHAIN_TOKEN, OWNER,
         WHITELISTED_CONTRACT_MAPPING,
@@ -23,7 +27,7 @@ pub fn handle_execute(
     env: Env,
     info: MessageInfo,
     msg: ExecuteMsg,
-) -> StdResult<Response<RouterMsg>> {
+) -> Result<Response<RouterMsg>, ContractError> {
     match msg {
         ExecuteMsg::SetChainTypes { chain_type_info } => {
             set_chain_types_info(deps, env, info, chain_type_info)
@@ -48,7 +52,7 @@ pub fn set_white_listed_contracts(
     _env: &Env,
     info: &MessageInfo,
     contracts: Vec<ContractInfo>,
-) -> StdResult<Response<RouterMsg>> {
+) -> Result<Response<RouterMsg>, ContractError> {
     is_owner_modifier(deps.as_ref(), &info)?;
 
     for i in 0..contracts.len() {
@@ -69,9 +73,13 @@ pub fn mint(
     info: &MessageInfo,
     recipient: String,
     amount: Uint128,
-) -> StdResult<Response<RouterMsg>> {
+) -> Result<Response<RouterMsg>, ContractError> {
     is_owner_modifier(deps.as_ref(), &info)?;
-    deps.api.addr_validate(&recipient)?;
+    deps.api
+        .addr_validate(&recipient)
+        .map_err(|_| ContractError::InvalidAddress {
+            address: recipient.clone(),
+        })?;
     let mint_msg = cw20_base::msg::ExecuteMsg::Mint { recipient, amount };
 
     let xerc20_token: String = CROSS_CHAIN_TOKEN.load(deps.storage)?;
@@ -92,8 +100,13 @@ pub fn update_owner(
     _env: &Env,
     info: &MessageInfo,
     new_owner: String,
-) -> StdResult<Response<RouterMsg>> {
+) -> Result<Response<RouterMsg>, ContractError> {
     is_owner_modifier(deps.as_ref(), &info)?;
+    deps.api
+        .addr_validate(&new_owner)
+        .map_err(|_| ContractError::InvalidAddress {
+            address: new_owner.clone(),
+        })?;
 
     OWNER.save(deps.storage, &new_owner)?;
     let res = Response::new();
@@ -111,7 +124,7 @@ pub fn set_chain_types_info(
     _env: Env,
     info: MessageInfo,
     chain_type_info: Vec<ChainTypeInfo>,
-) -> StdResult<Response<RouterMsg>> {
+) -> Result<Response<RouterMsg>, ContractError> {
     is_owner_modifier(deps.as_ref(), &info)?;
 
     for i in 0..chain_type_info.len() {
@@ -135,7 +148,7 @@ pub fn set_chain_id(
     _env: Env,
     info: MessageInfo,
     id: String,
-) -> StdResult<Response<RouterMsg>> {
+) -> Result<Response<RouterMsg>, ContractError> {
     is_owner_modifier(deps.as_ref(), &info)?;
 
     CHAIN_ID.save(deps.storage, &id)?;
@@ -159,7 +172,7 @@ pub fn set_xerc20_addr(
     _env: Env,
     info: MessageInfo,
     addr: String,
-) -> StdResult<Response<RouterMsg>> {
+) -> Result<Response<RouterMsg>, ContractError> {
     is_owner_modifier(deps.as_ref(), &info)?;
 
     CROSS_CHAIN_TOKEN.save(deps.storage, &addr)?;
@@ -179,7 +192,7 @@ pub fn transfer_cross_chain(
     amount: Uint128,
     recipient: Binary,
     dest_chain_id: String,
-) -> StdResult<Response<RouterMsg>> {
+) -> Result<Response<RouterMsg>, ContractError> {
     let u256: U256 = U256::from(amount.u128());
     let payload: Vec<u8> = encode(&[Token::Bytes(recipient.0), Token::Uint(u256)]);
     let burn_msg = cw20_base::msg::ExecuteMsg::BurnFrom {
@@ -196,44 +209,30 @@ pub fn transfer_cross_chain(
     let chain_id: String = CHAIN_ID.load(deps.storage)?;
     let ack_gas_price: u64 = fetch_oracle_gas_price(deps.as_ref(), chain_id)?;
     let dest_gas_price: u64 = fetch_oracle_gas_price(deps.as_ref(), dest_chain_id.clone())?;
-    let dest_contract_address: String = fetch_white_listed_contract(deps.as_ref(), &dest_chain_id)?;
-    let request_metadata: RequestMetaData = RequestMetaData {
-        dest_gas_limit: 200_000,
-        dest_gas_price,
-        ack_gas_limit: 200_000,
-        ack_gas_price,
-        relayer_fee: Uint128::zero(),
-        ack_type: AckType::AckOnBoth,
-        is_read_call: false,
-        asm_address: String::default(),
-    };
+    let dest_contract_address: String = WHITELISTED_CONTRACT_MAPPING
+        .may_load(deps.storage, &dest_chain_id)?
+        .ok_or_else(|| ContractError::UnknownRoute {
+            chain_id: dest_chain_id.clone(),
+        })?;
+    let request_metadata: RequestMetaData = RequestMetadataBuilder::new()
+        .dest_gas(DEFAULT_GAS_LIMIT, dest_gas_price)
+        .ack_gas(DEFAULT_GAS_LIMIT, ack_gas_price)
+        .build();
     let info_str: String = format!(
         "create_outbound_request-- dest_chain_id: {}, dest_contract_address: {}, request_metadata: {:?}",
         dest_chain_id, dest_contract_address.clone(), request_metadata
     );
     deps.api.debug(&info_str);
-    let request_packet: Bytes = encode(&[
-        Token::String(dest_contract_address.clone()),
-        Token::Bytes(payload),
-    ]);
-
-    let i_send_request: RouterMsg = RouterMsg::CrosschainCall {
-        version: 1,
-        route_amount: Uint128::zero(),
-        route_recipient: String::default(),
+    let request_packet: RequestPacket = RequestPacket::new(dest_contract_address.clone(), payload);
+    let cross_chain_sub_msg: SubMsg<RouterMsg> = ISendRequest::new(
         dest_chain_id,
-        request_metadata: request_metadata.get_abi_encoded_bytes(),
         request_packet,
-    };
-    let cross_chain_sub_msg: SubMsg<RouterMsg> = SubMsg {
-        id: CREATE_I_SEND_REQUEST,
-        msg: i_send_request.into(),
-        gas_limit: None,
-        reply_on: ReplyOn::Success,
-    };
+        request_metadata.get_abi_encoded_bytes(),
+    )
+    .into_sub_msg(CREATE_I_SEND_REQUEST);
     let res = Response::new()
         .add_message(exec_burn_msg)
-        .add_submessage(cross_chain_sub_msg.into())
+        .add_submessage(cross_chain_sub_msg)
         .add_attribute("dest_contract_address", dest_contract_address);
     Ok(res)
 }
@@ -1,15 +1,15 @@
 use cosmwasm_std::{
-    to_binary, Binary, Coin, CosmosMsg, DepsMut, Env, Event, Response, StdError, StdResult,
-    Uint128, WasmMsg,
+    to_binary, Binary, Coin, CosmosMsg, DepsMut, Env, Event, Response, Uint128, WasmMsg,
 };
 use router_wasm_bindings::{
-    ethabi::{decode, ParamType},
+    ethabi::{self, decode, ParamType},
     types::ChainType,
     utils::convert_address_from_bytes_to_string,
     Bytes, RouterMsg, RouterQuery,
 };
 
 use crate::{
+    error::ContractError,
     modifiers::is_white_listed_modifier,
     state::{CHAIN_TYPE_MAPPING, CROSS_CHAIN_TOKEN},
 };
@@ -21,9 +21,13 @@ pub fn handle_sudo_request(
     src_chain_id: String,
     request_identifier: u64,
     payload: Binary,
-) -> StdResult<Response<RouterMsg>> {
+) -> Result<Response<RouterMsg>, ContractError> {
     deps.api.debug("XERC20 INFO: Handle Sudo Request");
-    let src_chain_type: u64 = CHAIN_TYPE_MAPPING.load(deps.storage, &src_chain_id)?;
+    let src_chain_type: u64 = CHAIN_TYPE_MAPPING
+        .may_load(deps.storage, &src_chain_id)?
+        .ok_or_else(|| ContractError::UnknownRoute {
+            chain_id: src_chain_id.clone(),
+        })?;
     let sender: String = match src_chain_type {
         1 => request_sender.to_lowercase(),
         _ => request_sender.clone(),
@@ -32,25 +36,29 @@ pub fn handle_sudo_request(
     is_white_listed_modifier(deps.as_ref(), &src_chain_id, &sender)?;
     deps.api.debug("Request Coming from whitelisted Contract");
     // bytes memory packet = abi.encode(recipient, amount);
-    let token_vec = match decode(&[ParamType::Bytes, ParamType::Uint(128)], &payload.0) {
-        Ok(data) => data,
-        Err(_) => {
-            return Err(StdError::GenericErr {
-                msg: String::from("err.into()"),
-            });
-        }
-    };
+    let token_vec = decode(&[ParamType::Bytes, ParamType::Uint(128)], &payload.0)?;
 
-    let u128_val: u128 = token_vec[1].clone().into_uint().unwrap().as_u128();
+    let u128_val: u128 = token_vec[1]
+        .clone()
+        .into_uint()
+        .ok_or(ContractError::from(ethabi::Error::InvalidData))?
+        .as_u128();
     let amount = Uint128::new(u128_val);
-    let addr: Bytes = token_vec[0].clone().into_bytes().unwrap();
+    let addr: Bytes = token_vec[0]
+        .clone()
+        .into_bytes()
+        .ok_or(ContractError::from(ethabi::Error::InvalidData))?;
 
     let recipient =
         convert_address_from_bytes_to_string(&addr, ChainType::ChainTypeCosmos.get_chain_code())?;
     let info_str: String = format!("recipient {:?}, amount {:?}", recipient, amount);
     deps.api.debug(&info_str);
 
-    deps.api.addr_validate(&recipient)?;
+    deps.api
+        .addr_validate(&recipient)
+        .map_err(|_| ContractError::InvalidAddress {
+            address: recipient.clone(),
+        })?;
     let mint_msg = cw20_base::msg::ExecuteMsg::Mint { recipient, amount };
 
     let xerc20_token: String = CROSS_CHAIN_TOKEN.load(deps.storage)?;
@@ -77,7 +85,7 @@ pub fn handle_sudo_ack(
     exec_flag: bool,
     exec_data: Binary,
     _refund_amount: Coin,
-) -> StdResult<Response<RouterMsg>> {
+) -> Result<Response<RouterMsg>, ContractError> {
     let info_str: String = format!(
         "handle_sudo_ack, request_identifier {:?}, exec_data {:?}",
         request_identifier, exec_data
@@ -1,4 +1,5 @@
 pub mod contract;
+pub mod error;
 pub mod execution;
 pub mod handle_sudo_execution;
 pub mod modifiers;
@@ -7,4 +8,6 @@ pub mod state;
 
 pub use serde::{Deserialize, Serialize};
 #[cfg(test)]
+mod multitest;
+#[cfg(test)]
 mod tests;
@@ -1,17 +1,12 @@
-use cosmwasm_std::{Deps, MessageInfo, StdError, StdResult};
+use cosmwasm_std::{Deps, MessageInfo};
 use router_wasm_bindings::RouterQuery;
 
-use crate::{query::is_white_listed_contract, state::OWNER};
+use crate::{error::ContractError, query::is_white_listed_contract, state::OWNER};
 
-pub fn is_owner_modifier(deps: Deps<RouterQuery>, info: &MessageInfo) -> StdResult<()> {
-    let owner: String = match OWNER.load(deps.storage) {
-        Ok(owner) => owner,
-        Err(err) => return StdResult::Err(err),
-    };
+pub fn is_owner_modifier(deps: Deps<RouterQuery>, info: &MessageInfo) -> Result<(), ContractError> {
+    let owner: String = OWNER.load(deps.storage)?;
     if owner != info.sender {
-        return StdResult::Err(StdError::GenericErr {
-            msg: String::from("Auth: Invalid Owner"),
-        });
+        return Err(ContractError::Unauthorized {});
     }
     Ok(())
 }
@@ -20,7 +15,7 @@ pub fn is_white_listed_modifier(
     deps: Deps<RouterQuery>,
     chain_id: &str,
     contract: &str,
-) -> StdResult<()> {
+) -> Result<(), ContractError> {
     let is_white_listed_contract = is_white_listed_contract(deps, chain_id, contract);
     let info_str: String = format!("--chain_id: {:?}, contract: {:?}", chain_id, contract);
     deps.api.debug(&info_str);
@@ -30,7 +25,10 @@ pub fn is_white_listed_modifier(
             chain_id, contract
         );
         deps.api.debug(&info_str);
-        return StdResult::Err(StdError::GenericErr { msg: info_str });
+        return Err(ContractError::InvalidRequestSender {
+            src_chain_id: chain_id.to_string(),
+            request_sender: contract.to_string(),
+        });
     }
     Ok(())
 }
@@ -0,0 +1,208 @@
+use cosmwasm_std::{Addr, Binary, DepsMut, Env, MessageInfo, Response, Uint128};
+use cw20::{BalanceResponse, Cw20ExecuteMsg, Cw20QueryMsg};
+use cw_multi_test::{Contract, ContractWrapper, Executor};
+use new_crosstalk_sample::xerc20::{
+    ChainTypeInfo, ContractInfo, ExecuteMsg, InstantiateMsg, QueryMsg,
+};
+use router_multi_test::{
+    customize_response, outbound_requests, relay, router_app, RouterApp, RouterModule,
+};
+use router_wasm_bindings::{RouterMsg, RouterQuery};
+
+use crate::contract::{execute, instantiate, query, reply, sudo};
+use crate::error::ContractError;
+
+const OWNER: &str = "owner";
+const USER: &str = "user";
+const RECEIVER: &str = "receiver";
+const SRC_CHAIN_ID: &str = "router_9000-1";
+const DEST_CHAIN_ID: &str = "osmo-test-5";
+
+fn instantiate_contract(
+    deps: DepsMut<RouterQuery>,
+    env: Env,
+    info: MessageInfo,
+    msg: InstantiateMsg,
+) -> Result<Response<RouterMsg>, ContractError> {
+    instantiate(deps, env, info, msg).map(customize_response)
+}
+
+fn xerc20_contract() -> Box<dyn Contract<RouterMsg, RouterQuery>> {
+    let contract = ContractWrapper::new(execute, instantiate_contract, query)
+        .with_reply(reply)
+        .with_sudo(sudo);
+    Box::new(contract)
+}
+
+fn cw20_contract() -> Box<dyn Contract<RouterMsg, RouterQuery>> {
+    let contract = ContractWrapper::new_with_empty(
+        cw20_base::contract::execute,
+        cw20_base::contract::instantiate,
+        cw20_base::contract::query,
+    );
+    Box::new(contract)
+}
+
+// deploys a xerc20 and its token on chain_id, returns (xerc20, token)
+fn deploy(
+    app: &mut RouterApp,
+    xerc20_code_id: u64,
+    cw20_code_id: u64,
+    chain_id: &str,
+) -> (Addr, Addr) {
+    let owner: Addr = Addr::unchecked(OWNER);
+    let msg: InstantiateMsg = InstantiateMsg {
+        cw20_code_id,
+        token_name: String::from("Cross Chain Token"),
+        token_symbol: String::from("XERC"),
+    };
+    let xerc20 = app
+        .instantiate_contract(xerc20_code_id, owner.clone(), &msg, &[], "xerc20", None)
+        .unwrap();
+    let token: String = app
+        .wrap()
+        .query_wasm_smart(&xerc20, &QueryMsg::FetchXerc20 {})
+        .unwrap();
+
+    let chain_types: ExecuteMsg = ExecuteMsg::SetChainTypes {
+        chain_type_info: vec![
+            ChainTypeInfo {
+                chain_id: SRC_CHAIN_ID.to_string(),
+                chain_type: 2,
+            },
+            ChainTypeInfo {
+                chain_id: DEST_CHAIN_ID.to_string(),
+                chain_type: 2,
+            },
+        ],
+    };
+    app.execute_contract(owner.clone(), xerc20.clone(), &chain_types, &[])
+        .unwrap();
+    let set_chain_id: ExecuteMsg = ExecuteMsg::SetChainId {
+        id: chain_id.to_string(),
+    };
+    app.execute_contract(owner, xerc20.clone(), &set_chain_id, &[])
+        .unwrap();
+    (xerc20, Addr::unchecked(token))
+}
+
+fn whitelist(app: &mut RouterApp, xerc20: &Addr, chain_id: &str, contract: &Addr) {
+    let msg: ExecuteMsg = ExecuteMsg::SetWhiteListedContracts {
+        contracts: vec![ContractInfo {
+            chain_id: chain_id.to_string(),
+            contract_addr: contract.to_string(),
+        }],
+    };
+    app.execute_contract(Addr::unchecked(OWNER), xerc20.clone(), &msg, &[])
+        .unwrap();
+}
+
+fn balance(app: &RouterApp, token: &Addr, address: &str) -> Uint128 {
+    let msg: Cw20QueryMsg = Cw20QueryMsg::Balance {
+        address: address.to_string(),
+    };
+    let response: BalanceResponse = app.wrap().query_wasm_smart(token, &msg).unwrap();
+    response.balance
+}
+
+#[test]
+fn test_transfer_cross_chain_round_trip() {
+    let module: RouterModule = RouterModule::new()
+        .with_gas_price(SRC_CHAIN_ID, 10_000_000)
+        .with_gas_price(DEST_CHAIN_ID, 25_000_000);
+    let mut app: RouterApp = router_app(module);
+    let xerc20_code_id: u64 = app.store_code(xerc20_contract());
+    let cw20_code_id: u64 = app.store_code(cw20_contract());
+    let (src, src_token) = deploy(&mut app, xerc20_code_id, cw20_code_id, SRC_CHAIN_ID);
+    let (dest, dest_token) = deploy(&mut app, xerc20_code_id, cw20_code_id, DEST_CHAIN_ID);
+    whitelist(&mut app, &src, DEST_CHAIN_ID, &dest);
+    whitelist(&mut app, &dest, SRC_CHAIN_ID, &src);
+
+    let mint: ExecuteMsg = ExecuteMsg::Mint {
+        recipient: USER.to_string(),
+        amount: Uint128::new(100),
+    };
+    app.execute_contract(Addr::unchecked(OWNER), src.clone(), &mint, &[])
+        .unwrap();
+    // the xerc20 burns the transferred amount from the user
+    let allowance: Cw20ExecuteMsg = Cw20ExecuteMsg::IncreaseAllowance {
+        spender: src.to_string(),
+        amount: Uint128::new(40),
+        expires: None,
+    };
+    app.execute_contract(Addr::unchecked(USER), src_token.clone(), &allowance, &[])
+        .unwrap();
+
+    let transfer: ExecuteMsg = ExecuteMsg::TrasferCrossChain {
+        amount: Uint128::new(40),
+        recipient: Binary(RECEIVER.as_bytes().to_vec()),
+        dest_chain_id: DEST_CHAIN_ID.to_string(),
+    };
+    let res = app
+        .execute_contract(Addr::unchecked(USER), src.clone(), &transfer, &[])
+        .unwrap();
+    assert_eq!(balance(&app, &src_token, USER), Uint128::new(60));
+
+    let requests = outbound_requests(&res).unwrap();
+    assert_eq!(requests.len(), 1);
+    assert_eq!(requests[0].dest_chain_id, DEST_CHAIN_ID);
+    assert_eq!(
+        requests[0].request_packet.dest_contract_address,
+        dest.to_string()
+    );
+
+    let delivery = relay(&mut app, &requests[0], SRC_CHAIN_ID).unwrap();
+    assert!(delivery.receive.is_ok());
+    assert_eq!(balance(&app, &dest_token, RECEIVER), Uint128::new(40));
+    let status = delivery
+        .ack
+        .events
+        .iter()
+        .find(|event| event.ty == "wasm-ExecutionStatus")
+        .unwrap();
+    assert!(status
+        .attributes
+        .iter()
+        .any(|attribute| attribute.key == "execFlag" && attribute.value == "true"));
+}
+
+#[test]
+fn test_transfer_cross_chain_from_unknown_sender() {
+    let module: RouterModule = RouterModule::new()
+        .with_gas_price(SRC_CHAIN_ID, 10_000_000)
+        .with_gas_price(DEST_CHAIN_ID, 25_000_000);
+    let mut app: RouterApp = router_app(module);
+    let xerc20_code_id: u64 = app.store_code(xerc20_contract());
+    let cw20_code_id: u64 = app.store_code(cw20_contract());
+    let (src, src_token) = deploy(&mut app, xerc20_code_id, cw20_code_id, SRC_CHAIN_ID);
+    let (dest, dest_token) = deploy(&mut app, xerc20_code_id, cw20_code_id, DEST_CHAIN_ID);
+    // the destination does not whitelist the source contract
+    whitelist(&mut app, &src, DEST_CHAIN_ID, &dest);
+
+    let mint: ExecuteMsg = ExecuteMsg::Mint {
+        recipient: USER.to_string(),
+        amount: Uint128::new(100),
+    };
+    app.execute_contract(Addr::unchecked(OWNER), src.clone(), &mint, &[])
+        .unwrap();
+    let allowance: Cw20ExecuteMsg = Cw20ExecuteMsg::IncreaseAllowance {
+        spender: src.to_string(),
+        amount: Uint128::new(40),
+        expires: None,
+    };
+    app.execute_contract(Addr::unchecked(USER), src_token, &allowance, &[])
+        .unwrap();
+    let transfer: ExecuteMsg = ExecuteMsg::TrasferCrossChain {
+        amount: Uint128::new(40),
+        recipient: Binary(RECEIVER.as_bytes().to_vec()),
+        dest_chain_id: DEST_CHAIN_ID.to_string(),
+    };
+    let res = app
+        .execute_contract(Addr::unchecked(USER), src, &transfer, &[])
+        .unwrap();
+
+    let requests = outbound_requests(&res).unwrap();
+    let delivery = relay(&mut app, &requests[0], SRC_CHAIN_ID).unwrap();
+    assert!(delivery.receive.is_err());
+    assert_eq!(balance(&app, &dest_token, RECEIVER), Uint128::zero());
+}
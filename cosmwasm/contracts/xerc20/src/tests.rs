@@ -4,14 +4,19 @@ use std::marker::PhantomData;
 use std::vec;
 
 use crate::contract::instantiate;
-use crate::contract::{execute, sudo};
+use crate::contract::{execute, migrate, sudo};
+use crate::error::ContractError;
+use crate::query::fetch_chain_id;
 use cosmwasm_std::testing::{MockApi, MockQuerier, MockStorage};
 use cosmwasm_std::{
     testing::{mock_env, mock_info},
     DepsMut,
 };
 use cosmwasm_std::{Binary, Coin, OwnedDeps, Uint128};
-use new_crosstalk_sample::xerc20::{ChainTypeInfo, ExecuteMsg, InstantiateMsg};
+use new_crosstalk_sample::migration::MigrationError;
+use new_crosstalk_sample::xerc20::{
+    ChainTypeInfo, ContractInfo, ExecuteMsg, InstantiateMsg, MigrateMsg,
+};
 use router_wasm_bindings::ethabi::{decode, ParamType};
 use router_wasm_bindings::types::{AckType, ChainType, RequestMetaData};
 use router_wasm_bindings::utils::{
@@ -216,3 +221,97 @@ fn test_encode() {
     let bin: Binary = Binary(rm.get_abi_encoded_bytes());
     println!("{:?}", hex::encode(bin.0));
 }
+
+#[test]
+fn test_contract_errors() {
+    let mut deps = OwnedDeps {
+        storage: MockStorage::default(),
+        api: MockApi::default(),
+        querier: MockQuerier::default(),
+        custom_query_type: PhantomData,
+    };
+    do_instantiate(deps.as_mut());
+    let env = mock_env();
+
+    let set_chain_id: ExecuteMsg = ExecuteMsg::SetChainId {
+        id: "router_9000-1".to_string(),
+    };
+    let err = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("attacker", &[]),
+        set_chain_id,
+    )
+    .unwrap_err();
+    assert!(matches!(err, ContractError::Unauthorized {}));
+
+    let msg: SudoMsg = SudoMsg::HandleIReceive {
+        request_sender: BRIDGE_ADDRESS.into(),
+        src_chain_id: String::from("1"),
+        request_identifier: 2,
+        payload: Binary::default(),
+    };
+    let err = sudo(deps.as_mut(), env.clone(), msg).unwrap_err();
+    assert!(matches!(err, ContractError::UnknownRoute { chain_id } if chain_id == "1"));
+
+    let whitelist: ExecuteMsg = ExecuteMsg::SetWhiteListedContracts {
+        contracts: vec![ContractInfo {
+            chain_id: "80001".to_string(),
+            contract_addr: BRIDGE_ADDRESS.to_string(),
+        }],
+    };
+    execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info(INIT_ADDRESS, &[]),
+        whitelist,
+    )
+    .unwrap();
+
+    let msg: SudoMsg = SudoMsg::HandleIReceive {
+        request_sender: String::from("0x0000000000000000000000000000000000000001"),
+        src_chain_id: String::from("80001"),
+        request_identifier: 2,
+        payload: Binary::default(),
+    };
+    let err = sudo(deps.as_mut(), env.clone(), msg).unwrap_err();
+    assert!(matches!(err, ContractError::InvalidRequestSender { .. }));
+
+    let msg: SudoMsg = SudoMsg::HandleIReceive {
+        request_sender: BRIDGE_ADDRESS.into(),
+        src_chain_id: String::from("80001"),
+        request_identifier: 2,
+        payload: Binary(vec![1, 2, 3]),
+    };
+    let err = sudo(deps.as_mut(), env, msg).unwrap_err();
+    assert!(matches!(err, ContractError::Decode { .. }));
+}
+
+#[test]
+fn test_migrate() {
+    let mut deps = OwnedDeps {
+        storage: MockStorage::default(),
+        api: MockApi::default(),
+        querier: MockQuerier::default(),
+        custom_query_type: PhantomData,
+    };
+    do_instantiate(deps.as_mut());
+    let env = mock_env();
+
+    cw2::set_contract_version(deps.as_mut().storage, "XERC20", "0.1.0").unwrap();
+    let msg = MigrateMsg {
+        chain_id: Some(String::from("router_9000-1")),
+    };
+    migrate(deps.as_mut(), env.clone(), msg).unwrap();
+    assert_eq!(fetch_chain_id(deps.as_ref()).unwrap(), "router_9000-1");
+    let version = cw2::get_contract_version(deps.as_ref().storage).unwrap();
+    assert_eq!(version.version, "0.1.1");
+
+    // "0.1.10" > "0.1.1" in semver, not in string order
+    cw2::set_contract_version(deps.as_mut().storage, "XERC20", "0.1.10").unwrap();
+    let err = migrate(deps.as_mut(), env, MigrateMsg::default()).unwrap_err();
+    assert!(matches!(
+        err,
+        ContractError::Migration(MigrationError::Downgrade { .. })
+    ));
+}
@@ -1,8 +1,6 @@
 use cosmwasm_std::Empty;
 #[cfg(not(feature = "library"))]
-use cosmwasm_std::{
-    entry_point, Binary, Deps, DepsMut, Env, MessageInfo, Response, StdError, StdResult,
-};
+use cosmwasm_std::{entry_point, Binary, Deps, DepsMut, Env, MessageInfo, Response};
 use cw2::set_contract_version;
 use cw721::ContractInfoResponse;
 use cw721_base::MintMsg;
@@ -10,11 +8,14 @@ use cw721_base::MintMsg;
 use router_wasm_bindings::{RouterMsg, RouterQuery, SudoMsg};
 
 use crate::{
-    execution::{handle_execute, handle_sudo, Cw721ExecuteMsg, Cw721NFTContract, Cw721QueryMsg},
+    error::ContractError,
+    execution::{handle_execute, Cw721ExecuteMsg, Cw721NFTContract, Cw721QueryMsg, XErc721},
     query::handle_query,
     state::OWNER,
 };
 
+use new_crosstalk_sample::crosstalk::handle_sudo;
+use new_crosstalk_sample::migration::migrate_contract;
 use new_crosstalk_sample::xerc721::{InstantiateMsg, MigrateMsg, QueryMsg};
 
 // version info for migration info
@@ -27,7 +28,7 @@ pub fn instantiate(
     _env: Env,
     info: MessageInfo,
     msg: InstantiateMsg,
-) -> StdResult<Response> {
+) -> Result<Response, ContractError> {
     deps.api.debug("Instantiating the contract🚀");
 
     // Store state with owner address
@@ -40,7 +41,12 @@ pub fn instantiate(
     //     symbol: msg.symbol,
     // };
     // tract.contract_info.save(deps.storage, &info)?;
-    let minter = deps.api.addr_validate(&msg.minter)?;
+    let minter =
+        deps.api
+            .addr_validate(&msg.minter)
+            .map_err(|_| ContractError::InvalidAddress {
+                address: msg.minter.clone(),
+            })?;
     tract.minter.save(deps.storage, &minter)?;
 
     set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
@@ -52,10 +58,7 @@ pub fn instantiate(
         owner: minter.into_string(),
         extension: Empty {},
     };
-    match tract.mint(deps, _env, info, mint_msg) {
-        Err(_) => return Err(StdError::GenericErr { msg: "".into() }),
-        _ => {}
-    }
+    tract.mint(deps, _env, info, mint_msg)?;
 
     Ok(Response::new().add_attribute("action", "xcw721-init"))
 }
@@ -66,20 +69,31 @@ pub fn execute(
     env: Env,
     info: MessageInfo,
     msg: Cw721ExecuteMsg,
-) -> StdResult<Response<RouterMsg>> {
+) -> Result<Response<RouterMsg>, ContractError> {
     handle_execute(deps, env, info, msg)
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn migrate(deps: DepsMut<RouterQuery>, env: Env, _msg: MigrateMsg) -> StdResult<Response> {
-    let ver: cw2::ContractVersion = cw2::get_contract_version(deps.storage)?;
-    // ensure we are migrating from an allowed contract
-    if ver.contract != CONTRACT_NAME.to_string() {
-        return Err(StdError::generic_err("Can only upgrade from same type").into());
-    }
-    // note: better to do proper semver compare, but string compare *usually* works
-    if ver.version >= CONTRACT_VERSION.to_string() {
-        return Err(StdError::generic_err("Cannot upgrade from a newer version").into());
+pub fn migrate(
+    deps: DepsMut<RouterQuery>,
+    env: Env,
+    msg: MigrateMsg,
+) -> Result<Response, ContractError> {
+    let applied: Vec<&str> = migrate_contract::<MigrateMsg, ContractError>(
+        deps.storage,
+        CONTRACT_NAME,
+        CONTRACT_VERSION,
+        &[],
+        &msg,
+    )?;
+
+    if let Some(owner) = msg.owner {
+        deps.api
+            .addr_validate(&owner)
+            .map_err(|_| ContractError::InvalidAddress {
+                address: owner.clone(),
+            })?;
+        OWNER.save(deps.storage, &owner)?;
     }
 
     let info_str: String = format!(
@@ -89,16 +103,22 @@ pub fn migrate(deps: DepsMut<RouterQuery>, env: Env, _msg: MigrateMsg) -> StdRes
         CONTRACT_NAME.to_string()
     );
     deps.api.debug(&info_str);
-    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
-    Ok(Response::default())
+    Ok(Response::new()
+        .add_attribute("action", "migrate")
+        .add_attribute("version", CONTRACT_VERSION)
+        .add_attribute("migrations", applied.join(",")))
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn query(deps: Deps, env: Env, msg: Cw721QueryMsg) -> StdResult<Binary> {
-    handle_query(deps, env, msg)
+pub fn query(deps: Deps, env: Env, msg: Cw721QueryMsg) -> Result<Binary, ContractError> {
+    Ok(handle_query(deps, env, msg)?)
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn sudo(deps: DepsMut<RouterQuery>, env: Env, msg: SudoMsg) -> StdResult<Response<RouterMsg>> {
-    handle_sudo(deps, env, msg)
+pub fn sudo(
+    deps: DepsMut<RouterQuery>,
+    env: Env,
+    msg: SudoMsg,
+) -> Result<Response<RouterMsg>, ContractError> {
+    handle_sudo::<XErc721>(deps, env, msg)
 }
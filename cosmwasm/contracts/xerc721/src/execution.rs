@@ -1,16 +1,20 @@
-use cosmwasm_std::{
-    Binary, Deps, DepsMut, Empty, Env, MessageInfo, Response, StdError, StdResult, Uint128,
-};
+use cosmwasm_std::{Binary, Deps, DepsMut, Empty, Env, MessageInfo, Response, Storage};
 use cw721_base::{state::TokenInfo, Cw721Contract};
+use new_crosstalk_sample::crosstalk::{
+    CrosstalkDapp, ISendRequest, InboundRequest, RequestAck, RequestPacket,
+};
 use new_crosstalk_sample::xerc721::{ExecuteMsg, QueryMsg, TransferParams};
 // use rand::Rng;
 use router_wasm_bindings::{
-    ethabi::{decode, encode, ParamType, Token},
+    ethabi::{self, decode, encode, ParamType, Token},
     types::RequestMetaData,
-    Bytes, RouterMsg, RouterQuery, SudoMsg,
+    RouterMsg, RouterQuery,
 };
 
-use crate::state::{OWNER, REMOTE_CONTRACT_MAPPING};
+use crate::{
+    error::ContractError,
+    state::{OWNER, REMOTE_CONTRACT_MAPPING},
+};
 pub type Cw721NFTContract<'a> = Cw721Contract<'a, Empty, Empty, ExecuteMsg, QueryMsg>;
 pub type Cw721ExecuteMsg = cw721_base::ExecuteMsg<Empty, ExecuteMsg>;
 pub type Cw721QueryMsg = cw721_base::QueryMsg<QueryMsg>;
@@ -20,7 +24,7 @@ pub fn handle_execute(
     env: Env,
     info: MessageInfo,
     msg: Cw721ExecuteMsg,
-) -> StdResult<Response<RouterMsg>> {
+) -> Result<Response<RouterMsg>, ContractError> {
     match msg {
         Cw721ExecuteMsg::Extension { msg } => match msg {
             ExecuteMsg::EnrollRemoteContract {
@@ -42,27 +46,19 @@ pub fn handle_execute(
                 request_metadata,
             ),
         },
-        _ => match Cw721NFTContract::default().execute(deps, env, info, msg) {
-            Ok(cw721_res) => {
-                let response: Response<RouterMsg> = Response::<RouterMsg>::new()
-                    .add_attributes(cw721_res.attributes)
-                    .add_events(cw721_res.events);
-                return Ok(response);
-            }
-            Err(err) => {
-                return Err(StdError::GenericErr {
-                    msg: err.to_string(),
-                })
-            }
-        },
+        _ => {
+            let cw721_res = Cw721NFTContract::default().execute(deps, env, info, msg)?;
+            let response: Response<RouterMsg> = Response::<RouterMsg>::new()
+                .add_attributes(cw721_res.attributes)
+                .add_events(cw721_res.events);
+            Ok(response)
+        }
     }
 }
 
-pub fn only_owner(deps: Deps, info: MessageInfo) -> StdResult<Response<RouterMsg>> {
-    if info.sender.to_string() != OWNER.load(deps.storage).unwrap() {
-        return Err(StdError::GenericErr {
-            msg: "Auth: Invalid Owner".into(),
-        });
+pub fn only_owner(deps: Deps, info: MessageInfo) -> Result<Response<RouterMsg>, ContractError> {
+    if info.sender.to_string() != OWNER.load(deps.storage)? {
+        return Err(ContractError::Unauthorized {});
     } else {
         Ok(Response::new())
     }
@@ -74,12 +70,20 @@ pub fn enroll_remote_contract(
     info: MessageInfo,
     chain_id: String,
     remote_address: String,
-) -> StdResult<Response<RouterMsg>> {
+) -> Result<Response<RouterMsg>, ContractError> {
     only_owner(deps.as_ref(), info)?;
     REMOTE_CONTRACT_MAPPING.save(deps.storage, chain_id, &remote_address)?;
     Ok(Response::new())
 }
 
+fn fetch_remote_contract(storage: &dyn Storage, chain_id: &str) -> Result<String, ContractError> {
+    REMOTE_CONTRACT_MAPPING
+        .may_load(storage, chain_id.to_string())?
+        .ok_or_else(|| ContractError::UnknownRoute {
+            chain_id: chain_id.to_string(),
+        })
+}
+
 pub fn transfer_crosschain(
     deps: DepsMut,
     _env: Env,
@@ -88,73 +92,65 @@ pub fn transfer_crosschain(
     token_id: u64,
     recipient: String,
     request_metadata: RequestMetaData,
-) -> StdResult<Response<RouterMsg>> {
+) -> Result<Response<RouterMsg>, ContractError> {
     let tract = Cw721NFTContract::default();
     let rider_info = tract.tokens.load(deps.storage, &token_id.to_string())?;
-    match tract.check_can_send(deps.as_ref(), &_env, &info, &rider_info) {
-        Err(_) => {
-            return Err(StdError::GenericErr {
-                msg: "ContractError::Unauthorized".to_string(),
-            })
-        }
-        _ => (),
+    if tract
+        .check_can_send(deps.as_ref(), &_env, &info, &rider_info)
+        .is_err()
+    {
+        return Err(ContractError::Unauthorized {});
     }
 
     // burn nft
     tract.tokens.remove(deps.storage, &token_id.to_string())?;
     tract.decrement_tokens(deps.storage)?;
 
-    let dst_contract_add: String = REMOTE_CONTRACT_MAPPING
-        .load(deps.storage, dst_chain_id.clone())
-        .unwrap();
+    let dst_contract_add: String = fetch_remote_contract(deps.storage, &dst_chain_id)?;
     let transfer_params = TransferParams {
         nft_id: token_id,
         recipient,
     };
 
     let encoded_payload: Vec<u8> = encode(&[transfer_params.get_evm_encoding()?]);
-    let request_packet: Bytes = encode(&[
-        Token::String(dst_contract_add),
-        Token::Bytes(encoded_payload),
-    ]);
-
-    let i_send_request: RouterMsg = RouterMsg::CrosschainCall {
-        version: 1,
-        route_amount: Uint128::new(0u128),
-        route_recipient: String::from(""),
-        dest_chain_id: dst_chain_id.clone(),
-        request_metadata: request_metadata.get_abi_encoded_bytes(),
+    let request_packet: RequestPacket = RequestPacket::new(dst_contract_add, encoded_payload);
+
+    let i_send_request: RouterMsg = ISendRequest::new(
+        dst_chain_id,
         request_packet,
-    };
+        request_metadata.get_abi_encoded_bytes(),
+    )
+    .into_msg();
 
     Ok(Response::new().add_message(i_send_request))
 }
 
-pub fn handle_sudo(
-    deps: DepsMut<RouterQuery>,
-    env: Env,
-    msg: SudoMsg,
-) -> StdResult<Response<RouterMsg>> {
-    match msg {
-        SudoMsg::HandleIReceive {
-            request_sender,
-            src_chain_id,
-            request_identifier,
-            payload,
-        } => handle_sudo_request(
+pub struct XErc721;
+
+impl CrosstalkDapp for XErc721 {
+    type Error = ContractError;
+
+    fn on_receive(
+        deps: DepsMut<RouterQuery>,
+        env: Env,
+        request: InboundRequest,
+    ) -> Result<Response<RouterMsg>, ContractError> {
+        handle_sudo_request(
             deps,
             env,
-            request_sender,
-            src_chain_id,
-            request_identifier,
-            payload,
-        ),
-        SudoMsg::HandleIAck {
-            request_identifier: _,
-            exec_flag: _,
-            exec_data: _,
-            refund_amount: _,
-        } => Ok(Response::new()),
+            request.request_sender,
+            request.src_chain_id,
+            request.request_identifier,
+            request.payload,
+        )
+    }
+
+    fn on_ack(
+        _deps: DepsMut<RouterQuery>,
+        _env: Env,
+        _ack: RequestAck,
+    ) -> Result<Response<RouterMsg>, ContractError> {
+        Ok(Response::new())
     }
 }
 
@@ -165,34 +161,34 @@ pub fn handle_sudo_request(
     src_chain_id: String,
     _request_identifier: u64,
     payload: Binary,
-) -> StdResult<Response<RouterMsg>> {
-    let remote_contract_add = REMOTE_CONTRACT_MAPPING
-        .load(deps.storage, src_chain_id.clone())
-        .unwrap();
+) -> Result<Response<RouterMsg>, ContractError> {
+    let remote_contract_add: String = fetch_remote_contract(deps.storage, &src_chain_id)?;
 
     if remote_contract_add != request_sender {
-        return Err(StdError::GenericErr {
-            msg: "Invalid sender".to_string(),
+        return Err(ContractError::InvalidRequestSender {
+            src_chain_id,
+            request_sender,
         });
     }
 
     let params = TransferParams::get_params_types();
     let param_vec: Vec<ParamType> = vec![params];
-    let token_vec = match decode(&param_vec, &payload.0) {
-        Ok(data) => data,
-        Err(_) => {
-            return Err(StdError::GenericErr {
-                msg: String::from("err.into()"),
-            })
-        }
-    };
-    let transfer_params_tokens: Vec<Token> = token_vec[0].clone().into_tuple().unwrap();
+    let token_vec = decode(&param_vec, &payload.0)?;
+    let transfer_params_tokens: Vec<Token> = token_vec[0]
+        .clone()
+        .into_tuple()
+        .ok_or(ContractError::from(ethabi::Error::InvalidData))?;
     let transfer_params: TransferParams = TransferParams::from_token_tuple(transfer_params_tokens)?;
 
     // mint nft
     let tract = Cw721NFTContract::default();
     let token_info = TokenInfo {
-        owner: deps.api.addr_validate(&transfer_params.recipient)?,
+        owner: deps
+            .api
+            .addr_validate(&transfer_params.recipient)
+            .map_err(|_| ContractError::InvalidAddress {
+                address: transfer_params.recipient.clone(),
+            })?,
         approvals: vec![],
         token_uri: None,
         extension: Empty {},
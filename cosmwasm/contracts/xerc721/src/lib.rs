@@ -1,4 +1,5 @@
 pub mod contract;
+pub mod error;
 pub mod execution;
 pub mod query;
 pub mod state;
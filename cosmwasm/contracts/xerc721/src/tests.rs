@@ -1,10 +1,13 @@
-use crate::contract::{execute, instantiate, query};
+use crate::contract::{execute, instantiate, migrate, query};
+use crate::error::ContractError;
 use crate::execution::{Cw721ExecuteMsg, Cw721QueryMsg};
+use crate::state::OWNER;
 use cw721::{NftInfoResponse, OwnerOfResponse};
 use cw721_base::MintMsg;
-use new_crosstalk_sample::xerc721::{ExecuteMsg, InstantiateMsg, QueryMsg};
+use new_crosstalk_sample::migration::MigrationError;
+use new_crosstalk_sample::xerc721::{ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg};
 use router_wasm_bindings::types::RequestMetaData;
-use router_wasm_bindings::RouterMsg;
+use router_wasm_bindings::{RouterMsg, RouterQuery};
 
 use cosmwasm_std::testing::{
     mock_dependencies, mock_env, mock_info, MockApi, MockQuerier, MockStorage,
@@ -212,3 +215,89 @@ fn test_transfer_crosschain() {
     let response = get_nft_info(deps.as_ref(), env, "2".into());
     assert!(response.is_err());
 }
+
+#[test]
+fn test_contract_errors() {
+    let mut deps = mock_dependencies();
+    let env = mock_env();
+    let info = mock_info(SENDER, &[]);
+    do_instantiate(deps.as_mut());
+
+    // only the owner can enroll remote contracts
+    let enroll_msg = cw721_base::ExecuteMsg::Extension {
+        msg: ExecuteMsg::EnrollRemoteContract {
+            chain_id: "1".into(),
+            remote_address: "0x1C609537a32630c054202e2B089B9Da268667C5D".into(),
+        },
+    };
+    let err = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("attacker", &[]),
+        enroll_msg,
+    )
+    .unwrap_err();
+    assert!(matches!(err, ContractError::Unauthorized {}));
+
+    // transfers towards a chain without a remote contract are rejected
+    let request_metadata: RequestMetaData = RequestMetaData {
+        dest_gas_limit: 0,
+        ack_gas_limit: 0,
+        dest_gas_price: 0,
+        ack_gas_price: 0,
+        relayer_fee: Uint128::from(0u32),
+        ack_type: router_wasm_bindings::types::AckType::AckOnBoth,
+        is_read_call: false,
+        asm_address: "".into(),
+    };
+    let exec_msg = Cw721ExecuteMsg::Extension {
+        msg: ExecuteMsg::TransferCrossChain {
+            dst_chain_id: "1".into(),
+            token_id: 1,
+            recipient: "0x1C609537a32630c054202e2B089B9Da268667C5D".to_string(),
+            request_metadata,
+        },
+    };
+    let err = execute(deps.as_mut(), env.clone(), info, exec_msg).unwrap_err();
+    assert!(matches!(err, ContractError::UnknownRoute { chain_id } if chain_id == "1"));
+}
+
+#[test]
+fn test_migrate() {
+    let mut deps: OwnedDeps<_, _, _, RouterQuery> = OwnedDeps {
+        storage: MockStorage::default(),
+        api: MockApi::default(),
+        querier: MockQuerier::default(),
+        custom_query_type: PhantomData,
+    };
+    let env = mock_env();
+
+    // fabricate a 0.9.0 deployment
+    OWNER
+        .save(deps.as_mut().storage, &SENDER.to_string())
+        .unwrap();
+    cw2::set_contract_version(deps.as_mut().storage, "xerc721", "0.9.0").unwrap();
+    let msg = MigrateMsg {
+        owner: Some("router1newowner".into()),
+    };
+    migrate(deps.as_mut(), env.clone(), msg).unwrap();
+    assert_eq!(
+        OWNER.load(deps.as_ref().storage).unwrap(),
+        "router1newowner"
+    );
+
+    // "10.0.0" > "1.0.0" in semver, not in string order
+    cw2::set_contract_version(deps.as_mut().storage, "xerc721", "10.0.0").unwrap();
+    let err = migrate(deps.as_mut(), env.clone(), MigrateMsg::default()).unwrap_err();
+    assert!(matches!(
+        err,
+        ContractError::Migration(MigrationError::Downgrade { .. })
+    ));
+
+    cw2::set_contract_version(deps.as_mut().storage, "PingPong", "0.1.0").unwrap();
+    let err = migrate(deps.as_mut(), env, MigrateMsg::default()).unwrap_err();
+    assert!(matches!(
+        err,
+        ContractError::Migration(MigrationError::InvalidContract { .. })
+    ));
+}
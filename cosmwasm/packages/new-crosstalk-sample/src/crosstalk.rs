@@ -0,0 +1,384 @@
+use cosmwasm_std::{
+    from_binary, Binary, Coin, DepsMut, Env, Reply, ReplyOn, Response, StdError, StdResult,
+    Storage, SubMsg, SubMsgResult, Uint128,
+};
+use cw_storage_plus::Map;
+use router_wasm_bindings::{
+    ethabi::{self, decode, encode, ethereum_types::U256, ParamType, Token},
+    types::{AckType, CrosschainRequestResponse, RequestMetaData},
+    Bytes, RouterMsg, RouterQuery, SudoMsg,
+};
+
+use crate::ping_pong::{PingPayload, PongReceipt};
+
+// version of the CrosschainCall message understood by the Router gateway
+pub const CROSSCHAIN_CALL_VERSION: u64 = 1;
+
+pub const DEFAULT_GAS_LIMIT: u64 = 200_000;
+
+/// Request received from another chain through `SudoMsg::HandleIReceive`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct InboundRequest {
+    pub request_sender: String,
+    pub src_chain_id: String,
+    pub request_identifier: u64,
+    pub payload: Binary,
+}
+
+/// Ack of a request sent by the dapp, received through `SudoMsg::HandleIAck`.
+/// `request_id` is the local request id the `request_identifier` was tracked
+/// with, `None` if the dapp does not track its requests or the ack is unknown.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RequestAck {
+    pub request_identifier: u64,
+    pub request_id: Option<u64>,
+    pub exec_flag: bool,
+    pub exec_data: Binary,
+    pub refund_amount: Coin,
+}
+
+/// Hooks of a crosstalk dapp. The Router gateway calls into the dapp through
+/// the `sudo` entry point, which only has to forward to `handle_sudo`.
+pub trait CrosstalkDapp {
+    type Error: From<StdError>;
+
+    /// Tracker used to resolve the local request id of the acks.
+    const TRACKER: Option<RequestTracker<'static>> = None;
+
+    fn on_receive(
+        deps: DepsMut<RouterQuery>,
+        env: Env,
+        request: InboundRequest,
+    ) -> Result<Response<RouterMsg>, Self::Error>;
+
+    fn on_ack(
+        deps: DepsMut<RouterQuery>,
+        env: Env,
+        ack: RequestAck,
+    ) -> Result<Response<RouterMsg>, Self::Error>;
+}
+
+pub fn handle_sudo<D: CrosstalkDapp>(
+    deps: DepsMut<RouterQuery>,
+    env: Env,
+    msg: SudoMsg,
+) -> Result<Response<RouterMsg>, D::Error> {
+    match msg {
+        SudoMsg::HandleIReceive {
+            request_sender,
+            src_chain_id,
+            request_identifier,
+            payload,
+        } => {
+            let request: InboundRequest = InboundRequest {
+                request_sender,
+                src_chain_id,
+                request_identifier,
+                payload,
+            };
+            D::on_receive(deps, env, request)
+        }
+        SudoMsg::HandleIAck {
+            request_identifier,
+            exec_flag,
+            exec_data,
+            refund_amount,
+        } => {
+            let request_id: Option<u64> = match D::TRACKER {
+                Some(tracker) => tracker.take(deps.storage, request_identifier)?,
+                None => None,
+            };
+            let ack: RequestAck = RequestAck {
+                request_identifier,
+                request_id,
+                exec_flag,
+                exec_data,
+                refund_amount,
+            };
+            D::on_ack(deps, env, ack)
+        }
+    }
+}
+
+/// Packet of an i_send request, `abi.encode(dest_contract_address, payload)`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RequestPacket {
+    pub dest_contract_address: String,
+    pub payload: Bytes,
+}
+
+impl RequestPacket {
+    pub fn new(dest_contract_address: impl Into<String>, payload: Bytes) -> Self {
+        Self {
+            dest_contract_address: dest_contract_address.into(),
+            payload,
+        }
+    }
+
+    pub fn encode(&self) -> Bytes {
+        encode(&[
+            Token::String(self.dest_contract_address.clone()),
+            Token::Bytes(self.payload.clone()),
+        ])
+    }
+
+    pub fn decode(data: &[u8]) -> Result<Self, ethabi::Error> {
+        let token_vec = decode(&[ParamType::String, ParamType::Bytes], data)?;
+        let dest_contract_address: String = token_vec[0]
+            .clone()
+            .into_string()
+            .ok_or(ethabi::Error::InvalidData)?;
+        let payload: Bytes = token_vec[1]
+            .clone()
+            .into_bytes()
+            .ok_or(ethabi::Error::InvalidData)?;
+        Ok(Self {
+            dest_contract_address,
+            payload,
+        })
+    }
+}
+
+/// Builder of the `RequestMetaData` of an i_send request. Starts from
+/// `DEFAULT_GAS_LIMIT` gas limits, zero gas prices and relayer fee, and
+/// `AckType::AckOnBoth`.
+#[derive(Clone, Debug)]
+pub struct RequestMetadataBuilder {
+    request_metadata: RequestMetaData,
+}
+
+impl Default for RequestMetadataBuilder {
+    fn default() -> Self {
+        Self {
+            request_metadata: RequestMetaData {
+                dest_gas_limit: DEFAULT_GAS_LIMIT,
+                dest_gas_price: 0,
+                ack_gas_limit: DEFAULT_GAS_LIMIT,
+                ack_gas_price: 0,
+                relayer_fee: Uint128::zero(),
+                ack_type: AckType::AckOnBoth,
+                is_read_call: false,
+                asm_address: String::default(),
+            },
+        }
+    }
+}
+
+impl RequestMetadataBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn dest_gas(mut self, gas_limit: u64, gas_price: u64) -> Self {
+        self.request_metadata.dest_gas_limit = gas_limit;
+        self.request_metadata.dest_gas_price = gas_price;
+        self
+    }
+
+    pub fn ack_gas(mut self, gas_limit: u64, gas_price: u64) -> Self {
+        self.request_metadata.ack_gas_limit = gas_limit;
+        self.request_metadata.ack_gas_price = gas_price;
+        self
+    }
+
+    pub fn relayer_fee(mut self, relayer_fee: Uint128) -> Self {
+        self.request_metadata.relayer_fee = relayer_fee;
+        self
+    }
+
+    pub fn ack_type(mut self, ack_type: AckType) -> Self {
+        self.request_metadata.ack_type = ack_type;
+        self
+    }
+
+    pub fn read_call(mut self, is_read_call: bool) -> Self {
+        self.request_metadata.is_read_call = is_read_call;
+        self
+    }
+
+    pub fn asm_address(mut self, asm_address: impl Into<String>) -> Self {
+        self.request_metadata.asm_address = asm_address.into();
+        self
+    }
+
+    pub fn build(self) -> RequestMetaData {
+        self.request_metadata
+    }
+
+    pub fn encode(self) -> Bytes {
+        self.request_metadata.get_abi_encoded_bytes()
+    }
+}
+
+/// i_send request to the Router gateway.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ISendRequest {
+    pub dest_chain_id: String,
+    pub request_packet: RequestPacket,
+    pub request_metadata: Bytes,
+    pub route_amount: Uint128,
+    pub route_recipient: String,
+}
+
+impl ISendRequest {
+    pub fn new(
+        dest_chain_id: impl Into<String>,
+        request_packet: RequestPacket,
+        request_metadata: Bytes,
+    ) -> Self {
+        Self {
+            dest_chain_id: dest_chain_id.into(),
+            request_packet,
+            request_metadata,
+            route_amount: Uint128::zero(),
+            route_recipient: String::default(),
+        }
+    }
+
+    /// Sends `route_amount` ROUTE along with the request to `route_recipient`.
+    pub fn with_route(mut self, route_amount: Uint128, route_recipient: impl Into<String>) -> Self {
+        self.route_amount = route_amount;
+        self.route_recipient = route_recipient.into();
+        self
+    }
+
+    pub fn into_msg(self) -> RouterMsg {
+        RouterMsg::CrosschainCall {
+            version: CROSSCHAIN_CALL_VERSION,
+            route_amount: self.route_amount,
+            route_recipient: self.route_recipient,
+            dest_chain_id: self.dest_chain_id,
+            request_metadata: self.request_metadata,
+            request_packet: self.request_packet.encode(),
+        }
+    }
+
+    /// Submessage replying on success, the reply carries the
+    /// `CrosschainRequestResponse` of the gateway.
+    pub fn into_sub_msg(self, reply_id: u64) -> SubMsg<RouterMsg> {
+        SubMsg {
+            id: reply_id,
+            msg: self.into_msg().into(),
+            gas_limit: None,
+            reply_on: ReplyOn::Success,
+        }
+    }
+}
+
+/// Extracts the gateway response from the reply of an i_send submessage,
+/// `None` if the submessage failed or returned no data.
+pub fn parse_i_send_reply(msg: &Reply) -> StdResult<Option<CrosschainRequestResponse>> {
+    match &msg.result {
+        SubMsgResult::Ok(msg_result) => match &msg_result.data {
+            Some(binary_data) => Ok(Some(from_binary(binary_data)?)),
+            None => Ok(None),
+        },
+        SubMsgResult::Err(_) => Ok(None),
+    }
+}
+
+/// Request resolved from the reply of an i_send submessage.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TrackedRequest {
+    pub request_id: u64,
+    pub request_identifier: Option<u64>,
+}
+
+/// Correlates the local request ids of a dapp with the request identifiers
+/// assigned by the gateway. The request id is registered under the reply id
+/// of its i_send submessage before sending it, and mapped to the gateway
+/// request identifier once the reply comes back in the same transaction.
+pub struct RequestTracker<'a> {
+    // reply id => requestId waiting for the reply of its i_send submessage
+    pending: Map<'a, u64, u64>,
+    // gateway request_identifier => requestId
+    identifiers: Map<'a, u64, u64>,
+}
+
+impl<'a> RequestTracker<'a> {
+    pub const fn new(pending_namespace: &'a str, identifiers_namespace: &'a str) -> Self {
+        Self {
+            pending: Map::new(pending_namespace),
+            identifiers: Map::new(identifiers_namespace),
+        }
+    }
+
+    pub fn track(
+        &self,
+        storage: &mut dyn Storage,
+        reply_id: u64,
+        request_id: u64,
+    ) -> StdResult<()> {
+        self.pending.save(storage, reply_id, &request_id)
+    }
+
+    /// Resolves the request of the reply, `None` if no request was tracked
+    /// under the reply id.
+    pub fn handle_reply(
+        &self,
+        storage: &mut dyn Storage,
+        msg: &Reply,
+    ) -> StdResult<Option<TrackedRequest>> {
+        let request_id: u64 = match self.pending.may_load(storage, msg.id)? {
+            Some(request_id) => request_id,
+            None => return Ok(None),
+        };
+        self.pending.remove(storage, msg.id);
+
+        let request_identifier: Option<u64> = match parse_i_send_reply(msg)? {
+            Some(response) => {
+                self.identifiers
+                    .save(storage, response.request_identifier, &request_id)?;
+                Some(response.request_identifier)
+            }
+            None => None,
+        };
+        Ok(Some(TrackedRequest {
+            request_id,
+            request_identifier,
+        }))
+    }
+
+    pub fn request_id(
+        &self,
+        storage: &dyn Storage,
+        request_identifier: u64,
+    ) -> StdResult<Option<u64>> {
+        self.identifiers.may_load(storage, request_identifier)
+    }
+
+    /// Returns the request id of the request identifier and stops tracking it.
+    pub fn take(
+        &self,
+        storage: &mut dyn Storage,
+        request_identifier: u64,
+    ) -> StdResult<Option<u64>> {
+        let request_id: Option<u64> = self.identifiers.may_load(storage, request_identifier)?;
+        if request_id.is_some() {
+            self.identifiers.remove(storage, request_identifier);
+        }
+        Ok(request_id)
+    }
+}
+
+/// Encodes the `(uint64 requestId, string message)` payload used by the
+/// sample dapps.
+pub fn encode_request_message(request_id: u64, message: &str) -> Bytes {
+    encode(&[
+        Token::Uint(U256::from(request_id)),
+        Token::String(message.to_string()),
+    ])
+}
+
+/// Decodes the `(requestId, message)` of a payload sent or acked by the
+/// sample dapps: a `PongReceipt`, a `PingPayload` of any version or the
+/// `(uint64 requestId, string message)` payload.
+pub fn decode_request_message(data: &[u8]) -> Result<(u64, String), ethabi::Error> {
+    if let Ok(receipt) = PongReceipt::decode(data) {
+        return Ok((receipt.request_id, receipt.message));
+    }
+    match PingPayload::decode(data) {
+        Ok(ping) => Ok((ping.request_id, ping.message)),
+        Err(_) => Err(ethabi::Error::InvalidData),
+    }
+}
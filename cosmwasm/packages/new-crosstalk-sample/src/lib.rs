@@ -1,3 +1,5 @@
+pub mod crosstalk;
+pub mod migration;
 pub mod ping_pong;
 pub mod test_dapp;
 pub mod xerc1155;
@@ -0,0 +1,76 @@
+use cosmwasm_std::{StdError, Storage};
+use cw2::{get_contract_version, set_contract_version};
+use semver::Version;
+use thiserror::Error;
+
+#[derive(Error, Debug, PartialEq)]
+pub enum MigrationError {
+    #[error("Can only upgrade from same type, expected {expected} found {found}")]
+    InvalidContract { expected: String, found: String },
+
+    #[error("Invalid semver version {version}")]
+    InvalidVersion { version: String },
+
+    #[error("Cannot upgrade from a newer version {from} to {to}")]
+    Downgrade { from: String, to: String },
+}
+
+/// State migration bringing the storage of a contract to `version`.
+/// `M` is the `MigrateMsg` of the contract.
+pub struct MigrationStep<M, E> {
+    pub version: &'static str,
+    pub migrate: fn(&mut dyn Storage, &M) -> Result<(), E>,
+}
+
+/// Checks the stored cw2 version against `contract_name`/`contract_version`,
+/// runs every step whose version is newer than the stored version and not
+/// newer than `contract_version` and stores the new version.
+/// `steps` must be ordered by ascending version. Returns the versions of the
+/// applied steps.
+pub fn migrate_contract<M, E>(
+    storage: &mut dyn Storage,
+    contract_name: &str,
+    contract_version: &str,
+    steps: &[MigrationStep<M, E>],
+    msg: &M,
+) -> Result<Vec<&'static str>, E>
+where
+    E: From<StdError> + From<MigrationError>,
+{
+    let stored = get_contract_version(storage)?;
+    if stored.contract != contract_name {
+        return Err(MigrationError::InvalidContract {
+            expected: contract_name.to_string(),
+            found: stored.contract,
+        }
+        .into());
+    }
+
+    let from: Version = parse_version(&stored.version)?;
+    let to: Version = parse_version(contract_version)?;
+    if from > to {
+        return Err(MigrationError::Downgrade {
+            from: stored.version,
+            to: contract_version.to_string(),
+        }
+        .into());
+    }
+
+    let mut applied: Vec<&'static str> = vec![];
+    for step in steps {
+        let step_version: Version = parse_version(step.version)?;
+        if step_version > from && step_version <= to {
+            (step.migrate)(storage, msg)?;
+            applied.push(step.version);
+        }
+    }
+
+    set_contract_version(storage, contract_name, contract_version)?;
+    Ok(applied)
+}
+
+fn parse_version(version: &str) -> Result<Version, MigrationError> {
+    Version::parse(version).map_err(|_| MigrationError::InvalidVersion {
+        version: version.to_string(),
+    })
+}
@@ -1,10 +1,22 @@
 use crate::{Deserialize, Serialize};
 use schemars::JsonSchema;
 
-use cosmwasm_std::Binary;
+use cosmwasm_std::{Binary, Decimal, StdError, StdResult};
+use router_wasm_bindings::{
+    ethabi::{decode, encode, ethereum_types::U256, ParamType, Token},
+    Bytes,
+};
 
 // Define state for last lottery time and lottery unique limit
 
+// current version of the ping payload wire format
+pub const PING_PAYLOAD_VERSION: u8 = 2;
+// version reported for the unversioned `(uint64 requestId, string message)`
+// and `(uint64 requestId, string message, string recipient)` payloads
+pub const LEGACY_PING_PAYLOAD_VERSION: u8 = 0;
+// current version of the pong receipt wire format
+pub const PONG_RECEIPT_VERSION: u8 = 1;
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct InstantiateMsg {}
 
@@ -16,10 +28,17 @@ pub enum ExecuteMsg {
         dest_contract_address: String,
         dest_chain_id: String,
         request_metadata: Binary,
+        body: Option<Binary>,
+        recipient: Option<String>,
+    },
+    // destinations are (dest_chain_id, dest_contract_address, request_metadata)
+    IPingMany {
+        ping: String,
+        destinations: Vec<(String, String, Binary)>,
     },
 }
 
-#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, JsonSchema)]
 pub struct MigrateMsg {}
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -27,7 +46,327 @@ pub struct MigrateMsg {}
 pub enum QueryMsg {
     // fetch contract version
     GetContractVersion {},
-    FetchPing { chain_id: String, request_id: u64 },
-    FetchPong { request_id: u64 },
+    FetchPing {
+        chain_id: String,
+        request_id: u64,
+    },
+    FetchPong {
+        request_id: u64,
+    },
     FetchRequestId {},
+    // round trip statistics over the most recent pings sent to dest_chain_id
+    RouteStats {
+        dest_chain_id: String,
+    },
+    ListRoutes {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    FetchFanout {
+        fanout_id: u64,
+    },
+}
+
+/// Status of a ping sent to several destinations with `IPingMany`.
+/// `completed` is set once every request of the fan-out has been acked.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct FanoutInfo {
+    pub fanout_id: u64,
+    pub request_ids: Vec<u64>,
+    pub acked: u64,
+    pub succeeded: u64,
+    pub completed: bool,
+}
+
+/// Lifetime counters of a destination chain pinged by this contract.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, JsonSchema)]
+pub struct RouteInfo {
+    pub dest_chain_id: String,
+    pub sent: u64,
+    pub acked: u64,
+    pub succeeded: u64,
+}
+
+/// Statistics over the last `window` pings sent to `dest_chain_id`.
+/// Round trip times are in seconds and measured between the source block
+/// time of the ping and the source block time of its ack, and only cover the
+/// pings acked successfully. `failed` counts the acks with a failed execution.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct RouteStatsResponse {
+    pub dest_chain_id: String,
+    pub window: u64,
+    pub count: u64,
+    pub acked: u64,
+    pub succeeded: u64,
+    pub failed: u64,
+    pub success_rate: Decimal,
+    pub min_rtt: Option<u64>,
+    pub avg_rtt: Option<u64>,
+    pub p95_rtt: Option<u64>,
+}
+
+/// Payload sent from the source chain to the destination chain, shared by
+/// the ping-pong dapps of every chain.
+/// `timestamp` is the source block time in seconds, `sender` is the address
+/// of the account that created the ping on the source chain and `recipient`
+/// the account the ping is addressed to on the destination chain, empty when
+/// the ping is addressed to the dapp itself.
+///
+/// Version 2 is encoded as `abi.encode((uint8 version, uint64 requestId,
+/// uint64 timestamp, bytes sender, string message, bytes body, string
+/// recipient))`, version 1 is the same tuple without `recipient`. The
+/// unversioned payloads `abi.encode(uint64 requestId, string message)` and
+/// `abi.encode(uint64 requestId, string message, string recipient)` are
+/// decoded with `LEGACY_PING_PAYLOAD_VERSION`.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PingPayload {
+    pub version: u8,
+    pub request_id: u64,
+    pub timestamp: u64,
+    pub sender: Binary,
+    pub message: String,
+    pub body: Binary,
+    pub recipient: String,
+}
+
+impl PingPayload {
+    pub fn get_evm_encoding(&self) -> StdResult<Token> {
+        let mut tokens: Vec<Token> = vec![
+            Token::Uint(U256::from(self.version)),
+            Token::Uint(U256::from(self.request_id)),
+            Token::Uint(U256::from(self.timestamp)),
+            Token::Bytes(self.sender.0.clone()),
+            Token::String(self.message.clone()),
+            Token::Bytes(self.body.0.clone()),
+        ];
+        match self.version {
+            1 => {}
+            2 => tokens.push(Token::String(self.recipient.clone())),
+            version => {
+                return Err(StdError::generic_err(format!(
+                    "PingPayload: version {} is not a tuple",
+                    version
+                )))
+            }
+        }
+        Ok(Token::Tuple(tokens))
+    }
+
+    pub fn get_params_types() -> ParamType {
+        let mut params: Vec<ParamType> = Self::get_v1_params_types();
+        params.push(ParamType::String);
+        ParamType::Tuple(params)
+    }
+
+    fn get_v1_params_types() -> Vec<ParamType> {
+        vec![
+            ParamType::Uint(8),
+            ParamType::Uint(64),
+            ParamType::Uint(64),
+            ParamType::Bytes,
+            ParamType::String,
+            ParamType::Bytes,
+        ]
+    }
+
+    pub fn from_token_tuple(tuple: Vec<Token>) -> StdResult<Self> {
+        let version = match tuple.first() {
+            Some(token) => token_to_u64(token)?,
+            None => return Err(StdError::generic_err("PingPayload: invalid tuple length")),
+        };
+        let expected_len: usize = match version {
+            1 => 6,
+            2 => 7,
+            _ => {
+                return Err(StdError::generic_err(format!(
+                    "PingPayload: unsupported version {}",
+                    version
+                )))
+            }
+        };
+        if tuple.len() != expected_len {
+            return Err(StdError::generic_err("PingPayload: invalid tuple length"));
+        }
+        Ok(Self {
+            version: version as u8,
+            request_id: token_to_u64(&tuple[1])?,
+            timestamp: token_to_u64(&tuple[2])?,
+            sender: Binary(token_to_bytes(&tuple[3])?),
+            message: token_to_string(&tuple[4])?,
+            body: Binary(token_to_bytes(&tuple[5])?),
+            recipient: match tuple.get(6) {
+                Some(token) => token_to_string(token)?,
+                None => String::new(),
+            },
+        })
+    }
+
+    /// Legacy payloads are encoded without the recipient when it is empty.
+    pub fn encode(&self) -> StdResult<Bytes> {
+        if self.version != LEGACY_PING_PAYLOAD_VERSION {
+            return Ok(encode(&[self.get_evm_encoding()?]));
+        }
+        let mut tokens: Vec<Token> = vec![
+            Token::Uint(U256::from(self.request_id)),
+            Token::String(self.message.clone()),
+        ];
+        if !self.recipient.is_empty() {
+            tokens.push(Token::String(self.recipient.clone()));
+        }
+        Ok(encode(&tokens))
+    }
+
+    /// Decodes a payload of any version. The layouts are told apart by
+    /// requiring the data to be exactly the encoding of the decoded tokens.
+    pub fn decode(data: &[u8]) -> StdResult<Self> {
+        let versioned: [ParamType; 2] = [
+            Self::get_params_types(),
+            ParamType::Tuple(Self::get_v1_params_types()),
+        ];
+        for params in versioned {
+            if let Some(tokens) = decode_exact(&[params], data) {
+                return match tokens[0].clone().into_tuple() {
+                    Some(tuple) => Self::from_token_tuple(tuple),
+                    None => Err(StdError::generic_err("PingPayload: expected tuple")),
+                };
+            }
+        }
+
+        // the two fields payload is decoded leniently, the way it was decoded
+        // before the payload was versioned
+        let legacy: Option<Vec<Token>> = decode_exact(
+            &[ParamType::Uint(64), ParamType::String, ParamType::String],
+            data,
+        )
+        .or_else(|| decode(&[ParamType::Uint(64), ParamType::String], data).ok());
+        if let Some(tokens) = legacy {
+            return Ok(Self {
+                version: LEGACY_PING_PAYLOAD_VERSION,
+                request_id: token_to_u64(&tokens[0])?,
+                timestamp: 0,
+                sender: Binary::default(),
+                message: token_to_string(&tokens[1])?,
+                body: Binary::default(),
+                recipient: match tokens.get(2) {
+                    Some(token) => token_to_string(token)?,
+                    None => String::new(),
+                },
+            });
+        }
+        Err(StdError::generic_err("PingPayload: invalid data"))
+    }
+}
+
+/// Receipt returned by the destination chain as the ack data of a ping.
+/// `src_timestamp` echoes `PingPayload::timestamp` so the source chain can
+/// compute the round trip time without keeping extra state.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PongReceipt {
+    pub version: u8,
+    pub request_id: u64,
+    pub src_timestamp: u64,
+    pub dest_block_height: u64,
+    pub dest_block_time: u64,
+    pub message: String,
+}
+
+impl PongReceipt {
+    pub fn get_evm_encoding(&self) -> StdResult<Token> {
+        Ok(Token::Tuple(vec![
+            Token::Uint(U256::from(self.version)),
+            Token::Uint(U256::from(self.request_id)),
+            Token::Uint(U256::from(self.src_timestamp)),
+            Token::Uint(U256::from(self.dest_block_height)),
+            Token::Uint(U256::from(self.dest_block_time)),
+            Token::String(self.message.clone()),
+        ]))
+    }
+
+    pub fn get_params_types() -> ParamType {
+        ParamType::Tuple(vec![
+            ParamType::Uint(8),
+            ParamType::Uint(64),
+            ParamType::Uint(64),
+            ParamType::Uint(64),
+            ParamType::Uint(64),
+            ParamType::String,
+        ])
+    }
+
+    pub fn from_token_tuple(tuple: Vec<Token>) -> StdResult<Self> {
+        if tuple.len() != 6 {
+            return Err(StdError::generic_err("PongReceipt: invalid tuple length"));
+        }
+        let version = token_to_u64(&tuple[0])?;
+        if version != PONG_RECEIPT_VERSION as u64 {
+            return Err(StdError::generic_err(format!(
+                "PongReceipt: unsupported version {}",
+                version
+            )));
+        }
+        Ok(Self {
+            version: version as u8,
+            request_id: token_to_u64(&tuple[1])?,
+            src_timestamp: token_to_u64(&tuple[2])?,
+            dest_block_height: token_to_u64(&tuple[3])?,
+            dest_block_time: token_to_u64(&tuple[4])?,
+            message: token_to_string(&tuple[5])?,
+        })
+    }
+
+    pub fn encode(&self) -> StdResult<Bytes> {
+        Ok(encode(&[self.get_evm_encoding()?]))
+    }
+
+    pub fn decode(data: &[u8]) -> StdResult<Self> {
+        let token_vec = decode_exact(&[Self::get_params_types()], data)
+            .ok_or_else(|| StdError::generic_err("PongReceipt: invalid data"))?;
+        match token_vec[0].clone().into_tuple() {
+            Some(tuple) => Self::from_token_tuple(tuple),
+            None => Err(StdError::generic_err("PongReceipt: expected tuple")),
+        }
+    }
+}
+
+/// Pong stored on the source chain once the ack for a ping is received.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PongRecord {
+    pub request_id: u64,
+    pub message: String,
+    pub src_timestamp: u64,
+    pub dest_block_height: u64,
+    pub dest_block_time: u64,
+    pub ack_block_height: u64,
+    pub ack_block_time: u64,
+}
+
+// ethabi ignores the data following the decoded tokens, the payload layouts
+// only match when the data is exactly their encoding
+fn decode_exact(params: &[ParamType], data: &[u8]) -> Option<Vec<Token>> {
+    match decode(params, data) {
+        Ok(tokens) if encode(&tokens) == data => Some(tokens),
+        _ => None,
+    }
+}
+
+fn token_to_u64(token: &Token) -> StdResult<u64> {
+    match token.clone().into_uint() {
+        Some(value) if value <= U256::from(u64::MAX) => Ok(value.as_u64()),
+        Some(_) => Err(StdError::generic_err("uint value overflows u64")),
+        None => Err(StdError::generic_err("expected uint token")),
+    }
+}
+
+fn token_to_bytes(token: &Token) -> StdResult<Bytes> {
+    token
+        .clone()
+        .into_bytes()
+        .ok_or_else(|| StdError::generic_err("expected bytes token"))
+}
+
+fn token_to_string(token: &Token) -> StdResult<String> {
+    token
+        .clone()
+        .into_string()
+        .ok_or_else(|| StdError::generic_err("expected string token"))
 }
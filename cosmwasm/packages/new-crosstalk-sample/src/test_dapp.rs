@@ -2,11 +2,15 @@ use crate::{Deserialize, Serialize};
 use schemars::JsonSchema;
 
 use cosmwasm_std::{Binary, Uint128};
+use router_wasm_bindings::types::RequestMetaData;
 
 // Define state for last lottery time and lottery unique limit
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
-pub struct InstantiateMsg {}
+pub struct InstantiateMsg {
+    // denom accepted for route transfers, defaults to `route`
+    pub route_denom: Option<String>,
+}
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
@@ -19,10 +23,24 @@ pub enum ExecuteMsg {
         amount: Uint128,
         route_recipient: String,
     },
+    SetRouteDenom {
+        denom: String,
+    },
+    // read the state of dest_contract on dest_chain_id, is_read_call is always set to true
+    QueryRemote {
+        dest_chain_id: String,
+        dest_contract: String,
+        calldata: Binary,
+        request_metadata: RequestMetaData,
+    },
 }
 
-#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
-pub struct MigrateMsg {}
+// optional state overrides applied on migration
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, JsonSchema)]
+pub struct MigrateMsg {
+    pub owner: Option<String>,
+    pub route_denom: Option<String>,
+}
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
@@ -31,4 +49,47 @@ pub enum QueryMsg {
     GetContractVersion {},
     FetchGreetingRecord { chain_id: String, request_id: u64 },
     FetchAckRecord { request_id: u64 },
+    FetchReadCall { request_id: u64 },
+    FetchSendRequest { request_id: u64 },
+    FetchRouteDenom {},
+}
+
+/// Route transfer details of a request sent with `SendIRequest`.
+/// `overpayment_refund` is the part of the funds sent back to the sender on
+/// send and `ack_refund` the route amount refunded with the acknowledgement.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SendRequestRecord {
+    pub request_id: u64,
+    pub request_identifier: Option<u64>,
+    pub sender: String,
+    pub dest_chain_id: String,
+    pub route_amount: Uint128,
+    pub route_recipient: String,
+    pub route_denom: String,
+    pub overpayment_refund: Uint128,
+    pub ack_refund: Option<Uint128>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ReadCallStatus {
+    Pending,
+    Success,
+    Failed,
+}
+
+/// Read request sent with `QueryRemote`. `result` holds the data returned by
+/// the destination contract, or the error data if the read call failed, and
+/// `decoded_result` the message decoded from the data of a successful call
+/// when the data is a `(uint64, string)` request message.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ReadCallRecord {
+    pub request_id: u64,
+    pub request_identifier: Option<u64>,
+    pub dest_chain_id: String,
+    pub dest_contract: String,
+    pub calldata: Binary,
+    pub status: ReadCallStatus,
+    pub result: Option<Binary>,
+    pub decoded_result: Option<String>,
 }
@@ -0,0 +1,146 @@
+use anyhow::{anyhow, Result as AnyResult};
+use cosmwasm_std::testing::{MockApi, MockStorage};
+use cosmwasm_std::{Addr, Binary, Coin, CosmosMsg, Empty, Event, Response, SubMsg, Uint128};
+use cw_multi_test::{App, AppResponse, BankKeeper, BasicAppBuilder, WasmKeeper};
+use new_crosstalk_sample::crosstalk::RequestPacket;
+use router_wasm_bindings::{Bytes, RouterMsg, RouterQuery, SudoMsg};
+
+use crate::router::{RouterModule, CROSSCHAIN_CALL_EVENT};
+
+pub type RouterApp =
+    App<BankKeeper, MockApi, MockStorage, RouterModule, WasmKeeper<RouterMsg, RouterQuery>>;
+
+pub fn router_app(module: RouterModule) -> RouterApp {
+    BasicAppBuilder::<RouterMsg, RouterQuery>::new_custom()
+        .with_custom(module)
+        .build(|_, _, _| {})
+}
+
+/// Converts the `Response<Empty>` of the `instantiate`/`migrate` entry points
+/// so they can be wrapped along the `Response<RouterMsg>` ones.
+pub fn customize_response(res: Response<Empty>) -> Response<RouterMsg> {
+    let messages: Vec<SubMsg<RouterMsg>> = res
+        .messages
+        .into_iter()
+        .map(|sub_msg| SubMsg {
+            id: sub_msg.id,
+            msg: match sub_msg.msg {
+                CosmosMsg::Bank(msg) => CosmosMsg::Bank(msg),
+                CosmosMsg::Wasm(msg) => CosmosMsg::Wasm(msg),
+                msg => panic!("Unsupported message {:?}", msg),
+            },
+            gas_limit: sub_msg.gas_limit,
+            reply_on: sub_msg.reply_on,
+        })
+        .collect();
+    let mut customized: Response<RouterMsg> = Response::new()
+        .add_submessages(messages)
+        .add_attributes(res.attributes)
+        .add_events(res.events);
+    customized.data = res.data;
+    customized
+}
+
+/// CrosschainCall captured by the `RouterModule`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct OutboundRequest {
+    pub sender: Addr,
+    pub request_identifier: u64,
+    pub dest_chain_id: String,
+    pub route_amount: Uint128,
+    pub route_recipient: String,
+    pub request_metadata: Bytes,
+    pub request_packet: RequestPacket,
+}
+
+impl OutboundRequest {
+    fn from_event(event: &Event) -> AnyResult<Self> {
+        let attribute = |key: &str| -> AnyResult<String> {
+            event
+                .attributes
+                .iter()
+                .find(|attribute| attribute.key == key)
+                .map(|attribute| attribute.value.clone())
+                .ok_or_else(|| anyhow!("Missing {} in {} event", key, CROSSCHAIN_CALL_EVENT))
+        };
+        let request_packet: Bytes = hex::decode(attribute("request_packet")?)?;
+        Ok(Self {
+            sender: Addr::unchecked(attribute("sender")?),
+            request_identifier: attribute("request_identifier")?.parse()?,
+            dest_chain_id: attribute("dest_chain_id")?,
+            route_amount: Uint128::new(attribute("route_amount")?.parse()?),
+            route_recipient: attribute("route_recipient")?,
+            request_metadata: hex::decode(attribute("request_metadata")?)?,
+            request_packet: RequestPacket::decode(&request_packet).map_err(|_| {
+                anyhow!("Invalid request_packet in {} event", CROSSCHAIN_CALL_EVENT)
+            })?,
+        })
+    }
+}
+
+/// Requests sent to the gateway during the transaction, in execution order.
+pub fn outbound_requests(res: &AppResponse) -> AnyResult<Vec<OutboundRequest>> {
+    res.events
+        .iter()
+        .filter(|event| event.ty == CROSSCHAIN_CALL_EVENT)
+        .map(OutboundRequest::from_event)
+        .collect()
+}
+
+/// Outcome of a relayed request. `receive` is the result of the
+/// `HandleIReceive` on the destination contract, `ack` the response of the
+/// `HandleIAck` it triggered on the sender.
+#[derive(Debug)]
+pub struct Delivery {
+    pub receive: Result<AppResponse, String>,
+    pub ack: AppResponse,
+}
+
+/// Delivers the request to `dest_contract_address` of its packet as if it
+/// came from `src_chain_id`, then acks it back to the sender. The ack carries
+/// the data returned by the destination when the request succeeded, the
+/// error message otherwise, and no refund.
+pub fn relay(
+    app: &mut RouterApp,
+    request: &OutboundRequest,
+    src_chain_id: &str,
+) -> AnyResult<Delivery> {
+    let receive_msg: SudoMsg = SudoMsg::HandleIReceive {
+        request_sender: request.sender.to_string(),
+        src_chain_id: src_chain_id.to_string(),
+        request_identifier: request.request_identifier,
+        payload: Binary(request.request_packet.payload.clone()),
+    };
+    let receive: AnyResult<AppResponse> = app.wasm_sudo(
+        Addr::unchecked(&request.request_packet.dest_contract_address),
+        &receive_msg,
+    );
+
+    let (exec_flag, exec_data) = match &receive {
+        Ok(res) => (true, res.data.clone().unwrap_or_default()),
+        Err(err) => (false, Binary(err.to_string().into_bytes())),
+    };
+    let ack_msg: SudoMsg = SudoMsg::HandleIAck {
+        request_identifier: request.request_identifier,
+        exec_flag,
+        exec_data,
+        refund_amount: Coin::new(0u128, "route"),
+    };
+    let ack: AppResponse = app.wasm_sudo(request.sender.clone(), &ack_msg)?;
+    Ok(Delivery {
+        receive: receive.map_err(|err| err.to_string()),
+        ack,
+    })
+}
+
+/// Relays every request sent during the transaction of `res`.
+pub fn relay_all(
+    app: &mut RouterApp,
+    res: &AppResponse,
+    src_chain_id: &str,
+) -> AnyResult<Vec<Delivery>> {
+    outbound_requests(res)?
+        .iter()
+        .map(|request| relay(app, request, src_chain_id))
+        .collect()
+}
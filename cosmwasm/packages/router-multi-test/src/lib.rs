@@ -0,0 +1,8 @@
+pub mod app;
+pub mod router;
+
+pub use app::{
+    customize_response, outbound_requests, relay, relay_all, router_app, Delivery, OutboundRequest,
+    RouterApp,
+};
+pub use router::{RouterModule, CROSSCHAIN_CALL_EVENT};
@@ -0,0 +1,147 @@
+use std::collections::HashMap;
+use std::fmt::Debug;
+
+use anyhow::{bail, Result as AnyResult};
+use cosmwasm_std::{
+    to_binary, Addr, Api, Binary, BlockInfo, CustomQuery, Empty, Event, Querier, Storage, Uint128,
+};
+use cw_multi_test::{AppResponse, CosmosRouter, Module};
+use cw_storage_plus::Item;
+use router_wasm_bindings::types::{GasPriceResponse, TokenPriceResponse};
+use router_wasm_bindings::{RouterMsg, RouterQuery};
+use schemars::JsonSchema;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+// type of the event emitted for every captured CrosschainCall
+pub const CROSSCHAIN_CALL_EVENT: &str = "crosschain_call";
+
+// last request identifier assigned by the mock gateway
+const REQUEST_IDENTIFIER: Item<u64> = Item::new("router_module_request_identifier");
+
+// data returned to the sender of a CrosschainCall, parsed as a
+// `CrosschainRequestResponse` in the reply of the dapp
+#[derive(Serialize)]
+struct RequestResponse {
+    request_identifier: u64,
+    fee_deducted: Uint128,
+}
+
+/// Custom module playing the Router chain. Every `RouterMsg::CrosschainCall`
+/// gets the next request identifier and is captured as a `crosschain_call`
+/// event of the transaction, `RouterQuery` prices are answered from the
+/// configured tables.
+#[derive(Clone, Debug, Default)]
+pub struct RouterModule {
+    gas_prices: HashMap<String, u64>,
+    // symbol => token_price
+    token_prices: HashMap<String, Uint128>,
+}
+
+impl RouterModule {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_gas_price(mut self, chain_id: impl Into<String>, gas_price: u64) -> Self {
+        self.gas_prices.insert(chain_id.into(), gas_price);
+        self
+    }
+
+    pub fn with_token_price(mut self, symbol: impl Into<String>, token_price: Uint128) -> Self {
+        self.token_prices.insert(symbol.into(), token_price);
+        self
+    }
+}
+
+impl Module for RouterModule {
+    type ExecT = RouterMsg;
+    type QueryT = RouterQuery;
+    type SudoT = Empty;
+
+    fn execute<ExecC, QueryC>(
+        &self,
+        _api: &dyn Api,
+        storage: &mut dyn Storage,
+        _router: &dyn CosmosRouter<ExecC = ExecC, QueryC = QueryC>,
+        _block: &BlockInfo,
+        sender: Addr,
+        msg: RouterMsg,
+    ) -> AnyResult<AppResponse>
+    where
+        ExecC: Debug + Clone + PartialEq + JsonSchema + DeserializeOwned + 'static,
+        QueryC: CustomQuery + DeserializeOwned + 'static,
+    {
+        match msg {
+            RouterMsg::CrosschainCall {
+                version,
+                route_amount,
+                route_recipient,
+                dest_chain_id,
+                request_metadata,
+                request_packet,
+            } => {
+                let request_identifier: u64 =
+                    REQUEST_IDENTIFIER.may_load(storage)?.unwrap_or_default() + 1;
+                REQUEST_IDENTIFIER.save(storage, &request_identifier)?;
+
+                let event: Event = Event::new(CROSSCHAIN_CALL_EVENT)
+                    .add_attribute("sender", sender)
+                    .add_attribute("version", version.to_string())
+                    .add_attribute("request_identifier", request_identifier.to_string())
+                    .add_attribute("dest_chain_id", dest_chain_id)
+                    .add_attribute("route_amount", route_amount)
+                    .add_attribute("route_recipient", route_recipient)
+                    .add_attribute("request_metadata", hex::encode(request_metadata))
+                    .add_attribute("request_packet", hex::encode(request_packet));
+                let data: Binary = to_binary(&RequestResponse {
+                    request_identifier,
+                    fee_deducted: Uint128::zero(),
+                })?;
+                Ok(AppResponse {
+                    events: vec![event],
+                    data: Some(data),
+                })
+            }
+        }
+    }
+
+    fn sudo<ExecC, QueryC>(
+        &self,
+        _api: &dyn Api,
+        _storage: &mut dyn Storage,
+        _router: &dyn CosmosRouter<ExecC = ExecC, QueryC = QueryC>,
+        _block: &BlockInfo,
+        msg: Empty,
+    ) -> AnyResult<AppResponse>
+    where
+        ExecC: Debug + Clone + PartialEq + JsonSchema + DeserializeOwned + 'static,
+        QueryC: CustomQuery + DeserializeOwned + 'static,
+    {
+        bail!("Unexpected sudo msg to the Router module: {:?}", msg)
+    }
+
+    fn query(
+        &self,
+        _api: &dyn Api,
+        _storage: &dyn Storage,
+        _querier: &dyn Querier,
+        _block: &BlockInfo,
+        request: RouterQuery,
+    ) -> AnyResult<Binary> {
+        match request {
+            RouterQuery::GasPrice { chain_id } => match self.gas_prices.get(&chain_id) {
+                Some(gas_price) => Ok(to_binary(&GasPriceResponse {
+                    gas_price: *gas_price,
+                })?),
+                None => bail!("No gas price configured for chain {}", chain_id),
+            },
+            RouterQuery::TokenPrice { symbol } => match self.token_prices.get(&symbol) {
+                Some(token_price) => Ok(to_binary(&TokenPriceResponse {
+                    token_price: *token_price,
+                })?),
+                None => bail!("No token price configured for {}", symbol),
+            },
+        }
+    }
+}
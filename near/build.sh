@@ -10,3 +10,5 @@ rustup target add wasm32-unknown-unknown
 cargo build --all --target wasm32-unknown-unknown --release
 
 cp -r target/wasm32-unknown-unknown/release/*.wasm artifacts/
+# the library of the ping-pong crate is named near_ping_pong
+mv artifacts/near_ping_pong.wasm artifacts/ping_pong.wasm
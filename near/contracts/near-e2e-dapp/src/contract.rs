@@ -1,34 +1,56 @@
 use std::str::FromStr;
 
+use crate::errors::ContractError;
 use crate::external::*;
-use crate::types::ISendParams;
+use crate::types::{is_chain_address, ChainTypeInfo, ISendParams};
+use crate::upgrade::VersionedTestDapp;
 use ethabi::{decode, ParamType, Token};
+use near_crosstalk_sample::events::{
+    AckReceivedEvent, ConfigChangedEvent, CrosstalkEvent, PacketReceivedEvent, RequestSentEvent,
+};
+use near_crosstalk_sample::request_metadata::{RequestMetadata, RequestMetadataParam};
 use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
-use near_sdk::collections::UnorderedMap;
-use near_sdk::json_types::Base64VecU8;
-use near_sdk::{serde_json, PromiseOrValue};
+use near_sdk::collections::{LookupMap, UnorderedMap};
+use near_sdk::{serde_json, PromiseError};
 use near_sdk::{env, json_types::U128, near_bindgen, AccountId, Gas, Promise};
 
+// ft_transfer_call of the route token, including the ft_on_transfer of the
+// gateway and the resolution of the transfer
+const FT_TRANSFER_CALL_GAS: u64 = 60 * TGAS;
+// chainId => chainType, kept out of the contract state so that it does not
+// change its layout
+const CHAIN_TYPES_PREFIX: &[u8] = b"t";
+
 // Define the contract structure
 #[near_bindgen]
 #[derive(BorshDeserialize, BorshSerialize)]
 pub struct TestDapp {
-    gateway: AccountId,
-    route_token: AccountId,
-    owner: AccountId,
-    greeting_record: UnorderedMap<(String, u64), String>,
-    ack_record: UnorderedMap<U128, bool>,
+    pub(crate) gateway: AccountId,
+    pub(crate) route_token: AccountId,
+    pub(crate) owner: AccountId,
+    // account the ownership is being transferred to
+    pub(crate) pending_owner: Option<AccountId>,
+    pub(crate) paused: bool,
+    pub(crate) greeting_record: UnorderedMap<(String, u64), String>,
+    // request identifier => exec_flag of the ack
+    pub(crate) ack_record: UnorderedMap<U128, bool>,
+    // srcChainId => number of greetings received from the chain
+    pub(crate) greeting_counts: UnorderedMap<String, u64>,
 }
 
 impl Default for TestDapp {
     // The default trait with which to initialize the contract
     fn default() -> Self {
+        VersionedTestDapp::write_version();
         Self {
             gateway: AccountId::from_str("gateway").unwrap(),
             route_token: AccountId::from_str("route").unwrap(),
             owner: env::predecessor_account_id(),
+            pending_owner: None,
+            paused: false,
             greeting_record: UnorderedMap::new(b"g"),
             ack_record: UnorderedMap::new(b"a"),
+            greeting_counts: UnorderedMap::new(b"c"),
         }
     }
 }
@@ -38,12 +60,16 @@ impl Default for TestDapp {
 impl TestDapp {
     #[init]
     pub fn new(gateway: AccountId, route_token: AccountId) -> Self {
+        VersionedTestDapp::write_version();
         Self {
             gateway,
             route_token,
             owner: env::predecessor_account_id(),
+            pending_owner: None,
+            paused: false,
             greeting_record: UnorderedMap::new(b"g"),
             ack_record: UnorderedMap::new(b"a"),
+            greeting_counts: UnorderedMap::new(b"c"),
         }
     }
 
@@ -55,161 +81,307 @@ impl TestDapp {
         self.owner.clone()
     }
 
-    pub fn get_greeting_record(&self, src_chain_id: String, request_id: u64) -> String {
-        self.greeting_record
-            .get(&(src_chain_id, request_id))
-            .unwrap_or("".to_string())
+    #[handle_result]
+    pub fn encode_request_metadata(
+        &self,
+        request_metadata: RequestMetadata,
+    ) -> Result<Vec<u8>, ContractError> {
+        request_metadata.validate()?;
+
+        Ok(request_metadata.encode())
     }
 
-    pub fn get_ack_record(&self, request_id: U128) -> bool {
-        self.ack_record.get(&(request_id)).unwrap_or(false)
+    #[handle_result]
+    pub fn decode_request_metadata(
+        &self,
+        request_metadata: Vec<u8>,
+    ) -> Result<RequestMetadata, ContractError> {
+        Ok(RequestMetadata::decode(&request_metadata)?)
     }
 
-    pub fn set_dapp_metadata(&self, fee_payer_address: String) -> Promise {
-        if env::predecessor_account_id() != self.owner.clone() {
-            env::panic_str("only owner");
-        }
+    #[payable]
+    #[handle_result]
+    pub fn set_dapp_metadata(&mut self, fee_payer_address: String) -> Result<Promise, ContractError> {
+        self.require_owner()?;
+
+        CrosstalkEvent::ConfigChanged(vec![ConfigChangedEvent {
+            key: "fee_payer_address".to_string(),
+            value: fee_payer_address.clone(),
+        }])
+        .emit();
 
-        gateway_contract::ext(self.gateway.clone())
+        Ok(gateway_contract::ext(self.gateway.clone())
             .with_attached_deposit(env::attached_deposit())
             .with_static_gas(Gas(5 * TGAS))
-            .set_dapp_metadata(fee_payer_address)
+            .set_dapp_metadata(fee_payer_address))
     }
 
+    /// Chain types of the destination chains, the route recipients are
+    /// checked against them.
+    #[handle_result]
+    pub fn set_chain_types(&mut self, chain_type_info: Vec<ChainTypeInfo>) -> Result<(), ContractError> {
+        self.require_owner()?;
+
+        let mut chain_types: LookupMap<String, u64> = chain_types();
+        for info in chain_type_info {
+            chain_types.insert(&info.chain_id, &info.chain_type);
+
+            CrosstalkEvent::ConfigChanged(vec![ConfigChangedEvent {
+                key: format!("chain_type:{}", info.chain_id),
+                value: info.chain_type.to_string(),
+            }])
+            .emit();
+        }
+        Ok(())
+    }
+
+    pub fn get_chain_type(&self, chain_id: String) -> Option<u64> {
+        chain_types().get(&chain_id)
+    }
+
+    /// Sends the greeting of `payload` to the destination chain. With a
+    /// positive `amount`, the route tokens of the dapp are transferred to the
+    /// gateway with `ft_transfer_call`, the request being the msg of the
+    /// transfer, and the caller must attach exactly 1 yoctoNEAR. Otherwise
+    /// the attached deposit pays the gateway fee.
+    #[payable]
+    #[handle_result]
     pub fn send_i_request(
-        &self,
+        &mut self,
         payload: Vec<u8>,
         dest_contract_address: String,
         dest_chain_id: String,
-        request_metadata: Vec<u8>,
+        request_metadata: RequestMetadataParam,
         amount: U128,
         route_recipient: String,
-    ) -> Promise {
-        let dest_contract_addr_token: Token = Token::String(dest_contract_address);
-        let payload_token: Token = Token::Bytes(payload.clone());
-
-        let param_types: Vec<ParamType> = vec![ParamType::Uint(64), ParamType::String];
-        let decoded = decode(&param_types, &payload);
-
-        if decoded.is_err() {
-            env::panic_str(&format!(
-                "Error in decoding payload: {:?}",
-                decoded.unwrap_err()
-            ));
+    ) -> Result<Promise, ContractError> {
+        self.require_not_paused()?;
+
+        // every input is validated before building a promise
+        let (_, greeting) = decode_greeting("payload", &payload)?;
+        if greeting.is_empty() {
+            return Err(ContractError::EmptyMessage {
+                what: "greeting".to_string(),
+            });
         }
 
-        let decoded: Vec<Token> = decoded.unwrap();
-        let greeting: String = decoded[1].clone().into_string().unwrap();
+        let request_metadata: Vec<u8> = request_metadata.into_bytes()?;
 
-        let request_packet: Vec<u8> = ethabi::encode(&[dest_contract_addr_token, payload_token]);
-
-        if amount > U128::from(0) {
-            if route_recipient == "".to_string() {
-                env::panic_str("Route recipient cannot be empty");
+        if amount.0 > 0 {
+            if route_recipient.is_empty() {
+                return Err(ContractError::InvalidArgument {
+                    reason: "Route recipient cannot be empty".to_string(),
+                });
             }
-
-            let request_params: ISendParams = ISendParams {
-                version: U128::from(1),
-                route_recipient,
-                request_metadata: request_metadata.clone(),
-                request_packet: request_packet.clone(),
-                dest_chain_id,
+            let chain_type: u64 = match chain_types().get(&dest_chain_id) {
+                Some(chain_type) => chain_type,
+                None => {
+                    return Err(ContractError::InvalidArgument {
+                        reason: format!("Unknown chain type of chain {}", dest_chain_id),
+                    })
+                }
             };
-            let encoded: String =
-                near_sdk::base64::encode(serde_json::to_string(&request_params).unwrap());
-            let decoded = near_sdk::base64::decode(encoded);
-
-            if decoded.is_err() {
-                env::panic_str(&format!(
-                    "unable to decode the message: {:?}",
-                    decoded.unwrap_err()
-                ));
+            if !is_chain_address(chain_type, &route_recipient) {
+                return Err(ContractError::InvalidArgument {
+                    reason: format!(
+                        "Route recipient {} is not an address of chain {}",
+                        route_recipient, dest_chain_id
+                    ),
+                });
+            }
+            if env::attached_deposit() != 1 {
+                return Err(ContractError::InvalidDeposit {
+                    reason: "Requires attached deposit of exactly 1 yoctoNEAR".to_string(),
+                });
             }
+        }
 
-            let msg: Base64VecU8 = Base64VecU8::from(decoded.unwrap());
+        let request_packet: Vec<u8> = ethabi::encode(&[
+            Token::String(dest_contract_address),
+            Token::Bytes(payload),
+        ]);
 
-            let promise: Promise = route_token::ext(self.route_token.clone())
+        if amount.0 == 0 {
+            return Ok(gateway_contract::ext(self.gateway.clone())
+                .with_attached_deposit(env::attached_deposit())
                 .with_static_gas(Gas(5 * TGAS))
-                .burn_and_call_gateway(false, amount, msg);
+                .i_send(
+                    U128::from(1),
+                    dest_chain_id.clone(),
+                    request_metadata,
+                    request_packet,
+                )
+                .then(
+                    Self::ext(env::current_account_id())
+                        .with_static_gas(Gas(5 * TGAS))
+                        .i_send_callback(env::predecessor_account_id(), dest_chain_id),
+                ));
+        }
 
-            if greeting == "".to_string() {
-                env::panic_str("greeting cannot be empty");
-            }
+        let request_params: ISendParams = ISendParams {
+            version: U128::from(1),
+            route_recipient,
+            request_metadata,
+            request_packet,
+            dest_chain_id: dest_chain_id.clone(),
+        };
+        let msg: String =
+            serde_json::to_string(&request_params).map_err(|err| ContractError::InvalidArgument {
+                reason: format!("ISendParams cannot be serialized: {}", err),
+            })?;
+
+        Ok(route_token::ext(self.route_token.clone())
+            .with_attached_deposit(1)
+            .with_static_gas(Gas(FT_TRANSFER_CALL_GAS))
+            .ft_transfer_call(self.gateway.clone(), amount, None, msg)
+            .then(
+                Self::ext(env::current_account_id())
+                    .with_static_gas(Gas(5 * TGAS))
+                    .ft_transfer_call_callback(
+                        env::predecessor_account_id(),
+                        dest_chain_id,
+                        amount,
+                    ),
+            ))
+    }
 
-            return promise;
-        }
+    /// Emits the request identifier returned by the gateway.
+    #[private]
+    pub fn i_send_callback(
+        &self,
+        sender: AccountId,
+        dest_chain_id: String,
+        #[callback_result] result: Result<U128, PromiseError>,
+    ) -> Option<U128> {
+        let request_identifier: U128 = result.ok()?;
+
+        CrosstalkEvent::RequestSent(vec![RequestSentEvent {
+            sender,
+            dest_chain_id,
+            request_identifier: Some(request_identifier),
+        }])
+        .emit();
+
+        Some(request_identifier)
+    }
 
-        let promise: Promise = gateway_contract::ext(self.gateway.clone())
-            .with_attached_deposit(env::attached_deposit())
-            .with_static_gas(Gas(5 * TGAS))
-            .i_send(
-                U128::from(1),
+    /// Handles the route tokens the gateway did not use, they are refunded
+    /// to the dapp by the route token. Returns the refunded amount.
+    #[private]
+    pub fn ft_transfer_call_callback(
+        &self,
+        sender: AccountId,
+        dest_chain_id: String,
+        amount: U128,
+        #[callback_result] used_amount: Result<U128, PromiseError>,
+    ) -> U128 {
+        // the whole amount is kept by the dapp when the transfer failed
+        let used_amount: u128 = match used_amount {
+            Ok(used_amount) => std::cmp::min(used_amount.0, amount.0),
+            Err(_) => 0,
+        };
+        let refund_amount: u128 = amount.0 - used_amount;
+
+        // the gateway only uses the tokens of the requests it accepted
+        if used_amount > 0 {
+            CrosstalkEvent::RequestSent(vec![RequestSentEvent {
+                sender,
                 dest_chain_id,
-                request_metadata,
-                request_packet,
-            );
+                request_identifier: None,
+            }])
+            .emit();
+        }
 
-        if greeting == "".to_string() {
-            env::panic_str("greeting cannot be empty");
+        if refund_amount > 0 {
+            env::log_str(&format!(
+                "route token refunded {} of {} to the dapp",
+                refund_amount, amount.0
+            ));
         }
 
-        promise
+        U128::from(refund_amount)
     }
 
+    /// Returns the packet, sent back to the source chain as the ack data.
+    #[handle_result]
     pub fn i_receive(
         &mut self,
         request_sender: String,
         packet: Vec<u8>,
         src_chain_id: String,
-    ) -> PromiseOrValue<Vec<u8>> {
+    ) -> Result<Vec<u8>, ContractError> {
         if self.gateway.clone() != env::predecessor_account_id() {
-            env::panic_str("only gateway");
+            return Err(ContractError::NotGateway);
         }
+        self.require_not_paused()?;
 
-        let res = ethabi::decode(&[ParamType::Uint(64), ParamType::String], &packet);
+        let (nonce, greeting) = decode_greeting("packet", &packet)?;
 
-        if res.is_err() {
-            let error = res.unwrap_err();
-            let format_error_string: String = format!("Error in decoding packet: {:?}", error);
-            env::panic_str(&format_error_string);
+        if greeting == "Fail Dest Req".to_string() {
+            return Err(ContractError::ForcedFailure { message: greeting });
         }
 
-        let res: Vec<Token> = res.unwrap();
-        let nonce: u64 = res[0].clone().into_uint().unwrap().as_u64();
-        let greeting: String = res[1].clone().into_string().unwrap();
-
-        if greeting == "Fail Dest Req".to_string() {
-            env::panic_str("String != Fail Dest Req");
+        let previous: Option<String> = self
+            .greeting_record
+            .insert(&(src_chain_id.clone(), nonce), &greeting);
+        if previous.is_none() {
+            let count: u64 = self.get_greeting_count(src_chain_id.clone());
+            self.greeting_counts.insert(&src_chain_id, &(count + 1));
         }
 
-        self.greeting_record
-            .insert(&(src_chain_id, nonce), &greeting);
+        CrosstalkEvent::PacketReceived(vec![PacketReceivedEvent {
+            src_chain_id,
+            request_sender,
+        }])
+        .emit();
 
-        PromiseOrValue::Value(packet)
+        Ok(packet)
     }
 
-    pub fn i_ack(&mut self, request_identifier: U128, exec_flag: bool, exec_data: Vec<u8>) {
+    #[handle_result]
+    pub fn i_ack(
+        &mut self,
+        request_identifier: U128,
+        exec_flag: bool,
+        exec_data: Vec<u8>,
+    ) -> Result<(), ContractError> {
         if self.gateway.clone() != env::predecessor_account_id() {
-            env::panic_str("only gateway");
+            return Err(ContractError::NotGateway);
         }
 
         if exec_flag {
-            let res = ethabi::decode(&[ParamType::Uint(64), ParamType::String], &exec_data);
-
-            if res.is_err() {
-                let error = res.unwrap_err();
-                let format_error_string: String = format!("Error in decoding packet: {:?}", error);
-                env::panic_str(&format_error_string);
-            }
-
-            let res: Vec<Token> = res.unwrap();
-            let greeting: String = res[1].clone().into_string().unwrap();
+            let (_, greeting) = decode_greeting("exec data", &exec_data)?;
 
             if greeting == "Fail Ack Req".to_string() {
-                env::panic_str("String != Fail Ack Req");
+                return Err(ContractError::ForcedFailure { message: greeting });
             }
         }
 
-        self.ack_record.insert(&request_identifier, &true);
+        self.ack_record.insert(&request_identifier, &exec_flag);
+
+        CrosstalkEvent::AckReceived(vec![AckReceivedEvent {
+            request_identifier,
+            exec_flag,
+        }])
+        .emit();
+
+        Ok(())
+    }
+}
+
+fn chain_types() -> LookupMap<String, u64> {
+    LookupMap::new(CHAIN_TYPES_PREFIX)
+}
+
+// abi.decode(data, (uint64, string)), the nonce and greeting of the payloads
+// exchanged with the dapps of the other chains
+fn decode_greeting(what: &str, data: &[u8]) -> Result<(u64, String), ContractError> {
+    let tokens: Vec<Token> = decode(&[ParamType::Uint(64), ParamType::String], data)
+        .map_err(|err| ContractError::decode(what, err))?;
+    match (tokens.get(0).cloned(), tokens.get(1).cloned()) {
+        (Some(Token::Uint(nonce)), Some(Token::String(greeting))) if nonce.bits() <= 64 => {
+            Ok((nonce.as_u64(), greeting))
+        }
+        (nonce, greeting) => Err(ContractError::decode(what, (nonce, greeting))),
     }
 }
@@ -0,0 +1,2 @@
+// the errors are shared by the cross chain samples of NEAR
+pub use near_crosstalk_sample::errors::ContractError;
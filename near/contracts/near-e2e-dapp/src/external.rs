@@ -1,27 +1,31 @@
-use near_sdk::{ext_contract, json_types::U128};
+use near_sdk::{ext_contract, json_types::U128, AccountId};
 
 pub const TGAS: u64 = 1_000_000_000_000;
 
 // Validator interface, for cross-contract calls
 #[ext_contract(gateway_contract)]
 trait GatewayContract {
+    // returns the request identifier (nonce) assigned by the gateway
     fn i_send(
         &mut self,
         version: U128,
         dest_chain_id: String,
         request_metadata: Vec<u8>,
         request_packet: Vec<u8>,
-    ) -> bool;
+    ) -> U128;
 
     fn set_dapp_metadata(&self, fee_payer_address: String);
 }
 
+// NEP-141 route token, the gateway reads the `ISendParams` of the msg
 #[ext_contract(route_token)]
 trait RouteToken {
-    fn burn_and_call_gateway(
+    // returns the amount used by the receiver, the rest is refunded
+    fn ft_transfer_call(
         &mut self,
-        is_app_token_payer: bool,
+        receiver_id: AccountId,
         amount: U128,
-        msg: near_sdk::json_types::Base64VecU8,
-    );
+        memo: Option<String>,
+        msg: String,
+    ) -> U128;
 }
@@ -1,5 +1,9 @@
 pub mod contract;
+pub mod errors;
 mod external;
+mod owner;
+pub mod records;
 #[cfg(test)]
 mod tests;
 mod types;
+pub mod upgrade;
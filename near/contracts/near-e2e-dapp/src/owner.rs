@@ -0,0 +1,71 @@
+use near_sdk::{env, near_bindgen, AccountId};
+
+use crate::contract::{TestDapp, TestDappExt};
+use crate::errors::ContractError;
+
+#[near_bindgen]
+impl TestDapp {
+    /// First step of the ownership transfer, the new owner has to accept it.
+    #[handle_result]
+    pub fn transfer_ownership(&mut self, new_owner: AccountId) -> Result<(), ContractError> {
+        self.require_owner()?;
+        self.pending_owner = Some(new_owner);
+        Ok(())
+    }
+
+    #[handle_result]
+    pub fn accept_ownership(&mut self) -> Result<(), ContractError> {
+        let caller: AccountId = env::predecessor_account_id();
+        if self.pending_owner.as_ref() != Some(&caller) {
+            return Err(ContractError::NotPendingOwner);
+        }
+
+        self.owner = caller;
+        self.pending_owner = None;
+        Ok(())
+    }
+
+    pub fn get_pending_owner(&self) -> Option<AccountId> {
+        self.pending_owner.clone()
+    }
+
+    #[handle_result]
+    pub fn pause(&mut self) -> Result<(), ContractError> {
+        self.require_owner()?;
+        if self.paused {
+            return Err(ContractError::AlreadyPaused);
+        }
+        self.paused = true;
+        Ok(())
+    }
+
+    #[handle_result]
+    pub fn unpause(&mut self) -> Result<(), ContractError> {
+        self.require_owner()?;
+        if !self.paused {
+            return Err(ContractError::NotPaused);
+        }
+        self.paused = false;
+        Ok(())
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+}
+
+impl TestDapp {
+    pub(crate) fn require_owner(&self) -> Result<(), ContractError> {
+        if env::predecessor_account_id() != self.owner {
+            return Err(ContractError::NotOwner);
+        }
+        Ok(())
+    }
+
+    pub(crate) fn require_not_paused(&self) -> Result<(), ContractError> {
+        if self.paused {
+            return Err(ContractError::PausedState);
+        }
+        Ok(())
+    }
+}
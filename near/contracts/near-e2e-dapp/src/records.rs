@@ -0,0 +1,150 @@
+use near_crosstalk_sample::pagination::page;
+use near_sdk::{
+    borsh::{BorshDeserialize, BorshSerialize},
+    env,
+    json_types::U128,
+    near_bindgen,
+    serde::{Deserialize, Serialize},
+};
+
+use crate::contract::{TestDapp, TestDappExt};
+use crate::errors::ContractError;
+
+// next and end index of the greetings received before the counters, written
+// by the migration and removed once every greeting is counted
+const GREETINGS_RECOUNT_KEY: &[u8] = b"GREETINGS_RECOUNT";
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct GreetingRecord {
+    pub src_chain_id: String,
+    pub request_id: u64,
+    pub greeting: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct AckRecord {
+    pub request_identifier: U128,
+    pub exec_flag: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct ChainCount {
+    pub src_chain_id: String,
+    pub count: u64,
+}
+
+#[near_bindgen]
+impl TestDapp {
+    pub fn get_greeting_record(&self, src_chain_id: String, request_id: u64) -> Option<String> {
+        self.greeting_record.get(&(src_chain_id, request_id))
+    }
+
+    /// `exec_flag` of the ack of the request, `None` before the ack.
+    pub fn get_ack_record(&self, request_id: U128) -> Option<bool> {
+        self.ack_record.get(&request_id)
+    }
+
+    /// Greetings received from the other chains, in insertion order.
+    pub fn get_greeting_records(
+        &self,
+        from_index: Option<u64>,
+        limit: Option<u64>,
+    ) -> Vec<GreetingRecord> {
+        page(&self.greeting_record, from_index, limit)
+            .map(|((src_chain_id, request_id), greeting)| GreetingRecord {
+                src_chain_id,
+                request_id,
+                greeting,
+            })
+            .collect()
+    }
+
+    /// Acks of the requests sent by the dapp, in insertion order.
+    pub fn get_ack_records(&self, from_index: Option<u64>, limit: Option<u64>) -> Vec<AckRecord> {
+        page(&self.ack_record, from_index, limit)
+            .map(|(request_identifier, exec_flag)| AckRecord {
+                request_identifier,
+                exec_flag,
+            })
+            .collect()
+    }
+
+    /// Number of greetings received from `src_chain_id`.
+    pub fn get_greeting_count(&self, src_chain_id: String) -> u64 {
+        self.greeting_counts.get(&src_chain_id).unwrap_or(0)
+    }
+
+    pub fn get_greeting_counts(
+        &self,
+        from_index: Option<u64>,
+        limit: Option<u64>,
+    ) -> Vec<ChainCount> {
+        page(&self.greeting_counts, from_index, limit)
+            .map(|(src_chain_id, count)| ChainCount {
+                src_chain_id,
+                count,
+            })
+            .collect()
+    }
+}
+
+#[near_bindgen]
+impl TestDapp {
+    /// Counts up to `limit` of the greetings received before the migration
+    /// to the counters of the greetings per chain, and returns the number of
+    /// greetings left to count. The counters are incomplete until it
+    /// returns 0.
+    #[handle_result]
+    pub fn recount_greetings(&mut self, limit: u64) -> Result<u64, ContractError> {
+        self.require_owner()?;
+
+        let (next, end): (u64, u64) = match read_greetings_recount() {
+            Some(recount) => recount,
+            None => return Ok(0),
+        };
+        let stop: u64 = std::cmp::min(end, next.saturating_add(limit));
+
+        let keys = self.greeting_record.keys_as_vector();
+        for index in next..stop {
+            if let Some((src_chain_id, _)) = keys.get(index) {
+                let count: u64 = self.get_greeting_count(src_chain_id.clone());
+                self.greeting_counts.insert(&src_chain_id, &(count + 1));
+            }
+        }
+
+        write_greetings_recount(stop, end);
+        Ok(end - stop)
+    }
+
+    /// Number of greetings received before the migration and not counted yet.
+    pub fn get_greetings_to_recount(&self) -> u64 {
+        read_greetings_recount()
+            .map(|(next, end)| end - next)
+            .unwrap_or(0)
+    }
+}
+
+impl TestDapp {
+    // the states migrated from a layout without the counters have their
+    // greetings counted by recount_greetings, the greetings received after
+    // the migration are counted by i_receive
+    pub(crate) fn start_greetings_recount(&self) {
+        write_greetings_recount(0, self.greeting_record.len());
+    }
+}
+
+fn read_greetings_recount() -> Option<(u64, u64)> {
+    env::storage_read(GREETINGS_RECOUNT_KEY)
+        .and_then(|recount| <(u64, u64)>::try_from_slice(&recount).ok())
+}
+
+fn write_greetings_recount(next: u64, end: u64) {
+    if next < end {
+        env::storage_write(GREETINGS_RECOUNT_KEY, &(next, end).try_to_vec().unwrap());
+    } else {
+        env::storage_remove(GREETINGS_RECOUNT_KEY);
+    }
+}
@@ -1,16 +1,27 @@
 use std::str::FromStr;
 
 use ethabi::{encode, ethereum_types::U256, Token};
-use near_sdk::{env, json_types::U128, AccountId};
+use near_sdk::borsh::{self, BorshSerialize};
+use near_sdk::collections::UnorderedMap;
+use near_sdk::mock::VmAction;
+use near_sdk::test_utils::{get_created_receipts, get_logs, VMContextBuilder};
+use near_sdk::{env, json_types::U128, serde_json, testing_env, AccountId, PromiseError};
 
 use crate::contract::TestDapp;
+use crate::errors::ContractError;
+use crate::records::{AckRecord, ChainCount, GreetingRecord};
+use crate::types::{ChainTypeInfo, ISendParams, CHAIN_TYPE_COSMOS, CHAIN_TYPE_EVM, CHAIN_TYPE_NEAR};
+use crate::upgrade::STATE_VERSION;
+use near_crosstalk_sample::request_metadata::{
+    RequestMetadata, RequestMetadataError, RequestMetadataParam,
+};
 
 #[test]
 fn send_i_request_without_token() {
     let gateway: AccountId = AccountId::from_str("gateway").unwrap();
     let route_token: AccountId = AccountId::from_str("route").unwrap();
 
-    let test_dapp: TestDapp = TestDapp::new(gateway, route_token);
+    let mut test_dapp: TestDapp = TestDapp::new(gateway, route_token);
 
     let nonce: u64 = 1;
     let greeting: String = "hello".to_string();
@@ -25,19 +36,18 @@ fn send_i_request_without_token() {
         payload,
         "abcd".to_string(),
         "80001".to_string(),
-        vec![1, 2, 3, 4],
+        request_metadata(),
         U128::from(0),
         "".to_string(),
-    );
+    ).unwrap();
 }
 
 #[test]
-#[should_panic(expected = "greeting cannot be empty")]
 fn send_i_request_without_token_with_empty_string() {
     let gateway: AccountId = AccountId::from_str("gateway").unwrap();
     let route_token: AccountId = AccountId::from_str("route").unwrap();
 
-    let test_dapp: TestDapp = TestDapp::new(gateway, route_token);
+    let mut test_dapp: TestDapp = TestDapp::new(gateway, route_token);
 
     let nonce: u64 = 1;
     let greeting: String = "".to_string();
@@ -48,32 +58,33 @@ fn send_i_request_without_token_with_empty_string() {
 
     let payload: Vec<u8> = encode(&[nonce_token, greeting_token]);
 
-    test_dapp.send_i_request(
+    let err: ContractError = test_dapp.send_i_request(
         payload,
         "abcd".to_string(),
         "80001".to_string(),
-        vec![1, 2, 3, 4],
+        request_metadata(),
         U128::from(0),
         "".to_string(),
-    );
+    ).err().unwrap();
+    assert!(err.to_string().contains("greeting cannot be empty"));
 }
 
 #[test]
-#[should_panic(expected = "Error in decoding payload: InvalidData")]
 fn send_i_request_without_token_with_invalid_payload() {
     let gateway: AccountId = AccountId::from_str("gateway").unwrap();
     let route_token: AccountId = AccountId::from_str("route").unwrap();
 
-    let test_dapp: TestDapp = TestDapp::new(gateway, route_token);
+    let mut test_dapp: TestDapp = TestDapp::new(gateway, route_token);
 
-    test_dapp.send_i_request(
+    let err: ContractError = test_dapp.send_i_request(
         vec![1, 1],
         "abcd".to_string(),
         "80001".to_string(),
-        vec![1, 2, 3, 4],
+        request_metadata(),
         U128::from(0),
         "".to_string(),
-    );
+    ).err().unwrap();
+    assert!(err.to_string().contains("Error in decoding payload: InvalidData"));
 }
 
 #[test]
@@ -81,7 +92,7 @@ fn send_i_request_with_token() {
     let gateway: AccountId = AccountId::from_str("gateway").unwrap();
     let route_token: AccountId = AccountId::from_str("route").unwrap();
 
-    let test_dapp: TestDapp = TestDapp::new(gateway, route_token);
+    let mut test_dapp: TestDapp = TestDapp::new(gateway, route_token);
 
     let nonce: u64 = 1;
     let greeting: String = "hello".to_string();
@@ -92,24 +103,259 @@ fn send_i_request_with_token() {
 
     let payload: Vec<u8> = encode(&[nonce_token, greeting_token]);
 
+    set_chain_types(&mut test_dapp);
+    testing_env!(VMContextBuilder::new().attached_deposit(1).build());
     test_dapp.send_i_request(
+        payload.clone(),
+        "abcd".to_string(),
+        "80001".to_string(),
+        request_metadata(),
+        U128::from(12),
+        EVM_ADDRESS.to_string(),
+    ).unwrap();
+
+    // the request is the msg of a transfer of the route tokens to the gateway
+    let transfers: Vec<(AccountId, u128, serde_json::Value)> = get_created_receipts()
+        .into_iter()
+        .flat_map(|receipt| {
+            let receiver_id: AccountId = receipt.receiver_id.clone();
+            receipt.actions.into_iter().filter_map(move |action| match action {
+                VmAction::FunctionCall {
+                    function_name,
+                    args,
+                    deposit,
+                    ..
+                } if function_name == "ft_transfer_call" => Some((
+                    receiver_id.clone(),
+                    deposit,
+                    serde_json::from_slice(&args).unwrap(),
+                )),
+                _ => None,
+            })
+        })
+        .collect();
+    assert_eq!(transfers.len(), 1);
+
+    let (receiver_id, deposit, args) = transfers[0].clone();
+    assert_eq!(receiver_id, AccountId::from_str("route").unwrap());
+    assert_eq!(deposit, 1);
+    assert_eq!(args["receiver_id"], "gateway");
+    assert_eq!(args["amount"], "12");
+
+    let msg: ISendParams = serde_json::from_str(args["msg"].as_str().unwrap()).unwrap();
+    assert_eq!(
+        msg,
+        ISendParams {
+            version: U128::from(1),
+            route_recipient: EVM_ADDRESS.to_string(),
+            dest_chain_id: "80001".to_string(),
+            request_metadata: RequestMetadata::default().encode(),
+            request_packet: encode(&[Token::String("abcd".to_string()), Token::Bytes(payload)]),
+        }
+    );
+}
+
+#[test]
+fn send_i_request_with_token_without_deposit() {
+    let gateway: AccountId = AccountId::from_str("gateway").unwrap();
+    let route_token: AccountId = AccountId::from_str("route").unwrap();
+
+    let mut test_dapp: TestDapp = TestDapp::new(gateway, route_token);
+
+    let payload: Vec<u8> = encode(&[
+        Token::Uint(U256::from(1)),
+        Token::String("hello".to_string()),
+    ]);
+
+    set_chain_types(&mut test_dapp);
+    let err: ContractError = test_dapp.send_i_request(
         payload,
         "abcd".to_string(),
         "80001".to_string(),
-        vec![1, 2, 3, 4],
+        request_metadata(),
         U128::from(12),
-        "Hello".to_string(),
+        EVM_ADDRESS.to_string(),
+    ).err().unwrap();
+    assert!(err.to_string().contains("Requires attached deposit of exactly 1 yoctoNEAR"));
+}
+
+#[test]
+fn send_i_request_with_token_with_invalid_recipient() {
+    let gateway: AccountId = AccountId::from_str("gateway").unwrap();
+    let route_token: AccountId = AccountId::from_str("route").unwrap();
+
+    let mut test_dapp: TestDapp = TestDapp::new(gateway, route_token);
+
+    let payload: Vec<u8> = encode(&[
+        Token::Uint(U256::from(1)),
+        Token::String("hello".to_string()),
+    ]);
+
+    let err: ContractError = test_dapp.send_i_request(
+        payload.clone(),
+        "abcd".to_string(),
+        "80001".to_string(),
+        request_metadata(),
+        U128::from(12),
+        EVM_ADDRESS.to_string(),
+    ).err().unwrap();
+    assert_eq!(err, ContractError::InvalidArgument {
+        reason: "Unknown chain type of chain 80001".to_string(),
+    });
+
+    set_chain_types(&mut test_dapp);
+    testing_env!(VMContextBuilder::new().attached_deposit(1).build());
+    for (dest_chain_id, route_recipient) in [
+        ("80001", "Hello"),
+        ("80001", "0xeedb3ab68d567a6cd6d19fa819fe77b9f8ed153"),
+        ("80001", "0xeedb3ab68d567a6cd6d19fa819fe77b9f8ed153g"),
+        ("router_9000-1", EVM_ADDRESS),
+        ("router_9000-1", "router1qypqxpq9qcrsszg2pvxq6rs0zqg3yyc52edxeu"),
+        ("near-testnet", "Hello"),
+    ] {
+        let err: ContractError = test_dapp.send_i_request(
+            payload.clone(),
+            "abcd".to_string(),
+            dest_chain_id.to_string(),
+            request_metadata(),
+            U128::from(12),
+            route_recipient.to_string(),
+        ).err().unwrap();
+        assert_eq!(err, ContractError::InvalidArgument {
+            reason: format!(
+                "Route recipient {} is not an address of chain {}",
+                route_recipient, dest_chain_id
+            ),
+        });
+    }
+
+    for (dest_chain_id, route_recipient) in [
+        ("80001", EVM_ADDRESS),
+        ("router_9000-1", COSMOS_ADDRESS),
+        ("near-testnet", "alice.testnet"),
+    ] {
+        test_dapp.send_i_request(
+            payload.clone(),
+            "abcd".to_string(),
+            dest_chain_id.to_string(),
+            request_metadata(),
+            U128::from(12),
+            route_recipient.to_string(),
+        ).unwrap();
+    }
+}
+
+#[test]
+fn set_chain_types_only_owner() {
+    let gateway: AccountId = AccountId::from_str("gateway").unwrap();
+    let route_token: AccountId = AccountId::from_str("route").unwrap();
+
+    let mut test_dapp: TestDapp = TestDapp::new(gateway, route_token);
+    set_chain_types(&mut test_dapp);
+    assert_eq!(test_dapp.get_chain_type("router_9000-1".to_string()), Some(2));
+    assert_eq!(test_dapp.get_chain_type("43113".to_string()), None);
+
+    testing_env!(VMContextBuilder::new()
+        .predecessor_account_id(AccountId::from_str("attacker").unwrap())
+        .build());
+    assert_eq!(test_dapp.set_chain_types(vec![]), Err(ContractError::NotOwner));
+}
+
+#[test]
+fn ft_transfer_call_callback_returns_refund() {
+    let gateway: AccountId = AccountId::from_str("gateway").unwrap();
+    let route_token: AccountId = AccountId::from_str("route").unwrap();
+    let sender: AccountId = AccountId::from_str("sender").unwrap();
+
+    let test_dapp: TestDapp = TestDapp::new(gateway, route_token);
+
+    let refund: U128 = test_dapp.ft_transfer_call_callback(
+        sender.clone(),
+        "80001".to_string(),
+        U128::from(12),
+        Ok(U128::from(8)),
+    );
+    assert_eq!(refund, U128::from(4));
+    assert_eq!(
+        get_logs(),
+        vec![
+            "EVENT_JSON:{\"standard\":\"crosstalk\",\"version\":\"1.0.0\",\"event\":\"request_sent\",\
+             \"data\":[{\"sender\":\"sender\",\"dest_chain_id\":\"80001\",\"request_identifier\":null}]}"
+                .to_string(),
+            "route token refunded 4 of 12 to the dapp".to_string(),
+        ]
+    );
+
+    let refund: U128 = test_dapp.ft_transfer_call_callback(
+        sender.clone(),
+        "80001".to_string(),
+        U128::from(12),
+        Ok(U128::from(12)),
+    );
+    assert_eq!(refund, U128::from(0));
+
+    // nothing was transferred when the call failed
+    let refund: U128 = test_dapp.ft_transfer_call_callback(
+        sender,
+        "80001".to_string(),
+        U128::from(12),
+        Err(PromiseError::Failed),
     );
+    assert_eq!(refund, U128::from(12));
+    assert_eq!(get_logs().len(), 4);
 }
 
 #[test]
-#[should_panic(expected = "greeting cannot be empty")]
-fn send_i_request_with_token_with_empty_string() {
+fn i_send_callback_emits_request_sent() {
     let gateway: AccountId = AccountId::from_str("gateway").unwrap();
     let route_token: AccountId = AccountId::from_str("route").unwrap();
+    let sender: AccountId = AccountId::from_str("sender").unwrap();
 
     let test_dapp: TestDapp = TestDapp::new(gateway, route_token);
 
+    let request_identifier: Option<U128> =
+        test_dapp.i_send_callback(sender.clone(), "80001".to_string(), Ok(U128::from(42)));
+    assert_eq!(request_identifier, Some(U128::from(42)));
+    assert_eq!(
+        get_logs(),
+        vec![
+            "EVENT_JSON:{\"standard\":\"crosstalk\",\"version\":\"1.0.0\",\"event\":\"request_sent\",\
+             \"data\":[{\"sender\":\"sender\",\"dest_chain_id\":\"80001\",\"request_identifier\":\"42\"}]}"
+                .to_string()
+        ]
+    );
+
+    let request_identifier: Option<U128> =
+        test_dapp.i_send_callback(sender, "80001".to_string(), Err(PromiseError::Failed));
+    assert_eq!(request_identifier, None);
+    assert_eq!(get_logs().len(), 1);
+}
+
+#[test]
+fn set_dapp_metadata_emits_config_changed() {
+    let gateway: AccountId = AccountId::from_str("gateway").unwrap();
+    let route_token: AccountId = AccountId::from_str("route").unwrap();
+
+    let mut test_dapp: TestDapp = TestDapp::new(gateway, route_token);
+
+    test_dapp.set_dapp_metadata("fee-payer".to_string()).unwrap();
+    assert_eq!(
+        get_logs(),
+        vec![
+            "EVENT_JSON:{\"standard\":\"crosstalk\",\"version\":\"1.0.0\",\"event\":\"config_changed\",\
+             \"data\":[{\"key\":\"fee_payer_address\",\"value\":\"fee-payer\"}]}"
+                .to_string()
+        ]
+    );
+}
+
+#[test]
+fn send_i_request_with_token_with_empty_string() {
+    let gateway: AccountId = AccountId::from_str("gateway").unwrap();
+    let route_token: AccountId = AccountId::from_str("route").unwrap();
+
+    let mut test_dapp: TestDapp = TestDapp::new(gateway, route_token);
+
     let nonce: u64 = 1;
     let greeting: String = "".to_string();
 
@@ -119,44 +365,44 @@ fn send_i_request_with_token_with_empty_string() {
 
     let payload: Vec<u8> = encode(&[nonce_token, greeting_token]);
 
-    test_dapp.send_i_request(
+    let err: ContractError = test_dapp.send_i_request(
         payload,
         "abcd".to_string(),
         "80001".to_string(),
-        vec![1, 2, 3, 4],
+        request_metadata(),
         U128::from(12),
         "Hello".to_string(),
-    );
+    ).err().unwrap();
+    assert!(err.to_string().contains("greeting cannot be empty"));
 }
 
 #[test]
-#[should_panic(expected = "Error in decoding payload: InvalidData")]
 fn send_i_request_with_token_with_invalid_payload() {
     let gateway: AccountId = AccountId::from_str("gateway").unwrap();
     let route_token: AccountId = AccountId::from_str("route").unwrap();
 
-    let test_dapp: TestDapp = TestDapp::new(gateway, route_token);
+    let mut test_dapp: TestDapp = TestDapp::new(gateway, route_token);
 
-    test_dapp.send_i_request(
+    let err: ContractError = test_dapp.send_i_request(
         vec![1, 1],
         "abcd".to_string(),
         "80001".to_string(),
-        vec![1, 2, 3, 4],
+        request_metadata(),
         U128::from(0),
         "".to_string(),
-    );
+    ).err().unwrap();
+    assert!(err.to_string().contains("Error in decoding payload: InvalidData"));
 }
 
 #[test]
-#[should_panic(expected = "Route recipient cannot be empty")]
 fn send_i_request_with_token_with_empty_recipient() {
     let gateway: AccountId = AccountId::from_str("gateway").unwrap();
     let route_token: AccountId = AccountId::from_str("route").unwrap();
 
-    let test_dapp: TestDapp = TestDapp::new(gateway, route_token);
+    let mut test_dapp: TestDapp = TestDapp::new(gateway, route_token);
 
     let nonce: u64 = 1;
-    let greeting: String = "".to_string();
+    let greeting: String = "hello".to_string();
 
     let nonce_u256: U256 = U256::from(nonce);
     let nonce_token: Token = Token::Uint(nonce_u256);
@@ -164,14 +410,15 @@ fn send_i_request_with_token_with_empty_recipient() {
 
     let payload: Vec<u8> = encode(&[nonce_token, greeting_token]);
 
-    test_dapp.send_i_request(
+    let err: ContractError = test_dapp.send_i_request(
         payload,
         "abcd".to_string(),
         "80001".to_string(),
-        vec![1, 2, 3, 4],
+        request_metadata(),
         U128::from(12),
         "".to_string(),
-    );
+    ).err().unwrap();
+    assert!(err.to_string().contains("Route recipient cannot be empty"));
 }
 
 #[test]
@@ -194,44 +441,51 @@ fn test_i_receive() {
         "request_sender".to_string(),
         encoded_packet,
         "80001".to_string(),
+    ).unwrap();
+
+    let greeting: Option<String> = test_dapp.get_greeting_record("80001".to_string(), 1);
+    assert_eq!(greeting, Some("Hello".to_string()));
+    assert_eq!(
+        get_logs(),
+        vec![
+            "EVENT_JSON:{\"standard\":\"crosstalk\",\"version\":\"1.0.0\",\"event\":\"packet_received\",\
+             \"data\":[{\"src_chain_id\":\"80001\",\"request_sender\":\"request_sender\"}]}"
+                .to_string()
+        ]
     );
-
-    let greeting: String = test_dapp.get_greeting_record("80001".to_string(), 1);
-    assert_eq!(greeting, "Hello".to_string());
 }
 
 #[test]
-#[should_panic(expected = "only gateway")]
 fn test_i_receive_should_panic_wrong_gateway() {
     let gateway: AccountId = AccountId::from_str("gateway").unwrap();
     let route_token: AccountId = AccountId::from_str("route").unwrap();
 
     let mut test_dapp: TestDapp = TestDapp::new(gateway, route_token);
 
-    test_dapp.i_receive(
+    let err: ContractError = test_dapp.i_receive(
         "request_sender".to_string(),
         vec![0, 0, 0],
         "80001".to_string(),
-    );
+    ).err().unwrap();
+    assert!(err.to_string().contains("only gateway"));
 }
 
 #[test]
-#[should_panic(expected = "Error in decoding packet: InvalidData")]
 fn test_i_receive_should_panic_wrong_decoding() {
     let gateway: AccountId = env::predecessor_account_id();
     let route_token: AccountId = AccountId::from_str("route").unwrap();
 
     let mut test_dapp: TestDapp = TestDapp::new(gateway, route_token);
 
-    test_dapp.i_receive(
+    let err: ContractError = test_dapp.i_receive(
         "request_sender".to_string(),
         vec![0, 0, 0],
         "80001".to_string(),
-    );
+    ).err().unwrap();
+    assert!(err.to_string().contains("Error in decoding packet: InvalidData"));
 }
 
 #[test]
-#[should_panic(expected = "String != Fail Dest Req")]
 fn test_i_receive_should_panic_failure_string() {
     let gateway: AccountId = env::predecessor_account_id();
     let route_token: AccountId = AccountId::from_str("route").unwrap();
@@ -247,11 +501,12 @@ fn test_i_receive_should_panic_failure_string() {
 
     let encoded_packet: Vec<u8> = ethabi::encode(&[request_id_token, greeting_token]);
 
-    test_dapp.i_receive(
+    let err: ContractError = test_dapp.i_receive(
         "request_sender".to_string(),
         encoded_packet,
         "80001".to_string(),
-    );
+    ).err().unwrap();
+    assert!(err.to_string().contains("String != Fail Dest Req"));
 }
 
 #[test]
@@ -270,25 +525,32 @@ fn test_i_ack() {
 
     let encoded_packet: Vec<u8> = ethabi::encode(&[request_id_token, greeting_token]);
 
-    test_dapp.i_ack(U128::from(12), true, encoded_packet);
+    test_dapp.i_ack(U128::from(12), true, encoded_packet).unwrap();
 
     let ack_record = test_dapp.get_ack_record(U128::from(12));
-    assert_eq!(ack_record, true);
+    assert_eq!(ack_record, Some(true));
+    assert_eq!(
+        get_logs(),
+        vec![
+            "EVENT_JSON:{\"standard\":\"crosstalk\",\"version\":\"1.0.0\",\"event\":\"ack_received\",\
+             \"data\":[{\"request_identifier\":\"12\",\"exec_flag\":true}]}"
+                .to_string()
+        ]
+    );
 }
 
 #[test]
-#[should_panic(expected = "only gateway")]
 fn test_i_ack_should_panic_only_gateway() {
     let gateway: AccountId = AccountId::from_str("gateway").unwrap();
     let route_token: AccountId = AccountId::from_str("route").unwrap();
 
     let mut test_dapp: TestDapp = TestDapp::new(gateway, route_token);
 
-    test_dapp.i_ack(U128::from(12), true, vec![0, 0, 0]);
+    let err: ContractError = test_dapp.i_ack(U128::from(12), true, vec![0, 0, 0]).err().unwrap();
+    assert!(err.to_string().contains("only gateway"));
 }
 
 #[test]
-#[should_panic(expected = "String != Fail Ack Req")]
 fn test_i_ack_should_panic_failure_string() {
     let gateway: AccountId = env::predecessor_account_id();
     let route_token: AccountId = AccountId::from_str("route").unwrap();
@@ -304,5 +566,339 @@ fn test_i_ack_should_panic_failure_string() {
 
     let encoded_packet: Vec<u8> = ethabi::encode(&[request_id_token, greeting_token]);
 
-    test_dapp.i_ack(U128::from(12), true, encoded_packet);
+    let err: ContractError = test_dapp.i_ack(U128::from(12), true, encoded_packet).err().unwrap();
+    assert!(err.to_string().contains("String != Fail Ack Req"));
+}
+
+#[test]
+fn test_two_step_ownership_transfer() {
+    let gateway: AccountId = AccountId::from_str("gateway").unwrap();
+    let route_token: AccountId = AccountId::from_str("route").unwrap();
+    let owner: AccountId = env::predecessor_account_id();
+    let new_owner: AccountId = AccountId::from_str("new-owner").unwrap();
+
+    let mut test_dapp: TestDapp = TestDapp::new(gateway, route_token);
+
+    test_dapp.transfer_ownership(new_owner.clone()).unwrap();
+    assert_eq!(test_dapp.get_owner(), owner);
+    assert_eq!(test_dapp.get_pending_owner(), Some(new_owner.clone()));
+
+    testing_env!(VMContextBuilder::new()
+        .predecessor_account_id(new_owner.clone())
+        .build());
+    test_dapp.accept_ownership().unwrap();
+    assert_eq!(test_dapp.get_owner(), new_owner);
+    assert_eq!(test_dapp.get_pending_owner(), None);
+}
+
+#[test]
+fn test_i_receive_should_panic_paused() {
+    let gateway: AccountId = env::predecessor_account_id();
+    let route_token: AccountId = AccountId::from_str("route").unwrap();
+
+    let mut test_dapp: TestDapp = TestDapp::new(gateway, route_token);
+    test_dapp.pause().unwrap();
+    assert!(test_dapp.is_paused());
+
+    let encoded_packet: Vec<u8> = ethabi::encode(&[
+        Token::Uint(U256::from(1)),
+        Token::String("Hello".to_string()),
+    ]);
+
+    let err: ContractError = test_dapp.i_receive(
+        "request_sender".to_string(),
+        encoded_packet,
+        "80001".to_string(),
+    ).err().unwrap();
+    assert!(err.to_string().contains("PausedState"));
+}
+
+#[test]
+fn test_pause_and_unpause_reject_no_op() {
+    let gateway: AccountId = AccountId::from_str("gateway").unwrap();
+    let route_token: AccountId = AccountId::from_str("route").unwrap();
+
+    let mut test_dapp: TestDapp = TestDapp::new(gateway, route_token);
+    assert_eq!(test_dapp.unpause(), Err(ContractError::NotPaused));
+
+    test_dapp.pause().unwrap();
+    assert_eq!(test_dapp.pause(), Err(ContractError::AlreadyPaused));
+    assert!(test_dapp.is_paused());
+
+    test_dapp.unpause().unwrap();
+    assert_eq!(test_dapp.unpause(), Err(ContractError::NotPaused));
+    assert!(!test_dapp.is_paused());
+}
+
+#[test]
+fn test_upgrade_should_panic_only_owner() {
+    let gateway: AccountId = AccountId::from_str("gateway").unwrap();
+    let route_token: AccountId = AccountId::from_str("route").unwrap();
+
+    let test_dapp: TestDapp = TestDapp::new(gateway, route_token);
+
+    testing_env!(VMContextBuilder::new()
+        .predecessor_account_id(AccountId::from_str("attacker").unwrap())
+        .build());
+    let err: ContractError = test_dapp.upgrade(vec![0, 97, 115, 109].into()).err().unwrap();
+    assert!(err.to_string().contains("only owner"));
+}
+
+#[test]
+fn test_migrate_from_v1_state() {
+    // layout of the state deployed up to 1.0.0
+    #[derive(BorshSerialize)]
+    struct LegacyTestDapp {
+        gateway: AccountId,
+        route_token: AccountId,
+        owner: AccountId,
+        greeting_record: UnorderedMap<(String, u64), String>,
+        ack_record: UnorderedMap<U128, bool>,
+    }
+
+    let mut legacy: LegacyTestDapp = LegacyTestDapp {
+        gateway: AccountId::from_str("gateway").unwrap(),
+        route_token: AccountId::from_str("route").unwrap(),
+        owner: env::predecessor_account_id(),
+        greeting_record: UnorderedMap::new(b"g"),
+        ack_record: UnorderedMap::new(b"a"),
+    };
+    legacy
+        .greeting_record
+        .insert(&("80001".to_string(), 1), &"Hello".to_string());
+    env::state_write(&legacy);
+
+    let mut test_dapp: TestDapp = TestDapp::migrate().unwrap();
+
+    assert_eq!(test_dapp.get_gateway(), AccountId::from_str("gateway").unwrap());
+    assert_eq!(test_dapp.get_owner(), env::predecessor_account_id());
+    assert_eq!(
+        test_dapp.get_greeting_record("80001".to_string(), 1),
+        Some("Hello".to_string())
+    );
+    // the migration does not count the greetings, recount_greetings does
+    assert_eq!(test_dapp.get_greeting_count("80001".to_string()), 0);
+    assert_eq!(test_dapp.get_greetings_to_recount(), 1);
+    assert_eq!(test_dapp.recount_greetings(10), Ok(0));
+    assert_eq!(test_dapp.get_greeting_count("80001".to_string()), 1);
+    assert_eq!(test_dapp.get_greetings_to_recount(), 0);
+    assert!(!test_dapp.is_paused());
+    assert_eq!(
+        env::storage_read(b"STATE_VERSION"),
+        Some(vec![STATE_VERSION])
+    );
+}
+
+#[test]
+fn test_migrate_from_v2_state() {
+    // layout of the state before the counters of the greetings per chain
+    #[derive(BorshSerialize)]
+    struct LegacyTestDapp {
+        gateway: AccountId,
+        route_token: AccountId,
+        owner: AccountId,
+        pending_owner: Option<AccountId>,
+        paused: bool,
+        greeting_record: UnorderedMap<(String, u64), String>,
+        ack_record: UnorderedMap<U128, bool>,
+    }
+
+    let mut legacy: LegacyTestDapp = LegacyTestDapp {
+        gateway: AccountId::from_str("gateway").unwrap(),
+        route_token: AccountId::from_str("route").unwrap(),
+        owner: env::predecessor_account_id(),
+        pending_owner: None,
+        paused: true,
+        greeting_record: UnorderedMap::new(b"g"),
+        ack_record: UnorderedMap::new(b"a"),
+    };
+    for (src_chain_id, request_id) in [("80001", 1u64), ("43113", 1), ("80001", 2)] {
+        legacy
+            .greeting_record
+            .insert(&(src_chain_id.to_string(), request_id), &"Hello".to_string());
+    }
+    env::state_write(&legacy);
+    env::storage_write(b"STATE_VERSION", &[2]);
+
+    let mut test_dapp: TestDapp = TestDapp::migrate().unwrap();
+
+    assert!(test_dapp.is_paused());
+    assert_eq!(test_dapp.get_greetings_to_recount(), 3);
+    assert_eq!(test_dapp.recount_greetings(2), Ok(1));
+    assert_eq!(test_dapp.get_greeting_count("80001".to_string()), 1);
+    assert_eq!(test_dapp.get_greeting_count("43113".to_string()), 1);
+
+    // only the owner recounts the greetings
+    let owner: AccountId = env::predecessor_account_id();
+    testing_env!(VMContextBuilder::new()
+        .predecessor_account_id(AccountId::from_str("attacker").unwrap())
+        .build());
+    assert_eq!(test_dapp.recount_greetings(2), Err(ContractError::NotOwner));
+    testing_env!(VMContextBuilder::new().predecessor_account_id(owner).build());
+
+    assert_eq!(test_dapp.recount_greetings(2), Ok(0));
+    assert_eq!(test_dapp.get_greetings_to_recount(), 0);
+    assert_eq!(
+        test_dapp.get_greeting_counts(None, None),
+        vec![
+            ChainCount {
+                src_chain_id: "80001".to_string(),
+                count: 2,
+            },
+            ChainCount {
+                src_chain_id: "43113".to_string(),
+                count: 1,
+            },
+        ]
+    );
+    assert_eq!(
+        env::storage_read(b"STATE_VERSION"),
+        Some(vec![STATE_VERSION])
+    );
+}
+
+#[test]
+fn test_record_pagination() {
+    let gateway: AccountId = env::predecessor_account_id();
+    let route_token: AccountId = AccountId::from_str("route").unwrap();
+
+    let mut test_dapp: TestDapp = TestDapp::new(gateway, route_token);
+
+    for (src_chain_id, nonce) in [("80001", 1u64), ("80001", 2), ("43113", 1)] {
+        let packet: Vec<u8> = encode(&[
+            Token::Uint(U256::from(nonce)),
+            Token::String(format!("Hello {}", nonce)),
+        ]);
+        test_dapp.i_receive("request_sender".to_string(), packet.clone(), src_chain_id.to_string()).unwrap();
+        // a redelivered packet is counted once
+        test_dapp.i_receive("request_sender".to_string(), packet, src_chain_id.to_string()).unwrap();
+    }
+
+    assert_eq!(test_dapp.get_greeting_count("80001".to_string()), 2);
+    assert_eq!(test_dapp.get_greeting_count("43113".to_string()), 1);
+    assert_eq!(test_dapp.get_greeting_count("97".to_string()), 0);
+
+    assert_eq!(test_dapp.get_greeting_records(None, None).len(), 3);
+    assert_eq!(
+        test_dapp.get_greeting_records(Some(2), Some(5)),
+        vec![GreetingRecord {
+            src_chain_id: "43113".to_string(),
+            request_id: 1,
+            greeting: "Hello 1".to_string(),
+        }]
+    );
+    assert_eq!(test_dapp.get_greeting_records(Some(3), None), vec![]);
+
+    // a missing record differs from a failed ack
+    assert_eq!(test_dapp.get_greeting_record("97".to_string(), 1), None);
+    assert_eq!(test_dapp.get_ack_record(U128::from(1)), None);
+
+    test_dapp.i_ack(U128::from(1), false, vec![]).unwrap();
+    assert_eq!(test_dapp.get_ack_record(U128::from(1)), Some(false));
+    assert_eq!(
+        test_dapp.get_ack_records(None, Some(1)),
+        vec![AckRecord {
+            request_identifier: U128::from(1),
+            exec_flag: false,
+        }]
+    );
+}
+
+#[test]
+fn send_i_request_with_request_metadata_struct() {
+    let gateway: AccountId = AccountId::from_str("gateway").unwrap();
+    let route_token: AccountId = AccountId::from_str("route").unwrap();
+
+    let mut test_dapp: TestDapp = TestDapp::new(gateway, route_token);
+
+    let payload: Vec<u8> = encode(&[
+        Token::Uint(U256::from(1)),
+        Token::String("hello".to_string()),
+    ]);
+    let request_metadata: RequestMetadata = RequestMetadata {
+        dest_gas_limit: 1000000,
+        ack_type: 1,
+        asm_address: "router1qypqxpq9qcrsszg2pvxq6rs0zqg3yyc52edxev".to_string(),
+        ..RequestMetadata::default()
+    };
+
+    test_dapp.send_i_request(
+        payload,
+        "abcd".to_string(),
+        "80001".to_string(),
+        RequestMetadataParam::Struct(request_metadata.clone()),
+        U128::from(0),
+        "".to_string(),
+    ).unwrap();
+
+    let encoded: Vec<u8> = test_dapp.encode_request_metadata(request_metadata.clone()).unwrap();
+    assert_eq!(test_dapp.decode_request_metadata(encoded).unwrap(), request_metadata);
+
+    assert_eq!(
+        test_dapp.decode_request_metadata(vec![1, 2, 3, 4]),
+        Err(ContractError::InvalidRequestMetadata(RequestMetadataError::TooShort { len: 4 }))
+    );
+}
+
+#[test]
+fn test_i_receive_should_panic_nonce_overflow() {
+    let gateway: AccountId = env::predecessor_account_id();
+    let route_token: AccountId = AccountId::from_str("route").unwrap();
+
+    let mut test_dapp: TestDapp = TestDapp::new(gateway, route_token);
+
+    // the nonce of the packet does not fit in a uint64
+    let packet: Vec<u8> = encode(&[
+        Token::Uint(U256::from(u64::MAX) + 1),
+        Token::String("hello".to_string()),
+    ]);
+    let err: ContractError = test_dapp.i_receive("request_sender".to_string(), packet, "80001".to_string()).err().unwrap();
+    assert!(err.to_string().contains("DecodeError: Error in decoding packet"));
+}
+
+#[test]
+fn send_i_request_with_malformed_request_metadata() {
+    let gateway: AccountId = AccountId::from_str("gateway").unwrap();
+    let route_token: AccountId = AccountId::from_str("route").unwrap();
+
+    let mut test_dapp: TestDapp = TestDapp::new(gateway, route_token);
+
+    let payload: Vec<u8> = encode(&[
+        Token::Uint(U256::from(1)),
+        Token::String("hello".to_string()),
+    ]);
+
+    let err: ContractError = test_dapp.send_i_request(
+        payload,
+        "abcd".to_string(),
+        "80001".to_string(),
+        RequestMetadataParam::Bytes(vec![1, 2, 3, 4]),
+        U128::from(0),
+        "".to_string(),
+    ).err().unwrap();
+    assert!(err.to_string().contains("request metadata of 4 bytes, expected at least 50"));
+}
+
+const EVM_ADDRESS: &str = "0xeedb3ab68d567a6cd6d19fa819fe77b9f8ed1538";
+const COSMOS_ADDRESS: &str = "router1qypqxpq9qcrsszg2pvxq6rs0zqg3yyc52edxev";
+
+fn set_chain_types(test_dapp: &mut TestDapp) {
+    test_dapp.set_chain_types(vec![
+        ChainTypeInfo {
+            chain_id: "80001".to_string(),
+            chain_type: CHAIN_TYPE_EVM,
+        },
+        ChainTypeInfo {
+            chain_id: "router_9000-1".to_string(),
+            chain_type: CHAIN_TYPE_COSMOS,
+        },
+        ChainTypeInfo {
+            chain_id: "near-testnet".to_string(),
+            chain_type: CHAIN_TYPE_NEAR,
+        },
+    ]).unwrap();
+}
+
+fn request_metadata() -> RequestMetadataParam {
+    RequestMetadataParam::Bytes(RequestMetadata::default().encode())
 }
@@ -1,8 +1,16 @@
+use std::str::FromStr;
+
 use near_sdk::{
     json_types::U128,
     serde::{Deserialize, Serialize},
+    AccountId,
 };
 
+// chain types of the Router chain
+pub const CHAIN_TYPE_EVM: u64 = 1;
+pub const CHAIN_TYPE_COSMOS: u64 = 2;
+pub const CHAIN_TYPE_NEAR: u64 = 5;
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 #[serde(crate = "near_sdk::serde")]
@@ -13,3 +21,26 @@ pub struct ISendParams {
     pub request_metadata: Vec<u8>,
     pub request_packet: Vec<u8>,
 }
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct ChainTypeInfo {
+    pub chain_id: String,
+    pub chain_type: u64,
+}
+
+/// Whether `address` is an address of a chain of `chain_type`: a hex address
+/// on the EVM chains, a bech32 address on the Cosmos chains and an account id
+/// on NEAR. The addresses of the other chain types are not checked.
+pub fn is_chain_address(chain_type: u64, address: &str) -> bool {
+    match chain_type {
+        CHAIN_TYPE_EVM => {
+            address.len() == 42
+                && address.starts_with("0x")
+                && address[2..].chars().all(|c| c.is_ascii_hexdigit())
+        }
+        CHAIN_TYPE_COSMOS => bech32::decode(address).is_ok(),
+        CHAIN_TYPE_NEAR => AccountId::from_str(address).is_ok(),
+        _ => !address.is_empty(),
+    }
+}
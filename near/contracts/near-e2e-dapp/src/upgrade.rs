@@ -0,0 +1,116 @@
+use near_sdk::{
+    borsh::{self, BorshDeserialize},
+    collections::UnorderedMap,
+    env,
+    json_types::{Base64VecU8, U128},
+    near_bindgen, AccountId, Gas, Promise,
+};
+
+use crate::contract::{TestDapp, TestDappExt};
+use crate::errors::ContractError;
+use crate::external::TGAS;
+
+// version of the layout of the contract state
+pub const STATE_VERSION: u8 = 3;
+// the deployments up to 1.0.0 never wrote the state version
+const STATE_VERSION_KEY: &[u8] = b"STATE_VERSION";
+const MIGRATE_GAS: u64 = 50 * TGAS;
+
+/// State of the dapp deployed up to 1.0.0.
+#[derive(BorshDeserialize)]
+pub struct TestDappV1 {
+    gateway: AccountId,
+    route_token: AccountId,
+    owner: AccountId,
+    greeting_record: UnorderedMap<(String, u64), String>,
+    ack_record: UnorderedMap<U128, bool>,
+}
+
+/// State of the dapp before the counters of the greetings per chain.
+#[derive(BorshDeserialize)]
+pub struct TestDappV2 {
+    gateway: AccountId,
+    route_token: AccountId,
+    owner: AccountId,
+    pending_owner: Option<AccountId>,
+    paused: bool,
+    greeting_record: UnorderedMap<(String, u64), String>,
+    ack_record: UnorderedMap<U128, bool>,
+}
+
+/// Every layout of the contract state, read by `migrate`.
+pub enum VersionedTestDapp {
+    V1(TestDappV1),
+    V2(TestDappV2),
+    V3(TestDapp),
+}
+
+impl VersionedTestDapp {
+    pub fn read() -> Option<Self> {
+        match env::storage_read(STATE_VERSION_KEY).as_deref() {
+            None => env::state_read().map(VersionedTestDapp::V1),
+            Some([2]) => env::state_read().map(VersionedTestDapp::V2),
+            Some([3]) => env::state_read().map(VersionedTestDapp::V3),
+            Some(_) => None,
+        }
+    }
+
+    pub fn into_current(self) -> TestDapp {
+        let this: TestDapp = match self {
+            VersionedTestDapp::V1(state) => TestDapp {
+                gateway: state.gateway,
+                route_token: state.route_token,
+                owner: state.owner,
+                pending_owner: None,
+                paused: false,
+                greeting_record: state.greeting_record,
+                ack_record: state.ack_record,
+                greeting_counts: UnorderedMap::new(b"c"),
+            },
+            VersionedTestDapp::V2(state) => TestDapp {
+                gateway: state.gateway,
+                route_token: state.route_token,
+                owner: state.owner,
+                pending_owner: state.pending_owner,
+                paused: state.paused,
+                greeting_record: state.greeting_record,
+                ack_record: state.ack_record,
+                greeting_counts: UnorderedMap::new(b"c"),
+            },
+            VersionedTestDapp::V3(state) => return state,
+        };
+        this.start_greetings_recount();
+        this
+    }
+
+    pub(crate) fn write_version() {
+        env::storage_write(STATE_VERSION_KEY, &[STATE_VERSION]);
+    }
+}
+
+#[near_bindgen]
+impl TestDapp {
+    /// Deploys the new code on the contract account and migrates the state.
+    /// Upgrading in place keeps the address enrolled by the remote dapps.
+    #[handle_result]
+    pub fn upgrade(&self, code: Base64VecU8) -> Result<Promise, ContractError> {
+        self.require_owner()?;
+
+        Ok(Promise::new(env::current_account_id())
+            .deploy_contract(code.into())
+            .function_call("migrate".to_string(), vec![], 0, Gas(MIGRATE_GAS)))
+    }
+
+    #[private]
+    #[init(ignore_state)]
+    #[handle_result]
+    pub fn migrate() -> Result<Self, ContractError> {
+        let state: VersionedTestDapp = match VersionedTestDapp::read() {
+            Some(state) => state,
+            None => return Err(ContractError::UnknownStateVersion),
+        };
+
+        VersionedTestDapp::write_version();
+        Ok(state.into_current())
+    }
+}
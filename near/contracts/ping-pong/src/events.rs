@@ -1,7 +1,7 @@
 use near_sdk::{
     json_types::U128,
     serde::{Deserialize, Serialize},
-    serde_json,
+    serde_json, AccountId,
 };
 use std::fmt;
 
@@ -17,6 +17,14 @@ pub enum EventLogVariant {
     NewPing(Vec<NewPingEvent>),
     ExecutionStatus(Vec<ExecutionStatusEvent>),
     AckFromDestination(Vec<AckFromDestinationEvent>),
+    RequestSent(Vec<RequestSentEvent>),
+    RequestFailed(Vec<RequestFailedEvent>),
+    StorageDeposit(Vec<StorageDepositEvent>),
+    StorageWithdraw(Vec<StorageWithdrawEvent>),
+    OwnershipTransferStarted(Vec<OwnershipTransferStartedEvent>),
+    OwnershipTransferred(Vec<OwnershipTransferredEvent>),
+    Paused(Vec<PausedEvent>),
+    Unpaused(Vec<PausedEvent>),
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -55,6 +63,7 @@ pub struct PingFromSourceEvent {
     pub src_chain_id: String,
     pub request_id: u64,
     pub message: String,
+    pub recipient: String,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -76,3 +85,52 @@ pub struct AckFromDestinationEvent {
     pub request_id: u64,
     pub ack_message: String,
 }
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct RequestSentEvent {
+    pub request_id: u64,
+    pub request_identifier: U128,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct RequestFailedEvent {
+    pub request_id: u64,
+    pub sender: AccountId,
+    pub refund: U128,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct StorageDepositEvent {
+    pub account_id: AccountId,
+    pub amount: U128,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct StorageWithdrawEvent {
+    pub account_id: AccountId,
+    pub amount: U128,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct OwnershipTransferStartedEvent {
+    pub owner: AccountId,
+    pub pending_owner: AccountId,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct OwnershipTransferredEvent {
+    pub previous_owner: AccountId,
+    pub owner: AccountId,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct PausedEvent {
+    pub account_id: AccountId,
+}
@@ -5,13 +5,14 @@ pub const TGAS: u64 = 1_000_000_000_000;
 // Validator interface, for cross-contract calls
 #[ext_contract(gateway_contract)]
 trait GatewayContract {
+    // returns the request identifier (nonce) assigned by the gateway
     fn i_send(
         &mut self,
         version: U128,
         dest_chain_id: String,
         request_metadata: Vec<u8>,
         request_packet: Vec<u8>,
-    ) -> bool;
+    ) -> U128;
 
     fn set_dapp_metadata(&self, fee_payer_address: String);
 }
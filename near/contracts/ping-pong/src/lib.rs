@@ -1,47 +1,92 @@
+pub mod errors;
 mod events;
 mod external;
+mod owner;
+pub mod payload;
+pub mod records;
+pub mod storage;
 #[cfg(test)]
 mod tests;
+pub mod upgrade;
 
 use events::{
     AckFromDestinationEvent, EventLog,
-    EventLogVariant::{AckFromDestination, ExecutionStatus, NewPing, PingFromSource},
-    ExecutionStatusEvent, NewPingEvent, PingFromSourceEvent,
+    EventLogVariant::{
+        AckFromDestination, ExecutionStatus, NewPing, PingFromSource, RequestFailed, RequestSent,
+    },
+    ExecutionStatusEvent, NewPingEvent, PingFromSourceEvent, RequestFailedEvent, RequestSentEvent,
 };
+use errors::ContractError;
 use external::*;
+use near_crosstalk_sample::events::{
+    self as shared_events, AckReceivedEvent, ConfigChangedEvent, CrosstalkEvent, FeesWithdrawnEvent,
+    PacketReceivedEvent,
+};
+use near_crosstalk_sample::request_metadata::RequestMetadata;
+use payload::{decode_ack_message, PingPayload, PING_PAYLOAD_VERSION};
 use near_sdk::{
     borsh::{self, BorshDeserialize, BorshSerialize},
-    collections::UnorderedMap,
+    collections::{LookupMap, UnorderedMap},
     env,
     json_types::U128,
-    near_bindgen, AccountId, Gas, Promise, PromiseOrValue,
+    near_bindgen,
+    serde::{Deserialize, Serialize},
+    AccountId, Gas, Promise, PromiseError,
 };
-use router_wasm_bindings::ethabi::{decode, encode, ethereum_types::U256, ParamType, Token};
+use storage::StorageAccount;
+use upgrade::VersionedPingPong;
+use router_wasm_bindings::ethabi::{encode, Token};
 
 pub const CONTRACT_VERSION: &str = "1.0.0";
 pub const CONTRACT_NAME: &str = "PingPong";
+// balance kept by withdraw_fees on top of the storage and the user deposits
+pub const DEFAULT_SAFETY_MARGIN: u128 = 100_000_000_000_000_000_000_000;
+// longest message of a ping, its ack is paid for at this length
+pub const MAX_PING_MESSAGE_LENGTH: usize = 256;
 
 #[near_bindgen]
 #[derive(BorshDeserialize, BorshSerialize)]
 pub struct PingPong {
     owner: AccountId,
+    // account the ownership is being transferred to
+    pending_owner: Option<AccountId>,
     gateway: AccountId,
+    paused: bool,
     current_request_id: u64,
     // srcChainType, srcChainId, requestId -> pingFromSource
     ping_from_source: UnorderedMap<(String, u64), String>,
     // requestId => ackMessage
     ack_from_destination: UnorderedMap<u64, String>,
+    // srcChainId => number of pings received from the chain
+    ping_counts_from_source: UnorderedMap<String, u64>,
+    // requestId => request identifier returned by the gateway
+    request_identifiers: UnorderedMap<u64, u128>,
+    // NEP-145 storage deposits and fees paid, per account
+    accounts: LookupMap<AccountId, StorageAccount>,
+    total_storage_deposits: u128,
+    total_fees_paid: u128,
+    // fee attached to the i_send call of the gateway
+    gateway_fee: u128,
+    safety_margin: u128,
+    account_storage_usage: u64,
+    ping_storage_usage: u64,
+}
+
+/// Where the NEAR balance of the contract is committed.
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct BalanceBreakdown {
+    pub account_balance: U128,
+    pub storage_cost: U128,
+    pub storage_deposits: U128,
+    pub safety_margin: U128,
+    pub fees_paid: U128,
+    pub withdrawable: U128,
 }
 
 impl Default for PingPong {
     fn default() -> Self {
-        Self {
-            owner: env::predecessor_account_id(),
-            gateway: env::predecessor_account_id(),
-            current_request_id: 0,
-            ping_from_source: UnorderedMap::new(b'p'),
-            ack_from_destination: UnorderedMap::new(b'a'),
-        }
+        Self::new(env::predecessor_account_id())
     }
 }
 
@@ -49,37 +94,98 @@ impl Default for PingPong {
 impl PingPong {
     #[init]
     pub fn new(gateway: AccountId) -> Self {
-        Self {
+        let mut this: Self = Self {
             owner: env::predecessor_account_id(),
+            pending_owner: None,
             gateway,
+            paused: false,
             current_request_id: 0,
             ping_from_source: UnorderedMap::new(b'p'),
             ack_from_destination: UnorderedMap::new(b'a'),
-        }
+            ping_counts_from_source: UnorderedMap::new(b'c'),
+            request_identifiers: UnorderedMap::new(b'r'),
+            accounts: LookupMap::new(b'u'),
+            total_storage_deposits: 0,
+            total_fees_paid: 0,
+            gateway_fee: 0,
+            safety_margin: DEFAULT_SAFETY_MARGIN,
+            account_storage_usage: 0,
+            ping_storage_usage: 0,
+        };
+        this.measure_storage_usage();
+        VersionedPingPong::write_version();
+        this
     }
 
-    pub fn set_gateway(&mut self, gateway: AccountId) {
-        if env::predecessor_account_id() != self.owner.clone() {
-            env::panic_str("only owner");
-        }
+    #[handle_result]
+    pub fn set_gateway(&mut self, gateway: AccountId) -> Result<(), ContractError> {
+        self.require_owner()?;
+
+        CrosstalkEvent::ConfigChanged(vec![ConfigChangedEvent {
+            key: "gateway".to_string(),
+            value: gateway.to_string(),
+        }])
+        .emit();
 
         self.gateway = gateway;
+        Ok(())
     }
 
-    pub fn get_current_request_id(&self) -> u64 {
-        return self.current_request_id.clone();
+    #[handle_result]
+    pub fn set_gateway_fee(&mut self, gateway_fee: U128) -> Result<(), ContractError> {
+        self.require_owner()?;
+
+        self.gateway_fee = gateway_fee.into();
+
+        CrosstalkEvent::ConfigChanged(vec![ConfigChangedEvent {
+            key: "gateway_fee".to_string(),
+            value: gateway_fee.0.to_string(),
+        }])
+        .emit();
+        Ok(())
+    }
+
+    #[handle_result]
+    pub fn set_safety_margin(&mut self, safety_margin: U128) -> Result<(), ContractError> {
+        self.require_owner()?;
+
+        self.safety_margin = safety_margin.into();
+
+        CrosstalkEvent::ConfigChanged(vec![ConfigChangedEvent {
+            key: "safety_margin".to_string(),
+            value: safety_margin.0.to_string(),
+        }])
+        .emit();
+        Ok(())
+    }
+
+    /// Deposit to attach to `i_ping`, the excess is refunded. The storage of
+    /// the ping records is paid with the storage balance of the caller.
+    pub fn estimate_ping_fee(&self) -> U128 {
+        U128::from(self.gateway_fee)
+    }
+
+    pub fn get_ping_storage_cost(&self) -> U128 {
+        U128::from(Self::storage_cost(self.ping_storage_usage))
     }
 
-    pub fn get_ping_from_source(&self, src_chain_id: String, request_id: u64) -> String {
-        self.ping_from_source
-            .get(&(src_chain_id, request_id))
-            .unwrap_or("".to_string())
+    pub fn get_balance_breakdown(&self) -> BalanceBreakdown {
+        BalanceBreakdown {
+            account_balance: U128::from(env::account_balance()),
+            storage_cost: U128::from(Self::total_storage_cost()),
+            storage_deposits: U128::from(self.total_storage_deposits),
+            safety_margin: U128::from(self.safety_margin),
+            fees_paid: U128::from(self.total_fees_paid),
+            withdrawable: U128::from(self.withdrawable_fees()),
+        }
+    }
+
+    pub fn get_current_request_id(&self) -> u64 {
+        return self.current_request_id.clone();
     }
 
-    pub fn get_ack_from_destination(&self, request_id: u64) -> String {
-        self.ack_from_destination
-            .get(&request_id)
-            .unwrap_or("".to_string())
+    pub fn get_request_identifier(&self, request_id: u64) -> Option<U128> {
+        self.request_identifiers.get(&request_id).map(U128::from)
     }
 
     pub fn get_gateway(&self) -> AccountId {
@@ -91,17 +197,23 @@ impl PingPong {
     }
 
     #[payable]
-    pub fn set_dapp_metadata(&mut self, fee_payer_address: String) -> Promise {
-        if env::predecessor_account_id() != self.owner.clone() {
-            env::panic_str("only owner");
-        }
+    #[handle_result]
+    pub fn set_dapp_metadata(&mut self, fee_payer_address: String) -> Result<Promise, ContractError> {
+        self.require_owner()?;
+
+        CrosstalkEvent::ConfigChanged(vec![ConfigChangedEvent {
+            key: "fee_payer_address".to_string(),
+            value: fee_payer_address.clone(),
+        }])
+        .emit();
 
-        gateway_contract::ext(self.gateway.clone())
+        Ok(gateway_contract::ext(self.gateway.clone())
             .with_attached_deposit(env::attached_deposit())
             .with_static_gas(Gas(5 * TGAS))
-            .set_dapp_metadata(fee_payer_address)
+            .set_dapp_metadata(fee_payer_address))
     }
 
+    #[handle_result]
     pub fn get_request_metadata(
         dest_gas_limit: u64,
         dest_gas_price: u64,
@@ -111,27 +223,37 @@ impl PingPong {
         ack_type: u8,
         is_read_call: bool,
         asm_address: String,
-    ) -> Vec<u8> {
-        let mut request_metadata: Vec<u8> = vec![];
-
-        request_metadata.append(&mut dest_gas_limit.to_be_bytes().to_vec());
-        request_metadata.append(&mut dest_gas_price.to_be_bytes().to_vec());
-        request_metadata.append(&mut ack_gas_limit.to_be_bytes().to_vec());
-        request_metadata.append(&mut ack_gas_price.to_be_bytes().to_vec());
-        request_metadata.append(&mut u128::from(relayer_fees).to_be_bytes().to_vec());
-        request_metadata.append(&mut ack_type.to_be_bytes().to_vec());
-
-        if is_read_call {
-            request_metadata.append(&mut vec![1]);
-        } else {
-            request_metadata.append(&mut vec![0]);
-        }
-        request_metadata.append(&mut asm_address.as_bytes().to_vec());
+    ) -> Result<Vec<u8>, ContractError> {
+        Self::encode_request_metadata(RequestMetadata {
+            dest_gas_limit,
+            dest_gas_price,
+            ack_gas_limit,
+            ack_gas_price,
+            relayer_fees,
+            ack_type,
+            is_read_call,
+            asm_address,
+        })
+    }
 
-        request_metadata
+    #[handle_result]
+    pub fn encode_request_metadata(
+        request_metadata: RequestMetadata,
+    ) -> Result<Vec<u8>, ContractError> {
+        request_metadata.validate()?;
+
+        Ok(request_metadata.encode())
+    }
+
+    #[handle_result]
+    pub fn decode_request_metadata(
+        request_metadata: Vec<u8>,
+    ) -> Result<RequestMetadata, ContractError> {
+        Ok(RequestMetadata::decode(&request_metadata)?)
     }
 
     #[payable]
+    #[handle_result]
     pub fn i_ping(
         &mut self,
         dest_chain_id: String,
@@ -139,15 +261,60 @@ impl PingPong {
         str: String,
         request_metadata: Vec<u8>,
         recipient: String
-    ) -> Promise {
-        self.current_request_id += 1;
+    ) -> Result<Promise, ContractError> {
+        self.require_not_paused()?;
+        RequestMetadata::decode(&request_metadata)?;
+        if str.len() > MAX_PING_MESSAGE_LENGTH {
+            return Err(ContractError::InvalidArgument {
+                reason: format!("the ping message is longer than {} bytes", MAX_PING_MESSAGE_LENGTH),
+            });
+        }
+        let sender: AccountId = env::predecessor_account_id();
+        let mut account: StorageAccount = match self.accounts.get(&sender) {
+            Some(account) => account,
+            None => {
+                return Err(ContractError::NotRegistered {
+                    account_id: sender.to_string(),
+                })
+            }
+        };
+
+        let fee: u128 = self.gateway_fee;
+        let attached_deposit: u128 = env::attached_deposit();
+        if attached_deposit < fee {
+            return Err(ContractError::InvalidDeposit {
+                reason: format!("the attached deposit is less than the fee of {}", fee),
+            });
+        }
 
-        let request_id_token: Token = Token::Uint(U256::from(self.current_request_id.clone()));
-        let message_token: Token = Token::String(str);
-        let recipient_token: Token = Token::String(recipient);
+        if self.storage_available(&account) < Self::storage_cost(self.ping_storage_usage) {
+            return Err(ContractError::InsufficientStorageBalance {
+                reason: "not enough storage balance for the ping records".to_string(),
+            });
+        }
+
+        account.used_bytes += self.ping_storage_usage;
+        account.fees_paid += fee;
+        self.accounts.insert(&sender, &account);
+        self.total_fees_paid += fee;
 
-        // abi.encode(request_id, message)
-        let packet: Vec<u8> = encode(&[request_id_token, message_token, recipient_token]);
+        let excess: u128 = attached_deposit - fee;
+        if excess > 0 {
+            Promise::new(sender.clone()).transfer(excess);
+        }
+
+        self.current_request_id += 1;
+
+        let ping_payload: PingPayload = PingPayload {
+            version: PING_PAYLOAD_VERSION,
+            request_id: self.current_request_id.clone(),
+            timestamp: env::block_timestamp() / 1_000_000_000,
+            sender: sender.as_bytes().to_vec(),
+            message: str,
+            body: vec![],
+            recipient,
+        };
+        let packet: Vec<u8> = ping_payload.encode();
 
         let handler_token: Token = Token::String(destination_contract_address);
         let packet_token: Token = Token::Bytes(packet);
@@ -161,103 +328,229 @@ impl PingPong {
 
         env::log_str(&ping_event.to_string());
 
-        gateway_contract::ext(self.gateway.clone())
-            .with_attached_deposit(env::attached_deposit())
+        Ok(gateway_contract::ext(self.gateway.clone())
+            .with_attached_deposit(fee)
             .i_send(
                 U128::from(1),
-                dest_chain_id,
+                dest_chain_id.clone(),
                 request_metadata,
                 request_packet,
             )
+            .then(
+                Self::ext(env::current_account_id())
+                    .with_static_gas(Gas(5 * TGAS))
+                    .i_send_callback(
+                        self.current_request_id.clone(),
+                        dest_chain_id,
+                        sender,
+                        U128::from(fee),
+                    ),
+            ))
+    }
+
+    /// Records the request identifier returned by the gateway for the ping.
+    /// When the gateway call fails the request id and the storage of the
+    /// ping are released, and the fee is refunded to the sender.
+    #[private]
+    pub fn i_send_callback(
+        &mut self,
+        request_id: u64,
+        dest_chain_id: String,
+        sender: AccountId,
+        deposit: U128,
+        #[callback_result] result: Result<U128, PromiseError>,
+    ) -> Option<U128> {
+        match result {
+            Ok(request_identifier) => {
+                self.request_identifiers
+                    .insert(&request_id, &u128::from(request_identifier));
+
+                let request_sent_event: EventLog =
+                    EventLog::new(RequestSent(vec![RequestSentEvent {
+                        request_id,
+                        request_identifier,
+                    }]));
+
+                env::log_str(&request_sent_event.to_string());
+
+                CrosstalkEvent::RequestSent(vec![shared_events::RequestSentEvent {
+                    sender,
+                    dest_chain_id,
+                    request_identifier: Some(request_identifier),
+                }])
+                .emit();
+
+                Some(request_identifier)
+            }
+            Err(_) => {
+                // a later ping already took the next request id, the id of
+                // the failed ping stays unused
+                if self.current_request_id == request_id {
+                    self.current_request_id -= 1;
+                }
+
+                if let Some(mut account) = self.accounts.get(&sender) {
+                    account.used_bytes = account.used_bytes.saturating_sub(self.ping_storage_usage);
+                    account.fees_paid = account.fees_paid.saturating_sub(deposit.into());
+                    self.accounts.insert(&sender, &account);
+                }
+                self.total_fees_paid = self.total_fees_paid.saturating_sub(deposit.into());
+
+                if u128::from(deposit) > 0 {
+                    Promise::new(sender.clone()).transfer(u128::from(deposit));
+                }
+
+                let request_failed_event: EventLog =
+                    EventLog::new(RequestFailed(vec![RequestFailedEvent {
+                        request_id,
+                        sender,
+                        refund: deposit,
+                    }]));
+
+                env::log_str(&request_failed_event.to_string());
+
+                None
+            }
+        }
     }
 
+    /// Returns the packet, sent back to the source chain as the ack data.
+    #[handle_result]
     pub fn i_receive(
         &mut self,
         request_sender: String,
         packet: Vec<u8>,
         src_chain_id: String,
-    ) -> PromiseOrValue<Vec<u8>> {
+    ) -> Result<Vec<u8>, ContractError> {
         if env::predecessor_account_id() != self.gateway.clone() {
-            env::panic_str("not gateway");
+            return Err(ContractError::NotGateway);
         }
-
-        let param_vec: Vec<ParamType> = vec![ParamType::Uint(64), ParamType::String];
-
-        let token_vec: Vec<Token> = match decode(&param_vec, &packet) {
-            Ok(data) => data,
-            Err(_) => env::panic_str("not able to decode the packet"),
+        self.require_not_paused()?;
+
+        let ping_payload: PingPayload = match PingPayload::decode(&packet) {
+            Some(ping_payload) => ping_payload,
+            None => {
+                return Err(ContractError::DecodeError {
+                    what: "packet".to_string(),
+                    reason: "unknown ping payload layout".to_string(),
+                })
+            }
         };
 
-        let request_id: u64 = token_vec[0].clone().into_uint().unwrap().as_u64();
-        let message: String = token_vec[1].clone().into_string().unwrap();
+        let request_id: u64 = ping_payload.request_id;
+        let message: String = ping_payload.message;
 
         if message == "".to_string() {
-            env::panic_str("String should not be empty");
+            return Err(ContractError::EmptyMessage {
+                what: "message".to_string(),
+            });
         }
 
-        self.ping_from_source
+        let previous: Option<String> = self
+            .ping_from_source
             .insert(&(src_chain_id.clone(), request_id.clone()), &message);
+        if previous.is_none() {
+            let count: u64 = self.get_ping_count_from_source(src_chain_id.clone());
+            self.ping_counts_from_source.insert(&src_chain_id, &(count + 1));
+        }
 
         let ping_from_source: EventLog = EventLog::new(PingFromSource(vec![PingFromSourceEvent {
             src_chain_id: src_chain_id.clone(),
             request_id: request_id,
             message: message,
+            recipient: ping_payload.recipient,
         }]));
 
         env::log_str(&ping_from_source.to_string());
 
-        PromiseOrValue::Value(packet)
+        CrosstalkEvent::PacketReceived(vec![PacketReceivedEvent {
+            src_chain_id,
+            request_sender,
+        }])
+        .emit();
+
+        Ok(packet)
     }
 
+    /// The exec data of a failed request is the error of the destination
+    /// chain, only the exec data of a successful request holds the pong.
+    #[handle_result]
     pub fn i_ack(
         &mut self,
         request_identifier: U128,
         exec_flag: bool,
         exec_data: Vec<u8>,
-    ) {
+    ) -> Result<(), ContractError> {
         if env::predecessor_account_id() != self.gateway.clone() {
-            env::panic_str("not gateway");
-        }
-
-        let decoded = decode(&[ParamType::Uint(64), ParamType::String], &exec_data);
-        if decoded.is_err() {
-            let format_str: String = format!(
-                "Cannot decode the exec data for request_id: {:?}",
-                request_identifier.clone()
-            );
-            env::panic_str(&format_str);
+            return Err(ContractError::NotGateway);
         }
 
-        let decoded: Vec<Token> = decoded.unwrap();
-        let request_id: u64 = decoded[0].clone().into_uint().unwrap().as_u64();
-        let ack_message: String = decoded[1].clone().into_string().unwrap();
-
-        self.ack_from_destination.insert(&request_id, &ack_message);
-
         let exec_status_event: EventLog =
             EventLog::new(ExecutionStatus(vec![ExecutionStatusEvent {
                 request_identifier: request_identifier.clone(),
                 is_success: exec_flag,
             }]));
 
-        env::log_str(&exec_status_event.to_string());
+        if exec_flag {
+            let (request_id, ack_message): (u64, String) = match decode_ack_message(&exec_data) {
+                Some(decoded) => decoded,
+                None => {
+                    return Err(ContractError::DecodeError {
+                        what: "exec data".to_string(),
+                        reason: format!("unknown ack layout for request {:?}", request_identifier),
+                    })
+                }
+            };
 
-        let ack_from_destination_event: EventLog =
-            EventLog::new(AckFromDestination(vec![AckFromDestinationEvent {
-                request_id,
-                ack_message,
-            }]));
+            self.ack_from_destination.insert(&request_id, &ack_message);
+
+            env::log_str(&exec_status_event.to_string());
 
-        env::log_str(&ack_from_destination_event.to_string());
+            let ack_from_destination_event: EventLog =
+                EventLog::new(AckFromDestination(vec![AckFromDestinationEvent {
+                    request_id,
+                    ack_message,
+                }]));
+
+            env::log_str(&ack_from_destination_event.to_string());
+        } else {
+            env::log_str(&exec_status_event.to_string());
+        }
+
+        CrosstalkEvent::AckReceived(vec![AckReceivedEvent {
+            request_identifier,
+            exec_flag,
+        }])
+        .emit();
+
+        Ok(())
     }
 
-    pub fn withdraw_fees(&self, recipient: AccountId) -> Promise {
-        if env::predecessor_account_id() != self.owner {
-            env::panic_str("Only owner");
+    /// Withdraws the balance that is neither staked for storage nor
+    /// deposited by the users, keeping the safety margin.
+    #[handle_result]
+    pub fn withdraw_fees(&self, recipient: AccountId) -> Result<Promise, ContractError> {
+        self.require_owner()?;
+
+        let amount: u128 = self.withdrawable_fees();
+        if amount == 0 {
+            return Err(ContractError::NoFeesToWithdraw);
         }
 
-        let balance: u128 = env::account_balance() - Self::total_storage_cost();
-        Promise::new(recipient).transfer(balance)
+        CrosstalkEvent::FeesWithdrawn(vec![FeesWithdrawnEvent {
+            recipient: recipient.clone(),
+            amount: U128::from(amount),
+        }])
+        .emit();
+
+        Ok(Promise::new(recipient).transfer(amount))
+    }
+
+    fn withdrawable_fees(&self) -> u128 {
+        env::account_balance()
+            .saturating_sub(Self::total_storage_cost())
+            .saturating_sub(self.total_storage_deposits)
+            .saturating_sub(self.safety_margin)
     }
 
     pub fn total_storage_cost() -> u128 {
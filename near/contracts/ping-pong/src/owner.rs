@@ -0,0 +1,106 @@
+use near_sdk::{env, near_bindgen, AccountId};
+
+use crate::errors::ContractError;
+use crate::events::{
+    EventLog,
+    EventLogVariant::{OwnershipTransferStarted, OwnershipTransferred, Paused, Unpaused},
+    OwnershipTransferStartedEvent, OwnershipTransferredEvent, PausedEvent,
+};
+use crate::{PingPong, PingPongExt};
+
+#[near_bindgen]
+impl PingPong {
+    /// First step of the ownership transfer, the new owner has to accept it.
+    #[handle_result]
+    pub fn transfer_ownership(&mut self, new_owner: AccountId) -> Result<(), ContractError> {
+        self.require_owner()?;
+
+        self.pending_owner = Some(new_owner.clone());
+
+        let transfer_started_event: EventLog = EventLog::new(OwnershipTransferStarted(vec![
+            OwnershipTransferStartedEvent {
+                owner: self.owner.clone(),
+                pending_owner: new_owner,
+            },
+        ]));
+
+        env::log_str(&transfer_started_event.to_string());
+        Ok(())
+    }
+
+    #[handle_result]
+    pub fn accept_ownership(&mut self) -> Result<(), ContractError> {
+        let caller: AccountId = env::predecessor_account_id();
+        if self.pending_owner.as_ref() != Some(&caller) {
+            return Err(ContractError::NotPendingOwner);
+        }
+
+        let previous_owner: AccountId = std::mem::replace(&mut self.owner, caller);
+        self.pending_owner = None;
+
+        let transferred_event: EventLog =
+            EventLog::new(OwnershipTransferred(vec![OwnershipTransferredEvent {
+                previous_owner,
+                owner: self.owner.clone(),
+            }]));
+
+        env::log_str(&transferred_event.to_string());
+        Ok(())
+    }
+
+    pub fn get_pending_owner(&self) -> Option<AccountId> {
+        self.pending_owner.clone()
+    }
+
+    #[handle_result]
+    pub fn pause(&mut self) -> Result<(), ContractError> {
+        self.require_owner()?;
+        if self.paused {
+            return Err(ContractError::AlreadyPaused);
+        }
+        self.paused = true;
+
+        let paused_event: EventLog = EventLog::new(Paused(vec![PausedEvent {
+            account_id: env::predecessor_account_id(),
+        }]));
+
+        env::log_str(&paused_event.to_string());
+        Ok(())
+    }
+
+    #[handle_result]
+    pub fn unpause(&mut self) -> Result<(), ContractError> {
+        self.require_owner()?;
+        if !self.paused {
+            return Err(ContractError::NotPaused);
+        }
+        self.paused = false;
+
+        let unpaused_event: EventLog = EventLog::new(Unpaused(vec![PausedEvent {
+            account_id: env::predecessor_account_id(),
+        }]));
+
+        env::log_str(&unpaused_event.to_string());
+        Ok(())
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+}
+
+impl PingPong {
+    pub(crate) fn require_owner(&self) -> Result<(), ContractError> {
+        if env::predecessor_account_id() != self.owner {
+            return Err(ContractError::NotOwner);
+        }
+        Ok(())
+    }
+
+    pub(crate) fn require_not_paused(&self) -> Result<(), ContractError> {
+        if self.paused {
+            return Err(ContractError::PausedState);
+        }
+        Ok(())
+    }
+}
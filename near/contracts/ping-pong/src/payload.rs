@@ -0,0 +1,149 @@
+use router_wasm_bindings::ethabi::{decode, encode, ethereum_types::U256, ParamType, Token};
+
+// current version of the ping payload wire format
+pub const PING_PAYLOAD_VERSION: u8 = 2;
+// version reported for the unversioned (requestId, message[, recipient]) payloads
+pub const LEGACY_PING_PAYLOAD_VERSION: u8 = 0;
+
+/// Ping payload shared by the ping-pong dapps of every chain, see
+/// `new_crosstalk_sample::ping_pong::PingPayload` for the layouts.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PingPayload {
+    pub version: u8,
+    pub request_id: u64,
+    pub timestamp: u64,
+    pub sender: Vec<u8>,
+    pub message: String,
+    pub body: Vec<u8>,
+    pub recipient: String,
+}
+
+impl PingPayload {
+    /// abi.encode((uint8 version, uint64 requestId, uint64 timestamp,
+    /// bytes sender, string message, bytes body, string recipient))
+    pub fn encode(&self) -> Vec<u8> {
+        if self.version == LEGACY_PING_PAYLOAD_VERSION {
+            let mut tokens: Vec<Token> = vec![
+                Token::Uint(U256::from(self.request_id)),
+                Token::String(self.message.clone()),
+            ];
+            if !self.recipient.is_empty() {
+                tokens.push(Token::String(self.recipient.clone()));
+            }
+            return encode(&tokens);
+        }
+
+        let mut tokens: Vec<Token> = vec![
+            Token::Uint(U256::from(self.version)),
+            Token::Uint(U256::from(self.request_id)),
+            Token::Uint(U256::from(self.timestamp)),
+            Token::Bytes(self.sender.clone()),
+            Token::String(self.message.clone()),
+            Token::Bytes(self.body.clone()),
+        ];
+        if self.version >= 2 {
+            tokens.push(Token::String(self.recipient.clone()));
+        }
+        encode(&[Token::Tuple(tokens)])
+    }
+
+    /// Decodes a payload of any version, `None` if the data matches none of
+    /// the layouts.
+    pub fn decode(data: &[u8]) -> Option<Self> {
+        for version in [2u8, 1u8] {
+            if let Some(tokens) = decode_exact(&[ParamType::Tuple(versioned_params(version))], data)
+            {
+                let tuple: Vec<Token> = tokens[0].clone().into_tuple()?;
+                if token_to_u64(&tuple[0])? != version as u64 {
+                    return None;
+                }
+                return Some(Self {
+                    version,
+                    request_id: token_to_u64(&tuple[1])?,
+                    timestamp: token_to_u64(&tuple[2])?,
+                    sender: tuple[3].clone().into_bytes()?,
+                    message: tuple[4].clone().into_string()?,
+                    body: tuple[5].clone().into_bytes()?,
+                    recipient: match tuple.get(6) {
+                        Some(token) => token.clone().into_string()?,
+                        None => String::new(),
+                    },
+                });
+            }
+        }
+
+        // the two fields payload is decoded leniently, the way it was decoded
+        // before the payload was versioned
+        let tokens: Vec<Token> = decode_exact(
+            &[ParamType::Uint(64), ParamType::String, ParamType::String],
+            data,
+        )
+        .or_else(|| decode(&[ParamType::Uint(64), ParamType::String], data).ok())?;
+        Some(Self {
+            version: LEGACY_PING_PAYLOAD_VERSION,
+            request_id: token_to_u64(&tokens[0])?,
+            timestamp: 0,
+            sender: vec![],
+            message: tokens[1].clone().into_string()?,
+            body: vec![],
+            recipient: match tokens.get(2) {
+                Some(token) => token.clone().into_string()?,
+                None => String::new(),
+            },
+        })
+    }
+}
+
+/// Decodes the (requestId, message) acked by the destination: the pong
+/// receipt of the CosmWasm ping-pong, or the ping payload echoed by the other
+/// dapps.
+pub fn decode_ack_message(data: &[u8]) -> Option<(u64, String)> {
+    // (uint8 version, uint64 requestId, uint64 srcTimestamp,
+    // uint64 destBlockHeight, uint64 destBlockTime, string message)
+    let receipt_params: ParamType = ParamType::Tuple(vec![
+        ParamType::Uint(8),
+        ParamType::Uint(64),
+        ParamType::Uint(64),
+        ParamType::Uint(64),
+        ParamType::Uint(64),
+        ParamType::String,
+    ]);
+    if let Some(tokens) = decode_exact(&[receipt_params], data) {
+        let tuple: Vec<Token> = tokens[0].clone().into_tuple()?;
+        return Some((token_to_u64(&tuple[1])?, tuple[5].clone().into_string()?));
+    }
+
+    let ping: PingPayload = PingPayload::decode(data)?;
+    Some((ping.request_id, ping.message))
+}
+
+fn versioned_params(version: u8) -> Vec<ParamType> {
+    let mut params: Vec<ParamType> = vec![
+        ParamType::Uint(8),
+        ParamType::Uint(64),
+        ParamType::Uint(64),
+        ParamType::Bytes,
+        ParamType::String,
+        ParamType::Bytes,
+    ];
+    if version >= 2 {
+        params.push(ParamType::String);
+    }
+    params
+}
+
+// ethabi ignores the data following the decoded tokens, the payload layouts
+// only match when the data is exactly their encoding
+fn decode_exact(params: &[ParamType], data: &[u8]) -> Option<Vec<Token>> {
+    match decode(params, data) {
+        Ok(tokens) if encode(&tokens) == data => Some(tokens),
+        _ => None,
+    }
+}
+
+fn token_to_u64(token: &Token) -> Option<u64> {
+    match token.clone().into_uint() {
+        Some(value) if value <= U256::from(u64::MAX) => Some(value.as_u64()),
+        _ => None,
+    }
+}
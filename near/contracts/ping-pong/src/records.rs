@@ -0,0 +1,152 @@
+use near_crosstalk_sample::pagination::page;
+use near_sdk::{
+    borsh::{BorshDeserialize, BorshSerialize},
+    env, near_bindgen,
+    serde::{Deserialize, Serialize},
+};
+
+use crate::errors::ContractError;
+use crate::{PingPong, PingPongExt};
+
+// next and end index of the pings received before the counters, written by
+// the migration and removed once every ping is counted
+const PINGS_RECOUNT_KEY: &[u8] = b"PINGS_RECOUNT";
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct PingFromSourceRecord {
+    pub src_chain_id: String,
+    pub request_id: u64,
+    pub message: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct AckFromDestinationRecord {
+    pub request_id: u64,
+    pub ack_message: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct ChainCount {
+    pub src_chain_id: String,
+    pub count: u64,
+}
+
+#[near_bindgen]
+impl PingPong {
+    pub fn get_ping_from_source(&self, src_chain_id: String, request_id: u64) -> Option<String> {
+        self.ping_from_source.get(&(src_chain_id, request_id))
+    }
+
+    pub fn get_ack_from_destination(&self, request_id: u64) -> Option<String> {
+        self.ack_from_destination.get(&request_id)
+    }
+
+    /// Pings received from the other chains, in insertion order.
+    pub fn get_pings_from_source(
+        &self,
+        from_index: Option<u64>,
+        limit: Option<u64>,
+    ) -> Vec<PingFromSourceRecord> {
+        page(&self.ping_from_source, from_index, limit)
+            .map(
+                |((src_chain_id, request_id), message)| PingFromSourceRecord {
+                    src_chain_id,
+                    request_id,
+                    message,
+                },
+            )
+            .collect()
+    }
+
+    /// Acks of the pings sent by the contract, in insertion order.
+    pub fn get_acks_from_destination(
+        &self,
+        from_index: Option<u64>,
+        limit: Option<u64>,
+    ) -> Vec<AckFromDestinationRecord> {
+        page(&self.ack_from_destination, from_index, limit)
+            .map(|(request_id, ack_message)| AckFromDestinationRecord {
+                request_id,
+                ack_message,
+            })
+            .collect()
+    }
+
+    /// Number of pings received from `src_chain_id`.
+    pub fn get_ping_count_from_source(&self, src_chain_id: String) -> u64 {
+        self.ping_counts_from_source.get(&src_chain_id).unwrap_or(0)
+    }
+
+    pub fn get_ping_counts_from_source(
+        &self,
+        from_index: Option<u64>,
+        limit: Option<u64>,
+    ) -> Vec<ChainCount> {
+        page(&self.ping_counts_from_source, from_index, limit)
+            .map(|(src_chain_id, count)| ChainCount {
+                src_chain_id,
+                count,
+            })
+            .collect()
+    }
+}
+
+#[near_bindgen]
+impl PingPong {
+    /// Counts up to `limit` of the pings received before the migration to
+    /// the counters of the pings per chain, and returns the number of pings
+    /// left to count. The counters are incomplete until it returns 0.
+    #[handle_result]
+    pub fn recount_pings(&mut self, limit: u64) -> Result<u64, ContractError> {
+        self.require_owner()?;
+
+        let (next, end): (u64, u64) = match read_pings_recount() {
+            Some(recount) => recount,
+            None => return Ok(0),
+        };
+        let stop: u64 = std::cmp::min(end, next.saturating_add(limit));
+
+        let keys = self.ping_from_source.keys_as_vector();
+        for index in next..stop {
+            if let Some((src_chain_id, _)) = keys.get(index) {
+                let count: u64 = self.get_ping_count_from_source(src_chain_id.clone());
+                self.ping_counts_from_source.insert(&src_chain_id, &(count + 1));
+            }
+        }
+
+        write_pings_recount(stop, end);
+        Ok(end - stop)
+    }
+
+    /// Number of pings received before the migration and not counted yet.
+    pub fn get_pings_to_recount(&self) -> u64 {
+        read_pings_recount()
+            .map(|(next, end)| end - next)
+            .unwrap_or(0)
+    }
+}
+
+impl PingPong {
+    // the states migrated from a layout without the counters have their
+    // pings counted by recount_pings, the pings received after the
+    // migration are counted by i_receive
+    pub(crate) fn start_pings_recount(&self) {
+        write_pings_recount(0, self.ping_from_source.len());
+    }
+}
+
+fn read_pings_recount() -> Option<(u64, u64)> {
+    env::storage_read(PINGS_RECOUNT_KEY)
+        .and_then(|recount| <(u64, u64)>::try_from_slice(&recount).ok())
+}
+
+fn write_pings_recount(next: u64, end: u64) {
+    if next < end {
+        env::storage_write(PINGS_RECOUNT_KEY, &(next, end).try_to_vec().unwrap());
+    } else {
+        env::storage_remove(PINGS_RECOUNT_KEY);
+    }
+}
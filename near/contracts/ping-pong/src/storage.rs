@@ -0,0 +1,251 @@
+use near_sdk::{
+    borsh::{self, BorshDeserialize, BorshSerialize},
+    env,
+    json_types::U128,
+    near_bindgen,
+    serde::{Deserialize, Serialize},
+    AccountId, Promise,
+};
+
+use crate::errors::ContractError;
+use crate::events::{
+    EventLog,
+    EventLogVariant::{StorageDeposit, StorageWithdraw},
+    StorageDepositEvent, StorageWithdrawEvent,
+};
+use crate::{PingPong, PingPongExt, MAX_PING_MESSAGE_LENGTH};
+
+/// Storage balance of an account, as defined by NEP-145.
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct StorageBalance {
+    pub total: U128,
+    pub available: U128,
+}
+
+/// Storage balance bounds, as defined by NEP-145.
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct StorageBalanceBounds {
+    pub min: U128,
+    pub max: Option<U128>,
+}
+
+/// What a registered account deposited and what its pings consumed.
+#[derive(BorshDeserialize, BorshSerialize, Default)]
+pub struct StorageAccount {
+    // NEAR deposited through storage_deposit and not withdrawn
+    pub deposit: u128,
+    // bytes of the ping records paid with the deposit
+    pub used_bytes: u64,
+    // gateway fees paid for the pings of the account
+    pub fees_paid: u128,
+}
+
+#[near_bindgen]
+impl PingPong {
+    #[payable]
+    #[handle_result]
+    pub fn storage_deposit(
+        &mut self,
+        account_id: Option<AccountId>,
+        registration_only: Option<bool>,
+    ) -> Result<StorageBalance, ContractError> {
+        let amount: u128 = env::attached_deposit();
+        let account_id: AccountId = account_id.unwrap_or_else(env::predecessor_account_id);
+        let registration_only: bool = registration_only.unwrap_or(false);
+
+        let (mut account, deposit): (StorageAccount, u128) = match self.accounts.get(&account_id) {
+            Some(account) if registration_only => (account, 0),
+            Some(account) => (account, amount),
+            None => {
+                let min: u128 = self.storage_balance_min();
+                if amount < min {
+                    return Err(ContractError::InvalidDeposit {
+                        reason: "the attached deposit is less than the minimum storage balance"
+                            .to_string(),
+                    });
+                }
+                let deposit: u128 = if registration_only { min } else { amount };
+                (StorageAccount::default(), deposit)
+            }
+        };
+
+        let refund: u128 = amount - deposit;
+        if refund > 0 {
+            Promise::new(env::predecessor_account_id()).transfer(refund);
+        }
+
+        if deposit > 0 {
+            account.deposit += deposit;
+            self.accounts.insert(&account_id, &account);
+            self.total_storage_deposits += deposit;
+
+            let storage_deposit_event: EventLog =
+                EventLog::new(StorageDeposit(vec![StorageDepositEvent {
+                    account_id: account_id.clone(),
+                    amount: U128::from(deposit),
+                }]));
+
+            env::log_str(&storage_deposit_event.to_string());
+        }
+
+        Ok(self.storage_balance(&account))
+    }
+
+    #[payable]
+    #[handle_result]
+    pub fn storage_withdraw(&mut self, amount: Option<U128>) -> Result<StorageBalance, ContractError> {
+        require_one_yocto()?;
+        let account_id: AccountId = env::predecessor_account_id();
+        let mut account: StorageAccount = match self.accounts.get(&account_id) {
+            Some(account) => account,
+            None => {
+                return Err(ContractError::NotRegistered {
+                    account_id: account_id.to_string(),
+                })
+            }
+        };
+
+        let available: u128 = self.storage_available(&account);
+        let amount: u128 = amount.map(u128::from).unwrap_or(available);
+        if amount > available {
+            return Err(ContractError::InsufficientStorageBalance {
+                reason: "the amount is greater than the available storage balance".to_string(),
+            });
+        }
+
+        if amount > 0 {
+            account.deposit -= amount;
+            self.accounts.insert(&account_id, &account);
+            self.total_storage_deposits -= amount;
+            Promise::new(account_id.clone()).transfer(amount);
+
+            let storage_withdraw_event: EventLog =
+                EventLog::new(StorageWithdraw(vec![StorageWithdrawEvent {
+                    account_id,
+                    amount: U128::from(amount),
+                }]));
+
+            env::log_str(&storage_withdraw_event.to_string());
+        }
+
+        Ok(self.storage_balance(&account))
+    }
+
+    /// Unregisters the caller and refunds its deposit, less the cost of the
+    /// ping records it paid for. Records are kept, an account with records
+    /// can only be unregistered with `force`.
+    #[payable]
+    #[handle_result]
+    pub fn storage_unregister(&mut self, force: Option<bool>) -> Result<bool, ContractError> {
+        require_one_yocto()?;
+        let account_id: AccountId = env::predecessor_account_id();
+        let account: StorageAccount = match self.accounts.get(&account_id) {
+            Some(account) => account,
+            None => return Ok(false),
+        };
+
+        if account.used_bytes > 0 && !force.unwrap_or(false) {
+            return Err(ContractError::InvalidArgument {
+                reason: "the account paid for ping records, use force to unregister it".to_string(),
+            });
+        }
+
+        self.accounts.remove(&account_id);
+        self.total_storage_deposits -= account.deposit;
+
+        let refund: u128 = account
+            .deposit
+            .saturating_sub(Self::storage_cost(account.used_bytes));
+        if refund > 0 {
+            Promise::new(account_id.clone()).transfer(refund);
+
+            let storage_withdraw_event: EventLog =
+                EventLog::new(StorageWithdraw(vec![StorageWithdrawEvent {
+                    account_id,
+                    amount: U128::from(refund),
+                }]));
+
+            env::log_str(&storage_withdraw_event.to_string());
+        }
+
+        Ok(true)
+    }
+
+    pub fn storage_balance_bounds(&self) -> StorageBalanceBounds {
+        StorageBalanceBounds {
+            min: U128::from(self.storage_balance_min()),
+            max: None,
+        }
+    }
+
+    pub fn storage_balance_of(&self, account_id: AccountId) -> Option<StorageBalance> {
+        self.accounts
+            .get(&account_id)
+            .map(|account| self.storage_balance(&account))
+    }
+
+    pub fn get_fees_paid(&self, account_id: AccountId) -> U128 {
+        U128::from(
+            self.accounts
+                .get(&account_id)
+                .map(|account| account.fees_paid)
+                .unwrap_or(0),
+        )
+    }
+}
+
+// full access key confirmation of the storage withdrawals, as in NEP-145
+fn require_one_yocto() -> Result<(), ContractError> {
+    if env::attached_deposit() != 1 {
+        return Err(ContractError::InvalidDeposit {
+            reason: "requires an attached deposit of exactly 1 yoctoNEAR".to_string(),
+        });
+    }
+    Ok(())
+}
+
+impl PingPong {
+    pub(crate) fn storage_balance_min(&self) -> u128 {
+        Self::storage_cost(self.account_storage_usage)
+    }
+
+    pub(crate) fn storage_available(&self, account: &StorageAccount) -> u128 {
+        account
+            .deposit
+            .saturating_sub(self.storage_balance_min())
+            .saturating_sub(Self::storage_cost(account.used_bytes))
+    }
+
+    pub(crate) fn storage_cost(bytes: u64) -> u128 {
+        u128::from(bytes) * env::storage_byte_cost()
+    }
+
+    fn storage_balance(&self, account: &StorageAccount) -> StorageBalance {
+        StorageBalance {
+            total: U128::from(account.deposit),
+            available: U128::from(self.storage_available(account)),
+        }
+    }
+
+    // storage used by the registration of an account, and by the records of
+    // a ping (its request identifier and its ack), measured with the longest
+    // account id, the largest values and the longest message
+    pub(crate) fn measure_storage_usage(&mut self) {
+        let initial_storage_usage: u64 = env::storage_usage();
+        let tmp_account_id: AccountId = AccountId::new_unchecked("a".repeat(64));
+        self.accounts
+            .insert(&tmp_account_id, &StorageAccount::default());
+        self.account_storage_usage = env::storage_usage() - initial_storage_usage;
+        self.accounts.remove(&tmp_account_id);
+
+        let initial_storage_usage: u64 = env::storage_usage();
+        self.request_identifiers.insert(&u64::MAX, &u128::MAX);
+        self.ack_from_destination
+            .insert(&u64::MAX, &"a".repeat(MAX_PING_MESSAGE_LENGTH));
+        self.ping_storage_usage = env::storage_usage() - initial_storage_usage;
+        self.ack_from_destination.remove(&u64::MAX);
+        self.request_identifiers.remove(&u64::MAX);
+    }
+}
@@ -1,11 +1,26 @@
+use crate::payload::{PingPayload, LEGACY_PING_PAYLOAD_VERSION, PING_PAYLOAD_VERSION};
 use crate::*;
-use near_sdk::{env, json_types::U128};
+use crate::records::{AckFromDestinationRecord, ChainCount, PingFromSourceRecord};
+use crate::storage::{StorageAccount, StorageBalance};
+use crate::upgrade::STATE_VERSION;
+use near_crosstalk_sample::request_metadata::RequestMetadataError;
+use near_sdk::{
+    env,
+    json_types::U128,
+    mock::VmAction,
+    test_utils::{get_created_receipts, get_logs, VMContextBuilder},
+    testing_env,
+};
 use router_wasm_bindings::ethabi::{decode, encode, ethereum_types::U256, ParamType, Token};
 
+// bech32 address of the Router chain
+const ASM_ADDRESS: &str = "router1qypqxpq9qcrsszg2pvxq6rs0zqg3yyc52edxev";
+
 #[test]
 fn test_ping_to_dest() {
     let gateway: AccountId = env::predecessor_account_id();
     let mut ping_pong: PingPong = PingPong::new(gateway.clone());
+    register(&mut ping_pong, &gateway);
 
     let dest_gas_limit: u64 = 1000000;
     let dest_gas_price: u64 = 100000000000;
@@ -38,13 +53,14 @@ fn test_ping_to_dest() {
         "hello".to_string(),
         request_metadata,
         "shivam".to_string()
-    );
+    ).unwrap();
 }
 
 #[test]
 fn test_ping_from_source() {
     let gateway: AccountId = env::predecessor_account_id();
     let mut ping_pong: PingPong = PingPong::new(gateway.clone());
+    register(&mut ping_pong, &gateway);
 
     let message: String = String::from("hello");
 
@@ -79,7 +95,7 @@ fn test_ping_from_source() {
         "hello".to_string(),
         request_metadata,
         "shivam".to_string()
-    );
+    ).unwrap();
 
     let request_id: u64 = ping_pong.get_current_request_id();
 
@@ -91,10 +107,15 @@ fn test_ping_from_source() {
         "hello".to_string(),
         packet,
         "80001".to_string(),
-    );
+    ).unwrap();
 
     let ping_from_source = ping_pong.get_ping_from_source("80001".to_string(), request_id);
-    assert_eq!(ping_from_source, message);
+    assert_eq!(ping_from_source, Some(message));
+    assert_eq!(
+        get_logs().last().unwrap(),
+        "EVENT_JSON:{\"standard\":\"crosstalk\",\"version\":\"1.0.0\",\"event\":\"packet_received\",\
+         \"data\":[{\"src_chain_id\":\"80001\",\"request_sender\":\"hello\"}]}"
+    );
 }
 
 #[test]
@@ -117,10 +138,728 @@ fn test_crosstalk_ack() {
         request_identifier,
         exec_flags,
         exec_data,
-    );
+    ).unwrap();
 
     let ack_from_destination = ping_pong.get_ack_from_destination(request_id);
-    assert_eq!(ack_from_destination, message);
+    assert_eq!(ack_from_destination, Some(message));
+    assert_eq!(
+        get_logs().last().unwrap(),
+        "EVENT_JSON:{\"standard\":\"crosstalk\",\"version\":\"1.0.0\",\"event\":\"ack_received\",\
+         \"data\":[{\"request_identifier\":\"1\",\"exec_flag\":true}]}"
+    );
+}
+
+#[test]
+fn test_crosstalk_ack_of_failed_request() {
+    let gateway: AccountId = env::predecessor_account_id();
+    let mut ping_pong: PingPong = PingPong::new(gateway.clone());
+
+    // the exec data of a failed request is the error of the destination chain
+    ping_pong
+        .i_ack(U128::from(1), false, b"out of gas".to_vec())
+        .unwrap();
+
+    assert_eq!(ping_pong.get_acks_from_destination(None, None), vec![]);
+    assert_eq!(
+        get_logs().last().unwrap(),
+        "EVENT_JSON:{\"standard\":\"crosstalk\",\"version\":\"1.0.0\",\"event\":\"ack_received\",\
+         \"data\":[{\"request_identifier\":\"1\",\"exec_flag\":false}]}"
+    );
+
+    let err: ContractError = ping_pong
+        .i_ack(U128::from(2), true, b"out of gas".to_vec())
+        .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::DecodeError {
+            what: "exec data".to_string(),
+            reason: "unknown ack layout for request U128(2)".to_string(),
+        }
+    );
+}
+
+#[test]
+fn test_config_changed_events() {
+    let gateway: AccountId = env::predecessor_account_id();
+    let mut ping_pong: PingPong = PingPong::new(gateway.clone());
+
+    ping_pong.set_gateway_fee(U128::from(1_000)).unwrap();
+    ping_pong.set_safety_margin(U128::from(5)).unwrap();
+
+    assert_eq!(
+        get_logs(),
+        vec![
+            "EVENT_JSON:{\"standard\":\"crosstalk\",\"version\":\"1.0.0\",\"event\":\"config_changed\",\
+             \"data\":[{\"key\":\"gateway_fee\",\"value\":\"1000\"}]}"
+                .to_string(),
+            "EVENT_JSON:{\"standard\":\"crosstalk\",\"version\":\"1.0.0\",\"event\":\"config_changed\",\
+             \"data\":[{\"key\":\"safety_margin\",\"value\":\"5\"}]}"
+                .to_string(),
+        ]
+    );
+}
+
+#[test]
+fn test_ping_from_source_every_payload_version() {
+    let gateway: AccountId = env::predecessor_account_id();
+    let mut ping_pong: PingPong = PingPong::new(gateway.clone());
+
+    let v2: PingPayload = PingPayload {
+        version: PING_PAYLOAD_VERSION,
+        request_id: 1,
+        timestamp: 1_700_000_000,
+        sender: b"ping-pong.testnet".to_vec(),
+        message: String::from("v2 ping"),
+        body: vec![],
+        recipient: String::from("alice.near"),
+    };
+    let v1: PingPayload = PingPayload {
+        version: 1,
+        request_id: 2,
+        message: String::from("v1 ping"),
+        recipient: String::new(),
+        ..v2.clone()
+    };
+    let legacy: Vec<u8> = encode(&[
+        Token::Uint(U256::from(3u64)),
+        Token::String(String::from("legacy ping")),
+        Token::String(String::from("bob.near")),
+    ]);
+
+    for packet in [v2.encode(), v1.encode(), legacy.clone()] {
+        ping_pong.i_receive("hello".to_string(), packet, "80001".to_string()).unwrap();
+    }
+
+    assert_eq!(
+        ping_pong.get_ping_from_source("80001".to_string(), 1),
+        Some("v2 ping".to_string())
+    );
+    assert_eq!(
+        ping_pong.get_ping_from_source("80001".to_string(), 2),
+        Some("v1 ping".to_string())
+    );
+    assert_eq!(
+        ping_pong.get_ping_from_source("80001".to_string(), 3),
+        Some("legacy ping".to_string())
+    );
+
+    let decoded: PingPayload = PingPayload::decode(&legacy).unwrap();
+    assert_eq!(decoded.version, LEGACY_PING_PAYLOAD_VERSION);
+    assert_eq!(decoded.recipient, "bob.near");
+    assert_eq!(PingPayload::decode(&v2.encode()), Some(v2));
+}
+
+#[test]
+fn test_ping_from_source_invalid_payload() {
+    let gateway: AccountId = env::predecessor_account_id();
+    let mut ping_pong: PingPong = PingPong::new(gateway.clone());
+
+    let err: ContractError = ping_pong
+        .i_receive("hello".to_string(), vec![1, 2, 3], "80001".to_string())
+        .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::DecodeError {
+            what: "packet".to_string(),
+            reason: "unknown ping payload layout".to_string(),
+        }
+    );
+    assert_eq!(ping_pong.get_ping_count_from_source("80001".to_string()), 0);
+}
+
+#[test]
+fn test_crosstalk_ack_with_pong_receipt() {
+    let gateway: AccountId = env::predecessor_account_id();
+    let mut ping_pong: PingPong = PingPong::new(gateway.clone());
+
+    // pong receipt returned by the CosmWasm ping-pong
+    let exec_data: Vec<u8> = encode(&[Token::Tuple(vec![
+        Token::Uint(U256::from(1u64)),
+        Token::Uint(U256::from(7u64)),
+        Token::Uint(U256::from(1_700_000_000u64)),
+        Token::Uint(U256::from(100u64)),
+        Token::Uint(U256::from(1_700_000_010u64)),
+        Token::String(String::from("pong")),
+    ])]);
+
+    ping_pong.i_ack(U128::from(1), true, exec_data).unwrap();
+
+    assert_eq!(ping_pong.get_ack_from_destination(7), Some("pong".to_string()));
+}
+
+#[test]
+fn test_ping_records_request_identifier() {
+    let gateway: AccountId = env::predecessor_account_id();
+    let mut ping_pong: PingPong = PingPong::new(gateway.clone());
+    register(&mut ping_pong, &gateway);
+
+    ping_pong.i_ping(
+        "80001".to_string(),
+        "shivam.near".to_string(),
+        "hello".to_string(),
+        RequestMetadata::default().encode(),
+        "shivam".to_string()
+    ).unwrap();
+
+    let request_identifier: Option<U128> =
+        ping_pong.i_send_callback(
+            1,
+            "80001".to_string(),
+            gateway.clone(),
+            U128::from(0),
+            Ok(U128::from(42)),
+        );
+
+    assert_eq!(request_identifier, Some(U128::from(42)));
+    assert_eq!(ping_pong.get_request_identifier(1), Some(U128::from(42)));
+    assert_eq!(ping_pong.get_current_request_id(), 1);
+    let logs: Vec<String> = get_logs();
+    assert!(logs[logs.len() - 2].contains("\"event\":\"request_sent\""));
+    assert_eq!(
+        logs.last().unwrap(),
+        &format!(
+            "EVENT_JSON:{{\"standard\":\"crosstalk\",\"version\":\"1.0.0\",\"event\":\"request_sent\",\
+             \"data\":[{{\"sender\":\"{}\",\"dest_chain_id\":\"80001\",\"request_identifier\":\"42\"}}]}}",
+            gateway
+        )
+    );
+}
+
+#[test]
+fn test_ping_rolled_back_on_gateway_failure() {
+    let gateway: AccountId = env::predecessor_account_id();
+    let mut ping_pong: PingPong = PingPong::new(gateway.clone());
+    register(&mut ping_pong, &gateway);
+
+    for _ in 0..2 {
+        ping_pong.i_ping(
+            "80001".to_string(),
+            "shivam.near".to_string(),
+            "hello".to_string(),
+            RequestMetadata::default().encode(),
+            "shivam".to_string()
+        ).unwrap();
+    }
+
+    // the first ping fails after the second one took the next request id
+    ping_pong.i_send_callback(
+        1,
+        "80001".to_string(),
+        gateway.clone(),
+        U128::from(10),
+        Err(PromiseError::Failed),
+    );
+    assert_eq!(ping_pong.get_current_request_id(), 2);
+
+    let request_identifier: Option<U128> =
+        ping_pong.i_send_callback(
+            2,
+            "80001".to_string(),
+            gateway.clone(),
+            U128::from(10),
+            Err(PromiseError::Failed),
+        );
+
+    assert_eq!(request_identifier, None);
+    assert_eq!(ping_pong.get_request_identifier(2), None);
+    assert_eq!(ping_pong.get_current_request_id(), 1);
+    assert!(get_logs().last().unwrap().contains("\"event\":\"request_failed\""));
+    assert!(get_logs().last().unwrap().contains("\"refund\":\"10\""));
+}
+
+#[test]
+fn test_ping_requires_storage_deposit() {
+    let gateway: AccountId = env::predecessor_account_id();
+    let mut ping_pong: PingPong = PingPong::new(gateway.clone());
+
+    let result = ping_pong.i_ping(
+        "80001".to_string(),
+        "shivam.near".to_string(),
+        "hello".to_string(),
+        RequestMetadata::default().encode(),
+        "shivam".to_string()
+    );
+    assert_eq!(result.err(), Some(ContractError::NotRegistered {
+            account_id: gateway.to_string(),
+        }));
+}
+
+#[test]
+fn test_ping_storage_cost_covers_the_records() {
+    let gateway: AccountId = env::predecessor_account_id();
+    let mut ping_pong: PingPong = PingPong::new(gateway.clone());
+    register(&mut ping_pong, &gateway);
+
+    let result = ping_pong.i_ping(
+        "80001".to_string(),
+        "shivam.near".to_string(),
+        "a".repeat(MAX_PING_MESSAGE_LENGTH + 1),
+        RequestMetadata::default().encode(),
+        "shivam".to_string()
+    );
+    assert!(matches!(result.err(), Some(ContractError::InvalidArgument { .. })));
+
+    let message: String = "a".repeat(MAX_PING_MESSAGE_LENGTH);
+    ping_pong.i_ping(
+        "80001".to_string(),
+        "shivam.near".to_string(),
+        message.clone(),
+        RequestMetadata::default().encode(),
+        "shivam".to_string()
+    ).unwrap();
+
+    // the request identifier and the ack of the ping
+    let initial_storage_usage: u64 = env::storage_usage();
+    ping_pong.i_send_callback(
+        1,
+        "80001".to_string(),
+        gateway.clone(),
+        U128::from(0),
+        Ok(U128::from(u128::MAX)),
+    );
+    let exec_data: Vec<u8> = encode(&[Token::Uint(U256::from(1u64)), Token::String(message)]);
+    ping_pong.i_ack(U128::from(u128::MAX), true, exec_data).unwrap();
+    assert!(env::storage_usage() - initial_storage_usage <= ping_pong.ping_storage_usage);
+}
+
+#[test]
+fn test_storage_deposit_and_withdraw() {
+    let gateway: AccountId = env::predecessor_account_id();
+    let mut ping_pong: PingPong = PingPong::new(gateway.clone());
+    let user: AccountId = "user.near".parse().unwrap();
+    let min: u128 = ping_pong.storage_balance_bounds().min.into();
+    assert!(min > 0);
+
+    set_context(&user, min + 100);
+    let balance: StorageBalance = ping_pong.storage_deposit(None, None).unwrap();
+    assert_eq!(balance.total, U128::from(min + 100));
+    assert_eq!(balance.available, U128::from(100));
+
+    // a registration only deposit of a registered account is refunded
+    set_context(&user, 50);
+    ping_pong.storage_deposit(None, Some(true)).unwrap();
+    assert_eq!(
+        ping_pong.storage_balance_of(user.clone()).unwrap().total,
+        U128::from(min + 100)
+    );
+
+    set_context(&user, 1);
+    let balance: StorageBalance = ping_pong.storage_withdraw(Some(U128::from(60))).unwrap();
+    assert_eq!(balance.available, U128::from(40));
+    assert!(get_logs().last().unwrap().contains("\"event\":\"storage_withdraw\""));
+
+    set_context(&user, 1);
+    assert_eq!(ping_pong.storage_unregister(None), Ok(true));
+    assert_eq!(ping_pong.storage_balance_of(user), None);
+    assert_eq!(ping_pong.get_balance_breakdown().storage_deposits, U128::from(0));
+}
+
+#[test]
+fn test_ping_charges_fee_and_refunds_excess() {
+    let gateway: AccountId = env::predecessor_account_id();
+    let mut ping_pong: PingPong = PingPong::new(gateway.clone());
+    register(&mut ping_pong, &gateway);
+    ping_pong.set_gateway_fee(U128::from(1_000)).unwrap();
+    assert_eq!(ping_pong.estimate_ping_fee(), U128::from(1_000));
+
+    let available: u128 = ping_pong
+        .storage_balance_of(gateway.clone())
+        .unwrap()
+        .available
+        .into();
+
+    set_context(&gateway, 1_500);
+    ping_pong.i_ping(
+        "80001".to_string(),
+        "shivam.near".to_string(),
+        "hello".to_string(),
+        RequestMetadata::default().encode(),
+        "shivam".to_string()
+    ).unwrap();
+
+    let deposits: Vec<u128> = get_created_receipts()
+        .into_iter()
+        .flat_map(|receipt| receipt.actions)
+        .filter_map(|action| match action {
+            VmAction::Transfer { deposit } => Some(deposit),
+            VmAction::FunctionCall {
+                function_name,
+                deposit,
+                ..
+            } if function_name == "i_send" => Some(deposit),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(deposits, vec![500, 1_000]);
+
+    assert_eq!(ping_pong.get_fees_paid(gateway.clone()), U128::from(1_000));
+    assert_eq!(
+        u128::from(ping_pong.storage_balance_of(gateway.clone()).unwrap().available),
+        available - u128::from(ping_pong.get_ping_storage_cost())
+    );
+
+    // the storage and the fee of a failed ping are released
+    ping_pong.i_send_callback(
+        1,
+        "80001".to_string(),
+        gateway.clone(),
+        U128::from(1_000),
+        Err(PromiseError::Failed),
+    );
+    assert_eq!(ping_pong.get_fees_paid(gateway.clone()), U128::from(0));
+    assert_eq!(
+        ping_pong.storage_balance_of(gateway).unwrap().available,
+        U128::from(available)
+    );
+}
+
+#[test]
+fn test_ping_with_insufficient_fee() {
+    let gateway: AccountId = env::predecessor_account_id();
+    let mut ping_pong: PingPong = PingPong::new(gateway.clone());
+    register(&mut ping_pong, &gateway);
+    ping_pong.set_gateway_fee(U128::from(1_000)).unwrap();
+
+    set_context(&gateway, 999);
+    let result = ping_pong.i_ping(
+        "80001".to_string(),
+        "shivam.near".to_string(),
+        "hello".to_string(),
+        RequestMetadata::default().encode(),
+        "shivam".to_string()
+    );
+    assert_eq!(result.err(), Some(ContractError::InvalidDeposit {
+            reason: "the attached deposit is less than the fee of 1000".to_string(),
+        }));
+}
+
+#[test]
+fn test_withdraw_fees_keeps_deposits_and_safety_margin() {
+    let gateway: AccountId = env::predecessor_account_id();
+    let mut ping_pong: PingPong = PingPong::new(gateway.clone());
+    register(&mut ping_pong, &gateway);
+
+    let breakdown: BalanceBreakdown = ping_pong.get_balance_breakdown();
+    assert_eq!(breakdown.safety_margin, U128::from(DEFAULT_SAFETY_MARGIN));
+    assert_eq!(
+        u128::from(breakdown.withdrawable),
+        u128::from(breakdown.account_balance)
+            - u128::from(breakdown.storage_cost)
+            - u128::from(breakdown.storage_deposits)
+            - DEFAULT_SAFETY_MARGIN
+    );
+
+    ping_pong.withdraw_fees(gateway.clone()).unwrap();
+    assert_eq!(
+        get_logs().last().unwrap(),
+        &format!(
+            "EVENT_JSON:{{\"standard\":\"crosstalk\",\"version\":\"1.0.0\",\"event\":\"fees_withdrawn\",\
+             \"data\":[{{\"recipient\":\"{}\",\"amount\":\"{}\"}}]}}",
+            gateway,
+            u128::from(breakdown.withdrawable)
+        )
+    );
+}
+
+#[test]
+fn test_withdraw_fees_within_safety_margin() {
+    let gateway: AccountId = env::predecessor_account_id();
+    let mut ping_pong: PingPong = PingPong::new(gateway.clone());
+    ping_pong.set_safety_margin(U128::from(env::account_balance())).unwrap();
+
+    assert_eq!(
+        ping_pong.withdraw_fees(gateway).err(),
+        Some(ContractError::NoFeesToWithdraw)
+    );
+}
+
+#[test]
+fn test_two_step_ownership_transfer() {
+    let owner: AccountId = env::predecessor_account_id();
+    let mut ping_pong: PingPong = PingPong::new(owner.clone());
+    let new_owner: AccountId = "new-owner.near".parse().unwrap();
+
+    ping_pong.transfer_ownership(new_owner.clone()).unwrap();
+    assert_eq!(ping_pong.get_owner(), owner);
+    assert_eq!(ping_pong.get_pending_owner(), Some(new_owner.clone()));
+
+    set_context(&new_owner, 0);
+    ping_pong.accept_ownership().unwrap();
+    assert_eq!(ping_pong.get_owner(), new_owner);
+    assert_eq!(ping_pong.get_pending_owner(), None);
+    assert!(get_logs().last().unwrap().contains("\"event\":\"ownership_transferred\""));
+}
+
+#[test]
+fn test_accept_ownership_not_pending_owner() {
+    let owner: AccountId = env::predecessor_account_id();
+    let mut ping_pong: PingPong = PingPong::new(owner.clone());
+
+    ping_pong.transfer_ownership("new-owner.near".parse().unwrap()).unwrap();
+    set_context(&"attacker.near".parse().unwrap(), 0);
+    assert_eq!(ping_pong.accept_ownership(), Err(ContractError::NotPendingOwner));
+    assert_eq!(ping_pong.get_owner(), owner);
+}
+
+#[test]
+fn test_ping_when_paused() {
+    let gateway: AccountId = env::predecessor_account_id();
+    let mut ping_pong: PingPong = PingPong::new(gateway.clone());
+    register(&mut ping_pong, &gateway);
+
+    ping_pong.pause().unwrap();
+    assert!(ping_pong.is_paused());
+
+    let result = ping_pong.i_ping(
+        "80001".to_string(),
+        "shivam.near".to_string(),
+        "hello".to_string(),
+        RequestMetadata::default().encode(),
+        "shivam".to_string()
+    );
+    assert_eq!(result.err(), Some(ContractError::PausedState));
+}
+
+#[test]
+fn test_ping_after_unpause() {
+    let gateway: AccountId = env::predecessor_account_id();
+    let mut ping_pong: PingPong = PingPong::new(gateway.clone());
+    register(&mut ping_pong, &gateway);
+
+    ping_pong.pause().unwrap();
+    ping_pong.unpause().unwrap();
+    assert!(!ping_pong.is_paused());
+
+    ping_pong.i_ping(
+        "80001".to_string(),
+        "shivam.near".to_string(),
+        "hello".to_string(),
+        RequestMetadata::default().encode(),
+        "shivam".to_string()
+    ).unwrap();
+    assert_eq!(ping_pong.get_current_request_id(), 1);
+}
+
+#[test]
+fn test_pause_only_owner() {
+    let gateway: AccountId = env::predecessor_account_id();
+    let mut ping_pong: PingPong = PingPong::new(gateway.clone());
+
+    set_context(&"attacker.near".parse().unwrap(), 0);
+    assert_eq!(ping_pong.pause(), Err(ContractError::NotOwner));
+    assert!(!ping_pong.is_paused());
+}
+
+#[test]
+fn test_pause_and_unpause_reject_no_op() {
+    let gateway: AccountId = env::predecessor_account_id();
+    let mut ping_pong: PingPong = PingPong::new(gateway.clone());
+
+    assert_eq!(ping_pong.unpause(), Err(ContractError::NotPaused));
+
+    ping_pong.pause().unwrap();
+    let logs: usize = get_logs().len();
+    assert_eq!(ping_pong.pause(), Err(ContractError::AlreadyPaused));
+    assert_eq!(get_logs().len(), logs);
+    assert!(ping_pong.is_paused());
+
+    ping_pong.unpause().unwrap();
+    assert_eq!(ping_pong.unpause(), Err(ContractError::NotPaused));
+    assert!(!ping_pong.is_paused());
+}
+
+#[test]
+fn test_migrate_from_v1_state() {
+    // layout of the state deployed up to 1.0.0
+    #[derive(BorshSerialize)]
+    struct LegacyPingPong {
+        owner: AccountId,
+        gateway: AccountId,
+        current_request_id: u64,
+        ping_from_source: UnorderedMap<(String, u64), String>,
+        ack_from_destination: UnorderedMap<u64, String>,
+    }
+
+    let owner: AccountId = env::predecessor_account_id();
+    let mut legacy: LegacyPingPong = LegacyPingPong {
+        owner: owner.clone(),
+        gateway: "gateway.near".parse().unwrap(),
+        current_request_id: 7,
+        ping_from_source: UnorderedMap::new(b'p'),
+        ack_from_destination: UnorderedMap::new(b'a'),
+    };
+    legacy
+        .ping_from_source
+        .insert(&("80001".to_string(), 3), &"hello".to_string());
+    env::state_write(&legacy);
+
+    let mut ping_pong: PingPong = PingPong::migrate().unwrap();
+
+    assert_eq!(ping_pong.get_owner(), owner);
+    assert_eq!(ping_pong.get_gateway(), "gateway.near".parse::<AccountId>().unwrap());
+    assert_eq!(ping_pong.get_current_request_id(), 7);
+    assert_eq!(
+        ping_pong.get_ping_from_source("80001".to_string(), 3),
+        Some("hello".to_string())
+    );
+    // the migration does not count the pings, recount_pings does
+    assert_eq!(ping_pong.get_ping_count_from_source("80001".to_string()), 0);
+    assert_eq!(ping_pong.get_pings_to_recount(), 1);
+    assert_eq!(ping_pong.recount_pings(10), Ok(0));
+    assert_eq!(ping_pong.get_ping_count_from_source("80001".to_string()), 1);
+    assert_eq!(ping_pong.get_pings_to_recount(), 0);
+    assert!(!ping_pong.is_paused());
+    assert!(u128::from(ping_pong.storage_balance_bounds().min) > 0);
+    assert_eq!(
+        env::storage_read(b"STATE_VERSION"),
+        Some(vec![STATE_VERSION])
+    );
+}
+
+#[test]
+fn test_migrate_from_v2_state() {
+    let gateway: AccountId = env::predecessor_account_id();
+    let mut ping_pong: PingPong = PingPong::new(gateway.clone());
+    for (src_chain_id, request_id) in [("80001", 1u64), ("80001", 2), ("43113", 1)] {
+        ping_pong.ping_from_source.insert(
+            &(src_chain_id.to_string(), request_id),
+            &"hello".to_string(),
+        );
+    }
+
+    // layout of the state before the counters of the pings per chain
+    #[derive(BorshSerialize)]
+    struct LegacyPingPong<'a> {
+        owner: &'a AccountId,
+        pending_owner: &'a Option<AccountId>,
+        gateway: &'a AccountId,
+        paused: bool,
+        current_request_id: u64,
+        ping_from_source: &'a UnorderedMap<(String, u64), String>,
+        ack_from_destination: &'a UnorderedMap<u64, String>,
+        request_identifiers: &'a UnorderedMap<u64, u128>,
+        accounts: &'a LookupMap<AccountId, StorageAccount>,
+        total_storage_deposits: u128,
+        total_fees_paid: u128,
+        gateway_fee: u128,
+        safety_margin: u128,
+        account_storage_usage: u64,
+        ping_storage_usage: u64,
+    }
+
+    env::state_write(&LegacyPingPong {
+        owner: &ping_pong.owner,
+        pending_owner: &ping_pong.pending_owner,
+        gateway: &ping_pong.gateway,
+        paused: true,
+        current_request_id: 4,
+        ping_from_source: &ping_pong.ping_from_source,
+        ack_from_destination: &ping_pong.ack_from_destination,
+        request_identifiers: &ping_pong.request_identifiers,
+        accounts: &ping_pong.accounts,
+        total_storage_deposits: 0,
+        total_fees_paid: 0,
+        gateway_fee: 1_000,
+        safety_margin: 5,
+        account_storage_usage: ping_pong.account_storage_usage,
+        ping_storage_usage: ping_pong.ping_storage_usage,
+    });
+    env::storage_write(b"STATE_VERSION", &[2]);
+
+    let mut ping_pong: PingPong = PingPong::migrate().unwrap();
+
+    assert!(ping_pong.is_paused());
+    assert_eq!(ping_pong.get_current_request_id(), 4);
+    assert_eq!(ping_pong.estimate_ping_fee(), U128::from(1_000));
+    assert_eq!(ping_pong.get_pings_to_recount(), 3);
+    assert_eq!(ping_pong.recount_pings(2), Ok(1));
+    assert_eq!(ping_pong.get_ping_count_from_source("80001".to_string()), 2);
+    assert_eq!(ping_pong.get_ping_count_from_source("43113".to_string()), 0);
+
+    // only the owner recounts the pings
+    set_context(&"attacker.near".parse().unwrap(), 0);
+    assert_eq!(ping_pong.recount_pings(2), Err(ContractError::NotOwner));
+    set_context(&gateway, 0);
+
+    // a ping received during the recount is counted by i_receive only
+    ping_pong.unpause().unwrap();
+    let packet: Vec<u8> = encode(&[
+        Token::Uint(U256::from(5u64)),
+        Token::String("hello".to_string()),
+    ]);
+    ping_pong.i_receive("hello".to_string(), packet, "43113".to_string()).unwrap();
+    assert_eq!(ping_pong.get_ping_count_from_source("43113".to_string()), 1);
+
+    assert_eq!(ping_pong.recount_pings(2), Ok(0));
+    assert_eq!(ping_pong.recount_pings(2), Ok(0));
+    assert_eq!(
+        ping_pong.get_ping_counts_from_source(None, None),
+        vec![
+            ChainCount {
+                src_chain_id: "80001".to_string(),
+                count: 2,
+            },
+            ChainCount {
+                src_chain_id: "43113".to_string(),
+                count: 2,
+            },
+        ]
+    );
+    assert_eq!(
+        env::storage_read(b"STATE_VERSION"),
+        Some(vec![STATE_VERSION])
+    );
+}
+
+#[test]
+fn test_ping_records_pagination() {
+    let gateway: AccountId = env::predecessor_account_id();
+    let mut ping_pong: PingPong = PingPong::new(gateway.clone());
+
+    for (src_chain_id, request_id) in [("80001", 1u64), ("80001", 2), ("43113", 1)] {
+        let packet: Vec<u8> = encode(&[
+            Token::Uint(U256::from(request_id)),
+            Token::String(format!("hello {}", request_id)),
+        ]);
+        ping_pong.i_receive("hello".to_string(), packet.clone(), src_chain_id.to_string()).unwrap();
+        // a redelivered packet is counted once
+        ping_pong.i_receive("hello".to_string(), packet, src_chain_id.to_string()).unwrap();
+    }
+
+    assert_eq!(ping_pong.get_ping_count_from_source("80001".to_string()), 2);
+    assert_eq!(ping_pong.get_ping_count_from_source("43113".to_string()), 1);
+    assert_eq!(ping_pong.get_ping_count_from_source("97".to_string()), 0);
+
+    assert_eq!(ping_pong.get_pings_from_source(None, None).len(), 3);
+    assert_eq!(
+        ping_pong.get_pings_from_source(Some(1), Some(1)),
+        vec![PingFromSourceRecord {
+            src_chain_id: "80001".to_string(),
+            request_id: 2,
+            message: "hello 2".to_string(),
+        }]
+    );
+    assert_eq!(ping_pong.get_pings_from_source(Some(3), None), vec![]);
+    assert_eq!(ping_pong.get_pings_from_source(Some(u64::MAX), Some(u64::MAX)), vec![]);
+
+    // a missing record differs from an empty one
+    assert_eq!(ping_pong.get_ping_from_source("97".to_string(), 1), None);
+    assert_eq!(ping_pong.get_ack_from_destination(1), None);
+    assert_eq!(ping_pong.get_acks_from_destination(None, None), vec![]);
+
+    ping_pong.i_ack(
+        U128::from(1),
+        true,
+        encode(&[Token::Uint(U256::from(1u64)), Token::String(String::new())]),
+    ).unwrap();
+    assert_eq!(ping_pong.get_ack_from_destination(1), Some(String::new()));
+    assert_eq!(
+        ping_pong.get_acks_from_destination(None, Some(10)),
+        vec![AckFromDestinationRecord {
+            request_id: 1,
+            ack_message: String::new(),
+        }]
+    );
 }
 
 #[test]
@@ -153,6 +892,147 @@ fn get_request_metadata() {
     println!("Request Metadata: {:?}", request_metadata);
 }
 
+#[test]
+fn test_request_metadata_round_trip() {
+    let request_metadata: RequestMetadata = RequestMetadata {
+        dest_gas_limit: 1000000,
+        dest_gas_price: 100000000000,
+        ack_gas_limit: 100000000000000,
+        ack_gas_price: 1000000000,
+        relayer_fees: U128::from(1000000000000000),
+        ack_type: 3,
+        is_read_call: true,
+        asm_address: ASM_ADDRESS.to_string(),
+    };
+
+    let encoded: Vec<u8> = PingPong::encode_request_metadata(request_metadata.clone()).unwrap();
+    assert_eq!(encoded.len(), 50 + ASM_ADDRESS.len());
+    assert_eq!(
+        encoded,
+        PingPong::get_request_metadata(
+            1000000,
+            100000000000,
+            100000000000000,
+            1000000000,
+            U128::from(1000000000000000),
+            3,
+            true,
+            ASM_ADDRESS.to_string(),
+        )
+        .unwrap()
+    );
+    assert_eq!(PingPong::decode_request_metadata(encoded).unwrap(), request_metadata);
+}
+
+#[test]
+fn test_request_metadata_views_errors() {
+    let invalid_ack_type: RequestMetadata = RequestMetadata {
+        ack_type: 4,
+        asm_address: ASM_ADDRESS.to_string(),
+        ..RequestMetadata::default()
+    };
+    let err: ContractError = PingPong::encode_request_metadata(invalid_ack_type).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::InvalidRequestMetadata(RequestMetadataError::InvalidAckType { ack_type: 4 })
+    );
+    assert_eq!(err.code(), "InvalidRequestMetadata");
+
+    assert_eq!(
+        PingPong::decode_request_metadata(vec![0; 49]),
+        Err(ContractError::InvalidRequestMetadata(RequestMetadataError::TooShort { len: 49 }))
+    );
+}
+
+#[test]
+fn test_contract_error_messages() {
+    let err: ContractError = ContractError::DecodeError {
+        what: "packet".to_string(),
+        reason: "unknown ping payload layout".to_string(),
+    };
+    assert_eq!(err.code(), "DecodeError");
+    assert_eq!(
+        err.to_string(),
+        "DecodeError: Error in decoding packet: unknown ping payload layout"
+    );
+    assert_eq!(ContractError::NotGateway.to_string(), "NotGateway: only gateway");
+    assert_eq!(
+        ContractError::EmptyMessage { what: "message".to_string() }.to_string(),
+        "EmptyMessage: message cannot be empty"
+    );
+}
+
+#[test]
+fn test_request_metadata_validation() {
+    let valid: RequestMetadata = RequestMetadata {
+        asm_address: ASM_ADDRESS.to_string(),
+        ..RequestMetadata::default()
+    };
+    assert_eq!(valid.validate(), Ok(()));
+
+    let invalid_ack_type: RequestMetadata = RequestMetadata {
+        ack_type: 4,
+        ..valid.clone()
+    };
+    assert_eq!(
+        invalid_ack_type.validate(),
+        Err(RequestMetadataError::InvalidAckType { ack_type: 4 })
+    );
+
+    // wrong prefix, wrong checksum, and not bech32
+    for asm_address in [
+        "osmo1qypqxpq9qcrsszg2pvxq6rs0zqg3yyc52edxev",
+        "router1qypqxpq9qcrsszg2pvxq6rs0zqg3yyc52edxew",
+        "router1qypqxpq9qcrsszg2pvxq6rs0zqg3yyc52edxeB",
+        "0x1234",
+    ] {
+        let request_metadata: RequestMetadata = RequestMetadata {
+            asm_address: asm_address.to_string(),
+            ..valid.clone()
+        };
+        assert!(
+            matches!(
+                request_metadata.validate(),
+                Err(RequestMetadataError::InvalidAsmAddress { .. })
+            ),
+            "{}",
+            asm_address
+        );
+    }
+
+    let mut invalid_read_call_flag: Vec<u8> = valid.encode();
+    invalid_read_call_flag[49] = 2;
+    assert_eq!(
+        RequestMetadata::decode(&invalid_read_call_flag),
+        Err(RequestMetadataError::InvalidReadCallFlag { flag: 2 })
+    );
+    assert_eq!(
+        RequestMetadata::decode(&[0; 49]),
+        Err(RequestMetadataError::TooShort { len: 49 })
+    );
+}
+
+#[test]
+fn test_ping_with_malformed_request_metadata() {
+    let gateway: AccountId = env::predecessor_account_id();
+    let mut ping_pong: PingPong = PingPong::new(gateway.clone());
+    register(&mut ping_pong, &gateway);
+
+    let mut request_metadata: Vec<u8> = RequestMetadata::default().encode();
+    request_metadata[48] = 7;
+
+    let result = ping_pong.i_ping(
+        "80001".to_string(),
+        "shivam.near".to_string(),
+        "hello".to_string(),
+        request_metadata,
+        "shivam".to_string()
+    );
+    assert_eq!(result.err(), Some(ContractError::InvalidRequestMetadata(
+            RequestMetadataError::InvalidAckType { ack_type: 7 }
+        )));
+}
+
 #[test]
 fn abi_decode() {
     let data = hex
@@ -229,3 +1109,19 @@ fn hex_decode() {
         .unwrap();
     println!("{:?}", data);
 }
+
+fn set_context(predecessor_account_id: &AccountId, attached_deposit: u128) {
+    testing_env!(VMContextBuilder::new()
+        .predecessor_account_id(predecessor_account_id.clone())
+        .attached_deposit(attached_deposit)
+        .build());
+}
+
+// registers the account with enough storage balance for a few pings
+fn register(ping_pong: &mut PingPong, account_id: &AccountId) {
+    let deposit: u128 = u128::from(ping_pong.storage_balance_bounds().min)
+        + 10 * u128::from(ping_pong.get_ping_storage_cost());
+    set_context(account_id, deposit);
+    ping_pong.storage_deposit(None, None).unwrap();
+    set_context(account_id, 0);
+}
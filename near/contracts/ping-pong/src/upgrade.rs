@@ -0,0 +1,148 @@
+use near_sdk::{
+    borsh::{self, BorshDeserialize},
+    collections::{LookupMap, UnorderedMap},
+    env,
+    json_types::Base64VecU8,
+    near_bindgen, AccountId, Gas, Promise,
+};
+
+use crate::errors::ContractError;
+use crate::external::TGAS;
+use crate::storage::StorageAccount;
+use crate::{PingPong, PingPongExt, DEFAULT_SAFETY_MARGIN};
+
+// version of the layout of the contract state
+pub const STATE_VERSION: u8 = 3;
+// the deployments up to 1.0.0 never wrote the state version
+const STATE_VERSION_KEY: &[u8] = b"STATE_VERSION";
+const MIGRATE_GAS: u64 = 50 * TGAS;
+
+/// State of the ping-pong deployed up to 1.0.0.
+#[derive(BorshDeserialize)]
+pub struct PingPongV1 {
+    owner: AccountId,
+    gateway: AccountId,
+    current_request_id: u64,
+    ping_from_source: UnorderedMap<(String, u64), String>,
+    ack_from_destination: UnorderedMap<u64, String>,
+}
+
+/// State of the ping-pong before the counters of the pings per chain.
+#[derive(BorshDeserialize)]
+pub struct PingPongV2 {
+    owner: AccountId,
+    pending_owner: Option<AccountId>,
+    gateway: AccountId,
+    paused: bool,
+    current_request_id: u64,
+    ping_from_source: UnorderedMap<(String, u64), String>,
+    ack_from_destination: UnorderedMap<u64, String>,
+    request_identifiers: UnorderedMap<u64, u128>,
+    accounts: LookupMap<AccountId, StorageAccount>,
+    total_storage_deposits: u128,
+    total_fees_paid: u128,
+    gateway_fee: u128,
+    safety_margin: u128,
+    account_storage_usage: u64,
+    ping_storage_usage: u64,
+}
+
+/// Every layout of the contract state, read by `migrate`.
+pub enum VersionedPingPong {
+    V1(PingPongV1),
+    V2(PingPongV2),
+    V3(PingPong),
+}
+
+impl VersionedPingPong {
+    pub fn read() -> Option<Self> {
+        match env::storage_read(STATE_VERSION_KEY).as_deref() {
+            None => env::state_read().map(VersionedPingPong::V1),
+            Some([2]) => env::state_read().map(VersionedPingPong::V2),
+            Some([3]) => env::state_read().map(VersionedPingPong::V3),
+            Some(_) => None,
+        }
+    }
+
+    pub fn into_current(self) -> PingPong {
+        match self {
+            VersionedPingPong::V1(state) => {
+                let mut this: PingPong = PingPong {
+                    owner: state.owner,
+                    pending_owner: None,
+                    gateway: state.gateway,
+                    paused: false,
+                    current_request_id: state.current_request_id,
+                    ping_from_source: state.ping_from_source,
+                    ack_from_destination: state.ack_from_destination,
+                    ping_counts_from_source: UnorderedMap::new(b'c'),
+                    request_identifiers: UnorderedMap::new(b'r'),
+                    accounts: LookupMap::new(b'u'),
+                    total_storage_deposits: 0,
+                    total_fees_paid: 0,
+                    gateway_fee: 0,
+                    safety_margin: DEFAULT_SAFETY_MARGIN,
+                    account_storage_usage: 0,
+                    ping_storage_usage: 0,
+                };
+                this.measure_storage_usage();
+                this.start_pings_recount();
+                this
+            }
+            VersionedPingPong::V2(state) => {
+                let this: PingPong = PingPong {
+                    owner: state.owner,
+                    pending_owner: state.pending_owner,
+                    gateway: state.gateway,
+                    paused: state.paused,
+                    current_request_id: state.current_request_id,
+                    ping_from_source: state.ping_from_source,
+                    ack_from_destination: state.ack_from_destination,
+                    ping_counts_from_source: UnorderedMap::new(b'c'),
+                    request_identifiers: state.request_identifiers,
+                    accounts: state.accounts,
+                    total_storage_deposits: state.total_storage_deposits,
+                    total_fees_paid: state.total_fees_paid,
+                    gateway_fee: state.gateway_fee,
+                    safety_margin: state.safety_margin,
+                    account_storage_usage: state.account_storage_usage,
+                    ping_storage_usage: state.ping_storage_usage,
+                };
+                this.start_pings_recount();
+                this
+            }
+            VersionedPingPong::V3(state) => state,
+        }
+    }
+
+    pub(crate) fn write_version() {
+        env::storage_write(STATE_VERSION_KEY, &[STATE_VERSION]);
+    }
+}
+
+#[near_bindgen]
+impl PingPong {
+    /// Deploys the new code on the contract account and migrates the state.
+    /// Upgrading in place keeps the address enrolled by the remote dapps.
+    #[handle_result]
+    pub fn upgrade(&self, code: Base64VecU8) -> Result<Promise, ContractError> {
+        self.require_owner()?;
+
+        Ok(Promise::new(env::current_account_id())
+            .deploy_contract(code.into())
+            .function_call("migrate".to_string(), vec![], 0, Gas(MIGRATE_GAS)))
+    }
+
+    #[private]
+    #[init(ignore_state)]
+    #[handle_result]
+    pub fn migrate() -> Result<Self, ContractError> {
+        let state: VersionedPingPong = match VersionedPingPong::read() {
+            Some(state) => state,
+            None => return Err(ContractError::UnknownStateVersion),
+        };
+
+        VersionedPingPong::write_version();
+        Ok(state.into_current())
+    }
+}
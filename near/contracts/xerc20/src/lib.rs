@@ -0,0 +1,377 @@
+pub mod payload;
+#[cfg(test)]
+mod tests;
+
+use near_contract_standards::fungible_token::{
+    events::{FtBurn, FtMint},
+    metadata::{FungibleTokenMetadata, FungibleTokenMetadataProvider},
+    FungibleToken,
+};
+use near_crosstalk_sample::{errors::ContractError, request_metadata::RequestMetadataParam};
+use near_sdk::{
+    borsh::{self, BorshDeserialize, BorshSerialize},
+    collections::{LazyOption, UnorderedMap},
+    env,
+    json_types::U128,
+    near_bindgen,
+    serde::{Deserialize, Serialize},
+    serde_json::json,
+    AccountId, Gas, GasWeight, PanicOnDefault, Promise, PromiseError, PromiseOrValue,
+};
+use payload::TransferPayload;
+
+pub const TGAS: u64 = 1_000_000_000_000;
+
+// chain type of the EVM chains, their addresses are compared in lowercase
+pub const CHAIN_TYPE_EVM: u64 = 1;
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct ContractInfo {
+    pub chain_id: String,
+    pub contract_addr: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct ChainTypeInfo {
+    pub chain_id: String,
+    pub chain_type: u64,
+}
+
+/// Tokens burnt by a cross chain transfer, restored if the request fails.
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct PendingTransfer {
+    pub sender: AccountId,
+    pub amount: U128,
+}
+
+#[near_bindgen]
+#[derive(BorshDeserialize, BorshSerialize, PanicOnDefault)]
+pub struct Xerc20 {
+    owner: AccountId,
+    gateway: AccountId,
+    token: FungibleToken,
+    metadata: LazyOption<FungibleTokenMetadata>,
+    // chainId => xERC20 contract of the chain
+    white_listed_contracts: UnorderedMap<String, String>,
+    // chainId => chainType
+    chain_types: UnorderedMap<String, u64>,
+    // request identifier => transfer waiting for its ack
+    pending_transfers: UnorderedMap<u128, PendingTransfer>,
+}
+
+near_contract_standards::impl_fungible_token_core!(Xerc20, token);
+near_contract_standards::impl_fungible_token_storage!(Xerc20, token);
+
+#[near_bindgen]
+impl FungibleTokenMetadataProvider for Xerc20 {
+    fn ft_metadata(&self) -> FungibleTokenMetadata {
+        self.metadata.get().unwrap()
+    }
+}
+
+#[near_bindgen]
+impl Xerc20 {
+    #[init]
+    pub fn new(gateway: AccountId, metadata: FungibleTokenMetadata) -> Self {
+        metadata.assert_valid();
+        Self {
+            owner: env::predecessor_account_id(),
+            gateway,
+            token: FungibleToken::new(b"t".to_vec()),
+            metadata: LazyOption::new(b"m".to_vec(), Some(&metadata)),
+            white_listed_contracts: UnorderedMap::new(b"w"),
+            chain_types: UnorderedMap::new(b"c"),
+            pending_transfers: UnorderedMap::new(b"p"),
+        }
+    }
+
+    #[handle_result]
+    pub fn set_white_listed_contracts(
+        &mut self,
+        contracts: Vec<ContractInfo>,
+    ) -> Result<(), ContractError> {
+        self.require_owner()?;
+
+        for contract in contracts {
+            self.white_listed_contracts
+                .insert(&contract.chain_id, &contract.contract_addr);
+        }
+        Ok(())
+    }
+
+    #[handle_result]
+    pub fn set_chain_types(
+        &mut self,
+        chain_type_info: Vec<ChainTypeInfo>,
+    ) -> Result<(), ContractError> {
+        self.require_owner()?;
+
+        for info in chain_type_info {
+            self.chain_types.insert(&info.chain_id, &info.chain_type);
+        }
+        Ok(())
+    }
+
+    #[handle_result]
+    pub fn set_gateway(&mut self, gateway: AccountId) -> Result<(), ContractError> {
+        self.require_owner()?;
+
+        self.gateway = gateway;
+        Ok(())
+    }
+
+    #[handle_result]
+    pub fn mint(&mut self, recipient: AccountId, amount: U128) -> Result<(), ContractError> {
+        self.require_owner()?;
+
+        self.internal_mint(&recipient, amount.into(), None);
+        Ok(())
+    }
+
+    pub fn get_owner(&self) -> AccountId {
+        self.owner.clone()
+    }
+
+    pub fn get_gateway(&self) -> AccountId {
+        self.gateway.clone()
+    }
+
+    pub fn get_white_listed_contract(&self, chain_id: String) -> Option<String> {
+        self.white_listed_contracts.get(&chain_id)
+    }
+
+    pub fn get_chain_type(&self, chain_id: String) -> Option<u64> {
+        self.chain_types.get(&chain_id)
+    }
+
+    pub fn get_pending_transfer(&self, request_identifier: U128) -> Option<PendingTransfer> {
+        self.pending_transfers.get(&request_identifier.into())
+    }
+
+    /// Burns `amount` tokens of the caller and sends them to `recipient` on
+    /// the destination chain. The attached deposit pays the gateway fee.
+    #[payable]
+    #[handle_result]
+    pub fn transfer_cross_chain(
+        &mut self,
+        amount: U128,
+        recipient: Vec<u8>,
+        dest_chain_id: String,
+        request_metadata: RequestMetadataParam,
+    ) -> Result<Promise, ContractError> {
+        if env::attached_deposit() == 0 {
+            return Err(ContractError::InvalidDeposit {
+                reason: "requires an attached deposit of at least 1 yoctoNEAR".to_string(),
+            });
+        }
+        if u128::from(amount) == 0 {
+            return Err(ContractError::InvalidArgument {
+                reason: "amount should be positive".to_string(),
+            });
+        }
+
+        let dest_contract_address: String = match self.white_listed_contracts.get(&dest_chain_id) {
+            Some(contract) => contract,
+            None => return Err(unknown_route(&dest_chain_id)),
+        };
+        let request_metadata: Vec<u8> = request_metadata.into_bytes()?;
+
+        let sender: AccountId = env::predecessor_account_id();
+        self.internal_burn(&sender, amount.into(), Some("transfer cross chain"));
+
+        let payload: Vec<u8> = TransferPayload {
+            recipient,
+            amount: amount.into(),
+        }
+        .encode();
+        // abi.encode(handler, payload)
+        let request_packet: Vec<u8> = ethabi::encode(&[
+            ethabi::Token::String(dest_contract_address),
+            ethabi::Token::Bytes(payload),
+        ]);
+
+        // i_send of the gateway returns the request identifier (nonce) it assigned
+        let i_send_args: Vec<u8> = json!({
+            "version": U128::from(1),
+            "dest_chain_id": dest_chain_id,
+            "request_metadata": request_metadata,
+            "request_packet": request_packet,
+        })
+        .to_string()
+        .into_bytes();
+
+        Ok(Promise::new(self.gateway.clone())
+            .function_call_weight(
+                "i_send".to_string(),
+                i_send_args,
+                env::attached_deposit(),
+                Gas(0),
+                GasWeight(1),
+            )
+            .then(
+                Self::ext(env::current_account_id())
+                    .with_static_gas(Gas(10 * TGAS))
+                    .i_send_callback(sender, amount, U128::from(env::attached_deposit())),
+            ))
+    }
+
+    /// Keeps the burnt tokens until the ack of the request, or restores them
+    /// and refunds the deposit when the gateway call fails.
+    #[private]
+    pub fn i_send_callback(
+        &mut self,
+        sender: AccountId,
+        amount: U128,
+        deposit: U128,
+        #[callback_result] result: Result<U128, PromiseError>,
+    ) -> Option<U128> {
+        match result {
+            Ok(request_identifier) => {
+                self.pending_transfers.insert(
+                    &request_identifier.into(),
+                    &PendingTransfer { sender, amount },
+                );
+                Some(request_identifier)
+            }
+            Err(_) => {
+                self.internal_mint(&sender, amount.into(), Some("gateway call failed"));
+                if u128::from(deposit) > 0 {
+                    Promise::new(sender).transfer(deposit.into());
+                }
+                None
+            }
+        }
+    }
+
+    /// Mints the tokens sent by the xERC20 of a whitelisted chain.
+    #[handle_result]
+    pub fn i_receive(
+        &mut self,
+        request_sender: String,
+        packet: Vec<u8>,
+        src_chain_id: String,
+    ) -> Result<Vec<u8>, ContractError> {
+        if env::predecessor_account_id() != self.gateway {
+            return Err(ContractError::NotGateway);
+        }
+
+        let sender: String = match self.chain_types.get(&src_chain_id) {
+            Some(CHAIN_TYPE_EVM) => request_sender.to_lowercase(),
+            Some(_) => request_sender.clone(),
+            None => return Err(unknown_route(&src_chain_id)),
+        };
+        if self.white_listed_contracts.get(&src_chain_id) != Some(sender) {
+            return Err(ContractError::InvalidArgument {
+                reason: format!(
+                    "request sender {} of chain {} is not whitelisted",
+                    request_sender, src_chain_id
+                ),
+            });
+        }
+
+        let transfer: TransferPayload = match TransferPayload::decode(&packet) {
+            Some(transfer) => transfer,
+            None => {
+                return Err(ContractError::DecodeError {
+                    what: "packet".to_string(),
+                    reason: "not an abi encoded (bytes, uint256) transfer".to_string(),
+                })
+            }
+        };
+        let recipient: AccountId = match String::from_utf8(transfer.recipient)
+            .ok()
+            .and_then(|recipient| recipient.parse().ok())
+        {
+            Some(recipient) => recipient,
+            None => {
+                return Err(ContractError::InvalidArgument {
+                    reason: "the recipient is not a NEAR account id".to_string(),
+                })
+            }
+        };
+
+        self.internal_mint(&recipient, transfer.amount, Some("transfer cross chain"));
+
+        Ok(packet)
+    }
+
+    /// Restores the burnt tokens of a failed request.
+    #[handle_result]
+    pub fn i_ack(
+        &mut self,
+        request_identifier: U128,
+        exec_flag: bool,
+        exec_data: Vec<u8>,
+    ) -> Result<(), ContractError> {
+        if env::predecessor_account_id() != self.gateway {
+            return Err(ContractError::NotGateway);
+        }
+
+        let transfer: PendingTransfer =
+            match self.pending_transfers.remove(&request_identifier.into()) {
+                Some(transfer) => transfer,
+                None => {
+                    return Err(ContractError::InvalidArgument {
+                        reason: format!("unknown request identifier {:?}", request_identifier),
+                    })
+                }
+            };
+
+        if !exec_flag {
+            env::log_str(&format!(
+                "request {:?} failed on the destination: {:?}",
+                request_identifier, exec_data
+            ));
+            self.internal_mint(
+                &transfer.sender,
+                transfer.amount.into(),
+                Some("transfer cross chain failed"),
+            );
+        }
+        Ok(())
+    }
+}
+
+fn unknown_route(chain_id: &str) -> ContractError {
+    ContractError::InvalidArgument {
+        reason: format!("unknown route to chain {}", chain_id),
+    }
+}
+
+impl Xerc20 {
+    fn require_owner(&self) -> Result<(), ContractError> {
+        if env::predecessor_account_id() != self.owner {
+            return Err(ContractError::NotOwner);
+        }
+        Ok(())
+    }
+
+    // the storage of the accounts registered by a mint is paid by the contract
+    fn internal_mint(&mut self, account_id: &AccountId, amount: u128, memo: Option<&str>) {
+        if !self.token.accounts.contains_key(account_id) {
+            self.token.internal_register_account(account_id);
+        }
+        self.token.internal_deposit(account_id, amount);
+
+        FtMint {
+            owner_id: account_id,
+            amount: &U128::from(amount),
+            memo,
+        }
+        .emit();
+    }
+
+    fn internal_burn(&mut self, account_id: &AccountId, amount: u128, memo: Option<&str>) {
+        self.token.internal_withdraw(account_id, amount);
+
+        FtBurn {
+            owner_id: account_id,
+            amount: &U128::from(amount),
+            memo,
+        }
+        .emit();
+    }
+}
@@ -0,0 +1,31 @@
+use ethabi::{decode, encode, ethereum_types::U256, ParamType, Token};
+
+/// Cross chain transfer, `abi.encode(bytes recipient, uint256 amount)` as
+/// sent and received by the xERC20 of the other chains.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TransferPayload {
+    pub recipient: Vec<u8>,
+    pub amount: u128,
+}
+
+impl TransferPayload {
+    pub fn encode(&self) -> Vec<u8> {
+        encode(&[
+            Token::Bytes(self.recipient.clone()),
+            Token::Uint(U256::from(self.amount)),
+        ])
+    }
+
+    pub fn decode(data: &[u8]) -> Option<Self> {
+        let tokens: Vec<Token> = decode(&[ParamType::Bytes, ParamType::Uint(128)], data).ok()?;
+        let amount: U256 = tokens[1].clone().into_uint()?;
+        if amount > U256::from(u128::MAX) {
+            return None;
+        }
+
+        Some(Self {
+            recipient: tokens[0].clone().into_bytes()?,
+            amount: amount.as_u128(),
+        })
+    }
+}
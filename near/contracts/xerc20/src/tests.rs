@@ -0,0 +1,316 @@
+use crate::payload::TransferPayload;
+use crate::*;
+use near_contract_standards::fungible_token::core::FungibleTokenCore;
+use near_contract_standards::fungible_token::metadata::FT_METADATA_SPEC;
+use near_crosstalk_sample::request_metadata::RequestMetadata;
+use near_sdk::{
+    mock::VmAction,
+    serde_json,
+    test_utils::{get_created_receipts, VMContextBuilder},
+    testing_env,
+};
+
+const BRIDGE_ADDRESS: &str = "0xeedb3ab68d567a6cd6d19fa819fe77b9f8ed1538";
+
+fn owner() -> AccountId {
+    "owner.near".parse().unwrap()
+}
+
+fn gateway() -> AccountId {
+    "gateway.near".parse().unwrap()
+}
+
+fn user() -> AccountId {
+    "user.near".parse().unwrap()
+}
+
+fn set_context(predecessor_account_id: &AccountId, attached_deposit: u128) {
+    testing_env!(VMContextBuilder::new()
+        .predecessor_account_id(predecessor_account_id.clone())
+        .attached_deposit(attached_deposit)
+        .build());
+}
+
+fn do_instantiate() -> Xerc20 {
+    set_context(&owner(), 0);
+    let mut xerc20: Xerc20 = Xerc20::new(
+        gateway(),
+        FungibleTokenMetadata {
+            spec: FT_METADATA_SPEC.to_string(),
+            name: "Cross Chain Token".to_string(),
+            symbol: "XERC20".to_string(),
+            icon: None,
+            reference: None,
+            reference_hash: None,
+            decimals: 18,
+        },
+    );
+    xerc20
+        .set_chain_types(vec![
+            ChainTypeInfo {
+                chain_id: "80001".to_string(),
+                chain_type: CHAIN_TYPE_EVM,
+            },
+            ChainTypeInfo {
+                chain_id: "router_9000-1".to_string(),
+                chain_type: 2,
+            },
+        ])
+        .unwrap();
+    xerc20
+        .set_white_listed_contracts(vec![ContractInfo {
+            chain_id: "80001".to_string(),
+            contract_addr: BRIDGE_ADDRESS.to_string(),
+        }])
+        .unwrap();
+    xerc20
+}
+
+fn transfer_payload(recipient: &AccountId, amount: u128) -> Vec<u8> {
+    TransferPayload {
+        recipient: recipient.as_bytes().to_vec(),
+        amount,
+    }
+    .encode()
+}
+
+#[test]
+fn test_i_receive_mints_to_recipient() {
+    let mut xerc20: Xerc20 = do_instantiate();
+
+    set_context(&gateway(), 0);
+    // EVM addresses are compared in lowercase
+    xerc20
+        .i_receive(
+            BRIDGE_ADDRESS.to_uppercase().replace("0X", "0x"),
+            transfer_payload(&user(), 1_000),
+            "80001".to_string(),
+        )
+        .unwrap();
+
+    assert_eq!(xerc20.ft_balance_of(user()), U128::from(1_000));
+    assert_eq!(xerc20.ft_total_supply(), U128::from(1_000));
+}
+
+#[test]
+fn test_i_receive_not_whitelisted() {
+    let mut xerc20: Xerc20 = do_instantiate();
+
+    set_context(&gateway(), 0);
+    let result = xerc20.i_receive(
+        "0x0000000000000000000000000000000000000001".to_string(),
+        transfer_payload(&user(), 1_000),
+        "80001".to_string(),
+    );
+    assert_eq!(
+        result,
+        Err(ContractError::InvalidArgument {
+            reason: "request sender 0x0000000000000000000000000000000000000001 of chain 80001 \
+                     is not whitelisted"
+                .to_string(),
+        })
+    );
+    assert_eq!(xerc20.ft_total_supply(), U128::from(0));
+}
+
+#[test]
+fn test_i_receive_not_gateway() {
+    let mut xerc20: Xerc20 = do_instantiate();
+
+    let result = xerc20.i_receive(
+        BRIDGE_ADDRESS.to_string(),
+        transfer_payload(&user(), 1_000),
+        "80001".to_string(),
+    );
+    assert_eq!(result, Err(ContractError::NotGateway));
+}
+
+#[test]
+fn test_i_receive_invalid_packet() {
+    let mut xerc20: Xerc20 = do_instantiate();
+
+    set_context(&gateway(), 0);
+    let result = xerc20.i_receive(
+        BRIDGE_ADDRESS.to_string(),
+        vec![1, 2, 3],
+        "80001".to_string(),
+    );
+    assert!(matches!(result, Err(ContractError::DecodeError { .. })));
+
+    let result = xerc20.i_receive(
+        BRIDGE_ADDRESS.to_string(),
+        transfer_payload(&user(), 1_000),
+        "43113".to_string(),
+    );
+    assert_eq!(
+        result,
+        Err(ContractError::InvalidArgument {
+            reason: "unknown route to chain 43113".to_string(),
+        })
+    );
+}
+
+#[test]
+fn test_transfer_cross_chain_restored_on_failed_ack() {
+    let mut xerc20: Xerc20 = do_instantiate();
+    xerc20.mint(user(), U128::from(1_000)).unwrap();
+
+    set_context(&user(), 1);
+    xerc20
+        .transfer_cross_chain(
+            U128::from(400),
+            vec![0xee; 20],
+            "80001".to_string(),
+            RequestMetadata::default().into(),
+        )
+        .unwrap();
+    assert_eq!(xerc20.ft_balance_of(user()), U128::from(600));
+    assert_eq!(xerc20.ft_total_supply(), U128::from(600));
+
+    // the attached deposit pays the fee of i_send
+    let i_send: Vec<(u128, serde_json::Value)> = get_created_receipts()
+        .into_iter()
+        .filter(|receipt| receipt.receiver_id == gateway())
+        .flat_map(|receipt| receipt.actions)
+        .filter_map(|action| match action {
+            VmAction::FunctionCall {
+                function_name,
+                args,
+                deposit,
+                ..
+            } if function_name == "i_send" => {
+                Some((deposit, serde_json::from_slice(&args).unwrap()))
+            }
+            _ => None,
+        })
+        .collect();
+    assert_eq!(i_send.len(), 1);
+    assert_eq!(i_send[0].0, 1);
+    assert_eq!(i_send[0].1["version"], "1");
+    assert_eq!(i_send[0].1["dest_chain_id"], "80001");
+
+    xerc20.i_send_callback(user(), U128::from(400), U128::from(1), Ok(U128::from(5)));
+    assert_eq!(
+        xerc20.get_pending_transfer(U128::from(5)),
+        Some(PendingTransfer {
+            sender: user(),
+            amount: U128::from(400),
+        })
+    );
+
+    set_context(&gateway(), 0);
+    xerc20.i_ack(U128::from(5), false, vec![]).unwrap();
+    assert_eq!(xerc20.ft_balance_of(user()), U128::from(1_000));
+    assert_eq!(xerc20.get_pending_transfer(U128::from(5)), None);
+}
+
+#[test]
+fn test_transfer_cross_chain_kept_on_successful_ack() {
+    let mut xerc20: Xerc20 = do_instantiate();
+    xerc20.mint(user(), U128::from(1_000)).unwrap();
+
+    set_context(&user(), 1);
+    xerc20
+        .transfer_cross_chain(
+            U128::from(400),
+            vec![1; 20],
+            "80001".to_string(),
+            RequestMetadata::default().into(),
+        )
+        .unwrap();
+    xerc20.i_send_callback(user(), U128::from(400), U128::from(1), Ok(U128::from(5)));
+
+    set_context(&gateway(), 0);
+    xerc20.i_ack(U128::from(5), true, vec![]).unwrap();
+    assert_eq!(xerc20.ft_balance_of(user()), U128::from(600));
+    assert_eq!(xerc20.get_pending_transfer(U128::from(5)), None);
+}
+
+#[test]
+fn test_transfer_cross_chain_restored_when_gateway_fails() {
+    let mut xerc20: Xerc20 = do_instantiate();
+    xerc20.mint(user(), U128::from(1_000)).unwrap();
+
+    set_context(&user(), 1);
+    xerc20
+        .transfer_cross_chain(
+            U128::from(400),
+            vec![1; 20],
+            "80001".to_string(),
+            RequestMetadata::default().into(),
+        )
+        .unwrap();
+    let request_identifier: Option<U128> = xerc20.i_send_callback(
+        user(),
+        U128::from(400),
+        U128::from(1),
+        Err(PromiseError::Failed),
+    );
+
+    assert_eq!(request_identifier, None);
+    assert_eq!(xerc20.ft_balance_of(user()), U128::from(1_000));
+}
+
+#[test]
+fn test_transfer_cross_chain_unknown_route() {
+    let mut xerc20: Xerc20 = do_instantiate();
+    xerc20.mint(user(), U128::from(1_000)).unwrap();
+
+    set_context(&user(), 1);
+    let result = xerc20.transfer_cross_chain(
+        U128::from(400),
+        vec![1; 20],
+        "43113".to_string(),
+        RequestMetadata::default().into(),
+    );
+    assert_eq!(
+        result.err(),
+        Some(ContractError::InvalidArgument {
+            reason: "unknown route to chain 43113".to_string(),
+        })
+    );
+    assert_eq!(xerc20.ft_balance_of(user()), U128::from(1_000));
+
+    set_context(&user(), 0);
+    let result = xerc20.transfer_cross_chain(
+        U128::from(400),
+        vec![1; 20],
+        "80001".to_string(),
+        RequestMetadata::default().into(),
+    );
+    assert!(matches!(
+        result.err(),
+        Some(ContractError::InvalidDeposit { .. })
+    ));
+}
+
+#[test]
+fn test_only_owner() {
+    let mut xerc20: Xerc20 = do_instantiate();
+
+    set_context(&user(), 0);
+    assert_eq!(
+        xerc20.mint(user(), U128::from(1_000)),
+        Err(ContractError::NotOwner)
+    );
+    assert_eq!(xerc20.set_gateway(user()), Err(ContractError::NotOwner));
+    assert_eq!(xerc20.get_gateway(), gateway());
+}
+
+#[test]
+fn test_transfer_payload_matches_cosmwasm_layout() {
+    // abi.encode(bytes recipient, uint256 amount)
+    let encoded: Vec<u8> = ethabi::encode(&[
+        ethabi::Token::Bytes(b"user.near".to_vec()),
+        ethabi::Token::Uint(ethabi::ethereum_types::U256::from(1_000u64)),
+    ]);
+
+    assert_eq!(transfer_payload(&user(), 1_000), encoded);
+    assert_eq!(
+        TransferPayload::decode(&encoded),
+        Some(TransferPayload {
+            recipient: b"user.near".to_vec(),
+            amount: 1_000,
+        })
+    );
+}
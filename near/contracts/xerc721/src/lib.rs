@@ -0,0 +1,402 @@
+pub mod payload;
+#[cfg(test)]
+mod tests;
+
+use ethabi::ethereum_types::U256;
+use near_contract_standards::non_fungible_token::{
+    events::NftMint,
+    metadata::{NFTContractMetadata, NonFungibleTokenMetadataProvider, TokenMetadata},
+    NonFungibleToken, Token, TokenId,
+};
+use near_crosstalk_sample::{errors::ContractError, request_metadata::RequestMetadataParam};
+use near_sdk::{
+    borsh::{self, BorshDeserialize, BorshSerialize},
+    collections::{LazyOption, UnorderedMap},
+    env,
+    json_types::U128,
+    near_bindgen,
+    serde::{Deserialize, Serialize},
+    serde_json::json,
+    AccountId, Gas, GasWeight, PanicOnDefault, Promise, PromiseError, PromiseOrValue,
+};
+use payload::TransferParams;
+
+pub const TGAS: u64 = 1_000_000_000_000;
+
+/// NFT locked by a cross chain transfer, restored if the request fails.
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct PendingTransfer {
+    pub sender: AccountId,
+    pub token_id: TokenId,
+}
+
+#[near_bindgen]
+#[derive(BorshDeserialize, BorshSerialize, PanicOnDefault)]
+pub struct Xerc721 {
+    owner: AccountId,
+    gateway: AccountId,
+    tokens: NonFungibleToken,
+    metadata: LazyOption<NFTContractMetadata>,
+    // chainId => xerc721 contract of the chain
+    remote_contracts: UnorderedMap<String, String>,
+    // request identifier => transfer waiting for its ack
+    pending_transfers: UnorderedMap<u128, PendingTransfer>,
+}
+
+near_contract_standards::impl_non_fungible_token_core!(Xerc721, tokens);
+near_contract_standards::impl_non_fungible_token_approval!(Xerc721, tokens);
+near_contract_standards::impl_non_fungible_token_enumeration!(Xerc721, tokens);
+
+#[near_bindgen]
+impl NonFungibleTokenMetadataProvider for Xerc721 {
+    fn nft_metadata(&self) -> NFTContractMetadata {
+        self.metadata.get().unwrap()
+    }
+}
+
+#[near_bindgen]
+impl Xerc721 {
+    #[init]
+    pub fn new(gateway: AccountId, metadata: NFTContractMetadata) -> Self {
+        metadata.assert_valid();
+        Self {
+            owner: env::predecessor_account_id(),
+            gateway,
+            tokens: NonFungibleToken::new(
+                b"o".to_vec(),
+                env::current_account_id(),
+                Some(b"t".to_vec()),
+                Some(b"e".to_vec()),
+                Some(b"a".to_vec()),
+            ),
+            metadata: LazyOption::new(b"m".to_vec(), Some(&metadata)),
+            remote_contracts: UnorderedMap::new(b"r"),
+            pending_transfers: UnorderedMap::new(b"p"),
+        }
+    }
+
+    #[handle_result]
+    pub fn enroll_remote_contract(
+        &mut self,
+        chain_id: String,
+        remote_address: String,
+    ) -> Result<(), ContractError> {
+        self.require_owner()?;
+
+        self.remote_contracts.insert(&chain_id, &remote_address);
+        Ok(())
+    }
+
+    #[handle_result]
+    pub fn set_gateway(&mut self, gateway: AccountId) -> Result<(), ContractError> {
+        self.require_owner()?;
+
+        self.gateway = gateway;
+        Ok(())
+    }
+
+    /// Mints a token with a decimal id, the id sent across chains. The
+    /// attached deposit pays the storage of the token.
+    #[payable]
+    #[handle_result]
+    pub fn nft_mint(
+        &mut self,
+        token_id: TokenId,
+        receiver_id: AccountId,
+        token_metadata: TokenMetadata,
+    ) -> Result<Token, ContractError> {
+        self.require_owner()?;
+        parse_nft_id(&token_id)?;
+
+        Ok(self
+            .tokens
+            .internal_mint(token_id, receiver_id, Some(token_metadata)))
+    }
+
+    pub fn get_owner(&self) -> AccountId {
+        self.owner.clone()
+    }
+
+    pub fn get_gateway(&self) -> AccountId {
+        self.gateway.clone()
+    }
+
+    pub fn get_remote_contract(&self, chain_id: String) -> Option<String> {
+        self.remote_contracts.get(&chain_id)
+    }
+
+    pub fn get_pending_transfer(&self, request_identifier: U128) -> Option<PendingTransfer> {
+        self.pending_transfers.get(&request_identifier.into())
+    }
+
+    /// Locks the token of the caller in the contract and sends it to
+    /// `recipient` on the destination chain, with its NEP-177 metadata. The
+    /// attached deposit pays the gateway fee.
+    #[payable]
+    #[handle_result]
+    pub fn transfer_cross_chain(
+        &mut self,
+        token_id: TokenId,
+        recipient: Vec<u8>,
+        dest_chain_id: String,
+        request_metadata: RequestMetadataParam,
+    ) -> Result<Promise, ContractError> {
+        if env::attached_deposit() == 0 {
+            return Err(ContractError::InvalidDeposit {
+                reason: "requires an attached deposit of at least 1 yoctoNEAR".to_string(),
+            });
+        }
+
+        let nft_id: U256 = parse_nft_id(&token_id)?;
+        let dest_contract_address: String = match self.remote_contracts.get(&dest_chain_id) {
+            Some(contract) => contract,
+            None => {
+                return Err(ContractError::InvalidArgument {
+                    reason: format!("unknown route to chain {}", dest_chain_id),
+                })
+            }
+        };
+        let request_metadata: Vec<u8> = request_metadata.into_bytes()?;
+
+        // only the owner of the token can send it
+        let sender: AccountId = env::predecessor_account_id();
+        if self.tokens.owner_by_id.get(&token_id).as_ref() != Some(&sender) {
+            return Err(ContractError::InvalidArgument {
+                reason: "caller is not the owner of the token".to_string(),
+            });
+        }
+        self.tokens.internal_transfer(
+            &sender,
+            &env::current_account_id(),
+            &token_id,
+            None,
+            Some("transfer cross chain".to_string()),
+        );
+
+        let metadata: Option<TokenMetadata> = self
+            .tokens
+            .token_metadata_by_id
+            .as_ref()
+            .and_then(|by_id| by_id.get(&token_id));
+        let payload: Vec<u8> = TransferParams {
+            nft_id,
+            recipient,
+            uri: metadata
+                .as_ref()
+                .and_then(|metadata| metadata.media.clone())
+                .unwrap_or_default(),
+            metadata,
+        }
+        .encode()?;
+        // abi.encode(handler, payload)
+        let request_packet: Vec<u8> = ethabi::encode(&[
+            ethabi::Token::String(dest_contract_address),
+            ethabi::Token::Bytes(payload),
+        ]);
+
+        // i_send of the gateway returns the request identifier (nonce) it assigned
+        let i_send_args: Vec<u8> = json!({
+            "version": U128::from(1),
+            "dest_chain_id": dest_chain_id,
+            "request_metadata": request_metadata,
+            "request_packet": request_packet,
+        })
+        .to_string()
+        .into_bytes();
+
+        Ok(Promise::new(self.gateway.clone())
+            .function_call_weight(
+                "i_send".to_string(),
+                i_send_args,
+                env::attached_deposit(),
+                Gas(0),
+                GasWeight(1),
+            )
+            .then(
+                Self::ext(env::current_account_id())
+                    .with_static_gas(Gas(10 * TGAS))
+                    .i_send_callback(sender, token_id, U128::from(env::attached_deposit())),
+            ))
+    }
+
+    /// Keeps the token locked until the ack of the request, or restores it
+    /// and refunds the deposit when the gateway call fails.
+    #[private]
+    pub fn i_send_callback(
+        &mut self,
+        sender: AccountId,
+        token_id: TokenId,
+        deposit: U128,
+        #[callback_result] result: Result<U128, PromiseError>,
+    ) -> Option<U128> {
+        match result {
+            Ok(request_identifier) => {
+                self.pending_transfers.insert(
+                    &request_identifier.into(),
+                    &PendingTransfer { sender, token_id },
+                );
+                Some(request_identifier)
+            }
+            Err(_) => {
+                self.release(&sender, &token_id, "gateway call failed");
+                if u128::from(deposit) > 0 {
+                    Promise::new(sender).transfer(deposit.into());
+                }
+                None
+            }
+        }
+    }
+
+    /// Releases the token if the contract holds it, mints it otherwise.
+    #[handle_result]
+    pub fn i_receive(
+        &mut self,
+        request_sender: String,
+        packet: Vec<u8>,
+        src_chain_id: String,
+    ) -> Result<Vec<u8>, ContractError> {
+        if env::predecessor_account_id() != self.gateway {
+            return Err(ContractError::NotGateway);
+        }
+
+        if self.remote_contracts.get(&src_chain_id) != Some(request_sender.clone()) {
+            return Err(ContractError::InvalidArgument {
+                reason: format!(
+                    "request sender {} of chain {} is not enrolled",
+                    request_sender, src_chain_id
+                ),
+            });
+        }
+
+        let transfer: TransferParams = match TransferParams::decode(&packet) {
+            Some(transfer) => transfer,
+            None => {
+                return Err(ContractError::DecodeError {
+                    what: "packet".to_string(),
+                    reason: "unknown transfer params layout".to_string(),
+                })
+            }
+        };
+        let recipient: AccountId = match String::from_utf8(transfer.recipient)
+            .ok()
+            .and_then(|recipient| recipient.parse().ok())
+        {
+            Some(recipient) => recipient,
+            None => {
+                return Err(ContractError::InvalidArgument {
+                    reason: "the recipient is not a NEAR account id".to_string(),
+                })
+            }
+        };
+
+        let token_id: TokenId = transfer.nft_id.to_string();
+        match self.tokens.owner_by_id.get(&token_id) {
+            Some(owner) if owner == env::current_account_id() => {
+                self.release(&recipient, &token_id, "transfer cross chain");
+            }
+            Some(_) => {
+                return Err(ContractError::InvalidArgument {
+                    reason: format!("token {} already exists", token_id),
+                })
+            }
+            None => {
+                let metadata: TokenMetadata = transfer.metadata.unwrap_or(TokenMetadata {
+                    title: None,
+                    description: None,
+                    media: if transfer.uri.is_empty() {
+                        None
+                    } else {
+                        Some(transfer.uri)
+                    },
+                    media_hash: None,
+                    copies: None,
+                    issued_at: None,
+                    expires_at: None,
+                    starts_at: None,
+                    updated_at: None,
+                    extra: None,
+                    reference: None,
+                    reference_hash: None,
+                });
+                // the storage of the token is paid by the contract
+                self.tokens.internal_mint_with_refund(
+                    token_id.clone(),
+                    recipient.clone(),
+                    Some(metadata),
+                    None,
+                );
+                NftMint {
+                    owner_id: &recipient,
+                    token_ids: &[&token_id],
+                    memo: Some("transfer cross chain"),
+                }
+                .emit();
+            }
+        }
+
+        Ok(packet)
+    }
+
+    /// Restores the locked token of a failed request.
+    #[handle_result]
+    pub fn i_ack(
+        &mut self,
+        request_identifier: U128,
+        exec_flag: bool,
+        exec_data: Vec<u8>,
+    ) -> Result<(), ContractError> {
+        if env::predecessor_account_id() != self.gateway {
+            return Err(ContractError::NotGateway);
+        }
+
+        let transfer: PendingTransfer =
+            match self.pending_transfers.remove(&request_identifier.into()) {
+                Some(transfer) => transfer,
+                None => {
+                    return Err(ContractError::InvalidArgument {
+                        reason: format!("unknown request identifier {:?}", request_identifier),
+                    })
+                }
+            };
+
+        if !exec_flag {
+            env::log_str(&format!(
+                "request {:?} failed on the destination: {:?}",
+                request_identifier, exec_data
+            ));
+            self.release(
+                &transfer.sender,
+                &transfer.token_id,
+                "transfer cross chain failed",
+            );
+        }
+        Ok(())
+    }
+}
+
+impl Xerc721 {
+    fn require_owner(&self) -> Result<(), ContractError> {
+        if env::predecessor_account_id() != self.owner {
+            return Err(ContractError::NotOwner);
+        }
+        Ok(())
+    }
+
+    fn release(&mut self, receiver_id: &AccountId, token_id: &TokenId, memo: &str) {
+        self.tokens.internal_transfer(
+            &env::current_account_id(),
+            receiver_id,
+            token_id,
+            None,
+            Some(memo.to_string()),
+        );
+    }
+}
+
+// the token ids are the decimal uint256 nft ids of the other chains
+fn parse_nft_id(token_id: &TokenId) -> Result<U256, ContractError> {
+    U256::from_dec_str(token_id).map_err(|_| ContractError::InvalidArgument {
+        reason: "token id should be a decimal number".to_string(),
+    })
+}
@@ -0,0 +1,93 @@
+use ethabi::{decode, encode, ethereum_types::U256, ParamType, Token};
+use near_contract_standards::non_fungible_token::metadata::TokenMetadata;
+use near_crosstalk_sample::errors::ContractError;
+use near_sdk::serde_json;
+
+/// NFT sent across chains. Encoded as the `(uint256 nft_id, bytes recipient)`
+/// tuple of the CosmWasm xerc721, extended with the `string uri` of the EVM
+/// XERC721 and the NEP-177 metadata as JSON. The contracts of the other
+/// chains read the leading fields of the tuple.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TransferParams {
+    pub nft_id: U256,
+    pub recipient: Vec<u8>,
+    pub uri: String,
+    pub metadata: Option<TokenMetadata>,
+}
+
+impl TransferParams {
+    pub fn encode(&self) -> Result<Vec<u8>, ContractError> {
+        let metadata: String = match &self.metadata {
+            Some(metadata) => {
+                serde_json::to_string(metadata).map_err(|err| ContractError::InvalidArgument {
+                    reason: format!("token metadata cannot be serialized: {}", err),
+                })?
+            }
+            None => String::new(),
+        };
+
+        Ok(encode(&[Token::Tuple(vec![
+            Token::Uint(self.nft_id),
+            Token::Bytes(self.recipient.clone()),
+            Token::String(self.uri.clone()),
+            Token::String(metadata),
+        ])]))
+    }
+
+    /// Decodes the tuple of any of the chains, `None` if the data matches
+    /// none of the layouts.
+    pub fn decode(data: &[u8]) -> Option<Self> {
+        for params in [params_types(4), params_types(3)] {
+            if let Some(tokens) = decode_exact(&[params], data) {
+                let tuple: Vec<Token> = tokens[0].clone().into_tuple()?;
+                let metadata: Option<TokenMetadata> = match tuple.get(3) {
+                    Some(token) => {
+                        let metadata: String = token.clone().into_string()?;
+                        if metadata.is_empty() {
+                            None
+                        } else {
+                            Some(serde_json::from_str(&metadata).ok()?)
+                        }
+                    }
+                    None => None,
+                };
+                return Some(Self {
+                    nft_id: tuple[0].clone().into_uint()?,
+                    recipient: tuple[1].clone().into_bytes()?,
+                    uri: tuple[2].clone().into_string()?,
+                    metadata,
+                });
+            }
+        }
+
+        // the tuple of the CosmWasm xerc721 is decoded leniently, the way the
+        // CosmWasm contract decodes it
+        let tokens: Vec<Token> = decode(&[params_types(2)], data).ok()?;
+        let tuple: Vec<Token> = tokens[0].clone().into_tuple()?;
+        Some(Self {
+            nft_id: tuple[0].clone().into_uint()?,
+            recipient: tuple[1].clone().into_bytes()?,
+            uri: String::new(),
+            metadata: None,
+        })
+    }
+}
+
+fn params_types(len: usize) -> ParamType {
+    let params: Vec<ParamType> = vec![
+        ParamType::Uint(256),
+        ParamType::Bytes,
+        ParamType::String,
+        ParamType::String,
+    ];
+    ParamType::Tuple(params[..len].to_vec())
+}
+
+// ethabi ignores the data following the decoded tokens, the layouts only
+// match when the data is exactly their encoding
+fn decode_exact(params: &[ParamType], data: &[u8]) -> Option<Vec<Token>> {
+    match decode(params, data) {
+        Ok(tokens) if encode(&tokens) == data => Some(tokens),
+        _ => None,
+    }
+}
@@ -0,0 +1,359 @@
+use crate::payload::TransferParams;
+use crate::*;
+use near_contract_standards::non_fungible_token::core::NonFungibleTokenCore;
+use near_contract_standards::non_fungible_token::metadata::NFT_METADATA_SPEC;
+use near_crosstalk_sample::request_metadata::RequestMetadata;
+use near_sdk::{test_utils::VMContextBuilder, testing_env, ONE_NEAR};
+
+const BRIDGE_ADDRESS: &str = "0xeedb3ab68d567a6cd6d19fa819fe77b9f8ed1538";
+
+fn owner() -> AccountId {
+    "owner.near".parse().unwrap()
+}
+
+fn gateway() -> AccountId {
+    "gateway.near".parse().unwrap()
+}
+
+fn user() -> AccountId {
+    "user.near".parse().unwrap()
+}
+
+fn xerc721_account() -> AccountId {
+    "xerc721.near".parse().unwrap()
+}
+
+fn set_context(predecessor_account_id: &AccountId, attached_deposit: u128) {
+    testing_env!(VMContextBuilder::new()
+        .current_account_id(xerc721_account())
+        .predecessor_account_id(predecessor_account_id.clone())
+        .attached_deposit(attached_deposit)
+        .build());
+}
+
+fn token_metadata() -> TokenMetadata {
+    TokenMetadata {
+        title: Some("Router".to_string()),
+        description: None,
+        media: Some("ipfs://router".to_string()),
+        media_hash: None,
+        copies: Some(1),
+        issued_at: None,
+        expires_at: None,
+        starts_at: None,
+        updated_at: None,
+        extra: None,
+        reference: None,
+        reference_hash: None,
+    }
+}
+
+fn do_instantiate() -> Xerc721 {
+    set_context(&owner(), 0);
+    let mut xerc721: Xerc721 = Xerc721::new(
+        gateway(),
+        NFTContractMetadata {
+            spec: NFT_METADATA_SPEC.to_string(),
+            name: "Cross Chain NFT".to_string(),
+            symbol: "XERC721".to_string(),
+            icon: None,
+            base_uri: None,
+            reference: None,
+            reference_hash: None,
+        },
+    );
+    xerc721
+        .enroll_remote_contract("80001".to_string(), BRIDGE_ADDRESS.to_string())
+        .unwrap();
+    xerc721
+}
+
+fn mint_to_user(xerc721: &mut Xerc721, token_id: &str) {
+    set_context(&owner(), ONE_NEAR);
+    xerc721
+        .nft_mint(token_id.to_string(), user(), token_metadata())
+        .unwrap();
+}
+
+fn transfer_params(recipient: &AccountId, nft_id: u64) -> Vec<u8> {
+    TransferParams {
+        nft_id: U256::from(nft_id),
+        recipient: recipient.as_bytes().to_vec(),
+        uri: "ipfs://router".to_string(),
+        metadata: Some(token_metadata()),
+    }
+    .encode()
+    .unwrap()
+}
+
+fn owner_of(xerc721: &Xerc721, token_id: &str) -> Option<AccountId> {
+    xerc721
+        .nft_token(token_id.to_string())
+        .map(|token| token.owner_id)
+}
+
+#[test]
+fn test_i_receive_mints_with_metadata() {
+    let mut xerc721: Xerc721 = do_instantiate();
+
+    set_context(&gateway(), 0);
+    xerc721
+        .i_receive(
+            BRIDGE_ADDRESS.to_string(),
+            transfer_params(&user(), 7),
+            "80001".to_string(),
+        )
+        .unwrap();
+
+    let token: Token = xerc721.nft_token("7".to_string()).unwrap();
+    assert_eq!(token.owner_id, user());
+    assert_eq!(token.metadata, Some(token_metadata()));
+}
+
+#[test]
+fn test_i_receive_releases_locked_token() {
+    let mut xerc721: Xerc721 = do_instantiate();
+    mint_to_user(&mut xerc721, "7");
+
+    set_context(&user(), 1);
+    xerc721
+        .transfer_cross_chain(
+            "7".to_string(),
+            vec![0xee; 20],
+            "80001".to_string(),
+            RequestMetadata::default().into(),
+        )
+        .unwrap();
+    assert_eq!(owner_of(&xerc721, "7"), Some(xerc721_account()));
+
+    set_context(&gateway(), 0);
+    xerc721
+        .i_receive(
+            BRIDGE_ADDRESS.to_string(),
+            transfer_params(&user(), 7),
+            "80001".to_string(),
+        )
+        .unwrap();
+    assert_eq!(owner_of(&xerc721, "7"), Some(user()));
+}
+
+#[test]
+fn test_i_receive_evm_layout() {
+    let mut xerc721: Xerc721 = do_instantiate();
+
+    // abi.encode((uint256 nftId, bytes recipient, string uri))
+    let packet: Vec<u8> = ethabi::encode(&[ethabi::Token::Tuple(vec![
+        ethabi::Token::Uint(U256::from(9u64)),
+        ethabi::Token::Bytes(b"user.near".to_vec()),
+        ethabi::Token::String("ipfs://nine".to_string()),
+    ])]);
+
+    set_context(&gateway(), 0);
+    xerc721
+        .i_receive(BRIDGE_ADDRESS.to_string(), packet, "80001".to_string())
+        .unwrap();
+
+    let token: Token = xerc721.nft_token("9".to_string()).unwrap();
+    assert_eq!(token.owner_id, user());
+    assert_eq!(
+        token.metadata.unwrap().media,
+        Some("ipfs://nine".to_string())
+    );
+}
+
+#[test]
+fn test_i_receive_not_enrolled() {
+    let mut xerc721: Xerc721 = do_instantiate();
+
+    set_context(&gateway(), 0);
+    let result = xerc721.i_receive(
+        "0x0000000000000000000000000000000000000001".to_string(),
+        transfer_params(&user(), 7),
+        "80001".to_string(),
+    );
+    assert_eq!(
+        result,
+        Err(ContractError::InvalidArgument {
+            reason: "request sender 0x0000000000000000000000000000000000000001 of chain 80001 \
+                     is not enrolled"
+                .to_string(),
+        })
+    );
+    assert_eq!(owner_of(&xerc721, "7"), None);
+}
+
+#[test]
+fn test_i_receive_not_gateway() {
+    let mut xerc721: Xerc721 = do_instantiate();
+
+    let result = xerc721.i_receive(
+        BRIDGE_ADDRESS.to_string(),
+        transfer_params(&user(), 7),
+        "80001".to_string(),
+    );
+    assert_eq!(result, Err(ContractError::NotGateway));
+}
+
+#[test]
+fn test_i_receive_existing_token() {
+    let mut xerc721: Xerc721 = do_instantiate();
+    mint_to_user(&mut xerc721, "7");
+
+    set_context(&gateway(), 0);
+    let result = xerc721.i_receive(
+        BRIDGE_ADDRESS.to_string(),
+        transfer_params(&user(), 7),
+        "80001".to_string(),
+    );
+    assert_eq!(
+        result,
+        Err(ContractError::InvalidArgument {
+            reason: "token 7 already exists".to_string(),
+        })
+    );
+
+    let result = xerc721.i_receive(
+        BRIDGE_ADDRESS.to_string(),
+        vec![1, 2, 3],
+        "80001".to_string(),
+    );
+    assert!(matches!(result, Err(ContractError::DecodeError { .. })));
+}
+
+#[test]
+fn test_transfer_cross_chain_restored_on_failed_ack() {
+    let mut xerc721: Xerc721 = do_instantiate();
+    mint_to_user(&mut xerc721, "7");
+
+    set_context(&user(), 1);
+    xerc721
+        .transfer_cross_chain(
+            "7".to_string(),
+            vec![0xee; 20],
+            "80001".to_string(),
+            RequestMetadata::default().into(),
+        )
+        .unwrap();
+    xerc721.i_send_callback(user(), "7".to_string(), U128::from(1), Ok(U128::from(5)));
+    assert_eq!(
+        xerc721.get_pending_transfer(U128::from(5)),
+        Some(PendingTransfer {
+            sender: user(),
+            token_id: "7".to_string(),
+        })
+    );
+
+    set_context(&gateway(), 0);
+    xerc721.i_ack(U128::from(5), false, vec![]).unwrap();
+    assert_eq!(owner_of(&xerc721, "7"), Some(user()));
+    assert_eq!(xerc721.get_pending_transfer(U128::from(5)), None);
+}
+
+#[test]
+fn test_transfer_cross_chain_kept_on_successful_ack() {
+    let mut xerc721: Xerc721 = do_instantiate();
+    mint_to_user(&mut xerc721, "7");
+
+    set_context(&user(), 1);
+    xerc721
+        .transfer_cross_chain(
+            "7".to_string(),
+            vec![0xee; 20],
+            "80001".to_string(),
+            RequestMetadata::default().into(),
+        )
+        .unwrap();
+    xerc721.i_send_callback(user(), "7".to_string(), U128::from(1), Ok(U128::from(5)));
+
+    set_context(&gateway(), 0);
+    xerc721.i_ack(U128::from(5), true, vec![]).unwrap();
+    assert_eq!(owner_of(&xerc721, "7"), Some(xerc721_account()));
+    assert_eq!(xerc721.get_pending_transfer(U128::from(5)), None);
+}
+
+#[test]
+fn test_transfer_cross_chain_restored_when_gateway_fails() {
+    let mut xerc721: Xerc721 = do_instantiate();
+    mint_to_user(&mut xerc721, "7");
+
+    set_context(&user(), 1);
+    xerc721
+        .transfer_cross_chain(
+            "7".to_string(),
+            vec![0xee; 20],
+            "80001".to_string(),
+            RequestMetadata::default().into(),
+        )
+        .unwrap();
+    let request_identifier: Option<U128> = xerc721.i_send_callback(
+        user(),
+        "7".to_string(),
+        U128::from(1),
+        Err(PromiseError::Failed),
+    );
+
+    assert_eq!(request_identifier, None);
+    assert_eq!(owner_of(&xerc721, "7"), Some(user()));
+}
+
+#[test]
+fn test_transfer_cross_chain_not_owner() {
+    let mut xerc721: Xerc721 = do_instantiate();
+    mint_to_user(&mut xerc721, "7");
+
+    set_context(&owner(), 1);
+    let result = xerc721.transfer_cross_chain(
+        "7".to_string(),
+        vec![0xee; 20],
+        "80001".to_string(),
+        RequestMetadata::default().into(),
+    );
+    assert_eq!(
+        result.err(),
+        Some(ContractError::InvalidArgument {
+            reason: "caller is not the owner of the token".to_string(),
+        })
+    );
+    assert_eq!(owner_of(&xerc721, "7"), Some(user()));
+}
+
+#[test]
+fn test_nft_mint_checks() {
+    let mut xerc721: Xerc721 = do_instantiate();
+
+    set_context(&owner(), ONE_NEAR);
+    let result = xerc721.nft_mint("seven".to_string(), user(), token_metadata());
+    assert_eq!(
+        result.err(),
+        Some(ContractError::InvalidArgument {
+            reason: "token id should be a decimal number".to_string(),
+        })
+    );
+
+    set_context(&user(), ONE_NEAR);
+    let result = xerc721.nft_mint("7".to_string(), user(), token_metadata());
+    assert_eq!(result.err(), Some(ContractError::NotOwner));
+}
+
+#[test]
+fn test_transfer_params_extends_cosmwasm_layout() {
+    // the CosmWasm xerc721 reads abi.encode((uint256 nft_id, bytes recipient))
+    let packet: Vec<u8> = ethabi::encode(&[ethabi::Token::Tuple(vec![
+        ethabi::Token::Uint(U256::from(7u64)),
+        ethabi::Token::Bytes(b"user.near".to_vec()),
+    ])]);
+
+    assert_eq!(
+        TransferParams::decode(&packet),
+        Some(TransferParams {
+            nft_id: U256::from(7u64),
+            recipient: b"user.near".to_vec(),
+            uri: String::new(),
+            metadata: None,
+        })
+    );
+    assert_eq!(
+        TransferParams::decode(&transfer_params(&user(), 7)).map(|params| params.metadata),
+        Some(Some(token_metadata()))
+    );
+}
@@ -0,0 +1,96 @@
+use near_sdk::FunctionError;
+use std::fmt;
+
+use crate::request_metadata::RequestMetadataError;
+
+/// Failures of the cross chain samples. The message of a failed call starts
+/// with the name of the variant, `code`, followed by the details.
+#[derive(Debug, PartialEq, FunctionError)]
+pub enum ContractError {
+    // the method is disabled while the contract is paused
+    PausedState,
+    AlreadyPaused,
+    NotPaused,
+    NotGateway,
+    NotOwner,
+    NotPendingOwner,
+    // `what` is the decoded argument: payload, packet, exec data...
+    DecodeError { what: String, reason: String },
+    EmptyMessage { what: String },
+    // failure requested by the message, to test the acks of failed requests
+    ForcedFailure { message: String },
+    InvalidRequestMetadata(RequestMetadataError),
+    InvalidArgument { reason: String },
+    InvalidDeposit { reason: String },
+    NotRegistered { account_id: String },
+    InsufficientStorageBalance { reason: String },
+    NoFeesToWithdraw,
+    UnknownStateVersion,
+}
+
+impl ContractError {
+    pub fn decode(what: &str, reason: impl fmt::Debug) -> Self {
+        ContractError::DecodeError {
+            what: what.to_string(),
+            reason: format!("{:?}", reason),
+        }
+    }
+
+    /// Stable name of the failure, for the off-chain monitoring.
+    pub fn code(&self) -> &'static str {
+        match self {
+            ContractError::PausedState => "PausedState",
+            ContractError::AlreadyPaused => "AlreadyPaused",
+            ContractError::NotPaused => "NotPaused",
+            ContractError::NotGateway => "NotGateway",
+            ContractError::NotOwner => "NotOwner",
+            ContractError::NotPendingOwner => "NotPendingOwner",
+            ContractError::DecodeError { .. } => "DecodeError",
+            ContractError::EmptyMessage { .. } => "EmptyMessage",
+            ContractError::ForcedFailure { .. } => "ForcedFailure",
+            ContractError::InvalidRequestMetadata(_) => "InvalidRequestMetadata",
+            ContractError::InvalidArgument { .. } => "InvalidArgument",
+            ContractError::InvalidDeposit { .. } => "InvalidDeposit",
+            ContractError::NotRegistered { .. } => "NotRegistered",
+            ContractError::InsufficientStorageBalance { .. } => "InsufficientStorageBalance",
+            ContractError::NoFeesToWithdraw => "NoFeesToWithdraw",
+            ContractError::UnknownStateVersion => "UnknownStateVersion",
+        }
+    }
+}
+
+impl From<RequestMetadataError> for ContractError {
+    fn from(err: RequestMetadataError) -> Self {
+        ContractError::InvalidRequestMetadata(err)
+    }
+}
+
+impl fmt::Display for ContractError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: ", self.code())?;
+        match self {
+            ContractError::PausedState => f.write_str("the contract is paused"),
+            ContractError::AlreadyPaused => f.write_str("the contract is already paused"),
+            ContractError::NotPaused => f.write_str("the contract is not paused"),
+            ContractError::NotGateway => f.write_str("only gateway"),
+            ContractError::NotOwner => f.write_str("only owner"),
+            ContractError::NotPendingOwner => f.write_str("only pending owner"),
+            ContractError::DecodeError { what, reason } => {
+                write!(f, "Error in decoding {}: {}", what, reason)
+            }
+            ContractError::EmptyMessage { what } => write!(f, "{} cannot be empty", what),
+            ContractError::ForcedFailure { message } => write!(f, "String != {}", message),
+            ContractError::InvalidRequestMetadata(err) => write!(f, "{}", err),
+            ContractError::InvalidArgument { reason }
+            | ContractError::InvalidDeposit { reason }
+            | ContractError::InsufficientStorageBalance { reason } => f.write_str(reason),
+            ContractError::NotRegistered { account_id } => write!(
+                f,
+                "the account {} is not registered, call storage_deposit first",
+                account_id
+            ),
+            ContractError::NoFeesToWithdraw => f.write_str("no fees to withdraw"),
+            ContractError::UnknownStateVersion => f.write_str("unknown state version"),
+        }
+    }
+}
@@ -0,0 +1,109 @@
+use near_sdk::{
+    env,
+    json_types::U128,
+    serde::{Deserialize, Serialize},
+    serde_json, AccountId,
+};
+use std::fmt;
+
+// NEP-297 standard of the events shared by the cross chain samples, indexed
+// apart from the events of each contract
+pub const CROSSTALK_EVENT_STANDARD: &str = "crosstalk";
+// version of the schema of the events, bumped on any change of their fields
+pub const CROSSTALK_EVENT_VERSION: &str = "1.0.0";
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[serde(tag = "event", content = "data")]
+#[serde(rename_all = "snake_case")]
+#[serde(crate = "near_sdk::serde")]
+#[non_exhaustive]
+pub enum CrosstalkEvent {
+    RequestSent(Vec<RequestSentEvent>),
+    PacketReceived(Vec<PacketReceivedEvent>),
+    AckReceived(Vec<AckReceivedEvent>),
+    ConfigChanged(Vec<ConfigChangedEvent>),
+    FeesWithdrawn(Vec<FeesWithdrawnEvent>),
+}
+
+/// NEP-297 log of a cross chain event, `EVENT_JSON:{"standard":"crosstalk",
+/// "version":"1.0.0","event":...,"data":[...]}`.
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct CrosstalkEventLog {
+    pub standard: String,
+    pub version: String,
+
+    #[serde(flatten)]
+    pub event: CrosstalkEvent,
+}
+
+impl CrosstalkEventLog {
+    pub fn new(event: CrosstalkEvent) -> Self {
+        Self {
+            standard: CROSSTALK_EVENT_STANDARD.to_string(),
+            version: CROSSTALK_EVENT_VERSION.to_string(),
+            event,
+        }
+    }
+
+    pub fn emit(&self) {
+        env::log_str(&self.to_string());
+    }
+}
+
+impl fmt::Display for CrosstalkEventLog {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_fmt(format_args!(
+            "EVENT_JSON:{}",
+            &serde_json::to_string(self).map_err(|_| fmt::Error)?
+        ))
+    }
+}
+
+impl CrosstalkEvent {
+    pub fn emit(self) {
+        CrosstalkEventLog::new(self).emit();
+    }
+}
+
+/// Request handed to the gateway.
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct RequestSentEvent {
+    pub sender: AccountId,
+    pub dest_chain_id: String,
+    // nonce assigned by the gateway, `None` when the request is sent through
+    // the route token, which does not return it
+    pub request_identifier: Option<U128>,
+}
+
+/// Packet of a remote contract delivered by `i_receive`.
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct PacketReceivedEvent {
+    pub src_chain_id: String,
+    pub request_sender: String,
+}
+
+/// Ack of a request delivered by `i_ack`.
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct AckReceivedEvent {
+    pub request_identifier: U128,
+    pub exec_flag: bool,
+}
+
+/// Setting of the contract changed by its owner.
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct ConfigChangedEvent {
+    pub key: String,
+    pub value: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct FeesWithdrawnEvent {
+    pub recipient: AccountId,
+    pub amount: U128,
+}
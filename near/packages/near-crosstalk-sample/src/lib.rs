@@ -0,0 +1,4 @@
+pub mod errors;
+pub mod events;
+pub mod pagination;
+pub mod request_metadata;
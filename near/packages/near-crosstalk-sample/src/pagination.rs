@@ -0,0 +1,31 @@
+use near_sdk::{
+    borsh::{BorshDeserialize, BorshSerialize},
+    collections::UnorderedMap,
+};
+
+// records returned by a page when the caller gives no limit
+pub const DEFAULT_PAGE_LIMIT: u64 = 50;
+// records returned by a page at most, bounding the gas of the views
+pub const MAX_PAGE_LIMIT: u64 = 100;
+
+/// Records of `map` from `from_index`, in insertion order. At most `limit`
+/// records are returned, bounded by `MAX_PAGE_LIMIT`.
+pub fn page<'a, K, V>(
+    map: &'a UnorderedMap<K, V>,
+    from_index: Option<u64>,
+    limit: Option<u64>,
+) -> impl Iterator<Item = (K, V)> + 'a
+where
+    K: BorshSerialize + BorshDeserialize,
+    V: BorshSerialize + BorshDeserialize,
+{
+    let keys = map.keys_as_vector();
+    let values = map.values_as_vector();
+    let from_index: u64 = from_index.unwrap_or(0);
+    let limit: u64 = limit.unwrap_or(DEFAULT_PAGE_LIMIT).min(MAX_PAGE_LIMIT);
+    let end: u64 = from_index.saturating_add(limit).min(keys.len());
+
+    // the keys and values vectors are written together, a missing entry is
+    // skipped instead of failing the whole view
+    (from_index..end).filter_map(move |index| Some((keys.get(index)?, values.get(index)?)))
+}
@@ -0,0 +1,236 @@
+use near_sdk::{
+    json_types::U128,
+    serde::{Deserialize, Serialize},
+};
+use std::fmt;
+
+// dest_gas_limit, dest_gas_price, ack_gas_limit, ack_gas_price, relayer_fees,
+// ack_type and is_read_call, followed by the asm address
+pub const REQUEST_METADATA_FIXED_LEN: usize = 8 * 4 + 16 + 1 + 1;
+
+// ack types of the Router gateway
+pub const ACK_TYPE_NO_ACK: u8 = 0;
+pub const ACK_TYPE_ACK_ON_SUCCESS: u8 = 1;
+pub const ACK_TYPE_ACK_ON_ERROR: u8 = 2;
+pub const ACK_TYPE_ACK_ON_BOTH: u8 = 3;
+
+// human readable part of the Router chain addresses
+const ASM_ADDRESS_HRP: &str = "router";
+const BECH32_CHARSET: &[u8] = b"qpzry9x8gf2tvdw0s3jn54khce6mua7l";
+
+/// Metadata of an i_send request, packed by `encode` the way the Router
+/// gateway of NEAR reads it.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct RequestMetadata {
+    pub dest_gas_limit: u64,
+    pub dest_gas_price: u64,
+    pub ack_gas_limit: u64,
+    pub ack_gas_price: u64,
+    pub relayer_fees: U128,
+    pub ack_type: u8,
+    pub is_read_call: bool,
+    // address of the additional security module on the Router chain, empty
+    // when the request has none
+    pub asm_address: String,
+}
+
+// U128 has no Default in near-sdk 4.1
+impl Default for RequestMetadata {
+    fn default() -> Self {
+        Self {
+            dest_gas_limit: 0,
+            dest_gas_price: 0,
+            ack_gas_limit: 0,
+            ack_gas_price: 0,
+            relayer_fees: U128(0),
+            ack_type: ACK_TYPE_NO_ACK,
+            is_read_call: false,
+            asm_address: String::new(),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum RequestMetadataError {
+    TooShort { len: usize },
+    InvalidAckType { ack_type: u8 },
+    InvalidReadCallFlag { flag: u8 },
+    InvalidAsmAddress { asm_address: String },
+}
+
+impl fmt::Display for RequestMetadataError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RequestMetadataError::TooShort { len } => write!(
+                f,
+                "request metadata of {} bytes, expected at least {}",
+                len, REQUEST_METADATA_FIXED_LEN
+            ),
+            RequestMetadataError::InvalidAckType { ack_type } => write!(
+                f,
+                "invalid ack type {}, expected at most {}",
+                ack_type, ACK_TYPE_ACK_ON_BOTH
+            ),
+            RequestMetadataError::InvalidReadCallFlag { flag } => {
+                write!(f, "invalid read call flag {}", flag)
+            }
+            RequestMetadataError::InvalidAsmAddress { asm_address } => {
+                write!(f, "invalid asm address {:?}", asm_address)
+            }
+        }
+    }
+}
+
+impl RequestMetadata {
+    pub fn encode(&self) -> Vec<u8> {
+        let mut request_metadata: Vec<u8> =
+            Vec::with_capacity(REQUEST_METADATA_FIXED_LEN + self.asm_address.len());
+
+        request_metadata.extend_from_slice(&self.dest_gas_limit.to_be_bytes());
+        request_metadata.extend_from_slice(&self.dest_gas_price.to_be_bytes());
+        request_metadata.extend_from_slice(&self.ack_gas_limit.to_be_bytes());
+        request_metadata.extend_from_slice(&self.ack_gas_price.to_be_bytes());
+        request_metadata.extend_from_slice(&u128::from(self.relayer_fees).to_be_bytes());
+        request_metadata.push(self.ack_type);
+        request_metadata.push(u8::from(self.is_read_call));
+        request_metadata.extend_from_slice(self.asm_address.as_bytes());
+
+        request_metadata
+    }
+
+    /// Decodes and validates packed request metadata.
+    pub fn decode(data: &[u8]) -> Result<Self, RequestMetadataError> {
+        if data.len() < REQUEST_METADATA_FIXED_LEN {
+            return Err(RequestMetadataError::TooShort { len: data.len() });
+        }
+
+        let is_read_call: bool = match data[49] {
+            0 => false,
+            1 => true,
+            flag => return Err(RequestMetadataError::InvalidReadCallFlag { flag }),
+        };
+        let asm_address: String = String::from_utf8(data[REQUEST_METADATA_FIXED_LEN..].to_vec())
+            .map_err(|err| RequestMetadataError::InvalidAsmAddress {
+                asm_address: String::from_utf8_lossy(err.as_bytes()).into_owned(),
+            })?;
+
+        let request_metadata: Self = Self {
+            dest_gas_limit: read_u64(&data[0..8]),
+            dest_gas_price: read_u64(&data[8..16]),
+            ack_gas_limit: read_u64(&data[16..24]),
+            ack_gas_price: read_u64(&data[24..32]),
+            relayer_fees: U128::from(u128::from_be_bytes(data[32..48].try_into().unwrap())),
+            ack_type: data[48],
+            is_read_call,
+            asm_address,
+        };
+        request_metadata.validate()?;
+
+        Ok(request_metadata)
+    }
+
+    pub fn validate(&self) -> Result<(), RequestMetadataError> {
+        if self.ack_type > ACK_TYPE_ACK_ON_BOTH {
+            return Err(RequestMetadataError::InvalidAckType {
+                ack_type: self.ack_type,
+            });
+        }
+
+        if !self.asm_address.is_empty() && !is_router_address(&self.asm_address) {
+            return Err(RequestMetadataError::InvalidAsmAddress {
+                asm_address: self.asm_address.clone(),
+            });
+        }
+
+        Ok(())
+    }
+}
+
+/// Request metadata given to a contract method, either as the struct or
+/// already packed.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(crate = "near_sdk::serde")]
+#[serde(untagged)]
+pub enum RequestMetadataParam {
+    Struct(RequestMetadata),
+    Bytes(Vec<u8>),
+}
+
+impl RequestMetadataParam {
+    /// Packed request metadata, validated in both forms.
+    pub fn into_bytes(self) -> Result<Vec<u8>, RequestMetadataError> {
+        match self {
+            RequestMetadataParam::Struct(request_metadata) => {
+                request_metadata.validate()?;
+                Ok(request_metadata.encode())
+            }
+            RequestMetadataParam::Bytes(bytes) => {
+                RequestMetadata::decode(&bytes)?;
+                Ok(bytes)
+            }
+        }
+    }
+}
+
+impl From<RequestMetadata> for RequestMetadataParam {
+    fn from(request_metadata: RequestMetadata) -> Self {
+        RequestMetadataParam::Struct(request_metadata)
+    }
+}
+
+impl From<Vec<u8>> for RequestMetadataParam {
+    fn from(bytes: Vec<u8>) -> Self {
+        RequestMetadataParam::Bytes(bytes)
+    }
+}
+
+fn read_u64(data: &[u8]) -> u64 {
+    u64::from_be_bytes(data.try_into().unwrap())
+}
+
+// bech32 address of the Router chain, lowercase with a valid checksum
+fn is_router_address(address: &str) -> bool {
+    let (hrp, data) = match address.rsplit_once('1') {
+        Some(parts) => parts,
+        None => return false,
+    };
+    if hrp != ASM_ADDRESS_HRP || data.len() < 6 || address.len() > 90 {
+        return false;
+    }
+
+    let mut values: Vec<u8> = Vec::with_capacity(hrp.len() * 2 + 1 + data.len());
+    values.extend(hrp.bytes().map(|c| c >> 5));
+    values.push(0);
+    values.extend(hrp.bytes().map(|c| c & 31));
+    for c in data.bytes() {
+        match BECH32_CHARSET.iter().position(|&v| v == c) {
+            Some(value) => values.push(value as u8),
+            None => return false,
+        }
+    }
+
+    bech32_polymod(&values) == 1
+}
+
+fn bech32_polymod(values: &[u8]) -> u32 {
+    const GENERATORS: [u32; 5] = [
+        0x3b6a_57b2,
+        0x2650_8e6d,
+        0x1ea1_19fa,
+        0x3d42_33dd,
+        0x2a14_62b3,
+    ];
+
+    let mut checksum: u32 = 1;
+    for value in values {
+        let top: u32 = checksum >> 25;
+        checksum = ((checksum & 0x01ff_ffff) << 5) ^ u32::from(*value);
+        for (i, generator) in GENERATORS.iter().enumerate() {
+            if (top >> i) & 1 == 1 {
+                checksum ^= generator;
+            }
+        }
+    }
+    checksum
+}
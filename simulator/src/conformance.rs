@@ -0,0 +1,237 @@
+//! Conformance of the ping payload shared by the ping-pong dapps: every
+//! receiver accepts every version of the payload, and every pair of dapps
+//! completes a round trip.
+
+use cosmwasm_std::{Binary, Uint128};
+use new_crosstalk_sample::ping_pong::{
+    PingPayload, LEGACY_PING_PAYLOAD_VERSION, PING_PAYLOAD_VERSION,
+};
+
+use crate::cosmwasm::{CosmwasmPingPong, CosmwasmTestDapp};
+use crate::ink::InkTestDapp;
+use crate::near::NearPingPong;
+use crate::packet::{InboundRequest, OutboundRequest};
+use crate::relay::{relay, Chain, Delivery};
+
+const NEAR_ACCOUNT: &str = "ping-pong.testnet";
+const INK_ADDRESS: &str = "5FHneW46xGXgs5mUiveU4sbTyGBzmstUspZC92UhjJM694ty";
+const SENDER: &str = "sender.testnet";
+const RECIPIENT: &str = "recipient.testnet";
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Kind {
+    CosmwasmPingPong,
+    CosmwasmTestDapp,
+    Near,
+    Ink,
+}
+
+// the ink! dapp cannot send requests in the off-chain environment
+const SOURCES: [Kind; 3] = [Kind::CosmwasmPingPong, Kind::CosmwasmTestDapp, Kind::Near];
+const DESTINATIONS: [Kind; 4] = [
+    Kind::CosmwasmPingPong,
+    Kind::CosmwasmTestDapp,
+    Kind::Near,
+    Kind::Ink,
+];
+
+enum Dapp {
+    CosmwasmPingPong(CosmwasmPingPong),
+    CosmwasmTestDapp(CosmwasmTestDapp),
+    Near(Box<NearPingPong>),
+    Ink(InkTestDapp),
+}
+
+impl Dapp {
+    fn new(kind: Kind) -> Self {
+        match kind {
+            Kind::CosmwasmPingPong => Dapp::CosmwasmPingPong(CosmwasmPingPong::new("osmo-test-5")),
+            Kind::CosmwasmTestDapp => {
+                Dapp::CosmwasmTestDapp(CosmwasmTestDapp::new("injective-888"))
+            }
+            Kind::Near => Dapp::Near(Box::new(NearPingPong::new("near-testnet", NEAR_ACCOUNT))),
+            Kind::Ink => Dapp::Ink(InkTestDapp::new("alephzero-testnet", INK_ADDRESS)),
+        }
+    }
+
+    fn chain(&mut self) -> &mut dyn Chain {
+        match self {
+            Dapp::CosmwasmPingPong(dapp) => dapp,
+            Dapp::CosmwasmTestDapp(dapp) => dapp,
+            Dapp::Near(dapp) => dapp.as_mut(),
+            Dapp::Ink(dapp) => dapp,
+        }
+    }
+
+    /// Sends the first ping of the dapp, with request id 1.
+    fn ping(&mut self, dest: &mut Dapp, message: &str) -> OutboundRequest {
+        let dest_chain_id: String = dest.chain().chain_id().to_string();
+        let dest_contract_address: String = dest.chain().address();
+        match self {
+            Dapp::CosmwasmPingPong(dapp) => {
+                let msg = new_crosstalk_sample::ping_pong::ExecuteMsg::IPing {
+                    ping: message.to_string(),
+                    dest_contract_address,
+                    dest_chain_id,
+                    request_metadata: Binary(vec![]),
+                    body: None,
+                    recipient: Some(RECIPIENT.to_string()),
+                };
+                dapp.execute(SENDER, msg).unwrap().remove(0)
+            }
+            // the test dapp sends the payload it is given
+            Dapp::CosmwasmTestDapp(dapp) => {
+                let payload: PingPayload = ping_payload(PING_PAYLOAD_VERSION, 1, message);
+                let msg = new_crosstalk_sample::test_dapp::ExecuteMsg::SendIRequest {
+                    payload: Binary(payload.encode().unwrap()),
+                    dest_contract_address,
+                    dest_chain_id,
+                    request_metadata: Binary(vec![]),
+                    amount: Uint128::zero(),
+                    route_recipient: String::new(),
+                };
+                dapp.execute(SENDER, msg).unwrap().remove(0)
+            }
+            Dapp::Near(dapp) => dapp
+                .i_ping(
+                    SENDER,
+                    &dest_chain_id,
+                    &dest_contract_address,
+                    message,
+                    RECIPIENT,
+                )
+                .unwrap(),
+            Dapp::Ink(_) => panic!("the ink! dapp cannot send requests"),
+        }
+    }
+
+    fn received_ping(&self, src_chain_id: &str, request_id: u64) -> String {
+        match self {
+            Dapp::CosmwasmPingPong(dapp) => {
+                cosmwasm_ping_pong::query::fetch_ping(dapp.deps(), src_chain_id, request_id)
+                    .unwrap()
+            }
+            Dapp::CosmwasmTestDapp(dapp) => {
+                cosmwasm_test_dapp::query::fetch_ping(dapp.deps(), src_chain_id, request_id)
+                    .unwrap()
+            }
+            Dapp::Near(dapp) => dapp
+                .contract()
+                .get_ping_from_source(src_chain_id.to_string(), request_id)
+                .unwrap(),
+            Dapp::Ink(dapp) => dapp
+                .contract()
+                .get_greeting_record(src_chain_id.as_bytes().to_vec(), request_id)
+                .unwrap(),
+        }
+    }
+
+    fn acked_pong(&self, request_id: u64) -> String {
+        match self {
+            Dapp::CosmwasmPingPong(dapp) => {
+                cosmwasm_ping_pong::query::fetch_pong(dapp.deps(), request_id)
+                    .unwrap()
+                    .message
+            }
+            Dapp::CosmwasmTestDapp(dapp) => {
+                cosmwasm_test_dapp::query::fetch_pong(dapp.deps(), request_id).unwrap()
+            }
+            Dapp::Near(dapp) => dapp
+                .contract()
+                .get_ack_from_destination(request_id)
+                .unwrap(),
+            Dapp::Ink(_) => panic!("the ink! dapp cannot send requests"),
+        }
+    }
+}
+
+fn ping_payload(version: u8, request_id: u64, message: &str) -> PingPayload {
+    PingPayload {
+        version,
+        request_id,
+        timestamp: 1_700_000_000,
+        sender: Binary(SENDER.as_bytes().to_vec()),
+        message: message.to_string(),
+        body: Binary(vec![]),
+        recipient: if version == 1 {
+            String::new()
+        } else {
+            RECIPIENT.to_string()
+        },
+    }
+}
+
+#[test]
+fn test_every_pairing_round_trips() {
+    for src_kind in SOURCES {
+        for dest_kind in DESTINATIONS {
+            // the mocked blockchain of NEAR is global to the thread
+            if src_kind == Kind::Near && dest_kind == Kind::Near {
+                continue;
+            }
+            let mut src: Dapp = Dapp::new(src_kind);
+            let mut dest: Dapp = Dapp::new(dest_kind);
+            let message: String = format!("ping from {:?} to {:?}", src_kind, dest_kind);
+
+            let request: OutboundRequest = src.ping(&mut dest, &message);
+            let delivery: Delivery = relay(&request, src.chain(), dest.chain()).unwrap();
+            assert!(
+                delivery.receive.is_ok(),
+                "{}: {:?}",
+                message,
+                delivery.receive
+            );
+            assert_eq!(delivery.ack, Ok(()), "{}", message);
+
+            assert_eq!(dest.received_ping(&request.src_chain_id, 1), message);
+            assert_eq!(src.acked_pong(1), message);
+        }
+    }
+}
+
+#[test]
+fn test_every_receiver_accepts_every_payload_version() {
+    let src_chain_id: &str = "80001";
+    let mut legacy: PingPayload = ping_payload(LEGACY_PING_PAYLOAD_VERSION, 4, "legacy ping");
+    legacy.recipient = String::new();
+    let payloads: Vec<PingPayload> = vec![
+        ping_payload(PING_PAYLOAD_VERSION, 1, "v2 ping"),
+        ping_payload(1, 2, "v1 ping"),
+        // (uint64 requestId, string message, string recipient) of the NEAR ping-pong
+        ping_payload(LEGACY_PING_PAYLOAD_VERSION, 3, "legacy ping with recipient"),
+        // (uint64 requestId, string message)
+        legacy,
+    ];
+
+    for kind in DESTINATIONS {
+        let mut dest: Dapp = Dapp::new(kind);
+        for payload in &payloads {
+            let request: InboundRequest = InboundRequest {
+                request_sender: SENDER.to_string(),
+                src_chain_id: src_chain_id.to_string(),
+                request_identifier: payload.request_id,
+                payload: payload.encode().unwrap(),
+            };
+            let received = dest.chain().receive(&request);
+            assert!(
+                received.is_ok(),
+                "{:?} version {}: {:?}",
+                kind,
+                payload.version,
+                received
+            );
+            assert_eq!(
+                dest.received_ping(src_chain_id, payload.request_id),
+                payload.message
+            );
+        }
+
+        let request: InboundRequest = InboundRequest {
+            request_sender: SENDER.to_string(),
+            src_chain_id: src_chain_id.to_string(),
+            request_identifier: 5,
+            payload: vec![1, 2, 3],
+        };
+        assert!(dest.chain().receive(&request).is_err(), "{:?}", kind);
+    }
+}
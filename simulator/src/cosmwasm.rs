@@ -0,0 +1,178 @@
+use std::fmt::Display;
+use std::marker::PhantomData;
+
+use cosmwasm_std::testing::{mock_env, mock_info, MockApi, MockQuerier, MockStorage};
+use cosmwasm_std::{
+    Binary, Coin, CosmosMsg, Deps, DepsMut, Env, MessageInfo, OwnedDeps, Reply, ReplyOn, Response,
+    SubMsgResponse, SubMsgResult,
+};
+use router_wasm_bindings::{RouterMsg, RouterQuery, SudoMsg};
+
+use crate::packet::{Ack, InboundRequest, OutboundRequest};
+use crate::relay::Chain;
+
+const OWNER: &str = "owner";
+
+type ExecuteFn<M, E> =
+    fn(DepsMut<RouterQuery>, Env, MessageInfo, M) -> Result<Response<RouterMsg>, E>;
+type EntryFn<T, E> = fn(DepsMut<RouterQuery>, Env, T) -> Result<Response<RouterMsg>, E>;
+
+/// CosmWasm dapp running on mock dependencies. `M` and `E` are the
+/// `ExecuteMsg` and `ContractError` of the contract.
+pub struct CosmwasmDapp<M, E> {
+    chain_id: String,
+    env: Env,
+    deps: OwnedDeps<MockStorage, MockApi, MockQuerier, RouterQuery>,
+    execute: ExecuteFn<M, E>,
+    sudo: EntryFn<SudoMsg, E>,
+    reply: EntryFn<Reply, E>,
+    // last request identifier assigned by the gateway of the chain
+    request_identifier: u64,
+}
+
+pub type CosmwasmPingPong = CosmwasmDapp<
+    new_crosstalk_sample::ping_pong::ExecuteMsg,
+    cosmwasm_ping_pong::error::ContractError,
+>;
+
+pub type CosmwasmTestDapp = CosmwasmDapp<
+    new_crosstalk_sample::test_dapp::ExecuteMsg,
+    cosmwasm_test_dapp::error::ContractError,
+>;
+
+fn mock_router_dependencies() -> OwnedDeps<MockStorage, MockApi, MockQuerier, RouterQuery> {
+    OwnedDeps {
+        storage: MockStorage::default(),
+        api: MockApi::default(),
+        querier: MockQuerier::default(),
+        custom_query_type: PhantomData,
+    }
+}
+
+impl CosmwasmPingPong {
+    pub fn new(chain_id: &str) -> Self {
+        use cosmwasm_ping_pong::contract::{execute, instantiate, reply, sudo};
+
+        let mut deps = mock_router_dependencies();
+        let msg = new_crosstalk_sample::ping_pong::InstantiateMsg {};
+        instantiate(deps.as_mut(), mock_env(), mock_info(OWNER, &[]), msg).unwrap();
+        CosmwasmDapp {
+            chain_id: chain_id.to_string(),
+            env: mock_env(),
+            deps,
+            execute,
+            sudo,
+            reply,
+            request_identifier: 0,
+        }
+    }
+}
+
+impl CosmwasmTestDapp {
+    pub fn new(chain_id: &str) -> Self {
+        use cosmwasm_test_dapp::contract::{execute, instantiate, reply, sudo};
+
+        let mut deps = mock_router_dependencies();
+        let msg = new_crosstalk_sample::test_dapp::InstantiateMsg { route_denom: None };
+        instantiate(deps.as_mut(), mock_env(), mock_info(OWNER, &[]), msg).unwrap();
+        CosmwasmDapp {
+            chain_id: chain_id.to_string(),
+            env: mock_env(),
+            deps,
+            execute,
+            sudo,
+            reply,
+            request_identifier: 0,
+        }
+    }
+}
+
+impl<M, E: Display> CosmwasmDapp<M, E> {
+    pub fn deps(&self) -> Deps<'_, RouterQuery> {
+        self.deps.as_ref()
+    }
+
+    /// Executes `msg` and returns the requests the dapp sent to the gateway.
+    /// The gateway assigns the request identifiers and replies to the i_send
+    /// submessages with them.
+    pub fn execute(&mut self, sender: &str, msg: M) -> Result<Vec<OutboundRequest>, String> {
+        let res: Response<RouterMsg> = (self.execute)(
+            self.deps.as_mut(),
+            self.env.clone(),
+            mock_info(sender, &[]),
+            msg,
+        )
+        .map_err(|err| err.to_string())?;
+
+        let mut requests: Vec<OutboundRequest> = vec![];
+        for sub_msg in res.messages {
+            if let CosmosMsg::Custom(RouterMsg::CrosschainCall {
+                dest_chain_id,
+                request_metadata,
+                request_packet,
+                ..
+            }) = sub_msg.msg
+            {
+                self.request_identifier += 1;
+                if matches!(sub_msg.reply_on, ReplyOn::Success | ReplyOn::Always) {
+                    let data: String = format!(
+                        r#"{{"request_identifier":{},"fee_deducted":"0"}}"#,
+                        self.request_identifier
+                    );
+                    let reply_msg: Reply = Reply {
+                        id: sub_msg.id,
+                        result: SubMsgResult::Ok(SubMsgResponse {
+                            events: vec![],
+                            data: Some(Binary::from(data.into_bytes())),
+                        }),
+                    };
+                    (self.reply)(self.deps.as_mut(), self.env.clone(), reply_msg)
+                        .map_err(|err| err.to_string())?;
+                }
+                requests.push(OutboundRequest {
+                    src_chain_id: self.chain_id.clone(),
+                    request_sender: self.address(),
+                    request_identifier: self.request_identifier,
+                    dest_chain_id,
+                    request_metadata,
+                    request_packet,
+                });
+            }
+        }
+        Ok(requests)
+    }
+}
+
+impl<M, E: Display> Chain for CosmwasmDapp<M, E> {
+    fn chain_id(&self) -> &str {
+        &self.chain_id
+    }
+
+    fn address(&self) -> String {
+        self.env.contract.address.to_string()
+    }
+
+    fn receive(&mut self, request: &InboundRequest) -> Result<Vec<u8>, String> {
+        let msg: SudoMsg = SudoMsg::HandleIReceive {
+            request_sender: request.request_sender.clone(),
+            src_chain_id: request.src_chain_id.clone(),
+            request_identifier: request.request_identifier,
+            payload: Binary(request.payload.clone()),
+        };
+        let res: Response<RouterMsg> = (self.sudo)(self.deps.as_mut(), self.env.clone(), msg)
+            .map_err(|err| err.to_string())?;
+        Ok(res.data.map(|data| data.0).unwrap_or_default())
+    }
+
+    fn ack(&mut self, ack: &Ack) -> Result<(), String> {
+        let msg: SudoMsg = SudoMsg::HandleIAck {
+            request_identifier: ack.request_identifier,
+            exec_flag: ack.exec_flag,
+            exec_data: Binary(ack.exec_data.clone()),
+            refund_amount: Coin::new(0u128, "route"),
+        };
+        (self.sudo)(self.deps.as_mut(), self.env.clone(), msg)
+            .map(|_| ())
+            .map_err(|err| err.to_string())
+    }
+}
@@ -0,0 +1,84 @@
+use std::panic::AssertUnwindSafe;
+
+use ink::env::{test, DefaultEnvironment};
+use ink::primitives::AccountId;
+use ink::storage::Mapping;
+use ink_test_dapp::test_dapp::TestDapp;
+
+use crate::packet::{Ack, InboundRequest};
+use crate::relay::{catch_panic, Chain};
+
+/// ink! test dapp running in the off-chain environment of `ink::env::test`.
+/// The off-chain environment cannot call other contracts, so the dapp can
+/// only be the destination of the simulated requests.
+pub struct InkTestDapp {
+    chain_id: String,
+    address: String,
+    account_id: AccountId,
+    gateway: AccountId,
+    contract: TestDapp,
+}
+
+impl InkTestDapp {
+    /// `TestDapp::new` registers the dapp on the gateway, the contract is
+    /// created from its storage instead.
+    pub fn new(chain_id: &str, address: &str) -> Self {
+        let accounts = test::default_accounts::<DefaultEnvironment>();
+        let contract: TestDapp = TestDapp {
+            route_token: accounts.charlie,
+            gateway_contract: accounts.alice,
+            greeting_record: Mapping::default(),
+            ack_record_record: Mapping::default(),
+        };
+        Self {
+            chain_id: chain_id.to_string(),
+            address: address.to_string(),
+            account_id: accounts.bob,
+            gateway: accounts.alice,
+            contract,
+        }
+    }
+
+    pub fn contract(&self) -> &TestDapp {
+        &self.contract
+    }
+
+    fn set_gateway_caller(&self) {
+        test::set_callee::<DefaultEnvironment>(self.account_id);
+        test::set_caller::<DefaultEnvironment>(self.gateway);
+    }
+}
+
+impl Chain for InkTestDapp {
+    fn chain_id(&self) -> &str {
+        &self.chain_id
+    }
+
+    fn address(&self) -> String {
+        self.address.clone()
+    }
+
+    fn receive(&mut self, request: &InboundRequest) -> Result<Vec<u8>, String> {
+        self.set_gateway_caller();
+        let contract: &mut TestDapp = &mut self.contract;
+        catch_panic(AssertUnwindSafe(|| {
+            contract.i_receive(
+                request.request_sender.as_bytes().to_vec(),
+                request.payload.clone(),
+                request.src_chain_id.as_bytes().to_vec(),
+            )
+        }))
+    }
+
+    fn ack(&mut self, ack: &Ack) -> Result<(), String> {
+        self.set_gateway_caller();
+        let contract: &mut TestDapp = &mut self.contract;
+        catch_panic(AssertUnwindSafe(|| {
+            contract.i_ack(
+                ack.request_identifier as u128,
+                ack.exec_flag,
+                ack.exec_data.clone(),
+            );
+        }))
+    }
+}
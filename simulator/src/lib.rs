@@ -0,0 +1,21 @@
+//! Local simulator delivering Router request packets between the sample dapps
+//! of the different ecosystems, each dapp running in the unit-test
+//! environment of its ecosystem.
+
+pub mod packet;
+pub mod relay;
+
+#[cfg(feature = "cosmwasm")]
+pub mod cosmwasm;
+#[cfg(feature = "ink")]
+pub mod ink;
+#[cfg(feature = "near")]
+pub mod near;
+
+#[cfg(all(test, feature = "cosmwasm", feature = "near", feature = "ink"))]
+mod conformance;
+#[cfg(all(test, feature = "cosmwasm", feature = "near", feature = "ink"))]
+mod tests;
+
+pub use packet::{Ack, InboundRequest, OutboundRequest, RequestPacket};
+pub use relay::{relay, Chain, Delivery};
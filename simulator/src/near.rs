@@ -0,0 +1,173 @@
+use near_crosstalk_sample::request_metadata::RequestMetadata;
+use near_ping_pong::PingPong;
+use near_sdk::json_types::U128;
+use near_sdk::mock::VmAction;
+use near_sdk::serde::Deserialize;
+use near_sdk::test_utils::{get_created_receipts, VMContextBuilder};
+use near_sdk::{
+    env, serde_json, testing_env, AccountId, MockedBlockchain, RuntimeFeesConfig, VMConfig,
+};
+
+use crate::packet::{Ack, InboundRequest, OutboundRequest};
+use crate::relay::Chain;
+
+const GATEWAY: &str = "gateway.near";
+
+// arguments of the gateway i_send call
+#[derive(Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+struct ISendArgs {
+    dest_chain_id: String,
+    request_metadata: Vec<u8>,
+    request_packet: Vec<u8>,
+}
+
+/// NEAR ping-pong running on the mocked blockchain of `near_sdk`. The mocked
+/// blockchain is global to the thread, only one NEAR dapp can be simulated
+/// per test.
+///
+/// The `ContractError` returned by the dapp is the failure of the call. A
+/// panic raised inside the mocked host functions, `env::panic_str` included,
+/// cannot unwind through them and aborts the test process.
+pub struct NearPingPong {
+    chain_id: String,
+    account_id: AccountId,
+    gateway: AccountId,
+    contract: PingPong,
+    // last request identifier assigned by the gateway of the chain
+    request_identifier: u64,
+}
+
+impl NearPingPong {
+    pub fn new(chain_id: &str, account_id: &str) -> Self {
+        let account_id: AccountId = account_id.parse().unwrap();
+        let gateway: AccountId = GATEWAY.parse().unwrap();
+        // testing_env! keeps the storage of the mocked blockchain, a new dapp
+        // starts from an empty one
+        env::set_blockchain_interface(MockedBlockchain::new(
+            VMContextBuilder::new()
+                .current_account_id(account_id.clone())
+                .predecessor_account_id(account_id.clone())
+                .build(),
+            VMConfig::test(),
+            RuntimeFeesConfig::test(),
+            vec![],
+            Default::default(),
+            Default::default(),
+            None,
+        ));
+        let contract: PingPong = PingPong::new(gateway.clone());
+        Self {
+            chain_id: chain_id.to_string(),
+            account_id,
+            gateway,
+            contract,
+            request_identifier: 0,
+        }
+    }
+
+    pub fn contract(&self) -> &PingPong {
+        &self.contract
+    }
+
+    /// Calls `i_ping` and returns the request it handed to the gateway.
+    pub fn i_ping(
+        &mut self,
+        sender: &str,
+        dest_chain_id: &str,
+        destination_contract_address: &str,
+        message: &str,
+        recipient: &str,
+    ) -> Result<OutboundRequest, String> {
+        let sender: AccountId = sender
+            .parse()
+            .map_err(|_| format!("invalid account {}", sender))?;
+        if self.contract.storage_balance_of(sender.clone()).is_none() {
+            let min: U128 = self.contract.storage_balance_bounds().min;
+            let ping_storage_cost: U128 = self.contract.get_ping_storage_cost();
+            testing_env!(VMContextBuilder::new()
+                .current_account_id(self.account_id.clone())
+                .predecessor_account_id(sender.clone())
+                .attached_deposit(u128::from(min) + 10 * u128::from(ping_storage_cost))
+                .build());
+            self.contract
+                .storage_deposit(None, None)
+                .map_err(|err| err.to_string())?;
+        }
+        set_context(&self.account_id, &sender);
+        // the promise is scheduled when dropped
+        drop(
+            self.contract
+                .i_ping(
+                    dest_chain_id.to_string(),
+                    destination_contract_address.to_string(),
+                    message.to_string(),
+                    RequestMetadata::default().encode(),
+                    recipient.to_string(),
+                )
+                .map_err(|err| err.to_string())?,
+        );
+
+        let args: ISendArgs = get_created_receipts()
+            .into_iter()
+            .filter(|receipt| receipt.receiver_id == self.gateway)
+            .flat_map(|receipt| receipt.actions)
+            .find_map(|action| match action {
+                VmAction::FunctionCall {
+                    function_name,
+                    args,
+                    ..
+                } if function_name == "i_send" => serde_json::from_slice(&args).ok(),
+                _ => None,
+            })
+            .ok_or_else(|| String::from("no i_send call to the gateway"))?;
+        self.request_identifier += 1;
+        Ok(OutboundRequest {
+            src_chain_id: self.chain_id.clone(),
+            request_sender: self.account_id.to_string(),
+            request_identifier: self.request_identifier,
+            dest_chain_id: args.dest_chain_id,
+            request_metadata: args.request_metadata,
+            request_packet: args.request_packet,
+        })
+    }
+}
+
+fn set_context(current_account_id: &AccountId, predecessor_account_id: &AccountId) {
+    testing_env!(VMContextBuilder::new()
+        .current_account_id(current_account_id.clone())
+        .predecessor_account_id(predecessor_account_id.clone())
+        .build());
+}
+
+impl Chain for NearPingPong {
+    fn chain_id(&self) -> &str {
+        &self.chain_id
+    }
+
+    fn address(&self) -> String {
+        self.account_id.to_string()
+    }
+
+    fn receive(&mut self, request: &InboundRequest) -> Result<Vec<u8>, String> {
+        set_context(&self.account_id, &self.gateway);
+        self.contract
+            .i_receive(
+                request.request_sender.clone(),
+                request.payload.clone(),
+                request.src_chain_id.clone(),
+            )
+            .map_err(|err| err.to_string())
+    }
+
+    fn ack(&mut self, ack: &Ack) -> Result<(), String> {
+        set_context(&self.account_id, &self.gateway);
+        self.contract
+            .i_ack(
+                U128(ack.request_identifier as u128),
+                ack.exec_flag,
+                ack.exec_data.clone(),
+            )
+            .map_err(|err| err.to_string())
+    }
+}
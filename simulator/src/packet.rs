@@ -0,0 +1,69 @@
+use ethabi::{decode, encode, ParamType, Token};
+
+/// Router request packet, `abi.encode(string dest_contract_address, bytes payload)`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RequestPacket {
+    pub dest_contract_address: String,
+    pub payload: Vec<u8>,
+}
+
+impl RequestPacket {
+    pub fn new(dest_contract_address: impl Into<String>, payload: Vec<u8>) -> Self {
+        Self {
+            dest_contract_address: dest_contract_address.into(),
+            payload,
+        }
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        encode(&[
+            Token::String(self.dest_contract_address.clone()),
+            Token::Bytes(self.payload.clone()),
+        ])
+    }
+
+    pub fn decode(data: &[u8]) -> Result<Self, ethabi::Error> {
+        let token_vec: Vec<Token> = decode(&[ParamType::String, ParamType::Bytes], data)?;
+        let dest_contract_address: String = token_vec[0]
+            .clone()
+            .into_string()
+            .ok_or(ethabi::Error::InvalidData)?;
+        let payload: Vec<u8> = token_vec[1]
+            .clone()
+            .into_bytes()
+            .ok_or(ethabi::Error::InvalidData)?;
+        Ok(Self {
+            dest_contract_address,
+            payload,
+        })
+    }
+}
+
+/// Request handed by a dapp to the gateway of its chain. `request_packet` is
+/// kept as sent by the dapp, it is only decoded when relayed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct OutboundRequest {
+    pub src_chain_id: String,
+    pub request_sender: String,
+    pub request_identifier: u64,
+    pub dest_chain_id: String,
+    pub request_metadata: Vec<u8>,
+    pub request_packet: Vec<u8>,
+}
+
+/// Request delivered to the destination dapp.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct InboundRequest {
+    pub request_sender: String,
+    pub src_chain_id: String,
+    pub request_identifier: u64,
+    pub payload: Vec<u8>,
+}
+
+/// Ack delivered back to the sender of a request.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Ack {
+    pub request_identifier: u64,
+    pub exec_flag: bool,
+    pub exec_data: Vec<u8>,
+}
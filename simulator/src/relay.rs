@@ -0,0 +1,93 @@
+use crate::packet::{Ack, InboundRequest, OutboundRequest, RequestPacket};
+
+/// Dapp running in the unit-test environment of its ecosystem.
+pub trait Chain {
+    fn chain_id(&self) -> &str;
+
+    /// Address of the dapp as written in the request packets.
+    fn address(&self) -> String;
+
+    /// Executes the request, returns the execution data sent back with the
+    /// ack or the reason of the failure.
+    fn receive(&mut self, request: &InboundRequest) -> Result<Vec<u8>, String>;
+
+    fn ack(&mut self, ack: &Ack) -> Result<(), String>;
+}
+
+/// Outcome of a relayed request.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Delivery {
+    pub request_identifier: u64,
+    pub receive: Result<Vec<u8>, String>,
+    pub ack: Result<(), String>,
+}
+
+/// Delivers `request` from `src` to `dest` and acks the outcome back to
+/// `src`, the way the Router chain does. Fails without delivering anything
+/// when the request is not addressed to `dest` or its packet is not a valid
+/// request packet.
+pub fn relay(
+    request: &OutboundRequest,
+    src: &mut dyn Chain,
+    dest: &mut dyn Chain,
+) -> Result<Delivery, String> {
+    if request.src_chain_id != src.chain_id() || request.request_sender != src.address() {
+        return Err(format!(
+            "request {} was not sent by {} on {}",
+            request.request_identifier,
+            src.address(),
+            src.chain_id()
+        ));
+    }
+    let packet: RequestPacket = RequestPacket::decode(&request.request_packet)
+        .map_err(|err| format!("invalid request packet: {}", err))?;
+    if request.dest_chain_id != dest.chain_id() || packet.dest_contract_address != dest.address() {
+        return Err(format!(
+            "request {} is addressed to {} on {}",
+            request.request_identifier, packet.dest_contract_address, request.dest_chain_id
+        ));
+    }
+
+    let inbound: InboundRequest = InboundRequest {
+        request_sender: request.request_sender.clone(),
+        src_chain_id: request.src_chain_id.clone(),
+        request_identifier: request.request_identifier,
+        payload: packet.payload,
+    };
+    let receive: Result<Vec<u8>, String> = dest.receive(&inbound);
+
+    let ack: Ack = Ack {
+        request_identifier: request.request_identifier,
+        exec_flag: receive.is_ok(),
+        exec_data: match &receive {
+            Ok(exec_data) => exec_data.clone(),
+            Err(reason) => reason.as_bytes().to_vec(),
+        },
+    };
+    Ok(Delivery {
+        request_identifier: request.request_identifier,
+        receive,
+        ack: src.ack(&ack),
+    })
+}
+
+/// Runs a contract call which reports failures by panicking, the way the
+/// ink! contracts abort their execution. The panics of the off-chain
+/// environment of ink! unwind, the NEAR dapps return their failures instead.
+#[cfg(feature = "ink")]
+pub(crate) fn catch_panic<T>(
+    call: impl FnOnce() -> T + std::panic::UnwindSafe,
+) -> Result<T, String> {
+    std::panic::catch_unwind(call).map_err(|payload| panic_message(payload.as_ref()))
+}
+
+#[cfg(feature = "ink")]
+fn panic_message(payload: &(dyn std::any::Any + Send)) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        return message.to_string();
+    }
+    match payload.downcast_ref::<String>() {
+        Some(message) => message.clone(),
+        None => String::from("contract panicked"),
+    }
+}
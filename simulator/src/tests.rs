@@ -0,0 +1,184 @@
+use cosmwasm_std::{Binary, Uint128};
+use ethabi::{encode, ethereum_types::U256, Token};
+
+use crate::cosmwasm::{CosmwasmPingPong, CosmwasmTestDapp};
+use crate::ink::InkTestDapp;
+use crate::near::NearPingPong;
+use crate::packet::{OutboundRequest, RequestPacket};
+use crate::relay::{relay, Chain};
+
+const COSMWASM_CHAIN_ID: &str = "osmo-test-5";
+const NEAR_CHAIN_ID: &str = "near-testnet";
+const INK_CHAIN_ID: &str = "alephzero-testnet";
+
+const NEAR_ACCOUNT: &str = "ping-pong.testnet";
+const INK_ADDRESS: &str = "5FHneW46xGXgs5mUiveU4sbTyGBzmstUspZC92UhjJM694ty";
+const SENDER: &str = "sender.testnet";
+
+// (uint64 requestId, string message) payload of the test dapps
+fn greeting(request_id: u64, message: &str) -> Binary {
+    Binary(encode(&[
+        Token::Uint(U256::from(request_id)),
+        Token::String(message.to_string()),
+    ]))
+}
+
+fn send_greeting(
+    dapp: &mut CosmwasmTestDapp,
+    dest_chain_id: &str,
+    dest_contract_address: String,
+    message: &str,
+) -> OutboundRequest {
+    let msg = new_crosstalk_sample::test_dapp::ExecuteMsg::SendIRequest {
+        payload: greeting(1, message),
+        dest_contract_address,
+        dest_chain_id: dest_chain_id.to_string(),
+        request_metadata: Binary(vec![]),
+        amount: Uint128::zero(),
+        route_recipient: String::new(),
+    };
+    let mut requests: Vec<OutboundRequest> = dapp.execute(SENDER, msg).unwrap();
+    assert_eq!(requests.len(), 1);
+    requests.remove(0)
+}
+
+#[test]
+fn test_cosmwasm_to_near() {
+    let mut cosmwasm = CosmwasmTestDapp::new(COSMWASM_CHAIN_ID);
+    let mut near = NearPingPong::new(NEAR_CHAIN_ID, NEAR_ACCOUNT);
+
+    let request = send_greeting(&mut cosmwasm, NEAR_CHAIN_ID, near.address(), "hello");
+    let delivery = relay(&request, &mut cosmwasm, &mut near).unwrap();
+    assert!(delivery.receive.is_ok());
+    assert_eq!(delivery.ack, Ok(()));
+
+    assert_eq!(
+        near.contract()
+            .get_ping_from_source(COSMWASM_CHAIN_ID.to_string(), 1),
+        Some(String::from("hello"))
+    );
+    let pong = cosmwasm_test_dapp::query::fetch_pong(cosmwasm.deps(), 1).unwrap();
+    assert_eq!(pong, "hello");
+}
+
+#[test]
+fn test_near_to_cosmwasm() {
+    let mut near = NearPingPong::new(NEAR_CHAIN_ID, NEAR_ACCOUNT);
+    let mut cosmwasm = CosmwasmTestDapp::new(COSMWASM_CHAIN_ID);
+
+    let request = near
+        .i_ping(
+            SENDER,
+            COSMWASM_CHAIN_ID,
+            &cosmwasm.address(),
+            "hello",
+            "recipient",
+        )
+        .unwrap();
+    let packet = RequestPacket::decode(&request.request_packet).unwrap();
+    assert_eq!(packet.dest_contract_address, cosmwasm.address());
+
+    let delivery = relay(&request, &mut near, &mut cosmwasm).unwrap();
+    assert!(delivery.receive.is_ok());
+    assert_eq!(delivery.ack, Ok(()));
+
+    let ping = cosmwasm_test_dapp::query::fetch_ping(cosmwasm.deps(), NEAR_CHAIN_ID, 1).unwrap();
+    assert_eq!(ping, "hello");
+    assert_eq!(
+        near.contract().get_ack_from_destination(1),
+        Some(String::from("hello"))
+    );
+}
+
+#[test]
+fn test_cosmwasm_to_ink() {
+    let mut cosmwasm = CosmwasmTestDapp::new(COSMWASM_CHAIN_ID);
+    let mut ink = InkTestDapp::new(INK_CHAIN_ID, INK_ADDRESS);
+
+    let request = send_greeting(&mut cosmwasm, INK_CHAIN_ID, ink.address(), "hello");
+    let delivery = relay(&request, &mut cosmwasm, &mut ink).unwrap();
+    assert!(delivery.receive.is_ok());
+    assert_eq!(delivery.ack, Ok(()));
+
+    let record = ink
+        .contract()
+        .get_greeting_record(COSMWASM_CHAIN_ID.as_bytes().to_vec(), 1);
+    assert_eq!(record, Ok(String::from("hello")));
+}
+
+#[test]
+fn test_near_to_ink() {
+    let mut near = NearPingPong::new(NEAR_CHAIN_ID, NEAR_ACCOUNT);
+    let mut ink = InkTestDapp::new(INK_CHAIN_ID, INK_ADDRESS);
+
+    let request = near
+        .i_ping(SENDER, INK_CHAIN_ID, INK_ADDRESS, "hello", "recipient")
+        .unwrap();
+    let delivery = relay(&request, &mut near, &mut ink).unwrap();
+    assert!(delivery.receive.is_ok());
+    assert_eq!(delivery.ack, Ok(()));
+
+    let record = ink
+        .contract()
+        .get_greeting_record(NEAR_CHAIN_ID.as_bytes().to_vec(), 1);
+    assert_eq!(record, Ok(String::from("hello")));
+    assert_eq!(
+        near.contract().get_ack_from_destination(1),
+        Some(String::from("hello"))
+    );
+}
+
+#[test]
+fn test_failed_request_is_acked() {
+    let mut cosmwasm = CosmwasmTestDapp::new(COSMWASM_CHAIN_ID);
+    let mut ink = InkTestDapp::new(INK_CHAIN_ID, INK_ADDRESS);
+
+    let request = send_greeting(&mut cosmwasm, INK_CHAIN_ID, ink.address(), "Fail Dest Req");
+    let delivery = relay(&request, &mut cosmwasm, &mut ink).unwrap();
+    assert!(delivery.receive.is_err());
+    assert_eq!(delivery.ack, Ok(()));
+    assert!(cosmwasm_test_dapp::query::fetch_pong(cosmwasm.deps(), 1).is_err());
+}
+
+#[test]
+fn test_misaddressed_request() {
+    let mut cosmwasm = CosmwasmTestDapp::new(COSMWASM_CHAIN_ID);
+    let mut near = NearPingPong::new(NEAR_CHAIN_ID, NEAR_ACCOUNT);
+
+    let request = send_greeting(
+        &mut cosmwasm,
+        NEAR_CHAIN_ID,
+        String::from("other.testnet"),
+        "hello",
+    );
+    assert!(relay(&request, &mut cosmwasm, &mut near).is_err());
+}
+
+// the NEAR ping-pong decodes the versioned ping payload of the CosmWasm
+// ping-pong and acks with it
+#[test]
+fn test_cosmwasm_ping_pong_to_near() {
+    let mut cosmwasm = CosmwasmPingPong::new(COSMWASM_CHAIN_ID);
+    let mut near = NearPingPong::new(NEAR_CHAIN_ID, NEAR_ACCOUNT);
+
+    let msg = new_crosstalk_sample::ping_pong::ExecuteMsg::IPing {
+        ping: String::from("hello"),
+        dest_contract_address: near.address(),
+        dest_chain_id: NEAR_CHAIN_ID.to_string(),
+        request_metadata: Binary(vec![]),
+        body: None,
+        recipient: Some(String::from("alice.testnet")),
+    };
+    let requests = cosmwasm.execute(SENDER, msg).unwrap();
+    let delivery = relay(&requests[0], &mut cosmwasm, &mut near).unwrap();
+    assert!(delivery.receive.is_ok());
+    assert_eq!(delivery.ack, Ok(()));
+
+    assert_eq!(
+        near.contract()
+            .get_ping_from_source(COSMWASM_CHAIN_ID.to_string(), 1),
+        Some(String::from("hello"))
+    );
+    let pong = cosmwasm_ping_pong::query::fetch_pong(cosmwasm.deps(), 1).unwrap();
+    assert_eq!(pong.message, "hello");
+}
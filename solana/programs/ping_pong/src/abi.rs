@@ -0,0 +1,265 @@
+//! Solidity ABI codec of the payloads exchanged with the dapps of the other
+//! chains. Only depends on `core` and `alloc`, and never panics on malformed
+//! data: every read is bounds checked and fails with a `DappError`.
+
+use alloc::{boxed::Box, string::String, vec, vec::Vec};
+
+use crate::DappError;
+
+pub type Result<T> = core::result::Result<T, DappError>;
+
+/// Big endian uint256.
+pub type Word = [u8; 32];
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ParamType {
+    Uint,
+    Bool,
+    Bytes,
+    String,
+    Array(Box<ParamType>),
+    Tuple(Vec<ParamType>),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Token {
+    Uint(Word),
+    Bool(bool),
+    Bytes(Vec<u8>),
+    String(String),
+    Array(Vec<Token>),
+    Tuple(Vec<Token>),
+}
+
+impl ParamType {
+    fn is_dynamic(&self) -> bool {
+        match self {
+            ParamType::Uint | ParamType::Bool => false,
+            ParamType::Bytes | ParamType::String | ParamType::Array(_) => true,
+            ParamType::Tuple(types) => types.iter().any(ParamType::is_dynamic),
+        }
+    }
+
+    // size of the value in the head of the enclosing tuple
+    fn head_len(&self) -> usize {
+        match self {
+            ParamType::Tuple(types) if !self.is_dynamic() => {
+                types.iter().map(ParamType::head_len).sum()
+            }
+            _ => 32,
+        }
+    }
+}
+
+impl Token {
+    pub fn uint(value: u128) -> Self {
+        let mut word = [0u8; 32];
+        word[16..].copy_from_slice(&value.to_be_bytes());
+        Token::Uint(word)
+    }
+
+    fn is_dynamic(&self) -> bool {
+        match self {
+            Token::Uint(_) | Token::Bool(_) => false,
+            Token::Bytes(_) | Token::String(_) | Token::Array(_) => true,
+            Token::Tuple(tokens) => tokens.iter().any(Token::is_dynamic),
+        }
+    }
+
+    fn head_len(&self) -> usize {
+        match self {
+            Token::Tuple(tokens) if !self.is_dynamic() => tokens.iter().map(Token::head_len).sum(),
+            _ => 32,
+        }
+    }
+
+    /// The uint value, if it fits in a u128.
+    pub fn as_u128(&self) -> Result<u128> {
+        match self {
+            Token::Uint(word) => {
+                if word[..16].iter().any(|byte| *byte != 0) {
+                    return Err(DappError::AbiValueOverflow);
+                }
+                let mut value = [0u8; 16];
+                value.copy_from_slice(&word[16..]);
+                Ok(u128::from_be_bytes(value))
+            }
+            _ => Err(DappError::InvalidPayload),
+        }
+    }
+
+    /// The uint value, if it fits in a u64.
+    pub fn as_u64(&self) -> Result<u64> {
+        u64::try_from(self.as_u128()?).map_err(|_| DappError::AbiValueOverflow)
+    }
+
+    pub fn into_bytes(self) -> Result<Vec<u8>> {
+        match self {
+            Token::Bytes(data) => Ok(data),
+            _ => Err(DappError::InvalidPayload),
+        }
+    }
+
+    pub fn into_string(self) -> Result<String> {
+        match self {
+            Token::String(text) => Ok(text),
+            _ => Err(DappError::InvalidPayload),
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////
+/// Encoding
+////////////////////////////////////////////////////////////////////////////////////////
+
+/// abi.encode(tokens...)
+pub fn encode(tokens: &[Token]) -> Vec<u8> {
+    encode_sequence(tokens)
+}
+
+fn usize_word(value: usize) -> Vec<u8> {
+    let mut word = vec![0u8; 32];
+    word[24..].copy_from_slice(&(value as u64).to_be_bytes());
+    word
+}
+
+fn encode_sequence(tokens: &[Token]) -> Vec<u8> {
+    let heads_len: usize = tokens.iter().map(Token::head_len).sum();
+    let mut head = Vec::with_capacity(heads_len);
+    let mut tail = vec![];
+    for token in tokens {
+        if token.is_dynamic() {
+            head.extend(usize_word(heads_len + tail.len()));
+            tail.extend(encode_token(token));
+        } else {
+            head.extend(encode_token(token));
+        }
+    }
+    head.extend(tail);
+    head
+}
+
+fn encode_bytes(data: &[u8]) -> Vec<u8> {
+    let mut encoded = usize_word(data.len());
+    encoded.extend(data);
+    encoded.extend(vec![0u8; (32 - data.len() % 32) % 32]);
+    encoded
+}
+
+fn encode_token(token: &Token) -> Vec<u8> {
+    match token {
+        Token::Uint(word) => word.to_vec(),
+        Token::Bool(value) => usize_word(*value as usize),
+        Token::Bytes(data) => encode_bytes(data),
+        Token::String(text) => encode_bytes(text.as_bytes()),
+        Token::Array(tokens) => {
+            let mut encoded = usize_word(tokens.len());
+            encoded.extend(encode_sequence(tokens));
+            encoded
+        }
+        Token::Tuple(tokens) => encode_sequence(tokens),
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////
+/// Decoding
+////////////////////////////////////////////////////////////////////////////////////////
+
+/// abi.decode(data, (types...)), the data after the values is ignored.
+pub fn decode(types: &[ParamType], data: &[u8]) -> Result<Vec<Token>> {
+    decode_sequence(types.iter(), data, 0)
+}
+
+/// Like `decode`, but the data must be exactly the encoding of the values,
+/// so that different layouts cannot be mistaken for one another.
+pub fn decode_exact(types: &[ParamType], data: &[u8]) -> Result<Vec<Token>> {
+    let tokens = decode(types, data)?;
+    if encode(&tokens) != data {
+        return Err(DappError::AbiNonCanonical);
+    }
+    Ok(tokens)
+}
+
+fn read_word(data: &[u8], at: usize) -> Result<&[u8]> {
+    at.checked_add(32)
+        .and_then(|end| data.get(at..end))
+        .ok_or(DappError::AbiDataTooShort)
+}
+
+// lengths and offsets, which cannot exceed the data
+fn read_usize(data: &[u8], at: usize) -> Result<usize> {
+    let word = read_word(data, at)?;
+    if word[..24].iter().any(|byte| *byte != 0) {
+        return Err(DappError::AbiInvalidOffset);
+    }
+    let mut value = [0u8; 8];
+    value.copy_from_slice(&word[24..]);
+    let value =
+        usize::try_from(u64::from_be_bytes(value)).map_err(|_| DappError::AbiInvalidOffset)?;
+    if value > data.len() {
+        return Err(DappError::AbiInvalidOffset);
+    }
+    Ok(value)
+}
+
+fn read_bytes(data: &[u8], at: usize) -> Result<Vec<u8>> {
+    let len = read_usize(data, at)?;
+    let start = at.checked_add(32).ok_or(DappError::AbiInvalidOffset)?;
+    start
+        .checked_add(len)
+        .and_then(|end| data.get(start..end))
+        .map(|bytes| bytes.to_vec())
+        .ok_or(DappError::AbiDataTooShort)
+}
+
+// decodes the head/tail encoding of the values starting at `base`
+fn decode_sequence<'a>(
+    types: impl Iterator<Item = &'a ParamType>,
+    data: &[u8],
+    base: usize,
+) -> Result<Vec<Token>> {
+    let mut tokens = vec![];
+    let mut offset = base;
+    for param_type in types {
+        if param_type.is_dynamic() {
+            let start = base
+                .checked_add(read_usize(data, offset)?)
+                .ok_or(DappError::AbiInvalidOffset)?;
+            tokens.push(decode_token(param_type, data, start)?);
+        } else {
+            tokens.push(decode_token(param_type, data, offset)?);
+        }
+        offset = offset
+            .checked_add(param_type.head_len())
+            .ok_or(DappError::AbiInvalidOffset)?;
+    }
+    Ok(tokens)
+}
+
+fn decode_token(param_type: &ParamType, data: &[u8], at: usize) -> Result<Token> {
+    match param_type {
+        ParamType::Uint => {
+            let mut word = [0u8; 32];
+            word.copy_from_slice(read_word(data, at)?);
+            Ok(Token::Uint(word))
+        }
+        ParamType::Bool => {
+            let word = read_word(data, at)?;
+            if word[..31].iter().any(|byte| *byte != 0) || word[31] > 1 {
+                return Err(DappError::AbiInvalidBool);
+            }
+            Ok(Token::Bool(word[31] == 1))
+        }
+        ParamType::Bytes => Ok(Token::Bytes(read_bytes(data, at)?)),
+        ParamType::String => String::from_utf8(read_bytes(data, at)?)
+            .map(Token::String)
+            .map_err(|_| DappError::AbiInvalidUtf8),
+        ParamType::Array(inner) => {
+            // read_usize bounds the length by the size of the data
+            let len = read_usize(data, at)?;
+            let base = at.checked_add(32).ok_or(DappError::AbiInvalidOffset)?;
+            decode_sequence(core::iter::repeat(&**inner).take(len), data, base).map(Token::Array)
+        }
+        ParamType::Tuple(types) => decode_sequence(types.iter(), data, at).map(Token::Tuple),
+    }
+}
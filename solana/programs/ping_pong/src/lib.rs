@@ -1,4 +1,15 @@
+extern crate alloc;
+
+pub mod abi;
+#[cfg(test)]
+mod tests;
+
+use abi::{ParamType, Token};
 use anchor_lang::prelude::*;
+use anchor_lang::solana_program::{
+    entrypoint::MAX_PERMITTED_DATA_INCREASE, program::MAX_RETURN_DATA,
+};
+use anchor_lang::system_program;
 use anchor_spl::associated_token::AssociatedToken;
 use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
 use gateway::program::Gateway;
@@ -7,8 +18,18 @@ use internal::*;
 
 declare_id!("7dQqaHQFRBC8AhaRzqtQWLEM7fXxwGw9VEKwLpyf8rM3");
 
-pub const AUTHORIZED_DEPLOYER: &str = "AcSZ7ECK7yQk9TedzsjkT8dzhyuxi7TzXNhwuGADD9N";
 const PING_PONG_ACCOUNT: &[u8] = b"ping_pong";
+// seeds of the records, with (src_chain_id, request_id) for the pings and
+// request_identifier for the acks, the integers in little endian
+pub const PING_RECORD: &[u8] = b"ping_record";
+pub const ACK_RECORD: &[u8] = b"ack_record";
+// the reply of i_receive is the return data of the instruction, the abi
+// encoded (uint64 requestId, string message) takes 96 bytes besides the message
+pub const MAX_MESSAGE_LEN: usize = MAX_RETURN_DATA - 96;
+// the request packet account is created by a cpi of the gateway, which cannot
+// allocate more than MAX_PERMITTED_DATA_INCREASE bytes
+pub const MAX_PACKET_LEN: usize = MAX_PERMITTED_DATA_INCREASE;
+pub const MAX_REPLY_MESSAGE_LEN: usize = 100;
 
 #[event]
 pub struct AckFromDestination {
@@ -30,6 +51,40 @@ pub struct NewPing {
     pub request_id: u128,
 }
 
+#[event]
+pub struct PauseChanged {
+    pub paused: bool,
+}
+
+#[event]
+pub struct OwnershipTransferStarted {
+    pub owner: Pubkey,
+    pub pending_owner: Pubkey,
+}
+
+#[event]
+pub struct OwnershipTransferred {
+    pub previous_owner: Pubkey,
+    pub new_owner: Pubkey,
+}
+
+#[event]
+pub struct GatewayAuthorityUpdated {
+    pub previous_gateway_authority: Pubkey,
+    pub gateway_authority: Pubkey,
+}
+
+#[event]
+pub struct ISendDefaultFeeUpdated {
+    pub previous_i_send_default_fee: u64,
+    pub i_send_default_fee: u64,
+}
+
+#[event]
+pub struct ReplyMessageUpdated {
+    pub reply_message: String,
+}
+
 //NOTE: No Audit Required, Just A Test Dapp
 mod internal {
     use super::*;
@@ -46,62 +101,223 @@ mod internal {
     }
     pub(crate) fn _when_pause(dapp_acc: Account<'_, PingPongAccount>) -> Result<()> {
         if !dapp_acc.pause {
-            return Err(PausableError::Paused.into());
+            return Err(PausableError::UnPaused.into());
         }
         Ok(())
     }
 
-    fn encode_u256(value: u128) -> Vec<u8> {
-        let mut encoded = vec![0u8; 32];
-        encoded.splice(16.., value.to_be_bytes()); // place u64 into the last 8 bytes (big endian)
-        encoded
+    // cuts the message to at most max_len bytes, on a char boundary
+    pub(crate) fn truncate_message(message: &str, max_len: usize) -> &str {
+        let mut end = message.len().min(max_len);
+        while !message.is_char_boundary(end) {
+            end -= 1;
+        }
+        &message[..end]
     }
 
-    fn encode_string(s: &str) -> Vec<u8> {
-        let mut result = vec![];
-        let mut string_data = s.as_bytes().to_vec();
+    ////////////////////////////////////////////////////////////////////////////////////////
+    /// Records
+    ////////////////////////////////////////////////////////////////////////////////////////
 
-        let mut length = vec![0u8; 32];
-        length.splice(24.., (string_data.len() as u64).to_be_bytes()); // length of the string
-        result.extend(length);
+    /// The optional (record, payer) remaining accounts of i_receive and
+    /// i_ack. Without them the request is processed without a record.
+    pub(crate) fn record_accounts<'a, 'info>(
+        remaining_accounts: &'a [AccountInfo<'info>],
+    ) -> Result<Option<(&'a AccountInfo<'info>, &'a AccountInfo<'info>)>> {
+        match remaining_accounts {
+            [] => Ok(None),
+            [record, payer, ..] if record.is_writable && payer.is_writable && payer.is_signer => {
+                Ok(Some((record, payer)))
+            }
+            _ => Err(DappError::InvalidRecordAccount.into()),
+        }
+    }
 
-        let padding = (32 - (string_data.len() % 32)) % 32;
-        string_data.extend(vec![0u8; padding]); // padding with zeros
-        result.extend(string_data);
+    /// Creates the record at the PDA of `seeds`, the rent being paid by the
+    /// payer. An existing record is kept.
+    pub(crate) fn create_record<'info, T: AccountSerialize>(
+        payer: &AccountInfo<'info>,
+        record_account: &AccountInfo<'info>,
+        system_program: &AccountInfo<'info>,
+        seeds: &[&[u8]],
+        record: &T,
+    ) -> Result<()> {
+        let (address, bump) = Pubkey::find_program_address(seeds, &crate::ID);
+        if address != record_account.key() {
+            return Err(DappError::InvalidRecordAccount.into());
+        }
+        if record_account.owner == &crate::ID {
+            return Ok(());
+        }
+        let mut data = vec![];
+        record.try_serialize(&mut data)?;
+
+        let lamports = Rent::get()?
+            .minimum_balance(data.len())
+            .saturating_sub(record_account.lamports());
+        if lamports > 0 {
+            system_program::transfer(
+                CpiContext::new(
+                    system_program.clone(),
+                    system_program::Transfer {
+                        from: payer.clone(),
+                        to: record_account.clone(),
+                    },
+                ),
+                lamports,
+            )?;
+        }
 
-        result
+        let bump = [bump];
+        let mut seeds = seeds.to_vec();
+        seeds.push(&bump);
+        let signer_seeds = [seeds.as_slice()];
+        system_program::allocate(
+            CpiContext::new_with_signer(
+                system_program.clone(),
+                system_program::Allocate {
+                    account_to_allocate: record_account.clone(),
+                },
+                signer_seeds.as_slice(),
+            ),
+            data.len() as u64,
+        )?;
+        system_program::assign(
+            CpiContext::new_with_signer(
+                system_program.clone(),
+                system_program::Assign {
+                    account_to_assign: record_account.clone(),
+                },
+                signer_seeds.as_slice(),
+            ),
+            &crate::ID,
+        )?;
+        record_account.try_borrow_mut_data()?.copy_from_slice(&data);
+        Ok(())
     }
 
+    /// abi.encode(uint64 requestId, string message), the reply of i_receive.
     pub(crate) fn abi_encode_u128_string(value: u128, text: String) -> Vec<u8> {
-        let mut encoded = vec![];
-        encoded.extend(encode_u256(value));
-        let mut string_offset = vec![0u8; 32];
-        string_offset.splice(24.., 64u64.to_be_bytes()); // Offset for the string
-        encoded.extend(string_offset);
-        encoded.extend(encode_string(&text));
-        encoded
+        abi::encode(&[Token::uint(value), Token::String(text)])
     }
 
-    fn decode_u256(data: &[u8]) -> u128 {
-        u128::from_be_bytes(data[16..32].try_into().unwrap())
+    ////////////////////////////////////////////////////////////////////////////////////////
+    /// Ping Payload
+    ////////////////////////////////////////////////////////////////////////////////////////
+
+    // current version of the ping payload shared by the ping-pong dapps
+    pub(crate) const PING_PAYLOAD_VERSION: u8 = 2;
+
+    /// (uint8 version, uint64 requestId, uint64 timestamp, bytes sender,
+    /// string message, bytes body, string recipient), version 1 has no
+    /// recipient. Version 0 stands for the unversioned
+    /// (uint64 requestId, string message[, string recipient]) payloads.
+    #[derive(Debug, PartialEq)]
+    pub(crate) struct PingPayload {
+        pub version: u8,
+        pub request_id: u64,
+        pub timestamp: u64,
+        pub sender: Vec<u8>,
+        pub message: String,
+        pub body: Vec<u8>,
+        pub recipient: String,
     }
 
-    fn decode_string(data: &[u8], offset: usize) -> String {
-        let length_data = &data[offset..offset + 32];
-        let mut buffer = [0u8; 8];
-        buffer.copy_from_slice(&length_data[24..32]); // Extract the last 8 bytes for the string length
-        let string_length = u64::from_be_bytes(buffer) as usize;
-        let string_data = &data[offset + 32..offset + 32 + string_length];
-        String::from_utf8(string_data.to_vec()).expect("Invalid UTF-8 string")
+    impl PingPayload {
+        pub(crate) fn encode(&self) -> Vec<u8> {
+            if self.version == 0 {
+                let mut tokens = vec![
+                    Token::uint(self.request_id as u128),
+                    Token::String(self.message.clone()),
+                ];
+                if !self.recipient.is_empty() {
+                    tokens.push(Token::String(self.recipient.clone()));
+                }
+                return abi::encode(&tokens);
+            }
+            let mut tokens = vec![
+                Token::uint(self.version as u128),
+                Token::uint(self.request_id as u128),
+                Token::uint(self.timestamp as u128),
+                Token::Bytes(self.sender.clone()),
+                Token::String(self.message.clone()),
+                Token::Bytes(self.body.clone()),
+            ];
+            if self.version >= 2 {
+                tokens.push(Token::String(self.recipient.clone()));
+            }
+            abi::encode(&[Token::Tuple(tokens)])
+        }
+
+        pub(crate) fn decode(data: &[u8]) -> Result<Self> {
+            use ParamType::{Bytes, Uint};
+
+            let v1 = vec![Uint, Uint, Uint, Bytes, ParamType::String, Bytes];
+            let mut v2 = v1.clone();
+            v2.push(ParamType::String);
+            for (version, types) in [(2u64, v2), (1u64, v1)] {
+                if let Ok(tokens) = abi::decode_exact(&[ParamType::Tuple(types)], data) {
+                    let mut values = match tokens.into_iter().next() {
+                        Some(Token::Tuple(values)) => values.into_iter(),
+                        _ => return Err(DappError::InvalidPayload.into()),
+                    };
+                    let mut next = || values.next().ok_or(DappError::InvalidPayload);
+                    if next()?.as_u64()? != version {
+                        return Err(DappError::InvalidPayload.into());
+                    }
+                    return Ok(Self {
+                        version: version as u8,
+                        request_id: next()?.as_u64()?,
+                        timestamp: next()?.as_u64()?,
+                        sender: next()?.into_bytes()?,
+                        message: next()?.into_string()?,
+                        body: next()?.into_bytes()?,
+                        recipient: match next() {
+                            Ok(value) => value.into_string()?,
+                            Err(_) => String::new(),
+                        },
+                    });
+                }
+            }
+
+            // the two fields payload is decoded leniently, the way it was
+            // decoded before the payload was versioned
+            let legacy = [Uint, ParamType::String, ParamType::String];
+            let mut values = abi::decode_exact(&legacy, data)
+                .or_else(|_| abi::decode(&legacy[..2], data))?
+                .into_iter();
+            let mut next = || values.next().ok_or(DappError::InvalidPayload);
+            Ok(Self {
+                version: 0,
+                request_id: next()?.as_u64()?,
+                timestamp: 0,
+                sender: vec![],
+                message: next()?.into_string()?,
+                body: vec![],
+                recipient: match next() {
+                    Ok(value) => value.into_string()?,
+                    Err(_) => String::new(),
+                },
+            })
+        }
     }
 
-    pub(crate) fn abi_decode_u128_string(encoded: &[u8]) -> (u128, String) {
-        let decoded_u256 = decode_u256(&encoded[0..32]);
-        let mut buffer = [0u8; 8];
-        buffer.copy_from_slice(&encoded[56..64]); // Get the last 8 bytes for the string offset
-        let string_offset = u64::from_be_bytes(buffer) as usize;
-        let decoded_string = decode_string(&encoded, string_offset);
-        (decoded_u256, decoded_string)
+    /// Decodes the (requestId, message) acked by the destination: the pong
+    /// receipt (uint8 version, uint64 requestId, uint64 srcTimestamp,
+    /// uint64 destBlockHeight, uint64 destBlockTime, string message) of the
+    /// CosmWasm ping-pong, or a ping payload.
+    pub(crate) fn abi_decode_ack_message(data: &[u8]) -> Result<(u64, String)> {
+        use ParamType::Uint;
+
+        let receipt = ParamType::Tuple(vec![Uint, Uint, Uint, Uint, Uint, ParamType::String]);
+        if let Ok(tokens) = abi::decode_exact(&[receipt], data) {
+            if let Some(Token::Tuple(values)) = tokens.into_iter().next() {
+                let request_id = values[1].as_u64()?;
+                return Ok((request_id, values[5].clone().into_string()?));
+            }
+        }
+        let ping = PingPayload::decode(data)?;
+        Ok((ping.request_id, ping.message))
     }
 }
 
@@ -117,12 +333,49 @@ pub struct PingPongAccount {
     pub pause: bool,             //1
     pub i_send_default_fee: u64, //8
     pub owner: Pubkey,           // 32
+    // reply of i_receive, the received message is echoed when empty
+    #[max_len(100)]
+    pub reply_message: String, // 4 + size = 104
+    // owner accepting the ownership, see transfer_ownership
+    pub pending_owner: Option<Pubkey>, // 1 + 32
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RecordStatus {
+    Received,
+    Acked,
+    Failed,
+}
+
+/// Ping received from another chain, at the PDA of
+/// [PING_RECORD, src_chain_id, request_id].
+#[account]
+pub struct PingRecord {
+    pub src_chain_id: String,
+    pub request_id: u64,
+    pub request_sender: String,
+    pub message: String,
+    pub status: RecordStatus,
+    // refunded the rent when the record is closed
+    pub payer: Pubkey,
+}
+
+/// Ack of a ping sent by the dapp, at the PDA of
+/// [ACK_RECORD, request_identifier]. The request id and message are only
+/// known for the requests executed on the destination.
+#[account]
+pub struct AckRecord {
+    pub request_identifier: u128,
+    pub request_id: Option<u64>,
+    pub ack_message: Option<String>,
+    pub status: RecordStatus,
+    // refunded the rent when the record is closed
+    pub payer: Pubkey,
 }
 
 #[program]
 pub mod ping_pong {
     use super::*;
-    use std::str::FromStr;
 
     pub fn initialize(
         ctx: Context<Initialize>,
@@ -131,10 +384,9 @@ pub mod ping_pong {
         i_send_default_fee: u64,
         owner: Pubkey,
     ) -> Result<()> {
+        // only the upgrade authority of the program initializes it, see the
+        // constraints of Initialize
         let dapp_account = &mut ctx.accounts.ping_pong_account;
-        if Pubkey::from_str(AUTHORIZED_DEPLOYER).unwrap_or_default() != ctx.accounts.signer.key() {
-            return Err(DappError::UnAuthorized.into());
-        }
         dapp_account.isend_cnt = 0;
         dapp_account.iack_cnt = 0;
         dapp_account.ireceive_cnt = 0;
@@ -143,6 +395,18 @@ pub mod ping_pong {
         dapp_account.owner = owner;
         dapp_account.pause = false;
         dapp_account.i_send_default_fee = i_send_default_fee;
+        dapp_account.reply_message = String::new();
+        dapp_account.pending_owner = None;
+        Ok(())
+    }
+
+    /// Reallocs the ping pong account initialized by an earlier version of
+    /// the program to the current layout, the payer funding the rent. The
+    /// fields added at the end of the account start zeroed, which is the
+    /// encoding of an empty reply_message and of no pending_owner.
+    pub fn migrate(ctx: Context<Migrate>) -> Result<()> {
+        let data = ctx.accounts.ping_pong_account.try_borrow_data()?;
+        PingPongAccount::try_deserialize(&mut &data[..])?;
         Ok(())
     }
 
@@ -150,10 +414,14 @@ pub mod ping_pong {
     /// Setter
     //////////////////////////////////////////////////////////////////////////////////////////////////////
 
+    // The dapp instructions (i_ping, set_dapp_metadata, i_receive, i_ack) fail
+    // while the dapp is paused, the owner instructions work in both states.
+
     pub fn pause(ctx: Context<Execute>) -> Result<()> {
         let dapp_acc = &mut ctx.accounts.dapp_account;
         _when_not_pause(dapp_acc.clone())?;
         dapp_acc.pause = true;
+        emit!(PauseChanged { paused: true });
         Ok(())
     }
 
@@ -161,13 +429,97 @@ pub mod ping_pong {
         let dapp_acc = &mut ctx.accounts.dapp_account;
         _when_pause(dapp_acc.clone())?;
         dapp_acc.pause = false;
+        emit!(PauseChanged { paused: false });
+        Ok(())
+    }
+
+    /// First step of the ownership transfer, `new_owner` becomes the owner
+    /// with accept_ownership. A new call replaces the pending owner.
+    pub fn transfer_ownership(ctx: Context<Execute>, new_owner: Pubkey) -> Result<()> {
+        let dapp_account: &mut Account<'_, PingPongAccount> = &mut ctx.accounts.dapp_account;
+        dapp_account.pending_owner = Some(new_owner);
+        emit!(OwnershipTransferStarted {
+            owner: dapp_account.owner,
+            pending_owner: new_owner,
+        });
         Ok(())
     }
 
+    pub fn accept_ownership(ctx: Context<AcceptOwnership>) -> Result<()> {
+        let dapp_account: &mut Account<'_, PingPongAccount> = &mut ctx.accounts.dapp_account;
+        let previous_owner = dapp_account.owner;
+        dapp_account.owner = ctx.accounts.signer.key();
+        dapp_account.pending_owner = None;
+        emit!(OwnershipTransferred {
+            previous_owner,
+            new_owner: dapp_account.owner,
+        });
+        Ok(())
+    }
+
+    /// Deprecated, kept for the existing clients: sets the owner in one step,
+    /// without the confirmation of the new owner. Use transfer_ownership and
+    /// accept_ownership.
     pub fn update_owner(ctx: Context<Execute>, new_owner: Pubkey) -> Result<()> {
+        msg!("update_owner is deprecated, use transfer_ownership");
         let dapp_account: &mut Account<'_, PingPongAccount> = &mut ctx.accounts.dapp_account;
-        _when_not_pause(dapp_account.clone())?;
+        let previous_owner = dapp_account.owner;
         dapp_account.owner = new_owner;
+        dapp_account.pending_owner = None;
+        emit!(OwnershipTransferred {
+            previous_owner,
+            new_owner,
+        });
+        Ok(())
+    }
+
+    pub fn set_gateway_authority(ctx: Context<Execute>, gateway_authority: Pubkey) -> Result<()> {
+        let dapp_account: &mut Account<'_, PingPongAccount> = &mut ctx.accounts.dapp_account;
+        let previous_gateway_authority = dapp_account.gateway_authority;
+        dapp_account.gateway_authority = gateway_authority;
+        emit!(GatewayAuthorityUpdated {
+            previous_gateway_authority,
+            gateway_authority,
+        });
+        Ok(())
+    }
+
+    pub fn set_i_send_default_fee(ctx: Context<Execute>, i_send_default_fee: u64) -> Result<()> {
+        let dapp_account: &mut Account<'_, PingPongAccount> = &mut ctx.accounts.dapp_account;
+        let previous_i_send_default_fee = dapp_account.i_send_default_fee;
+        dapp_account.i_send_default_fee = i_send_default_fee;
+        emit!(ISendDefaultFeeUpdated {
+            previous_i_send_default_fee,
+            i_send_default_fee,
+        });
+        Ok(())
+    }
+
+    /// Sets the reply of i_receive, an empty reply echoes the received message.
+    pub fn set_reply_message(ctx: Context<Execute>, reply_message: String) -> Result<()> {
+        let dapp_account: &mut Account<'_, PingPongAccount> = &mut ctx.accounts.dapp_account;
+        if reply_message.len() > MAX_REPLY_MESSAGE_LEN {
+            return Err(DappError::MessageTooLong.into());
+        }
+        dapp_account.reply_message = reply_message.clone();
+        emit!(ReplyMessageUpdated { reply_message });
+        Ok(())
+    }
+
+    /// Closes the record of a received ping, its rent goes back to its payer.
+    pub fn close_ping_record(
+        _ctx: Context<ClosePingRecord>,
+        _src_chain_id: String,
+        _request_id: u64,
+    ) -> Result<()> {
+        Ok(())
+    }
+
+    /// Closes the record of an ack, its rent goes back to its payer.
+    pub fn close_ack_record(
+        _ctx: Context<CloseAckRecord>,
+        _request_identifier: u128,
+    ) -> Result<()> {
         Ok(())
     }
 
@@ -184,7 +536,17 @@ pub mod ping_pong {
         dest_chain_id: String,
         dst_contract: Vec<u8>,
         request_metadata: Vec<u8>,
+        message: String,
+        body: Option<Vec<u8>>,
     ) -> Result<()> {
+        if message.is_empty() {
+            return Err(DappError::EmptyMessage.into());
+        }
+        // the destination replies with the message, it should fit the reply
+        // of a solana dapp
+        if message.len() > MAX_MESSAGE_LEN {
+            return Err(DappError::MessageTooLong.into());
+        }
         let gateway_account = &ctx.accounts.gateway_account;
         let request_packet = &ctx.accounts.request_packet;
         let ping_pong_account = &mut ctx.accounts.ping_pong_account;
@@ -224,11 +586,20 @@ pub mod ping_pong {
         ping_pong_account.isend_cnt += 1;
 
         // initialize request packet account
-        let payload = abi_encode_u128_string(
-            ping_pong_account.isend_cnt,
-            String::from("Hello From Solana"),
-        );
+        let payload = PingPayload {
+            version: PING_PAYLOAD_VERSION,
+            request_id: ping_pong_account.isend_cnt as u64,
+            timestamp: Clock::get()?.unix_timestamp as u64,
+            sender: ctx.accounts.signer.key().to_bytes().to_vec(),
+            message,
+            body: body.unwrap_or_default(),
+            recipient: String::new(),
+        }
+        .encode();
         let len = (4 + dst_contract.len()) + (4 + payload.len());
+        if len > MAX_PACKET_LEN {
+            return Err(DappError::PacketTooLarge.into());
+        }
         let mut output = vec![0u8; len];
         let mut offset = 0;
         output[offset..offset + 4].copy_from_slice(&(dst_contract.len() as u32).to_le_bytes());
@@ -320,52 +691,100 @@ pub mod ping_pong {
         Ok(())
     }
 
-    pub fn i_receive(
-        ctx: Context<DappIReceive>,
-        _request_sender: String,
+    /// The record of the ping is stored when the remaining accounts are the
+    /// record and its payer, see record_accounts.
+    pub fn i_receive<'info>(
+        ctx: Context<'_, '_, '_, 'info, DappIReceive<'info>>,
+        request_sender: String,
         src_chain_id: String,
     ) -> Result<Vec<u8>> {
+        let system_program_info = ctx.accounts.system_program.to_account_info();
         let ping_pong_account = &mut ctx.accounts.ping_pong_account;
+        _when_not_pause(*ping_pong_account.clone())?;
         let packet_account = &ctx.accounts.packet_account.load()?;
-        let (request_id, sample_str) = abi_decode_u128_string(
-            &packet_account.get_packet_slice(0, packet_account.packet_len()),
-        );
+        let ping =
+            PingPayload::decode(&packet_account.get_packet_slice(0, packet_account.packet_len()))?;
+        let (request_id, sample_str) = (ping.request_id as u128, ping.message);
+
+        // echoes the message, like the ping-pong of the other chains, unless
+        // a reply is set
+        let reply = if ping_pong_account.reply_message.is_empty() {
+            truncate_message(&sample_str, MAX_MESSAGE_LEN).to_string()
+        } else {
+            ping_pong_account.reply_message.clone()
+        };
 
         ping_pong_account.ireceive_cnt += 1;
+
+        if let Some((ping_record, payer)) = record_accounts(ctx.remaining_accounts)? {
+            create_record(
+                payer,
+                ping_record,
+                &system_program_info,
+                &[
+                    PING_RECORD,
+                    src_chain_id.as_bytes(),
+                    &ping.request_id.to_le_bytes(),
+                ],
+                &PingRecord {
+                    src_chain_id: src_chain_id.clone(),
+                    request_id: ping.request_id,
+                    request_sender,
+                    message: sample_str.clone(),
+                    status: RecordStatus::Received,
+                    payer: payer.key(),
+                },
+            )?;
+        }
+
         emit_cpi!(PingFromSource {
             src_chain_id,
             request_id,
             sample_str
         });
-        Ok(abi_encode_u128_string(
-            ping_pong_account.ireceive_cnt,
-            format!("Hello From Solana"),
-        ))
+        Ok(abi_encode_u128_string(request_id, reply))
     }
 
-    pub fn i_ack(
-        ctx: Context<DappIAck>,
+    /// The record of the ack is stored when the remaining accounts are the
+    /// record and its payer, see record_accounts.
+    pub fn i_ack<'info>(
+        ctx: Context<'_, '_, '_, 'info, DappIAck<'info>>,
         request_identifier: u128,
         exec_flag: bool,
     ) -> Result<Vec<u8>> {
+        let system_program_info = ctx.accounts.system_program.to_account_info();
         let ping_pong_acount = &mut ctx.accounts.ping_pong_account;
+        _when_not_pause(ping_pong_acount.clone())?;
         let packet_account = ctx.accounts.packet_account.load()?;
-        if !exec_flag {
-            emit_cpi!(AckFromDestination {
-                request_id: None,
-                ack_message: None,
-                request_identifier,
-                exec_flag,
-            });
-            return Ok(vec![]);
+        let (request_id, ack_message, status) = if exec_flag {
+            let (request_id, ack_message) = abi_decode_ack_message(
+                &packet_account.get_packet_slice(0, packet_account.packet_len()),
+            )?;
+            ping_pong_acount.iack_cnt += 1;
+            (Some(request_id), Some(ack_message), RecordStatus::Acked)
+        } else {
+            (None, None, RecordStatus::Failed)
+        };
+
+        if let Some((ack_record, payer)) = record_accounts(ctx.remaining_accounts)? {
+            create_record(
+                payer,
+                ack_record,
+                &system_program_info,
+                &[ACK_RECORD, &request_identifier.to_le_bytes()],
+                &AckRecord {
+                    request_identifier,
+                    request_id,
+                    ack_message: ack_message.clone(),
+                    status,
+                    payer: payer.key(),
+                },
+            )?;
         }
-        let (request_id, ack_message) = abi_decode_u128_string(
-            &packet_account.get_packet_slice(0, packet_account.packet_len()),
-        );
-        ping_pong_acount.iack_cnt += 1;
+
         emit_cpi!(AckFromDestination {
-            request_id: Some(request_id),
-            ack_message: Some(ack_message),
+            request_id: request_id.map(u128::from),
+            ack_message,
             request_identifier,
             exec_flag,
         });
@@ -379,6 +798,37 @@ pub struct Initialize<'info> {
     pub ping_pong_account: Account<'info, PingPongAccount>,
     #[account(mut)]
     pub signer: Signer<'info>,
+    #[account(constraint = program.programdata_address()? == Some(program_data.key()))]
+    pub program: Program<'info, crate::program::PingPong>,
+    #[account(
+        constraint = program_data.upgrade_authority_address == Some(signer.key()) @ DappError::UnAuthorized
+    )]
+    pub program_data: Account<'info, ProgramData>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct Migrate<'info> {
+    /// CHECK: the account of the earlier layout cannot be deserialized before
+    /// the realloc, migrate checks it afterwards
+    #[account(
+        mut,
+        seeds = [PING_PONG_ACCOUNT],
+        bump,
+        owner = crate::ID,
+        realloc = 8 + PingPongAccount::INIT_SPACE,
+        realloc::payer = signer,
+        realloc::zero = true
+    )]
+    pub ping_pong_account: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub signer: Signer<'info>,
+    #[account(constraint = program.programdata_address()? == Some(program_data.key()))]
+    pub program: Program<'info, crate::program::PingPong>,
+    #[account(
+        constraint = program_data.upgrade_authority_address == Some(signer.key()) @ DappError::UnAuthorized
+    )]
+    pub program_data: Account<'info, ProgramData>,
     pub system_program: Program<'info, System>,
 }
 
@@ -386,7 +836,15 @@ pub struct Initialize<'info> {
 pub struct Execute<'info> {
     #[account(mut, seeds = [PING_PONG_ACCOUNT], bump)]
     pub dapp_account: Account<'info, PingPongAccount>,
-    #[account(mut, constraint = signer.key() == dapp_account.owner)]
+    #[account(mut, constraint = signer.key() == dapp_account.owner @ DappError::UnAuthorized)]
+    pub signer: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct AcceptOwnership<'info> {
+    #[account(mut, seeds = [PING_PONG_ACCOUNT], bump)]
+    pub dapp_account: Account<'info, PingPongAccount>,
+    #[account(constraint = dapp_account.pending_owner == Some(signer.key()) @ DappError::UnAuthorized)]
     pub signer: Signer<'info>,
 }
 
@@ -408,6 +866,28 @@ pub enum DappError {
     SolTransferFailed,
     #[msg("InvalidGatewayAccount")]
     InvalidGatewayAccount,
+    #[msg("InvalidPayload")]
+    InvalidPayload,
+    #[msg("EmptyMessage")]
+    EmptyMessage,
+    #[msg("MessageTooLong")]
+    MessageTooLong,
+    #[msg("PacketTooLarge")]
+    PacketTooLarge,
+    #[msg("AbiDataTooShort")]
+    AbiDataTooShort,
+    #[msg("AbiInvalidOffset")]
+    AbiInvalidOffset,
+    #[msg("AbiInvalidBool")]
+    AbiInvalidBool,
+    #[msg("AbiInvalidUtf8")]
+    AbiInvalidUtf8,
+    #[msg("AbiValueOverflow")]
+    AbiValueOverflow,
+    #[msg("AbiNonCanonical")]
+    AbiNonCanonical,
+    #[msg("InvalidRecordAccount")]
+    InvalidRecordAccount,
 }
 
 ////////////////////////////////////////////////////////////////////////////////////
@@ -489,3 +969,43 @@ pub struct DappIReceive<'info> {
     pub gateway_authority: Signer<'info>,
     pub system_program: Program<'info, System>,
 }
+
+#[derive(Accounts)]
+#[instruction(src_chain_id: String, request_id: u64)]
+pub struct ClosePingRecord<'info> {
+    #[account(seeds = [PING_PONG_ACCOUNT], bump)]
+    pub ping_pong_account: Account<'info, PingPongAccount>,
+    #[account(
+        mut,
+        close = payer,
+        has_one = payer,
+        seeds = [PING_RECORD, src_chain_id.as_bytes(), &request_id.to_le_bytes()],
+        bump
+    )]
+    pub ping_record: Account<'info, PingRecord>,
+    /// CHECK: the payer of the record, see has_one
+    #[account(mut)]
+    pub payer: UncheckedAccount<'info>,
+    #[account(constraint = signer.key() == ping_pong_account.owner)]
+    pub signer: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(request_identifier: u128)]
+pub struct CloseAckRecord<'info> {
+    #[account(seeds = [PING_PONG_ACCOUNT], bump)]
+    pub ping_pong_account: Account<'info, PingPongAccount>,
+    #[account(
+        mut,
+        close = payer,
+        has_one = payer,
+        seeds = [ACK_RECORD, &request_identifier.to_le_bytes()],
+        bump
+    )]
+    pub ack_record: Account<'info, AckRecord>,
+    /// CHECK: the payer of the record, see has_one
+    #[account(mut)]
+    pub payer: UncheckedAccount<'info>,
+    #[account(constraint = signer.key() == ping_pong_account.owner)]
+    pub signer: Signer<'info>,
+}
@@ -0,0 +1,615 @@
+use crate::abi::{self, ParamType, Token};
+use crate::internal::{
+    _when_not_pause, abi_decode_ack_message, abi_encode_u128_string, create_record,
+    record_accounts, truncate_message, PingPayload,
+};
+use crate::{
+    ping_pong, AcceptOwnership, AckRecord, DappError, Execute, PausableError, PingPongAccount,
+    RecordStatus, ACK_RECORD, MAX_MESSAGE_LEN, MAX_REPLY_MESSAGE_LEN, PING_PONG_ACCOUNT,
+};
+use anchor_lang::error::Error;
+use anchor_lang::solana_program::{program::MAX_RETURN_DATA, program_error::ProgramError};
+use anchor_lang::{
+    prelude::{Account, AccountInfo, Context, Pubkey},
+    system_program, AccountDeserialize, AccountSerialize, Accounts, AccountsExit, Bumps, Space,
+};
+use ethabi::ethereum_types::U256;
+use std::collections::BTreeSet;
+
+// xorshift64, the fuzz cases are reproducible from the seed
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    fn below(&mut self, n: u64) -> usize {
+        (self.next() % n) as usize
+    }
+
+    fn bytes(&mut self, max_len: u64) -> Vec<u8> {
+        let len = self.below(max_len + 1);
+        (0..len).map(|_| self.next() as u8).collect()
+    }
+}
+
+fn random_type(rng: &mut Rng, depth: u32) -> ParamType {
+    match rng.below(if depth == 0 { 4 } else { 6 }) {
+        0 => ParamType::Uint,
+        1 => ParamType::Bool,
+        2 => ParamType::Bytes,
+        3 => ParamType::String,
+        4 => ParamType::Array(Box::new(random_type(rng, depth - 1))),
+        _ => ParamType::Tuple(
+            (0..1 + rng.below(3))
+                .map(|_| random_type(rng, depth - 1))
+                .collect(),
+        ),
+    }
+}
+
+fn random_token(rng: &mut Rng, param_type: &ParamType) -> Token {
+    match param_type {
+        ParamType::Uint => {
+            let mut word = [0u8; 32];
+            // small values as well as values above u128
+            let start = [0, 16, 24][rng.below(3)];
+            for byte in &mut word[start..] {
+                *byte = rng.next() as u8;
+            }
+            Token::Uint(word)
+        }
+        ParamType::Bool => Token::Bool(rng.below(2) == 1),
+        ParamType::Bytes => Token::Bytes(rng.bytes(70)),
+        ParamType::String => Token::String(
+            (0..rng.below(40))
+                .map(|_| ['a', 'é', '€', '𝄞', ' '][rng.below(5)])
+                .collect(),
+        ),
+        ParamType::Array(inner) => Token::Array(
+            (0..rng.below(4))
+                .map(|_| random_token(rng, inner))
+                .collect(),
+        ),
+        ParamType::Tuple(types) => {
+            Token::Tuple(types.iter().map(|inner| random_token(rng, inner)).collect())
+        }
+    }
+}
+
+fn to_ethabi_type(param_type: &ParamType) -> ethabi::ParamType {
+    match param_type {
+        ParamType::Uint => ethabi::ParamType::Uint(256),
+        ParamType::Bool => ethabi::ParamType::Bool,
+        ParamType::Bytes => ethabi::ParamType::Bytes,
+        ParamType::String => ethabi::ParamType::String,
+        ParamType::Array(inner) => ethabi::ParamType::Array(Box::new(to_ethabi_type(inner))),
+        ParamType::Tuple(types) => {
+            ethabi::ParamType::Tuple(types.iter().map(to_ethabi_type).collect())
+        }
+    }
+}
+
+fn to_ethabi(token: &Token) -> ethabi::Token {
+    match token {
+        Token::Uint(word) => ethabi::Token::Uint(U256::from_big_endian(word)),
+        Token::Bool(value) => ethabi::Token::Bool(*value),
+        Token::Bytes(data) => ethabi::Token::Bytes(data.clone()),
+        Token::String(text) => ethabi::Token::String(text.clone()),
+        Token::Array(tokens) => ethabi::Token::Array(tokens.iter().map(to_ethabi).collect()),
+        Token::Tuple(tokens) => ethabi::Token::Tuple(tokens.iter().map(to_ethabi).collect()),
+    }
+}
+
+#[test]
+fn fuzz_abi_codec_against_ethabi() {
+    let mut rng = Rng(0x2545_f491_4f6c_dd1d);
+    for _ in 0..2000 {
+        let types: Vec<ParamType> = (0..1 + rng.below(4))
+            .map(|_| random_type(&mut rng, 2))
+            .collect();
+        let tokens: Vec<Token> = types.iter().map(|ty| random_token(&mut rng, ty)).collect();
+        let ethabi_tokens: Vec<ethabi::Token> = tokens.iter().map(to_ethabi).collect();
+        let ethabi_types: Vec<ethabi::ParamType> = types.iter().map(to_ethabi_type).collect();
+
+        let encoded = ethabi::encode(&ethabi_tokens);
+        assert_eq!(abi::encode(&tokens), encoded, "{:?}", tokens);
+        assert_eq!(abi::decode(&types, &encoded).unwrap(), tokens);
+        assert_eq!(abi::decode_exact(&types, &encoded).unwrap(), tokens);
+        assert_eq!(
+            ethabi::decode(&ethabi_types, &encoded).unwrap(),
+            ethabi_tokens
+        );
+    }
+}
+
+#[test]
+fn fuzz_abi_decode_malformed_data() {
+    let mut rng = Rng(0x9e37_79b9_7f4a_7c15);
+    for _ in 0..5000 {
+        let types: Vec<ParamType> = (0..1 + rng.below(4))
+            .map(|_| random_type(&mut rng, 2))
+            .collect();
+        let tokens: Vec<Token> = types.iter().map(|ty| random_token(&mut rng, ty)).collect();
+        let mut data = abi::encode(&tokens);
+
+        // flips, truncates or appends bytes of a valid encoding, the decoding
+        // must fail or return values without panicking
+        match rng.below(3) {
+            0 if !data.is_empty() => {
+                let at = rng.below(data.len() as u64);
+                data[at] = rng.next() as u8;
+            }
+            1 => data.truncate(rng.below(data.len() as u64 + 1)),
+            _ => data.extend(rng.bytes(40)),
+        }
+        if let Ok(decoded) = abi::decode(&types, &data) {
+            assert_eq!(decoded.len(), types.len());
+        }
+        if let Ok(decoded) = abi::decode_exact(&types, &data) {
+            assert_eq!(abi::encode(&decoded), data);
+        }
+        let _ = PingPayload::decode(&data);
+        let _ = abi_decode_ack_message(&data);
+    }
+}
+
+#[test]
+fn abi_decode_errors() {
+    let uint_string = [ParamType::Uint, ParamType::String];
+
+    assert!(matches!(
+        abi::decode(&uint_string, &[0u8; 40]),
+        Err(DappError::AbiDataTooShort)
+    ));
+
+    // offset of the string past the data
+    let mut data = abi_encode_u128_string(1, "hello".to_string());
+    data[63] = 0xff;
+    assert!(matches!(
+        abi::decode(&uint_string, &data),
+        Err(DappError::AbiInvalidOffset)
+    ));
+
+    let invalid_utf8 = ethabi::encode(&[
+        ethabi::Token::Uint(U256::from(1)),
+        ethabi::Token::Bytes(vec![0xff, 0xfe]),
+    ]);
+    assert!(matches!(
+        abi::decode(&uint_string, &invalid_utf8),
+        Err(DappError::AbiInvalidUtf8)
+    ));
+
+    // uint256 values are not truncated
+    let large = ethabi::encode(&[ethabi::Token::Uint(U256::MAX)]);
+    let tokens = abi::decode(&[ParamType::Uint], &large).unwrap();
+    assert!(matches!(
+        tokens[0].as_u128(),
+        Err(DappError::AbiValueOverflow)
+    ));
+    assert!(matches!(
+        Token::uint(u64::MAX as u128 + 1).as_u64(),
+        Err(DappError::AbiValueOverflow)
+    ));
+
+    assert!(matches!(
+        abi::decode(&[ParamType::Bool], &[2u8; 32]),
+        Err(DappError::AbiInvalidBool)
+    ));
+
+    // trailing data is only accepted by the lenient decoding
+    let mut padded = abi_encode_u128_string(1, "hello".to_string());
+    padded.extend([0u8; 32]);
+    assert!(abi::decode(&uint_string, &padded).is_ok());
+    assert!(matches!(
+        abi::decode_exact(&uint_string, &padded),
+        Err(DappError::AbiNonCanonical)
+    ));
+}
+
+#[test]
+fn ping_payload_round_trip() {
+    let ping = PingPayload {
+        version: 2,
+        request_id: 7,
+        timestamp: 1_700_000_000,
+        sender: vec![1; 32],
+        message: "Hello From Solana".to_string(),
+        body: vec![1, 2, 3],
+        recipient: "0xC44ce1FE770EdC617d4917159a65386885Dc6619".to_string(),
+    };
+    let encoded = ping.encode();
+    assert_eq!(
+        encoded,
+        ethabi::encode(&[ethabi::Token::Tuple(vec![
+            ethabi::Token::Uint(U256::from(2)),
+            ethabi::Token::Uint(U256::from(7)),
+            ethabi::Token::Uint(U256::from(1_700_000_000u64)),
+            ethabi::Token::Bytes(vec![1; 32]),
+            ethabi::Token::String("Hello From Solana".to_string()),
+            ethabi::Token::Bytes(vec![1, 2, 3]),
+            ethabi::Token::String("0xC44ce1FE770EdC617d4917159a65386885Dc6619".to_string()),
+        ])])
+    );
+    assert_eq!(PingPayload::decode(&encoded).unwrap(), ping);
+    assert_eq!(
+        abi_decode_ack_message(&encoded).unwrap(),
+        (7, "Hello From Solana".to_string())
+    );
+
+    // unversioned (uint64 requestId, string message) payload
+    let legacy = ethabi::encode(&[
+        ethabi::Token::Uint(U256::from(3)),
+        ethabi::Token::String("hello".to_string()),
+    ]);
+    let decoded = PingPayload::decode(&legacy).unwrap();
+    assert_eq!((decoded.version, decoded.request_id), (0, 3));
+    assert_eq!(decoded.message, "hello");
+    assert_eq!(decoded.encode(), legacy);
+}
+
+// accounts of the instruction tests, leaked so that they live as long as the
+// accounts deserialized from them
+fn account_info(
+    key: Pubkey,
+    is_signer: bool,
+    owner: Pubkey,
+    data: Vec<u8>,
+) -> AccountInfo<'static> {
+    AccountInfo::new(
+        Box::leak(Box::new(key)),
+        is_signer,
+        true,
+        Box::leak(Box::new(1_000_000_000)),
+        Box::leak(data.into_boxed_slice()),
+        Box::leak(Box::new(owner)),
+        false,
+        0,
+    )
+}
+
+fn signer(key: Pubkey) -> AccountInfo<'static> {
+    account_info(key, true, system_program::ID, vec![])
+}
+
+fn ping_pong_account(owner: Pubkey) -> AccountInfo<'static> {
+    let mut data = vec![];
+    PingPongAccount {
+        chain_id: "solana-devnet".to_string(),
+        owner,
+        ..Default::default()
+    }
+    .try_serialize(&mut data)
+    .unwrap();
+    data.resize(8 + PingPongAccount::INIT_SPACE, 0);
+    let (address, _) = Pubkey::find_program_address(&[PING_PONG_ACCOUNT], &crate::ID);
+    account_info(address, false, crate::ID, data)
+}
+
+fn state(account: &AccountInfo) -> PingPongAccount {
+    let data = account.try_borrow_data().unwrap();
+    PingPongAccount::try_deserialize(&mut &data[..]).unwrap()
+}
+
+// validates the accounts, runs the instruction and writes the accounts back,
+// like the entrypoint of the program
+fn run<T>(
+    accounts: &[AccountInfo<'static>],
+    instruction: impl FnOnce(Context<'_, '_, '_, 'static, T>) -> anchor_lang::Result<()>,
+) -> anchor_lang::Result<()>
+where
+    T: Bumps + Accounts<'static, <T as Bumps>::Bumps> + AccountsExit<'static>,
+    <T as Bumps>::Bumps: Default,
+{
+    let mut infos: &'static [AccountInfo<'static>] =
+        Box::leak(accounts.to_vec().into_boxed_slice());
+    let mut bumps = <T as Bumps>::Bumps::default();
+    let mut accounts = T::try_accounts(
+        &crate::ID,
+        &mut infos,
+        &[],
+        &mut bumps,
+        &mut BTreeSet::new(),
+    )?;
+    instruction(Context::new(&crate::ID, &mut accounts, &[], bumps))?;
+    accounts.exit(&crate::ID)
+}
+
+fn assert_error(result: anchor_lang::Result<()>, expected: impl Into<Error>) {
+    assert_eq!(
+        ProgramError::from(result.unwrap_err()),
+        ProgramError::from(expected.into())
+    );
+}
+
+#[test]
+fn set_reply_message() {
+    let owner = Pubkey::new_unique();
+    let dapp = ping_pong_account(owner);
+
+    run::<Execute>(&[dapp.clone(), signer(owner)], |ctx| {
+        ping_pong::set_reply_message(ctx, "Pong From Solana".to_string())
+    })
+    .unwrap();
+    assert_eq!(state(&dapp).reply_message, "Pong From Solana");
+
+    // the longest reply fits the account
+    let longest = "€".repeat(MAX_REPLY_MESSAGE_LEN / 3) + "a";
+    assert_eq!(longest.len(), MAX_REPLY_MESSAGE_LEN);
+    run::<Execute>(&[dapp.clone(), signer(owner)], |ctx| {
+        ping_pong::set_reply_message(ctx, longest.clone())
+    })
+    .unwrap();
+    assert_eq!(state(&dapp).reply_message, longest);
+
+    assert_error(
+        run::<Execute>(&[dapp.clone(), signer(owner)], |ctx| {
+            ping_pong::set_reply_message(ctx, "a".repeat(MAX_REPLY_MESSAGE_LEN + 1))
+        }),
+        DappError::MessageTooLong,
+    );
+    assert_error(
+        run::<Execute>(&[dapp.clone(), signer(Pubkey::new_unique())], |ctx| {
+            ping_pong::set_reply_message(ctx, "pong".to_string())
+        }),
+        DappError::UnAuthorized,
+    );
+    assert_eq!(state(&dapp).reply_message, longest);
+
+    // an empty reply restores the echo
+    run::<Execute>(&[dapp.clone(), signer(owner)], |ctx| {
+        ping_pong::set_reply_message(ctx, String::new())
+    })
+    .unwrap();
+    assert_eq!(state(&dapp).reply_message, "");
+}
+
+#[test]
+fn reply_fits_the_return_data() {
+    let reply = abi_encode_u128_string(u64::MAX as u128, "a".repeat(MAX_MESSAGE_LEN));
+    assert_eq!(reply.len(), MAX_RETURN_DATA);
+
+    // the echoed messages are cut on a char boundary
+    let message = "a".repeat(MAX_MESSAGE_LEN + 1);
+    assert_eq!(
+        truncate_message(&message, MAX_MESSAGE_LEN).len(),
+        MAX_MESSAGE_LEN
+    );
+    let message = "€".repeat(MAX_MESSAGE_LEN);
+    let truncated = truncate_message(&message, MAX_MESSAGE_LEN);
+    assert_eq!(truncated.len(), MAX_MESSAGE_LEN / 3 * 3);
+    assert!(truncated.chars().all(|c| c == '€'));
+    assert_eq!(truncate_message("pong", MAX_MESSAGE_LEN), "pong");
+}
+
+#[test]
+fn migrated_account_layout() {
+    let account = PingPongAccount {
+        isend_cnt: 3,
+        ireceive_cnt: 2,
+        iack_cnt: 1,
+        chain_id: "c".repeat(50),
+        owner: Pubkey::new_unique(),
+        ..Default::default()
+    };
+    let mut data = vec![];
+    account.try_serialize(&mut data).unwrap();
+    // the account of the earlier layout, without reply_message and
+    // pending_owner, with the longest chain id
+    data.truncate(data.len() - 5);
+    assert_eq!(
+        data.len(),
+        8 + PingPongAccount::INIT_SPACE - (4 + 100) - (1 + 32)
+    );
+    assert!(PingPongAccount::try_deserialize(&mut &data[..]).is_err());
+
+    // migrate reallocs it with zeroed bytes
+    data.resize(8 + PingPongAccount::INIT_SPACE, 0);
+    let migrated = PingPongAccount::try_deserialize(&mut &data[..]).unwrap();
+    assert_eq!(
+        (migrated.isend_cnt, migrated.ireceive_cnt, migrated.iack_cnt),
+        (3, 2, 1)
+    );
+    assert_eq!(migrated.chain_id, account.chain_id);
+    assert_eq!(migrated.owner, account.owner);
+    assert_eq!(migrated.reply_message, "");
+    assert_eq!(migrated.pending_owner, None);
+}
+
+#[test]
+fn pause_and_unpause() {
+    let owner = Pubkey::new_unique();
+    let dapp = ping_pong_account(owner);
+
+    assert_error(
+        run::<Execute>(&[dapp.clone(), signer(owner)], ping_pong::unpause),
+        PausableError::UnPaused,
+    );
+    assert_error(
+        run::<Execute>(
+            &[dapp.clone(), signer(Pubkey::new_unique())],
+            ping_pong::pause,
+        ),
+        DappError::UnAuthorized,
+    );
+    run::<Execute>(&[dapp.clone(), signer(owner)], ping_pong::pause).unwrap();
+    assert!(state(&dapp).pause);
+    assert_error(
+        run::<Execute>(&[dapp.clone(), signer(owner)], ping_pong::pause),
+        PausableError::Paused,
+    );
+
+    // the dapp instructions fail while paused, the owner instructions work
+    assert_error(
+        _when_not_pause(Account::try_from(&dapp).unwrap()),
+        PausableError::Paused,
+    );
+    run::<Execute>(&[dapp.clone(), signer(owner)], |ctx| {
+        ping_pong::set_i_send_default_fee(ctx, 10)
+    })
+    .unwrap();
+    run::<Execute>(&[dapp.clone(), signer(owner)], |ctx| {
+        ping_pong::set_reply_message(ctx, "pong".to_string())
+    })
+    .unwrap();
+    assert_eq!(state(&dapp).i_send_default_fee, 10);
+    assert_eq!(state(&dapp).reply_message, "pong");
+
+    run::<Execute>(&[dapp.clone(), signer(owner)], ping_pong::unpause).unwrap();
+    assert!(!state(&dapp).pause);
+    assert!(_when_not_pause(Account::try_from(&dapp).unwrap()).is_ok());
+}
+
+#[test]
+fn two_step_ownership_transfer() {
+    let owner = Pubkey::new_unique();
+    let new_owner = Pubkey::new_unique();
+    let dapp = ping_pong_account(owner);
+
+    assert_error(
+        run::<Execute>(&[dapp.clone(), signer(new_owner)], |ctx| {
+            ping_pong::transfer_ownership(ctx, new_owner)
+        }),
+        DappError::UnAuthorized,
+    );
+    // nothing to accept before the transfer
+    assert_error(
+        run::<AcceptOwnership>(
+            &[dapp.clone(), signer(new_owner)],
+            ping_pong::accept_ownership,
+        ),
+        DappError::UnAuthorized,
+    );
+
+    // a new transfer replaces the pending owner
+    run::<Execute>(&[dapp.clone(), signer(owner)], |ctx| {
+        ping_pong::transfer_ownership(ctx, Pubkey::new_unique())
+    })
+    .unwrap();
+    run::<Execute>(&[dapp.clone(), signer(owner)], |ctx| {
+        ping_pong::transfer_ownership(ctx, new_owner)
+    })
+    .unwrap();
+    assert_eq!(state(&dapp).owner, owner);
+    assert_eq!(state(&dapp).pending_owner, Some(new_owner));
+
+    assert_error(
+        run::<AcceptOwnership>(
+            &[dapp.clone(), signer(Pubkey::new_unique())],
+            ping_pong::accept_ownership,
+        ),
+        DappError::UnAuthorized,
+    );
+    run::<AcceptOwnership>(
+        &[dapp.clone(), signer(new_owner)],
+        ping_pong::accept_ownership,
+    )
+    .unwrap();
+    assert_eq!(state(&dapp).owner, new_owner);
+    assert_eq!(state(&dapp).pending_owner, None);
+
+    // the previous owner lost the owner instructions
+    assert_error(
+        run::<Execute>(&[dapp.clone(), signer(owner)], ping_pong::pause),
+        DappError::UnAuthorized,
+    );
+    run::<Execute>(&[dapp.clone(), signer(new_owner)], ping_pong::pause).unwrap();
+}
+
+#[test]
+fn deprecated_update_owner() {
+    let owner = Pubkey::new_unique();
+    let new_owner = Pubkey::new_unique();
+    let dapp = ping_pong_account(owner);
+
+    run::<Execute>(&[dapp.clone(), signer(owner)], |ctx| {
+        ping_pong::transfer_ownership(ctx, Pubkey::new_unique())
+    })
+    .unwrap();
+    run::<Execute>(&[dapp.clone(), signer(owner)], |ctx| {
+        ping_pong::update_owner(ctx, new_owner)
+    })
+    .unwrap();
+    // the owner is set in one step and the pending transfer is cancelled
+    assert_eq!(state(&dapp).owner, new_owner);
+    assert_eq!(state(&dapp).pending_owner, None);
+
+    assert_error(
+        run::<Execute>(&[dapp.clone(), signer(owner)], |ctx| {
+            ping_pong::update_owner(ctx, owner)
+        }),
+        DappError::UnAuthorized,
+    );
+}
+
+#[test]
+fn record_remaining_accounts() {
+    let record = account_info(Pubkey::new_unique(), false, system_program::ID, vec![]);
+    let payer = signer(Pubkey::new_unique());
+
+    // the requests are processed without a record when the accounts are missing
+    assert!(record_accounts(&[]).unwrap().is_none());
+    let (record_account, payer_account) = record_accounts(&[record.clone(), payer.clone()])
+        .unwrap()
+        .unwrap();
+    assert_eq!(record_account.key, record.key);
+    assert_eq!(payer_account.key, payer.key);
+
+    // the payer signs
+    let not_signer = account_info(Pubkey::new_unique(), false, system_program::ID, vec![]);
+    for accounts in [
+        vec![record.clone()],
+        vec![record.clone(), not_signer],
+        vec![payer.clone(), record.clone()],
+    ] {
+        assert_error(
+            record_accounts(&accounts).map(|_| ()),
+            DappError::InvalidRecordAccount,
+        );
+    }
+}
+
+#[test]
+fn create_record_checks_the_address() {
+    let request_identifier = 7u128.to_le_bytes();
+    let seeds: &[&[u8]] = &[ACK_RECORD, &request_identifier];
+    let payer = signer(Pubkey::new_unique());
+    let system_program_account = signer(system_program::ID);
+    let record = AckRecord {
+        request_identifier: 7,
+        request_id: None,
+        ack_message: None,
+        status: RecordStatus::Failed,
+        payer: *payer.key,
+    };
+
+    let wrong_record = account_info(Pubkey::new_unique(), false, system_program::ID, vec![]);
+    assert_error(
+        create_record(
+            &payer,
+            &wrong_record,
+            &system_program_account,
+            seeds,
+            &record,
+        ),
+        DappError::InvalidRecordAccount,
+    );
+
+    // an existing record is kept, the payer is not charged
+    let (address, _) = Pubkey::find_program_address(seeds, &crate::ID);
+    let mut data = vec![];
+    AckRecord {
+        status: RecordStatus::Acked,
+        ..record.clone()
+    }
+    .try_serialize(&mut data)
+    .unwrap();
+    let existing = account_info(address, false, crate::ID, data.clone());
+    create_record(&payer, &existing, &system_program_account, seeds, &record).unwrap();
+    assert_eq!(existing.try_borrow_data().unwrap()[..], data[..]);
+    assert_eq!(payer.lamports(), 1_000_000_000);
+}
@@ -3,9 +3,11 @@ import { getClustorConfig, parseCommandLineArgs } from "./utils";
 import {
   ping,
   initialize,
+  migrate,
   setDappMetadata,
   getDstContract,
   approveFeePayer,
+  setReplyMessage,
 } from "./ping-pong.utils";
 import { Keypair, PublicKey } from "@solana/web3.js";
 import fse from "fs-extra";
@@ -67,6 +69,10 @@ export async function ifn() {
       case "initialize":
         await initialize(provider, pingPongInstance, deployer, args.split(","));
         break;
+      //NOTE: ts-node ./scripts/ping-pong.ts --type "migrate" --net solana-devnet --program_id 7dQqaHQFRBC8AhaRzqtQWLEM7fXxwGw9VEKwLpyf8rM3
+      case "migrate":
+        await migrate(provider, pingPongInstance, deployer, []);
+        break;
       //NOTE: ts-node ./scripts/ping-pong.ts --type "set_dapp_metadata" --net solana-devnet --program_id 7dQqaHQFRBC8AhaRzqtQWLEM7fXxwGw9VEKwLpyf8rM3  --args "Aak2MJfJAhFk3vmg2LG97hmNa3TUtKzn4kM7FgWYLw5F,0x4E27128CdEF7a3CFFdF800BE3Be6EE74639CB639"
       case "set_dapp_metadata":
         await setDappMetadata(
@@ -85,7 +91,16 @@ export async function ifn() {
           args.split(",")
         );
         break;
-      //NOTE: ts-node ./scripts/ping-pong.ts --type "ping" --net solana-devnet --program_id 7dQqaHQFRBC8AhaRzqtQWLEM7fXxwGw9VEKwLpyf8rM3 --args "Aak2MJfJAhFk3vmg2LG97hmNa3TUtKzn4kM7FgWYLw5F,43113,0xC44ce1FE770EdC617d4917159a65386885Dc6619"
+      //NOTE: ts-node ./scripts/ping-pong.ts --type "set_reply_message" --net solana-devnet --program_id 7dQqaHQFRBC8AhaRzqtQWLEM7fXxwGw9VEKwLpyf8rM3 --args "Aak2MJfJAhFk3vmg2LG97hmNa3TUtKzn4kM7FgWYLw5F,Pong From Solana"
+      case "set_reply_message":
+        await setReplyMessage(
+          provider,
+          pingPongInstance,
+          deployer,
+          args.split(",")
+        );
+        break;
+      //NOTE: ts-node ./scripts/ping-pong.ts --type "ping" --net solana-devnet --program_id 7dQqaHQFRBC8AhaRzqtQWLEM7fXxwGw9VEKwLpyf8rM3 --args "Aak2MJfJAhFk3vmg2LG97hmNa3TUtKzn4kM7FgWYLw5F,43113,0xC44ce1FE770EdC617d4917159a65386885Dc6619,Hello From Solana"
       case "ping":
         await ping(provider, pingPongInstance, deployer, args.split(","));
         break;
@@ -29,6 +29,10 @@ export async function initialize(
     pingPongInstance.programId,
     gatewayProgramId
   );
+  const [programData] = anchor.web3.PublicKey.findProgramAddressSync(
+    [pingPongInstance.programId.toBuffer()],
+    anchor.web3.BPF_LOADER_UPGRADEABLE_PROGRAM_ID
+  );
   const instruction = await pingPongInstance.methods
     .initialize(
       args[0],
@@ -39,6 +43,9 @@ export async function initialize(
     .accounts({
       pingPongAccount: pingPongPdas.pingPongAccount.account,
       signer: signer.publicKey,
+      // the signer must be the upgrade authority of the program
+      program: pingPongInstance.programId,
+      programData: programData,
       systemProgram: anchor.web3.SystemProgram.programId,
     })
     .instruction();
@@ -52,6 +59,39 @@ export async function initialize(
   console.log("Initialize] Sig: ", sig);
 }
 
+export async function migrate(
+  provider: Provider,
+  pingPongInstance: anchor.Program<PingPong>,
+  signer: anchor.web3.Keypair,
+  args: string[]
+) {
+  const pingPongPdas = getPingPongPdas(pingPongInstance.programId);
+  const [programData] = anchor.web3.PublicKey.findProgramAddressSync(
+    [pingPongInstance.programId.toBuffer()],
+    anchor.web3.BPF_LOADER_UPGRADEABLE_PROGRAM_ID
+  );
+  const instruction = await pingPongInstance.methods
+    .migrate()
+    .accounts({
+      pingPongAccount: pingPongPdas.pingPongAccount.account,
+      // the signer must be the upgrade authority of the program, it pays
+      // the rent of the reallocated account
+      signer: signer.publicKey,
+      program: pingPongInstance.programId,
+      programData: programData,
+      systemProgram: anchor.web3.SystemProgram.programId,
+    })
+    .instruction();
+
+  const sig = await anchor.web3.sendAndConfirmTransaction(
+    provider.connection,
+    new anchor.web3.Transaction().add(instruction),
+    [signer],
+    { "commitment": "confirmed" }
+  );
+  console.log("Migrate] Sig: ", sig);
+}
+
 export async function setDappMetadata(
   provider: Provider,
   pingPongInstance: anchor.Program<PingPong>,
@@ -86,6 +126,34 @@ export async function setDappMetadata(
   console.log("SetMetadata] Sig: ", sig);
 }
 
+export async function setReplyMessage(
+  provider: Provider,
+  pingPongInstance: anchor.Program<PingPong>,
+  signer: anchor.web3.Keypair,
+  args: string[]
+) {
+  const gatewayProgramId = new anchor.web3.PublicKey(args[0]);
+  const pingPongPdas = getPingPongPdas(
+    pingPongInstance.programId,
+    gatewayProgramId
+  );
+  const instruction = await pingPongInstance.methods
+    .setReplyMessage(args.length > 1 ? args[1] : "")
+    .accounts({
+      dappAccount: pingPongPdas.pingPongAccount.account,
+      signer: signer.publicKey,
+    })
+    .instruction();
+
+  const sig = await anchor.web3.sendAndConfirmTransaction(
+    provider.connection,
+    new anchor.web3.Transaction().add(instruction),
+    [signer],
+    { "commitment": "confirmed" }
+  );
+  console.log("SetReplyMessage] Sig: ", sig);
+}
+
 export async function ping(
   provider: Provider,
   pingPongInstance: anchor.Program<PingPong>,
@@ -95,6 +163,7 @@ export async function ping(
   const gatewayProgramId = new anchor.web3.PublicKey(args[0]);
   const dstChainId = args[1];
   const dstContract = args[2];
+  const message = args.length > 3 ? args[3] : "Hello From Solana";
 
   const pdas = getGatewayPdas(gatewayProgramId);
   const pingPongPdas = getPingPongPdas(
@@ -119,7 +188,9 @@ export async function ping(
       "", // route_recipient
       dstChainId,
       Buffer.from(ethers.toUtf8Bytes(dstContract)),
-      Buffer.from(requestMetadata)
+      Buffer.from(requestMetadata),
+      message,
+      null // body
     )
     .accounts({
       pingPongAccount: pingPongPdas.pingPongAccount.account,
@@ -1 +1,201 @@
-describe("test", () => {});
+import * as anchor from "@coral-xyz/anchor";
+import { expect } from "chai";
+import { PingPong } from "../target/types/ping_pong";
+
+// The instructions of the gateway (i_ping, set_dapp_metadata, i_receive,
+// i_ack) need the gateway program, these tests cover the administration of
+// the dapp. The records and the abi codec are tested by `cargo test`.
+describe("ping-pong", () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+  const program = anchor.workspace.PingPong as anchor.Program<PingPong>;
+
+  const [pingPongAccount] = anchor.web3.PublicKey.findProgramAddressSync(
+    [anchor.utils.bytes.utf8.encode("ping_pong")],
+    program.programId
+  );
+  const [programData] = anchor.web3.PublicKey.findProgramAddressSync(
+    [program.programId.toBuffer()],
+    anchor.web3.BPF_LOADER_UPGRADEABLE_PROGRAM_ID
+  );
+  const owner = anchor.web3.Keypair.generate();
+  const newOwner = anchor.web3.Keypair.generate();
+
+  async function expectError(promise: Promise<unknown>, code: string) {
+    try {
+      await promise;
+    } catch (err) {
+      expect(err).to.be.instanceOf(anchor.AnchorError);
+      expect((err as anchor.AnchorError).error.errorCode.code).to.equal(code);
+      return;
+    }
+    expect.fail(`expected ${code}`);
+  }
+
+  function execute(signer: anchor.web3.Keypair) {
+    return { dappAccount: pingPongAccount, signer: signer.publicKey };
+  }
+
+  it("is initialized by the upgrade authority", async () => {
+    const stranger = anchor.web3.Keypair.generate();
+    await provider.connection.confirmTransaction(
+      await provider.connection.requestAirdrop(
+        stranger.publicKey,
+        anchor.web3.LAMPORTS_PER_SOL
+      )
+    );
+    const initialize = (signer: anchor.web3.Keypair | null) =>
+      program.methods
+        .initialize(
+          "solana-localnet",
+          anchor.web3.Keypair.generate().publicKey,
+          new anchor.BN(0),
+          owner.publicKey
+        )
+        .accounts({
+          pingPongAccount,
+          signer: signer ? signer.publicKey : provider.wallet.publicKey,
+          program: program.programId,
+          programData,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        })
+        .signers(signer ? [signer] : []);
+
+    await expectError(initialize(stranger).rpc(), "UnAuthorized");
+    await initialize(null).rpc();
+
+    const account = await program.account.pingPongAccount.fetch(
+      pingPongAccount
+    );
+    expect(account.owner.equals(owner.publicKey)).to.be.true;
+    expect(account.replyMessage).to.equal("");
+    expect(account.pendingOwner).to.be.null;
+  });
+
+  it("migrates an account of the current layout without changes", async () => {
+    const before = await provider.connection.getAccountInfo(pingPongAccount);
+    await program.methods
+      .migrate()
+      .accounts({
+        pingPongAccount,
+        signer: provider.wallet.publicKey,
+        program: program.programId,
+        programData,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .rpc();
+    const after = await provider.connection.getAccountInfo(pingPongAccount);
+    expect(after.data.equals(before.data)).to.be.true;
+    expect(after.lamports).to.equal(before.lamports);
+  });
+
+  it("pauses and unpauses", async () => {
+    await expectError(
+      program.methods.unpause().accounts(execute(owner)).signers([owner]).rpc(),
+      "UnPaused"
+    );
+    await expectError(
+      program.methods
+        .pause()
+        .accounts(execute(newOwner))
+        .signers([newOwner])
+        .rpc(),
+      "UnAuthorized"
+    );
+    await program.methods
+      .pause()
+      .accounts(execute(owner))
+      .signers([owner])
+      .rpc();
+    await expectError(
+      program.methods.pause().accounts(execute(owner)).signers([owner]).rpc(),
+      "Paused"
+    );
+    // the owner instructions work while paused
+    await program.methods
+      .setISendDefaultFee(new anchor.BN(10))
+      .accounts(execute(owner))
+      .signers([owner])
+      .rpc();
+    await program.methods
+      .unpause()
+      .accounts(execute(owner))
+      .signers([owner])
+      .rpc();
+
+    const account = await program.account.pingPongAccount.fetch(
+      pingPongAccount
+    );
+    expect(account.pause).to.be.false;
+    expect(account.iSendDefaultFee.toNumber()).to.equal(10);
+  });
+
+  it("sets the reply message", async () => {
+    await program.methods
+      .setReplyMessage("a".repeat(100))
+      .accounts(execute(owner))
+      .signers([owner])
+      .rpc();
+    await expectError(
+      program.methods
+        .setReplyMessage("a".repeat(101))
+        .accounts(execute(owner))
+        .signers([owner])
+        .rpc(),
+      "MessageTooLong"
+    );
+    let account = await program.account.pingPongAccount.fetch(pingPongAccount);
+    expect(account.replyMessage).to.equal("a".repeat(100));
+
+    await program.methods
+      .setReplyMessage("")
+      .accounts(execute(owner))
+      .signers([owner])
+      .rpc();
+    account = await program.account.pingPongAccount.fetch(pingPongAccount);
+    expect(account.replyMessage).to.equal("");
+  });
+
+  it("transfers the ownership in two steps", async () => {
+    const acceptOwnership = (signer: anchor.web3.Keypair) =>
+      program.methods
+        .acceptOwnership()
+        .accounts({ dappAccount: pingPongAccount, signer: signer.publicKey })
+        .signers([signer])
+        .rpc();
+
+    await expectError(acceptOwnership(newOwner), "UnAuthorized");
+    await program.methods
+      .transferOwnership(newOwner.publicKey)
+      .accounts(execute(owner))
+      .signers([owner])
+      .rpc();
+    await expectError(
+      acceptOwnership(anchor.web3.Keypair.generate()),
+      "UnAuthorized"
+    );
+    await acceptOwnership(newOwner);
+
+    const account = await program.account.pingPongAccount.fetch(
+      pingPongAccount
+    );
+    expect(account.owner.equals(newOwner.publicKey)).to.be.true;
+    expect(account.pendingOwner).to.be.null;
+    await expectError(
+      program.methods.pause().accounts(execute(owner)).signers([owner]).rpc(),
+      "UnAuthorized"
+    );
+  });
+
+  it("keeps the deprecated update_owner", async () => {
+    await program.methods
+      .updateOwner(owner.publicKey)
+      .accounts(execute(newOwner))
+      .signers([newOwner])
+      .rpc();
+    const account = await program.account.pingPongAccount.fetch(
+      pingPongAccount
+    );
+    expect(account.owner.equals(owner.publicKey)).to.be.true;
+  });
+});
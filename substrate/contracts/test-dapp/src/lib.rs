@@ -1,6 +1,7 @@
 #![cfg_attr(not(feature = "std"), no_std, no_main)]
 
 mod cpi;
+pub mod payload;
 mod types;
 
 #[ink::contract]
@@ -8,12 +9,11 @@ pub mod test_dapp {
     use crate::cpi::_call_i_send;
     use crate::cpi::_call_set_dapp_metadata;
     use crate::cpi::{_approve, _transfer_from};
+    use crate::payload::{decode_ack_message, decode_ping, PingMessage};
     use crate::types::types::Result;
     use crate::types::TestError;
     use crate::types::{Bytes, _get_utf8_bytes_to_string};
-    use ethabi::decode;
     use ethabi::encode;
-    use ethabi::ParamType;
     use ethabi::Token;
     use ink::prelude::string::String;
     use ink::prelude::vec;
@@ -70,12 +70,11 @@ pub mod test_dapp {
                 Token::String(_get_utf8_bytes_to_string(&dst_contract)),
                 Token::Bytes(payload.clone()),
             ]);
-            let decoded_data = decode(&[ParamType::Uint(64), ParamType::String], &payload);
-            if decoded_data.is_err() {
+            let ping: Option<PingMessage> = decode_ping(&payload);
+            if ping.is_none() {
                 return Err(TestError::DecodeError);
             }
-            let decoded_data = decoded_data.unwrap();
-            if decoded_data[1].clone().into_string().unwrap() == String::from("") {
+            if ping.unwrap().message == String::from("") {
                 return Err(TestError::EmptyGreeting);
             }
             if route_amount > 0 {
@@ -111,13 +110,13 @@ pub mod test_dapp {
                 panic!("Only Gateway");
             }
             let src_chain_id = _get_utf8_bytes_to_string(&src_chain_id);
-            let decoded_data = decode(&[ParamType::Uint(64), ParamType::String], &packet);
-            if decoded_data.is_err() {
+            let ping: Option<PingMessage> = decode_ping(&packet);
+            if ping.is_none() {
                 panic!("Decode Error");
             }
-            let decoded_data = decoded_data.unwrap();
-            let nonce = decoded_data[0].clone().into_uint().unwrap().as_u64();
-            let greeting = decoded_data[1].clone().into_string().unwrap();
+            let ping = ping.unwrap();
+            let nonce = ping.request_id;
+            let greeting = ping.message;
             if greeting == String::from("Fail Dest Req") {
                 panic!("Greeting == Fail Dest Req");
             }
@@ -137,12 +136,12 @@ pub mod test_dapp {
                 panic!("Only Gateway");
             }
             if exec_flag {
-                let decoded_data = decode(&[ParamType::Uint(64), ParamType::String], &exec_data);
-                if decoded_data.is_err() {
+                let decoded_data = decode_ack_message(&exec_data);
+                if decoded_data.is_none() {
                     panic!("Decode Error");
                 }
-                let decoded_data = decoded_data.unwrap();
-                if decoded_data[1].clone().into_string().unwrap() == String::from("Fail Ack Req") {
+                let (_, ack_message) = decoded_data.unwrap();
+                if ack_message == String::from("Fail Ack Req") {
                     panic!("Greeting == Fail Ack Req");
                 }
             }
@@ -0,0 +1,105 @@
+use ethabi::{decode, encode, ParamType, Token};
+use ink::prelude::string::String;
+use ink::prelude::vec;
+use ink::prelude::vec::Vec as InkVec;
+
+/// Fields of a ping payload read by the dapp. The payload is either the
+/// versioned `(uint8 version, uint64 requestId, uint64 timestamp, bytes
+/// sender, string message, bytes body[, string recipient])` tuple shared by
+/// the ping-pong dapps, or the unversioned `(uint64 requestId, string
+/// message[, string recipient])` payload reported with version 0.
+pub struct PingMessage {
+    pub version: u8,
+    pub request_id: u64,
+    pub message: String,
+    pub recipient: String,
+}
+
+pub fn decode_ping(data: &[u8]) -> Option<PingMessage> {
+    for version in [2u8, 1u8] {
+        if let Some(tokens) = decode_exact(&[ParamType::Tuple(versioned_params(version))], data) {
+            let tuple: InkVec<Token> = tokens[0].clone().into_tuple()?;
+            if token_to_u64(&tuple[0])? != version as u64 {
+                return None;
+            }
+            return Some(PingMessage {
+                version,
+                request_id: token_to_u64(&tuple[1])?,
+                message: tuple[4].clone().into_string()?,
+                recipient: match tuple.get(6) {
+                    Some(token) => token.clone().into_string()?,
+                    None => String::new(),
+                },
+            });
+        }
+    }
+
+    // the two fields payload is decoded leniently, the way it was decoded
+    // before the payload was versioned
+    let tokens: InkVec<Token> = decode_exact(
+        &[ParamType::Uint(64), ParamType::String, ParamType::String],
+        data,
+    )
+    .or_else(|| decode(&[ParamType::Uint(64), ParamType::String], data).ok())?;
+    Some(PingMessage {
+        version: 0,
+        request_id: token_to_u64(&tokens[0])?,
+        message: tokens[1].clone().into_string()?,
+        recipient: match tokens.get(2) {
+            Some(token) => token.clone().into_string()?,
+            None => String::new(),
+        },
+    })
+}
+
+/// Decodes the (requestId, message) acked by the destination: the pong
+/// receipt of the CosmWasm ping-pong, or the ping payload echoed by the other
+/// dapps.
+pub fn decode_ack_message(data: &[u8]) -> Option<(u64, String)> {
+    let receipt_params: ParamType = ParamType::Tuple(vec![
+        ParamType::Uint(8),
+        ParamType::Uint(64),
+        ParamType::Uint(64),
+        ParamType::Uint(64),
+        ParamType::Uint(64),
+        ParamType::String,
+    ]);
+    if let Some(tokens) = decode_exact(&[receipt_params], data) {
+        let tuple: InkVec<Token> = tokens[0].clone().into_tuple()?;
+        return Some((token_to_u64(&tuple[1])?, tuple[5].clone().into_string()?));
+    }
+
+    let ping: PingMessage = decode_ping(data)?;
+    Some((ping.request_id, ping.message))
+}
+
+fn versioned_params(version: u8) -> InkVec<ParamType> {
+    let mut params: InkVec<ParamType> = vec![
+        ParamType::Uint(8),
+        ParamType::Uint(64),
+        ParamType::Uint(64),
+        ParamType::Bytes,
+        ParamType::String,
+        ParamType::Bytes,
+    ];
+    if version >= 2 {
+        params.push(ParamType::String);
+    }
+    params
+}
+
+// ethabi ignores the data following the decoded tokens, the payload layouts
+// only match when the data is exactly their encoding
+fn decode_exact(params: &[ParamType], data: &[u8]) -> Option<InkVec<Token>> {
+    match decode(params, data) {
+        Ok(tokens) if encode(&tokens) == data => Some(tokens),
+        _ => None,
+    }
+}
+
+fn token_to_u64(token: &Token) -> Option<u64> {
+    match token.clone().into_uint() {
+        Some(value) if value.bits() <= 64 => Some(value.as_u64()),
+        _ => None,
+    }
+}